@@ -15,4 +15,9 @@
 fn main() {
     let hash = rustc_tools_util::get_commit_hash().unwrap_or_default();
     println!("cargo:rustc-env=GIT_HASH={}", hash);
+
+    // Expose the build target and profile for `--version --verbose`
+    for var in &["TARGET", "PROFILE"] {
+        println!("cargo:rustc-env={}={}", var, std::env::var(var).unwrap_or_default());
+    }
 }
@@ -15,14 +15,22 @@
 use std::borrow::Cow;
 use std::cmp::max;
 use std::path::{Path, PathBuf};
+use std::process;
 
-use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg};
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, App, AppSettings, Arg, SubCommand,
+};
 use log::{self, LevelFilter};
 
-use alacritty_terminal::config::{Config, Delta, Dimensions, Shell};
+use alacritty_terminal::config::{Config, Delta, Dimensions, Shell, DEFAULT_ALACRITTY_CONFIG};
 use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::util::expand_tilde;
 use alacritty_terminal::window::DEFAULT_NAME;
 
+use crate::config;
+use crate::config_dump::{BindingTable, EffectiveConfig};
+use crate::diagnostics::Report;
+
 /// Options specified on the command line
 pub struct Options {
     pub live_config_reload: Option<bool>,
@@ -37,6 +45,8 @@ pub struct Options {
     pub working_dir: Option<PathBuf>,
     pub config: Option<PathBuf>,
     pub persistent_logging: bool,
+    pub hold_env: bool,
+    pub trace_file: Option<PathBuf>,
 }
 
 impl Default for Options {
@@ -54,6 +64,8 @@ impl Default for Options {
             working_dir: None,
             config: None,
             persistent_logging: false,
+            hold_env: false,
+            trace_file: None,
         }
     }
 }
@@ -73,6 +85,19 @@ impl Options {
             .version(version.as_str())
             .author(crate_authors!("\n"))
             .about(crate_description!())
+            .setting(AppSettings::DisableVersion)
+            .arg(
+                Arg::with_name("version")
+                    .short("V")
+                    .long("version")
+                    .help("Prints version information"),
+            )
+            .arg(
+                Arg::with_name("verbose")
+                    .long("verbose")
+                    .requires("version")
+                    .help("Prints build and environment details along with the version"),
+            )
             .arg(Arg::with_name("ref-test").long("ref-test").help("Generates ref test"))
             .arg(
                 Arg::with_name("live-config-reload")
@@ -95,6 +120,20 @@ impl Options {
                     .long("persistent-logging")
                     .help("Keep the log file after quitting Alacritty"),
             )
+            .arg(
+                Arg::with_name("hold-env")
+                    .long("hold-env")
+                    .help("Pass environment variables only meant for Alacritty on to the shell"),
+            )
+            .arg(
+                Arg::with_name("trace-file")
+                    .long("trace-file")
+                    .takes_value(true)
+                    .help(
+                        "Write a Chrome trace of the frame pipeline to the specified file, \
+                         requires the tracing feature",
+                    ),
+            )
             .arg(
                 Arg::with_name("dimensions")
                     .long("dimensions")
@@ -146,6 +185,13 @@ impl Options {
                 Arg::with_name("working-directory")
                     .long("working-directory")
                     .takes_value(true)
+                    .validator(|dir| {
+                        if expand_tilde(&dir).is_dir() {
+                            Ok(())
+                        } else {
+                            Err(format!("{} is not a directory", dir))
+                        }
+                    })
                     .help("Start the shell in the specified working directory"),
             )
             .arg(Arg::with_name("config-file").long("config-file").takes_value(true).help(
@@ -162,8 +208,57 @@ impl Options {
                     .allow_hyphen_values(true)
                     .help("Command and args to execute (must be last argument)"),
             )
+            .subcommand(
+                SubCommand::with_name("config")
+                    .about("Prints the effective configuration and where each value is set")
+                    .arg(
+                        Arg::with_name("default")
+                            .long("default")
+                            .help("Prints the default configuration with its documentation"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("bindings")
+                    .about("Prints the effective key and mouse bindings for every terminal mode"),
+            )
             .get_matches();
 
+        if matches.is_present("version") {
+            if matches.is_present("verbose") {
+                let config_file = matches.value_of("config-file").map(Path::new);
+                print!("{}", Report::new(&version, config_file));
+            } else {
+                println!("{} {}", crate_name!(), version);
+            }
+
+            process::exit(0);
+        }
+
+        if let Some(config_matches) = matches.subcommand_matches("config") {
+            if config_matches.is_present("default") {
+                print!("{}", DEFAULT_ALACRITTY_CONFIG);
+            } else {
+                let config_file = matches.value_of("config-file").map(Path::new);
+                print!("{}", EffectiveConfig::new(config_file));
+            }
+
+            process::exit(0);
+        }
+
+        if matches.subcommand_matches("bindings").is_some() {
+            let config_file = matches
+                .value_of("config-file")
+                .map(|path| Cow::Owned(PathBuf::from(path)))
+                .or_else(config::installed_config);
+            let config = match config_file {
+                Some(path) => config::load_from(path.into_owned()),
+                None => Config::default(),
+            };
+            print!("{}", BindingTable::new(&config));
+
+            process::exit(0);
+        }
+
         if matches.is_present("ref-test") {
             options.ref_test = true;
         }
@@ -182,6 +277,10 @@ impl Options {
             options.persistent_logging = true;
         }
 
+        if matches.is_present("hold-env") {
+            options.hold_env = true;
+        }
+
         if let Some(mut dimensions) = matches.values_of("dimensions") {
             let width = dimensions.next().map(|w| w.parse().map(Column));
             let height = dimensions.next().map(|h| h.parse().map(Line));
@@ -215,7 +314,11 @@ impl Options {
         }
 
         if let Some(dir) = matches.value_of("working-directory") {
-            options.working_dir = Some(PathBuf::from(dir.to_string()));
+            options.working_dir = Some(expand_tilde(dir));
+        }
+
+        if let Some(path) = matches.value_of("trace-file") {
+            options.trace_file = Some(PathBuf::from(path.to_string()));
         }
 
         if let Some(path) = matches.value_of("config-file") {
@@ -246,6 +349,7 @@ impl Options {
             self.working_dir.or_else(|| config.working_directory().to_owned()),
         );
         config.shell = self.command.or(config.shell);
+        config.hold_env = self.hold_env || config.hold_env;
 
         config.window.dimensions = self.dimensions.unwrap_or(config.window.dimensions);
         config.window.position = self.position.or(config.window.position);
@@ -1,17 +1,19 @@
 use std::borrow::Cow;
 use std::env;
+use std::fmt::Debug;
 use std::fs::File;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 
 #[cfg(windows)]
 use dirs;
-use log::{error, warn};
+use log::{error, info, warn};
 use serde_yaml;
 #[cfg(not(windows))]
 use xdg;
 
-use alacritty_terminal::config::{Config, DEFAULT_ALACRITTY_CONFIG};
+use alacritty_terminal::config::{bindings, Config, DEFAULT_ALACRITTY_CONFIG};
+use alacritty_terminal::input::Binding;
 
 pub const SOURCE_FILE_PATH: &str = file!();
 
@@ -189,13 +191,71 @@ fn read_config(path: &PathBuf) -> Result<Config> {
         return Ok(Config::default());
     }
 
-    let config = serde_yaml::from_str(&contents)?;
+    let mut config: Config = serde_yaml::from_str(&contents)?;
+
+    // Replace the colors with the selected color scheme
+    if let Some(scheme) = config.colors.scheme.clone() {
+        config.set_color_scheme(&scheme);
+    }
 
     print_deprecation_warnings(&config);
+    print_binding_warnings(&config);
 
     Ok(config)
 }
 
+/// Describe where a binding was defined
+///
+/// Bindings of the config file come first, so `index` is also their position in the file.
+pub fn binding_source<T: PartialEq>(
+    kind: &str,
+    index: usize,
+    binding: &Binding<T>,
+    defaults: &[Binding<T>],
+) -> String {
+    if defaults.contains(binding) {
+        String::from("default")
+    } else {
+        format!("{}_bindings[{}]", kind, index)
+    }
+}
+
+fn print_binding_warnings(config: &Config) {
+    let key_defaults = bindings::default_key_bindings();
+    warn_binding_conflicts("key", &config.key_bindings, &key_defaults);
+    let mouse_defaults = bindings::default_mouse_bindings();
+    warn_binding_conflicts("mouse", &config.mouse_bindings, &mouse_defaults);
+}
+
+fn warn_binding_conflicts<T>(kind: &str, bindings: &[Binding<T>], defaults: &[Binding<T>])
+where
+    T: Eq + Debug,
+{
+    // Defaults which were replaced by a binding with the same trigger
+    for default in defaults.iter().filter(|default| !bindings.contains(default)) {
+        if let Some(index) = bindings.iter().position(|binding| binding.triggers_match(default)) {
+            let source = binding_source(kind, index, &bindings[index], defaults);
+            info!("Default {} binding {} is replaced by {}", kind, default, source);
+        }
+    }
+
+    for (i, j) in bindings::conflicts(bindings) {
+        // Default bindings are expected to work together
+        if defaults.contains(&bindings[i]) && defaults.contains(&bindings[j]) {
+            continue;
+        }
+
+        warn!(
+            "Conflicting {} bindings {} ({}) and {} ({})",
+            kind,
+            bindings[i],
+            binding_source(kind, i, &bindings[i], defaults),
+            bindings[j],
+            binding_source(kind, j, &bindings[j], defaults),
+        );
+    }
+}
+
 fn print_deprecation_warnings(config: &Config) {
     if config.window.start_maximized.is_some() {
         warn!(
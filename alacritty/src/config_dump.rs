@@ -0,0 +1,258 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configuration dumps for `alacritty config` and `alacritty bindings`
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::Value;
+
+use alacritty_terminal::config::{bindings, Config, DEFAULT_ALACRITTY_CONFIG};
+use alacritty_terminal::input::Binding;
+use alacritty_terminal::term::TermMode;
+
+use crate::config;
+
+/// Origin of a configuration value
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Source {
+    Default,
+    File,
+}
+
+/// Every configuration value with the place it was set in
+pub struct EffectiveConfig {
+    config_file: Option<PathBuf>,
+
+    /// Reason the config file was not used
+    error: Option<String>,
+
+    /// Dotted key paths with their values, in the order of the default config
+    entries: Vec<(String, Value, Source)>,
+}
+
+impl EffectiveConfig {
+    pub fn new(config_file: Option<&Path>) -> EffectiveConfig {
+        let config_file = match config_file {
+            Some(path) => Some(path.to_path_buf()),
+            None => config::installed_config().map(|path| path.to_path_buf()),
+        };
+
+        let (user, error) = match config_file.as_ref().map(PathBuf::as_path).map(read_yaml) {
+            Some(Ok(user)) => (Some(user), None),
+            Some(Err(err)) => (None, Some(err)),
+            None => (None, None),
+        };
+
+        let default = serde_yaml::from_str(DEFAULT_ALACRITTY_CONFIG).ok();
+        let mut entries = Vec::new();
+        merge(String::new(), default.as_ref(), user.as_ref(), &mut entries);
+
+        EffectiveConfig { config_file, error, entries }
+    }
+}
+
+impl Display for EffectiveConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.config_file, &self.error) {
+            (Some(path), None) => writeln!(f, "# Config file: {}", path.display())?,
+            (Some(path), Some(err)) => {
+                writeln!(f, "# Config file: {} (ignored: {})", path.display(), err)?
+            },
+            (None, _) => writeln!(f, "# Config file: none")?,
+        }
+
+        for (key, value, source) in &self.entries {
+            let source = match source {
+                Source::Default => "default",
+                Source::File => "config file",
+            };
+            writeln!(f, "{}: {}  # {}", key, inline(value), source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Effective key and mouse bindings, grouped by the terminal modes they are limited to
+pub struct BindingTable {
+    /// Binding descriptions for every kind of binding and combination of modes
+    groups: BTreeMap<(&'static str, String), Vec<String>>,
+}
+
+impl BindingTable {
+    pub fn new(config: &Config) -> BindingTable {
+        let mut table = BindingTable { groups: BTreeMap::new() };
+        table.add("key", &config.key_bindings, &bindings::default_key_bindings());
+        table.add("mouse", &config.mouse_bindings, &bindings::default_mouse_bindings());
+        table
+    }
+
+    fn add<T>(&mut self, kind: &'static str, bindings: &[Binding<T>], defaults: &[Binding<T>])
+    where
+        T: PartialEq + Debug,
+    {
+        for (index, binding) in bindings.iter().enumerate() {
+            let modes = describe_modes(binding.mode, binding.notmode);
+            let source = config::binding_source(kind, index, binding, defaults);
+            let line = format!("{}  # {}", binding, source);
+            self.groups.entry((kind, modes)).or_insert_with(Vec::new).push(line);
+        }
+    }
+}
+
+impl Display for BindingTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for ((kind, modes), lines) in &self.groups {
+            writeln!(f, "# {} bindings {}", kind, modes)?;
+            for line in lines {
+                writeln!(f, "{}", line)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn describe_modes(mode: TermMode, notmode: TermMode) -> String {
+    match (mode.is_empty(), notmode.is_empty()) {
+        (true, true) => String::from("in any mode"),
+        (false, true) => format!("in {:?}", mode),
+        (true, false) => format!("not in {:?}", notmode),
+        (false, false) => format!("in {:?}, not in {:?}", mode, notmode),
+    }
+}
+
+fn read_yaml(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    // Remove UTF-8 BOM
+    let contents = contents.trim_start_matches('\u{FEFF}');
+
+    if contents.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+
+    serde_yaml::from_str(contents).map_err(|err| err.to_string())
+}
+
+/// Flatten the default and user values below `key`, preferring the user's values
+///
+/// Mappings are merged key by key, every other value is replaced entirely.
+fn merge(
+    key: String,
+    default: Option<&Value>,
+    user: Option<&Value>,
+    entries: &mut Vec<(String, Value, Source)>,
+) {
+    let default_map = default.and_then(Value::as_mapping);
+    let user_map = user.and_then(Value::as_mapping);
+
+    let is_mapping = match (default_map, user) {
+        (_, Some(Value::Mapping(_))) => true,
+        (Some(map), None) | (Some(map), Some(Value::Null)) => !map.is_empty(),
+        _ => false,
+    };
+
+    if !is_mapping {
+        match (user, default) {
+            (Some(value), _) => entries.push((key, value.clone(), Source::File)),
+            (None, Some(value)) => entries.push((key, value.clone(), Source::Default)),
+            _ => (),
+        }
+        return;
+    }
+
+    // Keys in the order of the default config, followed by the ones only set by the user
+    let mut keys: Vec<&Value> =
+        default_map.into_iter().flat_map(|map| map.iter()).map(|(key, _)| key).collect();
+    for (user_key, _) in user_map.into_iter().flat_map(|map| map.iter()) {
+        if !keys.contains(&user_key) {
+            keys.push(user_key);
+        }
+    }
+
+    for child in keys {
+        let child_key = match key.as_str() {
+            "" => inline(child),
+            _ => format!("{}.{}", key, inline(child)),
+        };
+        let default = default_map.and_then(|map| map.get(child));
+        let user = user_map.and_then(|map| map.get(child));
+        merge(child_key, default, user, entries);
+    }
+}
+
+/// Single line YAML representation of a value
+fn inline(value: &Value) -> String {
+    match value {
+        Value::Sequence(items) => {
+            let items: Vec<String> = items.iter().map(inline).collect();
+            format!("[{}]", items.join(", "))
+        },
+        Value::Mapping(map) => {
+            let items: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{}: {}", inline(key), inline(value)))
+                .collect();
+            format!("{{ {} }}", items.join(", "))
+        },
+        Value::Null => String::from("null"),
+        scalar => serde_yaml::to_string(scalar)
+            .map(|yaml| yaml.trim_start_matches("---").trim().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_yaml::Value;
+
+    use super::{inline, merge, Source};
+
+    fn entries(default: &str, user: &str) -> Vec<(String, String, Source)> {
+        let default: Value = serde_yaml::from_str(default).unwrap();
+        let user: Value = serde_yaml::from_str(user).unwrap();
+
+        let mut entries = Vec::new();
+        merge(String::new(), Some(&default), Some(&user), &mut entries);
+        entries.into_iter().map(|(key, value, source)| (key, inline(&value), source)).collect()
+    }
+
+    #[test]
+    fn user_values_replace_defaults() {
+        let default = "window:\n  columns: 80\n  lines: 24\nscrolling: 10000\n";
+        let user = "window:\n  lines: 50\n";
+
+        assert_eq!(entries(default, user), vec![
+            (String::from("window.columns"), String::from("80"), Source::Default),
+            (String::from("window.lines"), String::from("50"), Source::File),
+            (String::from("scrolling"), String::from("10000"), Source::Default),
+        ]);
+    }
+
+    #[test]
+    fn sequences_are_replaced_entirely() {
+        let default = "bindings: [1, 2]\n";
+        let user = "bindings: [3]\nextra: true\n";
+
+        assert_eq!(entries(default, user), vec![
+            (String::from("bindings"), String::from("[3]"), Source::File),
+            (String::from("extra"), String::from("true"), Source::File),
+        ]);
+    }
+}
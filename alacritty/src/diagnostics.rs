@@ -0,0 +1,120 @@
+// Copyright 2019 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Build and runtime report for `alacritty --version --verbose`
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+/// Environment variables which influence the behavior of Alacritty
+const ENV_VARS: &[&str] = &[
+    "TERM",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XDG_SESSION_TYPE",
+    "XDG_CONFIG_HOME",
+    "WINIT_HIDPI_FACTOR",
+    "WINIT_UNIX_BACKEND",
+];
+
+/// Compile time features enabled in this build
+const FEATURES: &[(&str, bool)] = &[
+    ("live-shader-reload", cfg!(feature = "live-shader-reload")),
+    ("nightly", cfg!(feature = "nightly")),
+    ("bench", cfg!(feature = "bench")),
+    ("utempter", cfg!(feature = "utempter")),
+    ("tracing", cfg!(feature = "tracing")),
+];
+
+/// Diagnostic information for bug reports
+pub struct Report {
+    version: String,
+    config_file: Option<PathBuf>,
+}
+
+impl Report {
+    pub fn new(version: &str, config_file: Option<&Path>) -> Report {
+        let config_file = match config_file {
+            Some(path) => Some(path.to_path_buf()),
+            None => config::installed_config().map(|path| path.to_path_buf()),
+        };
+
+        Report { version: version.to_owned(), config_file }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "alacritty {}", self.version)?;
+
+        writeln!(f, "\nBuild:")?;
+        writeln!(f, "  target: {}", env!("TARGET"))?;
+        writeln!(f, "  profile: {}", env!("PROFILE"))?;
+        let features: Vec<&str> =
+            FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect();
+        if features.is_empty() {
+            writeln!(f, "  features: none")?;
+        } else {
+            writeln!(f, "  features: {}", features.join(", "))?;
+        }
+
+        writeln!(f, "\nWindowing:")?;
+        writeln!(f, "  backend: {}", window_backend())?;
+
+        writeln!(f, "\nConfig:")?;
+        match self.config_file {
+            Some(ref path) if path.exists() => writeln!(f, "  file: {}", path.display())?,
+            Some(ref path) => writeln!(f, "  file: {} (missing)", path.display())?,
+            None => writeln!(f, "  file: none")?,
+        }
+
+        writeln!(f, "\nEnvironment:")?;
+        for name in ENV_VARS {
+            match env::var(name) {
+                Ok(value) => writeln!(f, "  {}={}", name, value)?,
+                Err(_) => writeln!(f, "  {} is not set", name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Windowing system glutin is expected to pick
+#[cfg(not(any(target_os = "macos", windows)))]
+fn window_backend() -> &'static str {
+    match env::var("WINIT_UNIX_BACKEND").as_ref().map(String::as_str) {
+        Ok("x11") => "X11",
+        Ok("wayland") => "Wayland",
+        _ if env::var_os("WAYLAND_DISPLAY").is_some() => "Wayland",
+        _ if env::var_os("DISPLAY").is_some() => "X11",
+        _ => "unavailable",
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn window_backend() -> &'static str {
+    "Cocoa"
+}
+
+#[cfg(windows)]
+fn window_backend() -> &'static str {
+    "Win32"
+}
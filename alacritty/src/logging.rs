@@ -20,7 +20,7 @@
 use std::env;
 use std::fs::{File, OpenOptions};
 use std::io::{self, LineWriter, Stdout, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -28,6 +28,12 @@ use std::sync::{Arc, Mutex};
 use crossbeam_channel::Sender;
 use log::{self, Level};
 use time;
+#[cfg(feature = "tracing")]
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+#[cfg(feature = "tracing")]
+use tracing_core::Dispatch;
+#[cfg(feature = "tracing")]
+use tracing_subscriber::layer::SubscriberExt;
 
 use alacritty_terminal::message_bar::Message;
 use alacritty_terminal::term::color;
@@ -55,6 +61,30 @@ pub fn initialize(
     }
 }
 
+/// Record the spans of the frame pipeline as a Chrome trace
+///
+/// The trace is only written to `path` once the returned guard is dropped.
+#[cfg(feature = "tracing")]
+pub fn initialize_tracing(path: &Path) -> Option<FlushGuard> {
+    let (layer, guard) = ChromeLayerBuilder::new().file(path.to_path_buf()).build();
+    let subscriber = tracing_subscriber::registry().with(layer);
+
+    match tracing_core::dispatcher::set_global_default(Dispatch::new(subscriber)) {
+        Ok(()) => Some(guard),
+        Err(err) => {
+            log::error!("Unable to record trace: {}", err);
+            None
+        },
+    }
+}
+
+/// Spans are compiled out without the `tracing` feature, so there is nothing to record
+#[cfg(not(feature = "tracing"))]
+pub fn initialize_tracing(_path: &Path) -> Option<()> {
+    log::warn!("Unable to record trace, Alacritty was built without the tracing feature");
+    None
+}
+
 pub struct Logger {
     logfile: Mutex<OnDemandLogFile>,
     stdout: Mutex<LineWriter<Stdout>>,
@@ -41,9 +41,10 @@ use std::env;
 #[cfg(not(windows))]
 use std::os::unix::io::AsRawFd;
 
+use alacritty_terminal::appearance::AppearanceMonitor;
 use alacritty_terminal::clipboard::Clipboard;
 use alacritty_terminal::config::{Config, Monitor};
-use alacritty_terminal::display::Display;
+use alacritty_terminal::display::{self, Display};
 use alacritty_terminal::event_loop::{self, EventLoop, Msg};
 #[cfg(target_os = "macos")]
 use alacritty_terminal::locale;
@@ -57,11 +58,17 @@ use alacritty_terminal::{die, event};
 
 mod cli;
 mod config;
+mod config_dump;
+mod diagnostics;
 mod logging;
+mod startup;
 
 use crate::cli::Options;
+use crate::startup::StartupTimer;
 
 fn main() {
+    let mut startup = StartupTimer::new();
+
     panic::attach_handler();
 
     // When linked with the windows subsystem windows won't automatically attach
@@ -82,6 +89,12 @@ fn main() {
     let log_file =
         logging::initialize(&options, message_buffer.tx()).expect("Unable to initialize logger");
 
+    // Record profiling spans until Alacritty exits
+    let _trace_guard = options.trace_file.as_ref().and_then(|p| logging::initialize_tracing(p));
+
+    // Load the system fonts while the config is read
+    let font_preload = display::preload_fonts();
+
     // Load configuration file
     // If the file is a command line argument, we won't write a generated default file
     let config_path = options
@@ -99,6 +112,7 @@ fn main() {
 
     // Update the log level from config
     log::set_max_level(config.debug.log_level);
+    startup.phase("config");
 
     // Switch to home directory
     #[cfg(target_os = "macos")]
@@ -110,8 +124,11 @@ fn main() {
     // Store if log file should be deleted before moving config
     let persistent_logging = config.persistent_logging();
 
+    let _ = font_preload.join();
+    startup.phase("font preload");
+
     // Run alacritty
-    if let Err(err) = run(config, message_buffer) {
+    if let Err(err) = run(config, message_buffer, startup) {
         die!("Alacritty encountered an unrecoverable error:\n\n\t{}\n", Red(err));
     }
 
@@ -127,7 +144,11 @@ fn main() {
 ///
 /// Creates a window, the terminal state, pty, I/O event loop, input processor,
 /// config change monitor, and runs the main display loop.
-fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Error>> {
+fn run(
+    mut config: Config,
+    message_buffer: MessageBuffer,
+    mut startup: StartupTimer,
+) -> Result<(), Box<dyn Error>> {
     info!("Welcome to Alacritty");
     if let Some(config_path) = &config.config_path {
         info!("Configuration loaded from {:?}", config_path.display());
@@ -140,6 +161,7 @@ fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Erro
     //
     // The display manages a window and can draw the terminal
     let mut display = Display::new(&config)?;
+    startup.phase("display");
 
     info!("PTY Dimensions: {:?} x {:?}", display.size().lines(), display.size().cols());
 
@@ -156,6 +178,7 @@ fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Erro
     // access it.
     let terminal = Term::new(&config, display.size().to_owned(), message_buffer, clipboard);
     let terminal = Arc::new(FairMutex::new(terminal));
+    startup.phase("terminal");
 
     // Find the window ID for setting $WINDOWID
     let window_id = display.get_window_id();
@@ -165,7 +188,8 @@ fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Erro
     // The pty forks a process to run the shell on the slave side of the
     // pseudoterminal. A file descriptor for the master side is retained for
     // reading/writing to the shell.
-    let pty = tty::new(&config, &display.size(), window_id);
+    let pty = tty::new(&config, &display.pty_size(), window_id);
+    startup.phase("pty");
 
     // Get a reference to something that we can resize
     //
@@ -210,11 +234,26 @@ fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Erro
         None
     };
 
+    // Create an appearance monitor when schemes for light and dark mode are configured
+    //
+    // The monitor watches the system light/dark preference. Pending appearance changes
+    // are processed in the main loop. It is started later if a reloaded config needs it.
+    let mut appearance_monitor = if config.colors.follows_appearance() {
+        Some(AppearanceMonitor::new(display.notifier()))
+    } else {
+        None
+    };
+    let mut appearance = None;
+
     // Kick off the I/O thread
-    let _io_thread = event_loop.spawn(None);
+    let io_thread = event_loop.spawn(None);
+    startup.phase("event loop");
 
     info!("Initialisation complete");
 
+    // Timings are reported once, after the first frame
+    let mut startup = Some(startup);
+
     // Main display loop
     loop {
         // Process input and window events
@@ -225,13 +264,44 @@ fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Erro
             // Clear old config messages from bar
             terminal_lock.message_buffer_mut().remove_topic(config::SOURCE_FILE_PATH);
 
-            if let Ok(config) = config::reload_from(path) {
+            if let Ok(mut new_config) = config::reload_from(path) {
+                new_config.config_path = config.config_path.take();
+                config = new_config;
+
+                if appearance_monitor.is_none() && config.colors.follows_appearance() {
+                    appearance_monitor = Some(AppearanceMonitor::new(display.notifier()));
+                }
+
+                // Keep following the system appearance
+                let scheme = appearance.and_then(|a| config.colors.scheme_for(a)).cloned();
+                if let Some(scheme) = scheme {
+                    config.set_color_scheme(&scheme);
+                }
+
                 display.update_config(&config);
                 processor.update_config(&config);
                 terminal_lock.update_config(&config);
             }
 
             terminal_lock.dirty = true;
+            terminal_lock.force_redraw = true;
+        }
+
+        // Switch the color scheme when the system appearance changed
+        if let Some(new) = appearance_monitor.as_ref().and_then(AppearanceMonitor::pending) {
+            appearance = Some(new);
+
+            if let Some(scheme) = config.colors.scheme_for(new).cloned() {
+                config.set_color_scheme(&scheme);
+                terminal_lock.update_config(&config);
+                terminal_lock.dirty = true;
+            }
+        }
+
+        // Switch the color scheme when requested by the user
+        if let Some(scheme) = terminal_lock.get_next_color_scheme() {
+            config.set_color_scheme(&scheme);
+            terminal_lock.update_config(&config);
         }
 
         // Begin shutdown if the flag was raised
@@ -255,11 +325,29 @@ fn run(config: Config, message_buffer: MessageBuffer) -> Result<(), Box<dyn Erro
 
             // Draw the current state of the terminal
             display.draw(&terminal, &config);
+
+            if let Some(mut startup) = startup.take() {
+                startup.phase("first frame");
+                startup.report();
+            }
         }
     }
 
     loop_tx.send(Msg::Shutdown).expect("Error sending shutdown to event loop");
 
+    // Dropping the PTY hangs up the shell
+    if io_thread.join().is_err() {
+        error!("PTY event loop panicked");
+    }
+
+    // Keep copied text available after the window is gone
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        if config.selection.persist_on_exit {
+            terminal.lock().clipboard().persist();
+        }
+    }
+
     // FIXME patch notify library to have a shutdown method
     // config_reloader.join().ok();
 
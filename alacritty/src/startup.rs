@@ -0,0 +1,62 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Time spent in each phase between launching and presenting the first frame
+use std::time::{Duration, Instant};
+
+use log::info;
+
+/// Time to the first frame which startup is expected to stay below with a warm cache
+const FIRST_FRAME_BUDGET: Duration = Duration::from_millis(50);
+
+pub struct StartupTimer {
+    start: Instant,
+
+    /// End of the last recorded phase
+    last: Instant,
+
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StartupTimer {
+    pub fn new() -> StartupTimer {
+        let now = Instant::now();
+        StartupTimer { start: now, last: now, phases: Vec::new() }
+    }
+
+    /// Record the end of a phase, which started at the end of the previous one
+    pub fn phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.last));
+        self.last = now;
+    }
+
+    /// Log the duration of every phase and the total time to the first frame
+    pub fn report(&self) {
+        let total = self.last - self.start;
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(name, duration)| format!("{} {}", name, format_duration(*duration)))
+            .collect();
+
+        let budget = if total > FIRST_FRAME_BUDGET { " (over budget)" } else { "" };
+        info!("First frame after {}{}: {}", format_duration(total), budget, phases.join(", "));
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_secs() as f64 * 1000. + f64::from(duration.subsec_micros()) / 1000.;
+    format!("{:.1}ms", millis)
+}
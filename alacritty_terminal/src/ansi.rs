@@ -13,6 +13,7 @@
 // limitations under the License.
 //
 //! ANSI Terminal Stream Parsing
+use std::cmp::min;
 use std::io;
 use std::ops::Range;
 use std::str;
@@ -23,6 +24,7 @@ use glutin::MouseCursor;
 use vte;
 
 use crate::term::color::Rgb;
+use crate::term::sixel::{SixelImage, SixelParser};
 
 // Parse color arguments
 //
@@ -76,13 +78,19 @@ fn parse_rgb_color(color: &[u8]) -> Option<Rgb> {
             }
             let b = parse_hex!();
 
-            Some(Rgb { r, g, b })
+            Some(Rgb::new(r, g, b))
         },
-        Some('#') => Some(Rgb { r: parse_hex!(), g: parse_hex!(), b: parse_hex!() }),
+        Some('#') => Some(Rgb::new(parse_hex!(), parse_hex!(), parse_hex!())),
         _ => None,
     }
 }
 
+/// Join OSC parameters which were split on `;`, but belong to the same text
+fn join_params(params: &[&[u8]]) -> String {
+    let params: Vec<_> = params.iter().map(|param| String::from_utf8_lossy(param)).collect();
+    params.join(";")
+}
+
 fn parse_number(input: &[u8]) -> Option<u8> {
     if input.is_empty() {
         return None;
@@ -111,6 +119,12 @@ pub struct Processor {
 /// Internal state for VTE processor
 struct ProcessorState {
     preceding_char: Option<char>,
+
+    /// Byte which is being parsed, since `vte` doesn't pass the final byte of DCS sequences
+    byte: u8,
+
+    /// Image of the DCS sequence which is being received
+    sixel: Option<SixelParser>,
 }
 
 /// Helper type that implements `vte::Perform`.
@@ -137,7 +151,8 @@ impl<'a, H: Handler + TermInfo + 'a, W: io::Write> Performer<'a, H, W> {
 
 impl Default for Processor {
     fn default() -> Processor {
-        Processor { state: ProcessorState { preceding_char: None }, parser: vte::Parser::new() }
+        let state = ProcessorState { preceding_char: None, byte: 0, sixel: None };
+        Processor { state, parser: vte::Parser::new() }
     }
 }
 
@@ -152,6 +167,7 @@ impl Processor {
         H: Handler + TermInfo,
         W: io::Write,
     {
+        self.state.byte = byte;
         let mut performer = Performer::new(&mut self.state, handler, writer);
         self.parser.advance(&mut performer, byte);
     }
@@ -203,9 +219,25 @@ pub trait Handler {
     /// TODO this should probably return an io::Result
     fn identify_terminal<W: io::Write>(&mut self, _: &mut W) {}
 
+    /// Reply to ENQ with the answerback string
+    fn answerback<W: io::Write>(&mut self, _: &mut W) {}
+
     // Report device status
     fn device_status<W: io::Write>(&mut self, _: &mut W, _: usize) {}
 
+    /// Query or change graphics attributes (XTSMGRAPHICS)
+    fn graphics_attribute<W: io::Write>(
+        &mut self,
+        _: &mut W,
+        _: Option<GraphicsItem>,
+        _: Option<GraphicsAction>,
+        _: &[i64],
+    ) {
+    }
+
+    /// Place a sixel image at the cursor
+    fn sixel_image(&mut self, _: SixelImage) {}
+
     /// Move cursor forward `cols`
     fn move_forward(&mut self, _: Column) {}
 
@@ -338,11 +370,50 @@ pub trait Handler {
     /// Reset an indexed color to original value
     fn reset_color(&mut self, _: usize) {}
 
-    /// Set the clipboard
-    fn set_clipboard(&mut self, _: &str) {}
+    /// Store text in the clipboard named by an OSC 52 selection character
+    fn set_clipboard(&mut self, _: u8, _: &str) {}
+
+    /// Write the text of the clipboard named by an OSC 52 selection character
+    fn write_clipboard<W: io::Write>(&mut self, _: &mut W, _: u8) {}
 
     /// Run the dectest routine
     fn dectest(&mut self) {}
+
+    /// Report the progress of a long running command, `None` removes it
+    fn set_progress(&mut self, _: Option<Progress>) {}
+
+    /// Show a desktop notification
+    fn notify(&mut self, _title: Option<&str>, _body: &str) {}
+
+    /// Attach a hyperlink to all cells written from now on, `None` ends it
+    fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
+}
+
+/// Hyperlink started with `OSC 8`
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Hyperlink {
+    /// Identifier joining separately written parts of the same link
+    pub id: Option<String>,
+
+    pub uri: String,
+}
+
+/// Progress of a command reported with `OSC 9 ; 4`
+///
+/// Percentages are always within `0..=100`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Progress {
+    /// Command is making progress
+    Normal(u8),
+
+    /// Command has failed
+    Error(u8),
+
+    /// Command is busy, without a known percentage
+    Indeterminate,
+
+    /// Command is paused or reports a warning
+    Paused(u8),
 }
 
 /// Describes shape of cursor
@@ -417,6 +488,14 @@ pub enum Mode {
     ReportFocusInOut = 1004,
     /// ?1006
     SgrMouse = 1006,
+    /// ?1016
+    ///
+    /// SGR mouse reports with pixel instead of cell coordinates
+    SgrPixels = 1016,
+    /// ?66
+    ///
+    /// DECNKM, numeric keypad sends application sequences
+    ApplicationKeypad = 66,
     /// ?1049
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
@@ -436,11 +515,13 @@ impl Mode {
                 7 => Mode::LineWrap,
                 12 => Mode::BlinkingCursor,
                 25 => Mode::ShowCursor,
+                66 => Mode::ApplicationKeypad,
                 1000 => Mode::ReportMouseClicks,
                 1002 => Mode::ReportCellMouseMotion,
                 1003 => Mode::ReportAllMouseMotion,
                 1004 => Mode::ReportFocusInOut,
                 1006 => Mode::SgrMouse,
+                1016 => Mode::SgrPixels,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
                 _ => {
@@ -495,6 +576,53 @@ pub enum TabulationClearMode {
     All,
 }
 
+/// Graphics attribute of an XTSMGRAPHICS request
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GraphicsItem {
+    /// Number of sixel color registers
+    ColorRegisters = 1,
+    /// Sixel image geometry in pixels
+    SixelGeometry = 2,
+    /// ReGIS graphics geometry in pixels
+    RegisGeometry = 3,
+}
+
+impl GraphicsItem {
+    pub fn from_primitive(num: i64) -> Option<GraphicsItem> {
+        match num {
+            1 => Some(GraphicsItem::ColorRegisters),
+            2 => Some(GraphicsItem::SixelGeometry),
+            3 => Some(GraphicsItem::RegisGeometry),
+            _ => None,
+        }
+    }
+}
+
+/// Action of an XTSMGRAPHICS request
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GraphicsAction {
+    /// Read the current value
+    Read,
+    /// Reset to the default value
+    Reset,
+    /// Set a new value
+    Set,
+    /// Read the largest supported value
+    ReadMaximum,
+}
+
+impl GraphicsAction {
+    pub fn from_primitive(num: i64) -> Option<GraphicsAction> {
+        match num {
+            1 => Some(GraphicsAction::Read),
+            2 => Some(GraphicsAction::Reset),
+            3 => Some(GraphicsAction::Set),
+            4 => Some(GraphicsAction::ReadMaximum),
+            _ => None,
+        }
+    }
+}
+
 /// Standard colors
 ///
 /// The order here matters since the enum should be castable to a `usize` for
@@ -709,6 +837,7 @@ where
     fn execute(&mut self, byte: u8) {
         match byte {
             C0::HT => self.handler.put_tab(1),
+            C0::ENQ => self.handler.answerback(self.writer),
             C0::BS => self.handler.backspace(),
             C0::CR => self.handler.carriage_return(),
             C0::LF | C0::VT | C0::FF => self.handler.linefeed(),
@@ -725,20 +854,35 @@ where
 
     #[inline]
     fn hook(&mut self, params: &[i64], intermediates: &[u8], ignore: bool) {
-        debug!(
-            "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
-            params, intermediates, ignore
-        );
+        match self._state.byte {
+            b'q' if intermediates.is_empty() && !ignore => {
+                self._state.sixel = Some(SixelParser::new(params));
+            },
+            _ => debug!(
+                "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
+                params, intermediates, ignore
+            ),
+        }
     }
 
     #[inline]
     fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
+        match self._state.sixel {
+            Some(ref mut sixel) => sixel.put(byte),
+            None => debug!("[unhandled put] byte={:?}", byte),
+        }
     }
 
     #[inline]
     fn unhook(&mut self) {
-        debug!("[unhandled unhook]");
+        match self._state.sixel.take() {
+            Some(sixel) => {
+                if let Some(image) = sixel.finish() {
+                    self.handler.sixel_image(image);
+                }
+            },
+            None => debug!("[unhandled unhook]"),
+        }
     }
 
     // TODO replace OSC parsing with parser combinators
@@ -778,6 +922,56 @@ where
             // This is ignored, since alacritty has no concept of tabs
             b"1" => return,
 
+            // ConEmu progress report
+            b"9" if params.len() >= 2 && params[1] == b"4" => {
+                let state = params.get(2).and_then(|param| parse_number(param)).unwrap_or(0);
+                let value = params.get(3).and_then(|param| parse_number(param)).unwrap_or(0);
+                let value = min(value, 100);
+
+                let progress = match state {
+                    0 => None,
+                    1 => Some(Progress::Normal(value)),
+                    2 => Some(Progress::Error(value)),
+                    3 => Some(Progress::Indeterminate),
+                    4 => Some(Progress::Paused(value)),
+                    _ => return unhandled(params),
+                };
+                self.handler.set_progress(progress);
+            },
+
+            // Other ConEmu extensions, like reporting the working directory
+            b"9" if params.len() >= 2 && parse_number(params[1]).is_some() => unhandled(params),
+
+            // iTerm2 desktop notification
+            b"9" => {
+                let body = join_params(&params[1..]);
+                self.handler.notify(None, &body);
+            },
+
+            // rxvt desktop notification
+            b"777" if params.len() >= 3 && params[1] == b"notify" => {
+                let title = String::from_utf8_lossy(params[2]);
+                let body = join_params(&params[3..]);
+                self.handler.notify(Some(&title), &body);
+            },
+
+            // Hyperlink
+            b"8" if params.len() >= 3 => {
+                // The URI may contain semicolons, which are split up like parameters
+                let uri = join_params(&params[2..]);
+                if uri.is_empty() {
+                    self.handler.set_hyperlink(None);
+                    return;
+                }
+
+                let id = params[1]
+                    .split(|&b| b == b':')
+                    .find(|param| param.starts_with(b"id="))
+                    .map(|param| String::from_utf8_lossy(&param[3..]).into_owned())
+                    .filter(|id| !id.is_empty());
+                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+            },
+
             // Set color index
             b"4" => {
                 if params.len() > 1 && params.len() % 2 != 0 {
@@ -841,18 +1035,20 @@ where
                 unhandled(params);
             },
 
-            // Set clipboard
+            // Set or query the clipboard
             b"52" => {
                 if params.len() < 3 {
                     return unhandled(params);
                 }
 
+                // Without a selection character the clipboard is used
+                let clipboard = params[1].first().cloned().unwrap_or(b'c');
                 match params[2] {
-                    b"?" => unhandled(params),
+                    b"?" => self.handler.write_clipboard(writer, clipboard),
                     selection => {
                         if let Ok(string) = base64::decode(selection) {
                             if let Ok(utf8_string) = str::from_utf8(&string) {
-                                self.handler.set_clipboard(utf8_string);
+                                self.handler.set_clipboard(clipboard, utf8_string);
                             }
                         }
                     },
@@ -972,6 +1168,12 @@ where
 
                 handler.clear_line(mode);
             },
+            'S' if private => {
+                let item = args.get(0).and_then(|item| GraphicsItem::from_primitive(*item));
+                let action = args.get(1).and_then(|action| GraphicsAction::from_primitive(*action));
+                let values = args.get(2..).unwrap_or(&[]);
+                handler.graphics_attribute(writer, item, action, values);
+            },
             'S' => handler.scroll_up(Line(arg_or_default!(idx: 0, default: 1) as usize)),
             'T' => handler.scroll_down(Line(arg_or_default!(idx: 0, default: 1) as usize)),
             'L' => handler.insert_blank_lines(Line(arg_or_default!(idx: 0, default: 1) as usize)),
@@ -1202,7 +1404,7 @@ fn parse_color(attrs: &[i64], i: &mut usize) -> Option<Color> {
                 return None;
             }
 
-            Some(Color::Spec(Rgb { r: r as u8, g: g as u8, b: b as u8 }))
+            Some(Color::Spec(Rgb::new(r as u8, g as u8, b as u8)))
         },
         5 => {
             if attrs.len() < 3 {
@@ -1377,11 +1579,12 @@ pub mod C1 {
 #[cfg(test)]
 mod tests {
     use super::{
-        parse_number, parse_rgb_color, Attr, CharsetIndex, Color, Handler, Processor,
-        StandardCharset, TermInfo,
+        parse_number, parse_rgb_color, Attr, CharsetIndex, Color, Handler, Hyperlink, Processor,
+        Progress, StandardCharset, TermInfo,
     };
     use crate::index::{Column, Line};
     use crate::term::color::Rgb;
+    use crate::term::sixel::SixelImage;
     use std::io;
 
     /// The /dev/null of `io::Write`
@@ -1446,7 +1649,7 @@ mod tests {
             parser.advance(&mut handler, *byte, &mut Void);
         }
 
-        let spec = Rgb { r: 128, g: 66, b: 255 };
+        let spec = Rgb::new(128, 66, 255);
 
         assert_eq!(handler.attr, Some(Attr::Foreground(Color::Spec(spec))));
     }
@@ -1545,12 +1748,12 @@ mod tests {
 
     #[test]
     fn parse_valid_rgb_color() {
-        assert_eq!(parse_rgb_color(b"rgb:11/aa/ff"), Some(Rgb { r: 0x11, g: 0xaa, b: 0xff }));
+        assert_eq!(parse_rgb_color(b"rgb:11/aa/ff"), Some(Rgb::new(0x11, 0xaa, 0xff)));
     }
 
     #[test]
     fn parse_valid_rgb_color2() {
-        assert_eq!(parse_rgb_color(b"#11aaff"), Some(Rgb { r: 0x11, g: 0xaa, b: 0xff }));
+        assert_eq!(parse_rgb_color(b"#11aaff"), Some(Rgb::new(0x11, 0xaa, 0xff)));
     }
 
     #[test]
@@ -1563,6 +1766,163 @@ mod tests {
         assert_eq!(parse_number(b"123"), Some(123));
     }
 
+    #[derive(Default)]
+    struct ProgressHandler {
+        progress: Vec<Option<Progress>>,
+    }
+
+    impl Handler for ProgressHandler {
+        fn set_progress(&mut self, progress: Option<Progress>) {
+            self.progress.push(progress);
+        }
+    }
+
+    impl TermInfo for ProgressHandler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_progress() {
+        static BYTES: &[u8] = b"\x1b]9;4;1;42\x07\x1b]9;4;2;250\x1b\\\x1b]9;4;3\x07\x1b]9;4;0\x07";
+
+        let mut parser = Processor::new();
+        let mut handler = ProgressHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.progress, vec![
+            Some(Progress::Normal(42)),
+            Some(Progress::Error(100)),
+            Some(Progress::Indeterminate),
+            None,
+        ]);
+    }
+
+    #[derive(Default)]
+    struct NotificationHandler {
+        notifications: Vec<(Option<String>, String)>,
+    }
+
+    impl Handler for NotificationHandler {
+        fn notify(&mut self, title: Option<&str>, body: &str) {
+            self.notifications.push((title.map(String::from), body.to_owned()));
+        }
+    }
+
+    impl TermInfo for NotificationHandler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_notifications() {
+        static BYTES: &[u8] = b"\x1b]9;done; 3 failed\x07\x1b]777;notify;make;built\x1b\\";
+
+        let mut parser = Processor::new();
+        let mut handler = NotificationHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.notifications, vec![
+            (None, String::from("done; 3 failed")),
+            (Some(String::from("make")), String::from("built")),
+        ]);
+    }
+
+    #[derive(Default)]
+    struct HyperlinkHandler {
+        hyperlinks: Vec<Option<Hyperlink>>,
+    }
+
+    impl Handler for HyperlinkHandler {
+        fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+            self.hyperlinks.push(hyperlink);
+        }
+    }
+
+    impl TermInfo for HyperlinkHandler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_hyperlinks() {
+        static BYTES: &[u8] =
+            b"\x1b]8;;https://a.org/?x=1;y=2\x1b\\\x1b]8;;\x07\x1b]8;foo=1:id=item;file:///tmp\x07";
+
+        let mut parser = Processor::new();
+        let mut handler = HyperlinkHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.hyperlinks, vec![
+            Some(Hyperlink { id: None, uri: String::from("https://a.org/?x=1;y=2") }),
+            None,
+            Some(Hyperlink { id: Some(String::from("item")), uri: String::from("file:///tmp") }),
+        ]);
+    }
+
+    #[derive(Default)]
+    struct SixelHandler {
+        images: Vec<SixelImage>,
+    }
+
+    impl Handler for SixelHandler {
+        fn sixel_image(&mut self, image: SixelImage) {
+            self.images.push(image);
+        }
+    }
+
+    impl TermInfo for SixelHandler {
+        fn lines(&self) -> Line {
+            Line(24)
+        }
+
+        fn cols(&self) -> Column {
+            Column(80)
+        }
+    }
+
+    #[test]
+    fn parse_sixel_image() {
+        // DECRQSS shares the final byte but is not an image
+        static BYTES: &[u8] = b"\x1bP$qm\x1b\\\x1bP0;1;0q#1!3~\x1b\\";
+
+        let mut parser = Processor::new();
+        let mut handler = SixelHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.images.len(), 1);
+        let image = &handler.images[0];
+        assert_eq!((image.width, image.height), (3, 6));
+        assert!(image.transparent);
+    }
+
     #[test]
     fn parse_number_too_large() {
         assert_eq!(parse_number(b"321"), None);
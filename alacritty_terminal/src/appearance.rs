@@ -0,0 +1,379 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the light/dark preference of the operating system
+//!
+//! Instead of polling, the monitor waits for the change notification of the platform: the
+//! `SettingChanged` signal of the XDG settings portal, `AppleInterfaceThemeChangedNotification`
+//! on macOS and changes to the `Personalize` registry key on Windows.
+#[cfg(not(windows))]
+use std::process::Command;
+use std::sync::mpsc;
+
+use crate::config::OnConfigReload;
+
+/// Color preference of the operating system
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+/// Watches the system appearance and reports changes to it
+pub struct AppearanceMonitor {
+    _thread: ::std::thread::JoinHandle<()>,
+    rx: mpsc::Receiver<Appearance>,
+}
+
+impl AppearanceMonitor {
+    /// Get the latest appearance change
+    pub fn pending(&self) -> Option<Appearance> {
+        let mut appearance = None;
+        while let Ok(new) = self.rx.try_recv() {
+            appearance = Some(new);
+        }
+
+        appearance
+    }
+
+    /// Start watching the system appearance
+    ///
+    /// The current appearance is always reported as the first change.
+    pub fn new<H>(mut handler: H) -> AppearanceMonitor
+    where
+        H: OnConfigReload + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+
+        AppearanceMonitor {
+            _thread: crate::util::thread::spawn_named("appearance watcher", move || {
+                let mut current = None;
+
+                watch(|appearance| {
+                    if Some(appearance) == current {
+                        return true;
+                    }
+                    current = Some(appearance);
+
+                    if tx.send(appearance).is_err() {
+                        return false;
+                    }
+                    handler.on_config_reload();
+                    true
+                });
+            }),
+            rx,
+        }
+    }
+}
+
+/// Report the current appearance and every change signaled by the settings portal
+///
+/// Without a portal, changes of the GNOME setting are monitored instead. Watching stops once
+/// `report` returns `false`.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn watch<F: FnMut(Appearance) -> bool>(mut report: F) {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let mut monitor = match portal_appearance() {
+        Some(appearance) => {
+            if !report(appearance) {
+                return;
+            }
+
+            let mut monitor = Command::new("gdbus");
+            monitor.args(&[
+                "monitor",
+                "--session",
+                "--dest",
+                "org.freedesktop.portal.Desktop",
+                "--object-path",
+                "/org/freedesktop/portal/desktop",
+            ]);
+            monitor
+        },
+        None => {
+            if !report(gnome_appearance()) {
+                return;
+            }
+
+            let mut monitor = Command::new("gsettings");
+            monitor.args(&["monitor", "org.gnome.desktop.interface", "color-scheme"]);
+            monitor
+        },
+    };
+
+    let mut child = match monitor.stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            warn!("Unable to watch the system appearance: {}", err);
+            return;
+        },
+    };
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            if line.contains("color-scheme") {
+                if let Some(appearance) = parse_color_scheme(&line) {
+                    if !report(appearance) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Read the color scheme from the XDG settings portal
+#[cfg(not(any(target_os = "macos", windows)))]
+fn portal_appearance() -> Option<Appearance> {
+    let output = Command::new("gdbus")
+        .args(&[
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+            "--method",
+            "org.freedesktop.portal.Settings.Read",
+            "org.freedesktop.appearance",
+            "color-scheme",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    parse_color_scheme(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Read the color scheme from the GNOME settings
+#[cfg(not(any(target_os = "macos", windows)))]
+fn gnome_appearance() -> Appearance {
+    Command::new("gsettings")
+        .args(&["get", "org.gnome.desktop.interface", "color-scheme"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_color_scheme(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or(Appearance::Light)
+}
+
+/// Appearance in a portal reply or signal, or in a GNOME setting
+///
+/// The portal reports `uint32 1` for dark and `uint32 2` for light, GNOME uses `'prefer-dark'`.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn parse_color_scheme(text: &str) -> Option<Appearance> {
+    if let Some(index) = text.find("uint32 ") {
+        return match text[index + "uint32 ".len()..].chars().next() {
+            Some('1') => Some(Appearance::Dark),
+            Some(_) => Some(Appearance::Light),
+            None => None,
+        };
+    }
+
+    if text.contains("dark") {
+        Some(Appearance::Dark)
+    } else if text.contains('\'') {
+        Some(Appearance::Light)
+    } else {
+        None
+    }
+}
+
+/// Report the current appearance and every `AppleInterfaceThemeChangedNotification`
+///
+/// Watching stops once `report` returns `false`.
+#[cfg(target_os = "macos")]
+fn watch<F: FnMut(Appearance) -> bool>(mut report: F) {
+    use std::ffi::c_void;
+    use std::ptr;
+
+    use core_foundation_sys::base::CFIndex;
+    use core_foundation_sys::runloop::{CFRunLoopGetCurrent, CFRunLoopRun, CFRunLoopStop};
+    use core_foundation_sys::string::{
+        kCFStringEncodingUTF8, CFStringCreateWithBytes, CFStringRef,
+    };
+
+    type CFNotificationCenterRef = *mut c_void;
+    type CFNotificationCallback = extern "C" fn(
+        CFNotificationCenterRef,
+        *mut c_void,
+        CFStringRef,
+        *const c_void,
+        *const c_void,
+    );
+
+    const DELIVER_IMMEDIATELY: CFIndex = 4;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFNotificationCenterGetDistributedCenter() -> CFNotificationCenterRef;
+        fn CFNotificationCenterAddObserver(
+            center: CFNotificationCenterRef,
+            observer: *const c_void,
+            callback: CFNotificationCallback,
+            name: CFStringRef,
+            object: *const c_void,
+            suspension_behavior: CFIndex,
+        );
+    }
+
+    extern "C" fn on_change<F: FnMut(Appearance) -> bool>(
+        _: CFNotificationCenterRef,
+        observer: *mut c_void,
+        _: CFStringRef,
+        _: *const c_void,
+        _: *const c_void,
+    ) {
+        let report = unsafe { &mut *(observer as *mut F) };
+        if !report(system_appearance()) {
+            unsafe { CFRunLoopStop(CFRunLoopGetCurrent()) };
+        }
+    }
+
+    if !report(system_appearance()) {
+        return;
+    }
+
+    let name = "AppleInterfaceThemeChangedNotification";
+    unsafe {
+        let name = CFStringCreateWithBytes(
+            ptr::null(),
+            name.as_ptr(),
+            name.len() as CFIndex,
+            kCFStringEncodingUTF8,
+            false as _,
+        );
+
+        // The observer outlives the run loop, which only returns once it is stopped
+        CFNotificationCenterAddObserver(
+            CFNotificationCenterGetDistributedCenter(),
+            &mut report as *mut F as *const c_void,
+            on_change::<F>,
+            name,
+            ptr::null(),
+            DELIVER_IMMEDIATELY,
+        );
+        CFRunLoopRun();
+    }
+}
+
+/// `AppleInterfaceStyle` is only present when the dark appearance is active
+#[cfg(target_os = "macos")]
+fn system_appearance() -> Appearance {
+    let output = Command::new("defaults").args(&["read", "-g", "AppleInterfaceStyle"]).output();
+
+    match output {
+        Ok(ref output)
+            if output.status.success()
+                && String::from_utf8_lossy(&output.stdout).contains("Dark") =>
+        {
+            Appearance::Dark
+        },
+        _ => Appearance::Light,
+    }
+}
+
+/// Report the current appearance and every change of the `Personalize` registry key
+///
+/// Watching stops once `report` returns `false`.
+#[cfg(windows)]
+fn watch<F: FnMut(Appearance) -> bool>(mut report: F) {
+    use std::mem;
+    use std::ptr;
+
+    use widestring::U16CString;
+    use winapi::shared::minwindef::{DWORD, FALSE, HKEY};
+    use winapi::shared::winerror::ERROR_SUCCESS;
+    use winapi::um::winnt::{KEY_NOTIFY, KEY_QUERY_VALUE, REG_NOTIFY_CHANGE_LAST_SET};
+    use winapi::um::winreg::{
+        RegCloseKey, RegGetValueW, RegNotifyChangeKeyValue, RegOpenKeyExW, HKEY_CURRENT_USER,
+        RRF_RT_REG_DWORD,
+    };
+
+    let path =
+        U16CString::from_str(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize")
+            .unwrap();
+    let value = U16CString::from_str("AppsUseLightTheme").unwrap();
+
+    let mut key: HKEY = ptr::null_mut();
+    let access = KEY_NOTIFY | KEY_QUERY_VALUE;
+    if unsafe { RegOpenKeyExW(HKEY_CURRENT_USER, path.as_ptr(), 0, access, &mut key) } as DWORD
+        != ERROR_SUCCESS
+    {
+        report(Appearance::Light);
+        return;
+    }
+
+    loop {
+        let mut light: DWORD = 1;
+        let mut size = mem::size_of::<DWORD>() as DWORD;
+        unsafe {
+            RegGetValueW(
+                key,
+                ptr::null(),
+                value.as_ptr(),
+                RRF_RT_REG_DWORD,
+                ptr::null_mut(),
+                &mut light as *mut DWORD as *mut _,
+                &mut size,
+            );
+        }
+
+        let appearance = if light == 0 { Appearance::Dark } else { Appearance::Light };
+        if !report(appearance) {
+            break;
+        }
+
+        // Block until any value of the key changes
+        let status = unsafe {
+            RegNotifyChangeKeyValue(key, FALSE, REG_NOTIFY_CHANGE_LAST_SET, ptr::null_mut(), FALSE)
+        };
+        if status as DWORD != ERROR_SUCCESS {
+            warn!("Unable to watch the system appearance");
+            break;
+        }
+    }
+
+    unsafe { RegCloseKey(key) };
+}
+
+#[cfg(all(test, not(any(target_os = "macos", windows))))]
+mod tests {
+    use super::{parse_color_scheme, Appearance};
+
+    #[test]
+    fn parse_portal_and_gnome_schemes() {
+        assert_eq!(parse_color_scheme("(<<uint32 1>>,)"), Some(Appearance::Dark));
+        assert_eq!(parse_color_scheme("(<<uint32 0>>,)"), Some(Appearance::Light));
+
+        let signal = "/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.\
+                      SettingChanged ('org.freedesktop.appearance', 'color-scheme', <uint32 2>)";
+        assert_eq!(parse_color_scheme(signal), Some(Appearance::Light));
+
+        assert_eq!(parse_color_scheme("color-scheme: 'prefer-dark'"), Some(Appearance::Dark));
+        assert_eq!(parse_color_scheme("'default'"), Some(Appearance::Light));
+        assert_eq!(parse_color_scheme(""), None);
+    }
+}
@@ -14,6 +14,10 @@
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 use std::ffi::c_void;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use std::io::Write;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use std::process::{Command, Stdio};
 
 use copypasta::nop_clipboard::NopClipboardContext;
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
@@ -22,12 +26,20 @@ use copypasta::wayland_clipboard::{
     WaylandClipboardContext,
 };
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-use copypasta::x11_clipboard::{Primary as X11SelectionClipboard, X11ClipboardContext};
+use copypasta::x11_clipboard::{
+    Primary as X11SelectionClipboard, Secondary as X11SecondaryClipboard, X11ClipboardContext,
+};
 use copypasta::{ClipboardContext, ClipboardProvider};
 
+pub use copypasta::RichText;
+
 pub struct Clipboard {
     clipboard: Box<dyn ClipboardProvider>,
     selection: Option<Box<dyn ClipboardProvider>>,
+    secondary: Option<Box<dyn ClipboardProvider>>,
+
+    /// Text last stored in the clipboard and selection, when it needs to be persisted
+    stored: Option<(Option<String>, Option<String>)>,
 }
 
 impl Clipboard {
@@ -54,49 +66,134 @@ impl Clipboard {
                         ),
                     ))
                 },
+                secondary: None,
+                // Wayland clipboard contents are lost when the window is closed
+                stored: Some((None, None)),
             };
         }
 
         Self {
             clipboard: Box::new(ClipboardContext::new().unwrap()),
             selection: Some(Box::new(X11ClipboardContext::<X11SelectionClipboard>::new().unwrap())),
+            secondary: Some(Box::new(X11ClipboardContext::<X11SecondaryClipboard>::new().unwrap())),
+            stored: None,
         }
     }
 
     // Use for tests and ref-tests
     pub fn new_nop() -> Self {
-        Self { clipboard: Box::new(NopClipboardContext::new().unwrap()), selection: None }
+        Self {
+            clipboard: Box::new(NopClipboardContext::new().unwrap()),
+            selection: None,
+            secondary: None,
+            stored: None,
+        }
+    }
+
+    /// Hand the stored text over to `wl-copy`, so it outlives the window
+    ///
+    /// Text is only handed over while it is still the content of the clipboard, this way a
+    /// clipboard manager or other client which took ownership in the meantime is not overridden.
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    pub fn persist(&mut self) {
+        let (clipboard, selection) = match self.stored.take() {
+            Some(stored) => stored,
+            None => return,
+        };
+
+        let targets = [(ClipboardType::Clipboard, clipboard), (ClipboardType::Selection, selection)];
+        for (ty, stored) in targets.iter() {
+            let text = match stored {
+                Some(text) if *text == self.load(*ty) => text,
+                _ => continue,
+            };
+
+            let mut command = Command::new("wl-copy");
+            if let ClipboardType::Selection = ty {
+                command.arg("--primary");
+            }
+
+            let child = command.stdin(Stdio::piped()).stdout(Stdio::null()).spawn();
+            let result = child.and_then(|mut child| {
+                child.stdin.take().unwrap().write_all(text.as_bytes())?;
+                child.wait()
+            });
+
+            if let Err(err) = result {
+                warn!("Unable to persist {:?} contents with wl-copy: {}", ty, err);
+            }
+        }
     }
 }
 
 impl Default for Clipboard {
     fn default() -> Self {
-        Self { clipboard: Box::new(ClipboardContext::new().unwrap()), selection: None }
+        Self {
+            clipboard: Box::new(ClipboardContext::new().unwrap()),
+            selection: None,
+            secondary: None,
+            stored: None,
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ClipboardType {
     Clipboard,
     Selection,
+    /// Secondary selection, only available on X11
+    Secondary,
+}
+
+impl ClipboardType {
+    /// Clipboard named by a selection character of OSC 52
+    pub fn from_selection_char(c: u8) -> Option<ClipboardType> {
+        match c {
+            b'c' => Some(ClipboardType::Clipboard),
+            b'p' | b's' => Some(ClipboardType::Selection),
+            b'q' => Some(ClipboardType::Secondary),
+            _ => None,
+        }
+    }
 }
 
 impl Clipboard {
     pub fn store(&mut self, ty: ClipboardType, text: impl Into<String>) {
-        let clipboard = match (ty, &mut self.selection) {
-            (ClipboardType::Selection, Some(provider)) => provider,
-            (ClipboardType::Selection, None) => return,
-            _ => &mut self.clipboard,
+        let clipboard = match (ty, &mut self.selection, &mut self.secondary) {
+            (ClipboardType::Selection, Some(provider), _) => provider,
+            (ClipboardType::Secondary, _, Some(provider)) => provider,
+            (ClipboardType::Clipboard, ..) => &mut self.clipboard,
+            _ => return,
         };
 
-        clipboard.set_contents(text.into()).unwrap_or_else(|err| {
+        let text = text.into();
+        match (ty, &mut self.stored) {
+            (ClipboardType::Clipboard, Some((stored, _))) => *stored = Some(text.clone()),
+            (ClipboardType::Selection, Some((_, stored))) => *stored = Some(text.clone()),
+            _ => (),
+        }
+
+        clipboard.set_contents(text).unwrap_or_else(|err| {
             warn!("Unable to store text in clipboard: {}", err);
         });
     }
 
+    /// Store text with its HTML and RTF representation in the clipboard
+    pub fn store_rich_text(&mut self, text: RichText) {
+        if let Some((stored, _)) = &mut self.stored {
+            *stored = Some(text.text.clone());
+        }
+
+        self.clipboard.set_rich_contents(text).unwrap_or_else(|err| {
+            warn!("Unable to store rich text in clipboard: {}", err);
+        });
+    }
+
     pub fn load(&mut self, ty: ClipboardType) -> String {
-        let clipboard = match (ty, &mut self.selection) {
-            (ClipboardType::Selection, Some(provider)) => provider,
+        let clipboard = match (ty, &mut self.selection, &mut self.secondary) {
+            (ClipboardType::Selection, Some(provider), _) => provider,
+            (ClipboardType::Secondary, _, Some(provider)) => provider,
+            (ClipboardType::Secondary, _, None) => return String::new(),
             _ => &mut self.clipboard,
         };
 
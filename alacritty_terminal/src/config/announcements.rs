@@ -0,0 +1,33 @@
+use crate::config::{failure_default, Shell};
+
+/// Settings for announcing completed output lines
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Announcements {
+    /// Command receiving the completed lines on its standard input
+    #[serde(deserialize_with = "failure_default")]
+    pub command: Option<Shell<'static>>,
+
+    /// Maximum number of lines announced per second
+    #[serde(deserialize_with = "failure_default")]
+    max_lines_per_second: MaxLines,
+
+    /// Lines containing any of these strings are not announced
+    #[serde(deserialize_with = "failure_default")]
+    pub ignore: Vec<String>,
+}
+
+impl Announcements {
+    pub fn max_lines_per_second(&self) -> usize {
+        self.max_lines_per_second.0
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+struct MaxLines(usize);
+
+impl Default for MaxLines {
+    fn default() -> Self {
+        MaxLines(5)
+    }
+}
@@ -60,6 +60,22 @@ macro_rules! bindings {
     }}
 }
 
+/// Pairs of bindings which are triggered by the same input
+///
+/// Bindings with identical triggers are not reported, since they are used to run multiple actions
+/// with a single input. The first index of each pair is always the lower one.
+pub fn conflicts<T: Eq>(bindings: &[Binding<T>]) -> Vec<(usize, usize)> {
+    let mut conflicts = Vec::new();
+    for (i, binding) in bindings.iter().enumerate() {
+        for (j, other) in bindings.iter().enumerate().skip(i + 1) {
+            if binding.conflicts_with(other) && !binding.triggers_match(other) {
+                conflicts.push((i, j));
+            }
+        }
+    }
+    conflicts
+}
+
 pub fn default_mouse_bindings() -> Vec<MouseBinding> {
     bindings!(
         MouseBinding;
@@ -175,7 +191,8 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         Key::F10, [logo: true]; Action::Esc("\x1b[21;3~".into());
         Key::F11, [logo: true]; Action::Esc("\x1b[23;3~".into());
         Key::F12, [logo: true]; Action::Esc("\x1b[24;3~".into());
-        Key::NumpadEnter; Action::Esc("\n".into());
+        Key::NumpadEnter, ~TermMode::APP_KEYPAD; Action::Esc("\n".into());
+        Key::NumpadEnter, +TermMode::APP_KEYPAD; Action::Esc("\x1bOM".into());
     );
 
     bindings.extend(platform_key_bindings());
@@ -195,6 +212,8 @@ fn common_keybindings() -> Vec<KeyBinding> {
         Key::Add, [ctrl: true]; Action::IncreaseFontSize;
         Key::Subtract, [ctrl: true]; Action::DecreaseFontSize;
         Key::Minus, [ctrl: true]; Action::DecreaseFontSize;
+        Key::F, [ctrl: true, shift: true]; Action::Search;
+        Key::U, [ctrl: true, shift: true]; Action::ShowHints;
     )
 }
 
@@ -208,6 +227,8 @@ pub fn platform_key_bindings() -> Vec<KeyBinding> {
     let mut bindings = bindings!(
         KeyBinding;
         Key::Return, [alt: true]; Action::ToggleFullscreen;
+        Key::Space, [ctrl: true]; Action::Esc("\x00".into());
+        Key::Key2, [ctrl: true, shift: true]; Action::Esc("\x00".into());
     );
     bindings.extend(common_keybindings());
     bindings
@@ -226,6 +247,8 @@ pub fn platform_key_bindings() -> Vec<KeyBinding> {
         Key::K, [logo: true]; Action::Esc("\x0c".into());
         Key::V, [logo: true]; Action::Paste;
         Key::C, [logo: true]; Action::Copy;
+        Key::F, [logo: true]; Action::Search;
+        Key::U, [logo: true, shift: true]; Action::ShowHints;
         Key::H, [logo: true]; Action::Hide;
         Key::Q, [logo: true]; Action::Quit;
         Key::W, [logo: true]; Action::Quit;
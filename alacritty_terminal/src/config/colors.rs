@@ -1,11 +1,21 @@
 use serde::{Deserialize, Deserializer};
 
+use crate::appearance::Appearance;
 use crate::config::failure_default;
 use crate::term::color::Rgb;
 
 #[serde(default)]
-#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Colors {
+    /// Name of the entry in `schemes` which replaces these colors
+    #[serde(deserialize_with = "failure_default")]
+    pub scheme: Option<String>,
+    /// Scheme used while the system prefers a light appearance
+    #[serde(deserialize_with = "failure_default")]
+    pub light_scheme: Option<String>,
+    /// Scheme used while the system prefers a dark appearance
+    #[serde(deserialize_with = "failure_default")]
+    pub dark_scheme: Option<String>,
     #[serde(deserialize_with = "failure_default")]
     pub primary: PrimaryColors,
     #[serde(deserialize_with = "failure_default")]
@@ -30,10 +40,23 @@ impl Colors {
     pub fn bright(&self) -> &AnsiColors {
         &self.bright.0
     }
+
+    /// Scheme configured for the system appearance
+    pub fn scheme_for(&self, appearance: Appearance) -> Option<&String> {
+        match appearance {
+            Appearance::Light => self.light_scheme.as_ref(),
+            Appearance::Dark => self.dark_scheme.as_ref(),
+        }
+    }
+
+    /// Follow changes to the system appearance
+    pub fn follows_appearance(&self) -> bool {
+        self.light_scheme.is_some() || self.dark_scheme.is_some()
+    }
 }
 
 #[serde(default)]
-#[derive(Deserialize, Default, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Default, Clone, Debug, PartialEq, Eq)]
 pub struct IndexedColor {
     #[serde(deserialize_with = "deserialize_color_index")]
     pub index: u8,
@@ -89,7 +112,7 @@ pub struct SelectionColors {
 }
 
 #[serde(default)]
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct PrimaryColors {
     #[serde(default = "default_background", deserialize_with = "failure_default")]
     pub background: Rgb,
@@ -99,6 +122,9 @@ pub struct PrimaryColors {
     pub bright_foreground: Option<Rgb>,
     #[serde(deserialize_with = "failure_default")]
     pub dim_foreground: Option<Rgb>,
+    /// Color of the padding around the grid, the background color is used if unset
+    #[serde(deserialize_with = "failure_default")]
+    pub padding: Option<Rgb>,
 }
 
 impl Default for PrimaryColors {
@@ -108,20 +134,21 @@ impl Default for PrimaryColors {
             foreground: default_foreground(),
             bright_foreground: Default::default(),
             dim_foreground: Default::default(),
+            padding: Default::default(),
         }
     }
 }
 
 fn default_background() -> Rgb {
-    Rgb { r: 0, g: 0, b: 0 }
+    Rgb::new(0, 0, 0)
 }
 
 fn default_foreground() -> Rgb {
-    Rgb { r: 0xea, g: 0xea, b: 0xea }
+    Rgb::new(0xea, 0xea, 0xea)
 }
 
 /// The 8-colors sections of config
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct AnsiColors {
     #[serde(deserialize_with = "failure_default")]
     pub black: Rgb,
@@ -141,38 +168,38 @@ pub struct AnsiColors {
     pub white: Rgb,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 struct NormalColors(AnsiColors);
 
 impl Default for NormalColors {
     fn default() -> Self {
         NormalColors(AnsiColors {
-            black: Rgb { r: 0x00, g: 0x00, b: 0x00 },
-            red: Rgb { r: 0xd5, g: 0x4e, b: 0x53 },
-            green: Rgb { r: 0xb9, g: 0xca, b: 0x4a },
-            yellow: Rgb { r: 0xe6, g: 0xc5, b: 0x47 },
-            blue: Rgb { r: 0x7a, g: 0xa6, b: 0xda },
-            magenta: Rgb { r: 0xc3, g: 0x97, b: 0xd8 },
-            cyan: Rgb { r: 0x70, g: 0xc0, b: 0xba },
-            white: Rgb { r: 0xea, g: 0xea, b: 0xea },
+            black: Rgb::new(0x00, 0x00, 0x00),
+            red: Rgb::new(0xd5, 0x4e, 0x53),
+            green: Rgb::new(0xb9, 0xca, 0x4a),
+            yellow: Rgb::new(0xe6, 0xc5, 0x47),
+            blue: Rgb::new(0x7a, 0xa6, 0xda),
+            magenta: Rgb::new(0xc3, 0x97, 0xd8),
+            cyan: Rgb::new(0x70, 0xc0, 0xba),
+            white: Rgb::new(0xea, 0xea, 0xea),
         })
     }
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 struct BrightColors(AnsiColors);
 
 impl Default for BrightColors {
     fn default() -> Self {
         BrightColors(AnsiColors {
-            black: Rgb { r: 0x66, g: 0x66, b: 0x66 },
-            red: Rgb { r: 0xff, g: 0x33, b: 0x34 },
-            green: Rgb { r: 0x9e, g: 0xc4, b: 0x00 },
-            yellow: Rgb { r: 0xe7, g: 0xc5, b: 0x47 },
-            blue: Rgb { r: 0x7a, g: 0xa6, b: 0xda },
-            magenta: Rgb { r: 0xb7, g: 0x7e, b: 0xe0 },
-            cyan: Rgb { r: 0x54, g: 0xce, b: 0xd6 },
-            white: Rgb { r: 0xff, g: 0xff, b: 0xff },
+            black: Rgb::new(0x66, 0x66, 0x66),
+            red: Rgb::new(0xff, 0x33, 0x34),
+            green: Rgb::new(0x9e, 0xc4, 0x00),
+            yellow: Rgb::new(0xe7, 0xc5, 0x47),
+            blue: Rgb::new(0x7a, 0xa6, 0xda),
+            magenta: Rgb::new(0xb7, 0x7e, 0xe0),
+            cyan: Rgb::new(0x54, 0xce, 0xd6),
+            white: Rgb::new(0xff, 0xff, 0xff),
         })
     }
 }
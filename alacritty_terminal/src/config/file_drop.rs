@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+
+use crate::config::failure_default;
+
+/// Characters which have a special meaning in POSIX shells
+const SHELL_SPECIAL_CHARS: &str = " \t\n!\"#$&'()*;<=>?[\\]^`{|}~";
+
+/// Settings for pasting the paths of files dropped on the window
+#[serde(default)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FileDrop {
+    /// Quoting applied to every dropped path
+    #[serde(deserialize_with = "failure_default")]
+    pub quoting: Quoting,
+
+    /// Text inserted between the paths of multiple files dropped at once
+    #[serde(deserialize_with = "failure_default")]
+    pub separator: String,
+}
+
+impl Default for FileDrop {
+    fn default() -> FileDrop {
+        FileDrop { quoting: Default::default(), separator: String::from(" ") }
+    }
+}
+
+impl FileDrop {
+    /// Join and quote the dropped paths
+    pub fn format<'a, I>(&self, paths: I) -> String
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let paths: Vec<_> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let quoted = self.quoting.quote(path);
+                if quoted.is_none() {
+                    warn!("Ignoring dropped path with control characters: {:?}", path);
+                }
+                quoted
+            })
+            .collect();
+        paths.join(&self.separator)
+    }
+}
+
+/// Quoting style for dropped paths
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Quoting {
+    /// Paste paths unmodified
+    None,
+    /// Escape special characters with a backslash
+    Backslash,
+    /// Wrap paths with special characters in single quotes
+    Single,
+    /// Wrap paths with special characters in double quotes
+    Double,
+}
+
+impl Default for Quoting {
+    fn default() -> Self {
+        Quoting::Single
+    }
+}
+
+impl Quoting {
+    /// Escape a path so it is interpreted as a single word by the shell
+    ///
+    /// Paths with control characters are rejected, since the shell receives those like keys such
+    /// as Enter or Control+C no matter how they are quoted.
+    pub fn quote(self, path: &str) -> Option<Cow<'_, str>> {
+        if path.chars().any(char::is_control) {
+            return None;
+        }
+
+        if self == Quoting::None || !path.chars().any(|c| SHELL_SPECIAL_CHARS.contains(c)) {
+            return Some(Cow::Borrowed(path));
+        }
+
+        let mut quoted = String::with_capacity(path.len() + 2);
+        match self {
+            Quoting::None => unreachable!(),
+            Quoting::Backslash => {
+                for c in path.chars() {
+                    if SHELL_SPECIAL_CHARS.contains(c) {
+                        quoted.push('\\');
+                    }
+                    quoted.push(c);
+                }
+            },
+            Quoting::Single => {
+                quoted.push('\'');
+                quoted.push_str(&path.replace('\'', "'\\''"));
+                quoted.push('\'');
+            },
+            Quoting::Double => {
+                quoted.push('"');
+                for c in path.chars() {
+                    if "\"$`\\".contains(c) {
+                        quoted.push('\\');
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('"');
+            },
+        }
+
+        Some(Cow::Owned(quoted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileDrop, Quoting};
+
+    #[test]
+    fn plain_path_is_not_quoted() {
+        for quoting in &[Quoting::None, Quoting::Backslash, Quoting::Single, Quoting::Double] {
+            assert_eq!(quoting.quote("/tmp/file.txt").unwrap(), "/tmp/file.txt");
+        }
+    }
+
+    #[test]
+    fn quote_spaces() {
+        assert_eq!(Quoting::None.quote("/tmp/a file").unwrap(), "/tmp/a file");
+        assert_eq!(Quoting::Backslash.quote("/tmp/a file").unwrap(), "/tmp/a\\ file");
+        assert_eq!(Quoting::Single.quote("/tmp/a file").unwrap(), "'/tmp/a file'");
+        assert_eq!(Quoting::Double.quote("/tmp/a file").unwrap(), "\"/tmp/a file\"");
+    }
+
+    #[test]
+    fn quote_quotes() {
+        assert_eq!(Quoting::Backslash.quote("it's \"x\"").unwrap(), "it\\'s\\ \\\"x\\\"");
+        assert_eq!(Quoting::Single.quote("it's").unwrap(), "'it'\\''s'");
+        assert_eq!(Quoting::Double.quote("say \"$HOME\"").unwrap(), "\"say \\\"\\$HOME\\\"\"");
+    }
+
+    #[test]
+    fn quote_unicode() {
+        assert_eq!(Quoting::Single.quote("/tmp/日本語").unwrap(), "/tmp/日本語");
+        assert_eq!(Quoting::Single.quote("/tmp/日本 語").unwrap(), "'/tmp/日本 語'");
+        assert_eq!(Quoting::Backslash.quote("/tmp/🦀 🦀").unwrap(), "/tmp/🦀\\ 🦀");
+    }
+
+    #[test]
+    fn join_multiple_paths() {
+        let file_drop = FileDrop::default();
+        assert_eq!(file_drop.format(vec!["/tmp/a", "/tmp/b c"]), "/tmp/a '/tmp/b c'");
+
+        let file_drop = FileDrop { quoting: Quoting::Backslash, separator: String::from("\n") };
+        assert_eq!(file_drop.format(vec!["/tmp/a", "/tmp/b c"]), "/tmp/a\n/tmp/b\\ c");
+    }
+
+    #[test]
+    fn reject_control_chars() {
+        for quoting in &[Quoting::None, Quoting::Backslash, Quoting::Single, Quoting::Double] {
+            assert_eq!(quoting.quote("/tmp/a\rb"), None);
+            assert_eq!(quoting.quote("/tmp/\x1b[201~"), None);
+            assert_eq!(quoting.quote("/tmp/\x03"), None);
+        }
+
+        let file_drop = FileDrop::default();
+        assert_eq!(file_drop.format(vec!["/tmp/a", "/tmp/b\nrm -rf ~"]), "/tmp/a");
+    }
+}
@@ -1,12 +1,11 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
-use font::Size;
+use font::{Size, Variation};
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer};
 
-#[cfg(target_os = "macos")]
-use crate::config::DefaultTrueBool;
-use crate::config::{failure_default, Delta};
+use crate::config::{failure_default, DefaultTrueBool, Delta};
 
 /// Font config
 ///
@@ -29,6 +28,10 @@ pub struct Font {
     #[serde(deserialize_with = "failure_default")]
     bold: SecondaryFontDescription,
 
+    /// Fonts tried in order when the font face has no glyph for a character
+    #[serde(deserialize_with = "failure_default")]
+    fallback: Vec<FontDescription>,
+
     /// Font size in points
     #[serde(deserialize_with = "DeserializeSize::deserialize")]
     pub size: Size,
@@ -41,11 +44,35 @@ pub struct Font {
     #[serde(deserialize_with = "failure_default")]
     pub glyph_offset: Delta<i8>,
 
+    /// Antialiasing of the glyph edges
+    #[serde(deserialize_with = "failure_default")]
+    pub rendering_mode: RenderingMode,
+
+    /// Generate box drawing, block and powerline characters instead of using the font
+    #[serde(deserialize_with = "failure_default")]
+    builtin_box_drawing: DefaultTrueBool,
+
     #[cfg(target_os = "macos")]
     #[serde(deserialize_with = "failure_default")]
     use_thin_strokes: DefaultTrueBool,
 }
 
+/// Antialiasing used for glyphs
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum RenderingMode {
+    /// Separate coverage for every color channel, as rasterized by the platform
+    Subpixel,
+
+    /// Same coverage for all color channels
+    Grayscale,
+}
+
+impl Default for RenderingMode {
+    fn default() -> RenderingMode {
+        RenderingMode::Subpixel
+    }
+}
+
 impl Default for Font {
     fn default() -> Font {
         Font {
@@ -53,8 +80,11 @@ impl Default for Font {
             normal: Default::default(),
             bold: Default::default(),
             italic: Default::default(),
+            fallback: Default::default(),
             glyph_offset: Default::default(),
             offset: Default::default(),
+            rendering_mode: Default::default(),
+            builtin_box_drawing: Default::default(),
             #[cfg(target_os = "macos")]
             use_thin_strokes: Default::default(),
         }
@@ -82,6 +112,15 @@ impl Font {
         self.bold.desc(&self.normal)
     }
 
+    // Get fallback font descriptions
+    pub fn fallback(&self) -> &[FontDescription] {
+        &self.fallback
+    }
+
+    pub fn builtin_box_drawing(&self) -> bool {
+        self.builtin_box_drawing.0
+    }
+
     #[cfg(target_os = "macos")]
     pub fn use_thin_strokes(&self) -> bool {
         self.use_thin_strokes.0
@@ -105,6 +144,8 @@ pub struct FontDescription {
     pub family: String,
     #[serde(deserialize_with = "failure_default")]
     pub style: Option<String>,
+    #[serde(deserialize_with = "failure_default")]
+    pub variations: FontVariations,
 }
 
 impl Default for FontDescription {
@@ -117,6 +158,7 @@ impl Default for FontDescription {
             #[cfg(windows)]
             family: "Consolas".into(),
             style: None,
+            variations: Default::default(),
         }
     }
 }
@@ -129,6 +171,8 @@ pub struct SecondaryFontDescription {
     family: Option<String>,
     #[serde(deserialize_with = "failure_default")]
     style: Option<String>,
+    #[serde(deserialize_with = "failure_default")]
+    variations: FontVariations,
 }
 
 impl SecondaryFontDescription {
@@ -136,7 +180,34 @@ impl SecondaryFontDescription {
         FontDescription {
             family: self.family.clone().unwrap_or_else(|| fallback.family.clone()),
             style: self.style.clone(),
+            variations: self.variations.clone(),
+        }
+    }
+}
+
+/// Values for the design axes of a variable font, keyed by their four character tag
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FontVariations(pub Vec<Variation>);
+
+impl<'de> Deserialize<'de> for FontVariations {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let values = BTreeMap::<String, f32>::deserialize(deserializer)?;
+
+        let mut variations = Vec::with_capacity(values.len());
+        for (tag, value) in values {
+            if tag.len() == 4 {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(tag.as_bytes());
+                variations.push(Variation::new(bytes, value));
+            } else {
+                error!("Problem with config: font variation axis {:?} is not a valid tag", tag);
+            }
         }
+
+        Ok(FontVariations(variations))
     }
 }
 
@@ -0,0 +1,45 @@
+use crate::config::{failure_default, Shell};
+
+/// Text labeled by the `ShowHints` action, in addition to URLs and file paths
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Hints {
+    #[serde(deserialize_with = "failure_default")]
+    pub enabled: Vec<HintRule>,
+}
+
+/// Regex whose matches are labeled, with the action for a selected match
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct HintRule {
+    #[serde(deserialize_with = "failure_default")]
+    pub regex: String,
+
+    #[serde(deserialize_with = "failure_default")]
+    pub action: HintAction,
+
+    /// Program for the `Open` and `Pipe` actions
+    #[serde(deserialize_with = "failure_default")]
+    pub command: Option<Shell<'static>>,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HintAction {
+    /// Store the match in the clipboard
+    Copy,
+
+    /// Paste the match into the terminal
+    Paste,
+
+    /// Run the command with the match as its last argument
+    Open,
+
+    /// Write the match to the standard input of the command
+    Pipe,
+}
+
+impl Default for HintAction {
+    fn default() -> HintAction {
+        HintAction::Copy
+    }
+}
@@ -18,29 +18,52 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Deserializer};
 
-mod bindings;
+mod announcements;
+pub mod bindings;
 mod colors;
 mod debug;
+mod file_drop;
 mod font;
+mod hints;
 mod monitor;
 mod mouse;
+mod notifications;
+mod output_filters;
+mod paste;
+mod render;
 mod scrolling;
+mod security;
+mod shutdown;
+mod status_line;
 #[cfg(test)]
 mod test;
 mod visual_bell;
+mod watch;
 mod window;
 
 use crate::ansi::CursorStyle;
 use crate::input::{Binding, KeyBinding, MouseBinding};
+use crate::util;
 
+pub use crate::config::announcements::Announcements;
 pub use crate::config::bindings::Key;
 pub use crate::config::colors::Colors;
 pub use crate::config::debug::Debug;
-pub use crate::config::font::{Font, FontDescription};
-pub use crate::config::monitor::Monitor;
+pub use crate::config::file_drop::{FileDrop, Quoting};
+pub use crate::config::font::{Font, FontDescription, RenderingMode};
+pub use crate::config::hints::{HintAction, HintRule, Hints};
+pub use crate::config::monitor::{Monitor, OnConfigReload};
 pub use crate::config::mouse::{ClickHandler, Mouse};
+pub use crate::config::notifications::Notifications;
+pub use crate::config::output_filters::{OutputFilter, OutputFilters};
+pub use crate::config::paste::Paste;
+pub use crate::config::render::Render;
 pub use crate::config::scrolling::Scrolling;
+pub use crate::config::security::{Security, SecurityMode};
+pub use crate::config::shutdown::Shutdown;
+pub use crate::config::status_line::StatusLine;
 pub use crate::config::visual_bell::{VisualBellAnimation, VisualBellConfig};
+pub use crate::config::watch::WatchRule;
 pub use crate::config::window::{Decorations, Dimensions, StartupMode, WindowConfig};
 
 pub static DEFAULT_ALACRITTY_CONFIG: &str =
@@ -58,6 +81,14 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default")]
     pub env: HashMap<String, String>,
 
+    /// Variables removed from the environment of the shell
+    #[serde(default, deserialize_with = "failure_default")]
+    pub env_remove: Vec<String>,
+
+    /// Pass variables only meant for Alacritty on to the shell
+    #[serde(default, deserialize_with = "failure_default")]
+    pub hold_env: bool,
+
     /// Font configuration
     #[serde(default, deserialize_with = "failure_default")]
     pub font: Font,
@@ -69,6 +100,10 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default")]
     pub colors: Colors,
 
+    /// Named color schemes which can replace `colors`
+    #[serde(default, deserialize_with = "failure_default")]
+    pub schemes: HashMap<String, Colors>,
+
     /// Background opacity from 0.0 to 1.0
     #[serde(default, deserialize_with = "failure_default")]
     background_opacity: Alpha,
@@ -91,6 +126,14 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default")]
     pub mouse: Mouse,
 
+    /// Pasting of files dropped on the window
+    #[serde(default, deserialize_with = "failure_default")]
+    pub file_drop: FileDrop,
+
+    /// Filtering and confirmation of pasted text
+    #[serde(default, deserialize_with = "failure_default")]
+    pub paste: Paste,
+
     /// Path to a shell program to run on startup
     #[serde(default, deserialize_with = "failure_default")]
     pub shell: Option<Shell<'static>>,
@@ -103,6 +146,34 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default")]
     pub visual_bell: VisualBellConfig,
 
+    /// Announcement of completed output lines
+    #[serde(default, deserialize_with = "failure_default")]
+    pub announcements: Announcements,
+
+    /// Bar with information about the terminal below the grid
+    #[serde(default, deserialize_with = "failure_default")]
+    pub status_line: StatusLine,
+
+    /// Desktop notifications requested by applications
+    #[serde(default, deserialize_with = "failure_default")]
+    pub notifications: Notifications,
+
+    /// Rules rewriting or highlighting the output
+    #[serde(default, deserialize_with = "failure_default")]
+    pub output_filters: OutputFilters,
+
+    /// Actions triggered by output lines
+    #[serde(default, deserialize_with = "failure_default")]
+    pub watch: Vec<WatchRule>,
+
+    /// Regexes labeled in hint mode
+    #[serde(default, deserialize_with = "failure_default")]
+    pub hints: Hints,
+
+    /// Restrictions for escape sequences
+    #[serde(default, deserialize_with = "failure_default")]
+    pub security: Security,
+
     /// Use dynamic title
     #[serde(default, deserialize_with = "failure_default")]
     dynamic_title: DefaultTrueBool,
@@ -137,6 +208,14 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default")]
     working_directory: WorkingDirectory,
 
+    /// Termination of the shell when the window is closed
+    #[serde(default, deserialize_with = "failure_default")]
+    pub shutdown: Shutdown,
+
+    /// Rendering options
+    #[serde(default, deserialize_with = "failure_default")]
+    pub render: Render,
+
     /// Debug options
     #[serde(default, deserialize_with = "failure_default")]
     pub debug: Debug,
@@ -219,6 +298,31 @@ impl Config {
     pub fn set_working_directory(&mut self, working_directory: Option<PathBuf>) {
         self.working_directory.0 = working_directory;
     }
+
+    /// Names of all available color schemes, in alphabetical order
+    pub fn color_schemes(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.schemes.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Replace the active colors with the color scheme `name`
+    ///
+    /// The current colors are left untouched if there is no scheme with this name.
+    pub fn set_color_scheme(&mut self, name: &str) {
+        match self.schemes.get(name) {
+            Some(colors) => {
+                let light_scheme = self.colors.light_scheme.take();
+                let dark_scheme = self.colors.dark_scheme.take();
+
+                self.colors = colors.clone();
+                self.colors.scheme = Some(name.to_owned());
+                self.colors.light_scheme = light_scheme;
+                self.colors.dark_scheme = dark_scheme;
+            },
+            None => error!("Problem with config: no color scheme named {:?}; ignoring it", name),
+        }
+    }
 }
 
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -237,7 +341,15 @@ impl<'de> Deserialize<'de> for WorkingDirectory {
         }
 
         Ok(match PathBuf::deserialize(value) {
-            Ok(path) => WorkingDirectory(Some(path)),
+            Ok(path) => {
+                let path = util::expand_tilde(path);
+                if path.is_dir() {
+                    WorkingDirectory(Some(path))
+                } else {
+                    error!("Problem with config: {:?} is not a directory; using None", path);
+                    WorkingDirectory(None)
+                }
+            },
             Err(err) => {
                 error!("Problem with config: {}; using None", err);
                 WorkingDirectory(None)
@@ -295,6 +407,8 @@ pub struct Selection {
     semantic_escape_chars: EscapeChars,
     #[serde(deserialize_with = "failure_default")]
     pub save_to_clipboard: bool,
+    #[serde(deserialize_with = "failure_default")]
+    pub persist_on_exit: bool,
 }
 
 impl Selection {
@@ -333,7 +447,7 @@ impl Cursor {
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct Shell<'a> {
     pub program: Cow<'a, str>,
 
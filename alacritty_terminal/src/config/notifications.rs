@@ -0,0 +1,35 @@
+use crate::config::failure_default;
+
+/// Settings for desktop notifications requested by applications
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Notifications {
+    /// Show notifications requested with OSC 9 and OSC 777
+    #[serde(deserialize_with = "failure_default")]
+    enabled: bool,
+
+    /// Maximum number of notifications with the same title per minute
+    #[serde(deserialize_with = "failure_default")]
+    max_per_minute: MaxNotifications,
+}
+
+impl Notifications {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn max_per_minute(&self) -> usize {
+        self.max_per_minute.0
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+struct MaxNotifications(usize);
+
+impl Default for MaxNotifications {
+    fn default() -> Self {
+        MaxNotifications(5)
+    }
+}
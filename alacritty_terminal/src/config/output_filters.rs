@@ -0,0 +1,43 @@
+use crate::config::{failure_default, DefaultTrueBool};
+use crate::term::color::Rgb;
+
+/// Rules rewriting or highlighting output lines
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct OutputFilters {
+    /// Apply the filters on startup
+    #[serde(deserialize_with = "failure_default")]
+    enabled: DefaultTrueBool,
+
+    #[serde(deserialize_with = "failure_default")]
+    pub rules: Vec<OutputFilter>,
+}
+
+impl OutputFilters {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled.0
+    }
+}
+
+/// Single filter matching a regex against every completed row
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct OutputFilter {
+    #[serde(deserialize_with = "failure_default")]
+    pub regex: String,
+
+    /// Text replacing the match, can reference capture groups like `$1`
+    #[serde(deserialize_with = "failure_default")]
+    pub replacement: Option<String>,
+
+    #[serde(deserialize_with = "failure_default")]
+    pub foreground: Option<Rgb>,
+
+    #[serde(deserialize_with = "failure_default")]
+    pub background: Option<Rgb>,
+
+    /// Apply the colors to the entire row instead of the match
+    #[serde(deserialize_with = "failure_default")]
+    pub whole_line: bool,
+}
@@ -0,0 +1,25 @@
+use crate::config::{failure_default, DefaultTrueBool};
+
+/// Filtering and confirmation of pasted text
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Paste {
+    /// Remove control characters other than tabs and line breaks
+    #[serde(deserialize_with = "failure_default")]
+    filter_control_chars: DefaultTrueBool,
+
+    /// Hold back pastes with line breaks outside of bracketed paste mode until they're repeated
+    #[serde(deserialize_with = "failure_default")]
+    pub confirm_multiline: bool,
+
+    /// Hold back pastes with more bytes until they're repeated, `0` disables this
+    #[serde(deserialize_with = "failure_default")]
+    pub confirm_bytes: usize,
+}
+
+impl Paste {
+    #[inline]
+    pub fn filter_control_chars(&self) -> bool {
+        self.filter_control_chars.0
+    }
+}
@@ -0,0 +1,18 @@
+use crate::config::failure_default;
+
+/// Rendering options
+#[serde(default)]
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Render {
+    /// Maximum number of frames drawn per second
+    #[serde(deserialize_with = "failure_default")]
+    max_fps: Option<u32>,
+}
+
+impl Render {
+    /// Frame rate limit, `0` disables the limit
+    #[inline]
+    pub fn max_fps(self) -> Option<u32> {
+        self.max_fps.filter(|&fps| fps > 0)
+    }
+}
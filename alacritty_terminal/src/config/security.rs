@@ -0,0 +1,34 @@
+use crate::config::failure_default;
+
+/// Restrictions for escape sequences sent by applications
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Security {
+    /// Escape sequences which are allowed on startup
+    #[serde(deserialize_with = "failure_default")]
+    pub mode: SecurityMode,
+
+    /// Reply to ENQ, nothing is sent back when it is empty
+    #[serde(deserialize_with = "failure_default")]
+    pub answerback: String,
+
+    /// Allow applications to read the clipboard with OSC 52
+    #[serde(deserialize_with = "failure_default")]
+    pub clipboard_read: bool,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SecurityMode {
+    /// Allow all supported escape sequences
+    #[serde(rename = "normal")]
+    Normal,
+    /// Ignore escape sequences which leak data or have side effects outside the terminal
+    #[serde(rename = "paranoid")]
+    Paranoid,
+}
+
+impl Default for SecurityMode {
+    fn default() -> SecurityMode {
+        SecurityMode::Normal
+    }
+}
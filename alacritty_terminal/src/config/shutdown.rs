@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use crate::config::failure_default;
+
+/// Termination of the shell when the window is closed
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Shutdown {
+    /// Milliseconds the process group of the shell has to exit after SIGHUP
+    #[serde(deserialize_with = "failure_default")]
+    kill_timeout: u32,
+}
+
+impl Shutdown {
+    /// Time to wait before the process group is killed, `None` if it's not waited for
+    #[inline]
+    pub fn kill_timeout(&self) -> Option<Duration> {
+        if self.kill_timeout == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(u64::from(self.kill_timeout)))
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::config::failure_default;
+use crate::term::color::Rgb;
+
+/// Single line bar shown below the terminal grid
+#[serde(default)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StatusLine {
+    /// Show the status line
+    #[serde(deserialize_with = "failure_default")]
+    pub enabled: bool,
+
+    /// Template for the text on the left side
+    #[serde(deserialize_with = "failure_default")]
+    pub format: String,
+
+    /// Background color of the status line
+    #[serde(deserialize_with = "failure_default")]
+    pub background: Rgb,
+}
+
+impl Default for StatusLine {
+    fn default() -> StatusLine {
+        StatusLine {
+            enabled: false,
+            format: String::from("{title}"),
+            background: Rgb::new(0xc5, 0xc8, 0xc6),
+        }
+    }
+}
+
+impl StatusLine {
+    /// Expand the placeholders of the format template
+    pub fn expand(&self, title: &str, columns: usize, lines: usize) -> String {
+        self.format
+            .replace("{title}", title)
+            .replace("{columns}", &columns.to_string())
+            .replace("{lines}", &lines.to_string())
+    }
+}
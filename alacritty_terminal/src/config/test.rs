@@ -1,4 +1,5 @@
 use crate::config::{Config, DEFAULT_ALACRITTY_CONFIG};
+use crate::term::color::Rgb;
 
 #[test]
 fn parse_config() {
@@ -20,3 +21,20 @@ fn default_match_empty() {
 
     assert_eq!(default, empty);
 }
+
+#[test]
+fn set_color_scheme() {
+    let mut config: Config = serde_yaml::from_str(
+        "schemes:\n  light:\n    primary:\n      background: '0xffffff'\n      foreground: \
+         '0x000000'\n",
+    )
+    .unwrap();
+
+    config.set_color_scheme("light");
+    assert_eq!(config.colors.scheme, Some(String::from("light")));
+    assert_eq!(config.colors.primary.background, Rgb::new(0xff, 0xff, 0xff));
+
+    // Unknown schemes leave the colors untouched
+    config.set_color_scheme("missing");
+    assert_eq!(config.colors.scheme, Some(String::from("light")));
+}
@@ -60,5 +60,5 @@ impl Default for VisualBellAnimation {
 }
 
 fn default_visual_bell_color() -> Rgb {
-    Rgb { r: 255, g: 255, b: 255 }
+    Rgb::new(255, 255, 255)
 }
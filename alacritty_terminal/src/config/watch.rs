@@ -0,0 +1,45 @@
+use crate::config::{failure_default, Shell};
+
+/// Actions triggered when a completed output line matches a regex
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct WatchRule {
+    #[serde(deserialize_with = "failure_default")]
+    pub regex: String,
+
+    /// Show a desktop notification with the matching line
+    #[serde(deserialize_with = "failure_default")]
+    pub notify: bool,
+
+    /// Ring the bell
+    #[serde(deserialize_with = "failure_default")]
+    pub bell: bool,
+
+    /// Attach a mark to the matching row
+    #[serde(deserialize_with = "failure_default")]
+    pub mark: bool,
+
+    /// Command started with the matching line as its last argument
+    #[serde(deserialize_with = "failure_default")]
+    pub command: Option<Shell<'static>>,
+
+    /// Maximum number of times the rule is triggered per minute
+    #[serde(deserialize_with = "failure_default")]
+    max_per_minute: MaxTriggers,
+}
+
+impl WatchRule {
+    #[inline]
+    pub fn max_per_minute(&self) -> usize {
+        self.max_per_minute.0
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+struct MaxTriggers(usize);
+
+impl Default for MaxTriggers {
+    fn default() -> Self {
+        MaxTriggers(10)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::{failure_default, Delta};
+use crate::config::{failure_default, DefaultTrueBool, Delta};
 use crate::index::{Column, Line};
 
 #[serde(default)]
@@ -8,6 +8,10 @@ pub struct WindowConfig {
     #[serde(deserialize_with = "failure_default")]
     pub dimensions: Dimensions,
 
+    /// Minimum dimensions the window can be resized to
+    #[serde(deserialize_with = "failure_default")]
+    pub min_dimensions: Dimensions,
+
     /// Initial position
     #[serde(deserialize_with = "failure_default")]
     pub position: Option<Delta<i32>>,
@@ -24,6 +28,10 @@ pub struct WindowConfig {
     #[serde(deserialize_with = "failure_default")]
     pub dynamic_padding: bool,
 
+    /// Ask the window manager to resize in steps of whole cells
+    #[serde(deserialize_with = "failure_default")]
+    resize_increments: DefaultTrueBool,
+
     /// Startup mode
     #[serde(deserialize_with = "failure_default")]
     startup_mode: StartupMode,
@@ -42,6 +50,11 @@ pub struct WindowConfig {
 }
 
 impl WindowConfig {
+    #[inline]
+    pub fn resize_increments(&self) -> bool {
+        self.resize_increments.0
+    }
+
     pub fn startup_mode(&self) -> StartupMode {
         match self.start_maximized {
             Some(true) => StartupMode::Maximized,
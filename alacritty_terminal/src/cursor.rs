@@ -16,7 +16,7 @@
 
 use std::cmp;
 
-use font::{Metrics, RasterizedGlyph};
+use font::{BitmapBuffer, Metrics, RasterizedGlyph};
 
 use crate::ansi::CursorStyle;
 
@@ -59,7 +59,7 @@ pub fn get_cursor_glyph(
 // Returns a custom underline cursor character
 pub fn get_underline_cursor_glyph(width: i32, line_width: i32) -> RasterizedGlyph {
     // Create a new rectangle, the height is relative to the font width
-    let buf = vec![255u8; (width * line_width * 3) as usize];
+    let buf = BitmapBuffer::RGB(vec![255u8; (width * line_width * 3) as usize]);
 
     // Create a custom glyph with the rectangle data attached to it
     RasterizedGlyph { c: ' ', top: line_width, left: 0, height: line_width, width, buf }
@@ -68,7 +68,7 @@ pub fn get_underline_cursor_glyph(width: i32, line_width: i32) -> RasterizedGlyp
 // Returns a custom beam cursor character
 pub fn get_beam_cursor_glyph(height: i32, line_width: i32) -> RasterizedGlyph {
     // Create a new rectangle that is at least one pixel wide
-    let buf = vec![255u8; (line_width * height * 3) as usize];
+    let buf = BitmapBuffer::RGB(vec![255u8; (line_width * height * 3) as usize]);
 
     // Create a custom glyph with the rectangle data attached to it
     RasterizedGlyph { c: ' ', top: height, left: 0, height, width: line_width, buf }
@@ -93,13 +93,14 @@ pub fn get_box_cursor_glyph(height: i32, width: i32, line_width: i32) -> Rasteri
     }
 
     // Create a custom glyph with the rectangle data attached to it
+    let buf = BitmapBuffer::RGB(buf);
     RasterizedGlyph { c: ' ', top: height, left: 0, height, width, buf }
 }
 
 // Returns a custom block cursor character
 pub fn get_block_cursor_glyph(height: i32, width: i32) -> RasterizedGlyph {
     // Create a completely filled glyph
-    let buf = vec![255u8; (width * height * 3) as usize];
+    let buf = BitmapBuffer::RGB(vec![255u8; (width * height * 3) as usize]);
 
     // Create a custom glyph with the rectangle data attached to it
     RasterizedGlyph { c: ' ', top: height, left: 0, height, width, buf }
@@ -17,24 +17,68 @@
 use std::f64;
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 use std::ffi::c_void;
-use std::sync::mpsc;
+use std::mem;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use glutin::dpi::{PhysicalPosition, PhysicalSize};
 use glutin::EventsLoop;
 use parking_lot::MutexGuard;
 
+use crate::ansi::Progress;
 use crate::config::{Config, StartupMode};
-use crate::index::Line;
+use crate::grid::LineDamage;
+use crate::index::{Column, Line};
 use crate::message_bar::Message;
-use crate::meter::Meter;
-use crate::renderer::rects::{Rect, Rects};
-use crate::renderer::{self, GlyphCache, QuadRenderer};
+use crate::pacing::FramePacer;
+use crate::renderer::rects::{Rect, RectKind, Rects};
+use crate::renderer::stats::RenderStats;
+use crate::renderer::{self, GlyphCache, QuadRenderer, RenderConfig};
 use crate::sync::FairMutex;
 use crate::term::color::Rgb;
+use crate::term::images::Image;
 use crate::term::{RenderableCell, SizeInfo, Term};
+use crate::util::RateLimit;
 use crate::window::{self, Window};
 use font::{self, Rasterize};
 
+/// Time the grid dimensions are shown for after the last resize
+const RESIZE_OVERLAY_DURATION: Duration = Duration::from_millis(1000);
+
+/// Background of the resize overlay
+const RESIZE_OVERLAY_COLOR: Rgb = Rgb::new(0xd0, 0xd0, 0xd0);
+
+/// Background of the row timestamps
+const TIMESTAMP_COLOR: Rgb = Rgb::new(0xd0, 0xd0, 0xd0);
+
+/// Columns reserved for row timestamps, including the one separating them from the grid
+const TIMESTAMP_COLUMNS: usize = 9;
+
+/// Overlay of the search matches
+const SEARCH_MATCH_COLOR: Rgb = Rgb { r: 0xff, g: 0xd7, b: 0x00, a: 0x50 };
+
+/// Overlay of the search match which is navigated to
+const SEARCH_FOCUSED_MATCH_COLOR: Rgb = Rgb { r: 0xff, g: 0x87, b: 0x00, a: 0x90 };
+
+/// Background of the search bar
+const SEARCH_BAR_COLOR: Rgb = Rgb::new(0xd0, 0xd0, 0xd0);
+
+/// Background of the hint labels
+const HINT_LABEL_COLOR: Rgb = Rgb::new(0xff, 0xd7, 0x00);
+
+/// Gutter of the rows marked by watch rules
+const MARK_COLOR: Rgb = Rgb::new(0x81, 0xa2, 0xbe);
+
+/// Width of the mark gutter in logical pixels
+const MARK_WIDTH: f32 = 3.;
+
+/// Maximum number of window title updates per second
+const MAX_TITLE_UPDATES: usize = 10;
+
+/// Maximum number of progress reports forwarded to the desktop per second
+const MAX_PROGRESS_UPDATES: usize = 4;
+
 #[derive(Debug)]
 pub enum Error {
     /// Error with window management
@@ -97,14 +141,43 @@ impl From<renderer::Error> for Error {
 pub struct Display {
     window: Window,
     renderer: QuadRenderer,
+    render_config: RenderConfig,
     glyph_cache: GlyphCache,
     render_timer: bool,
     rx: mpsc::Receiver<PhysicalSize>,
     tx: mpsc::Sender<PhysicalSize>,
-    meter: Meter,
+    stats: RenderStats,
+    pacer: FramePacer,
     font_size: font::Size,
     size_info: SizeInfo,
     last_message: Option<Message>,
+    status_line: bool,
+    timestamps: bool,
+    resize_overlay: Option<ResizeOverlay>,
+
+    /// Hash of the frame which is currently on screen
+    last_frame_hash: Option<u64>,
+
+    /// Renderable cells of every line, only lines damaged since the last frame are updated
+    line_cells: Vec<Vec<RenderableCell>>,
+
+    /// Rects of the current frame, the buffer is reused by the next one
+    rects: Rects,
+
+    /// Title change which was held back by the rate limit
+    pending_title: Option<String>,
+    title_limit: RateLimit,
+
+    /// Progress change which was held back by the rate limit
+    pending_progress: Option<Option<Progress>>,
+    shown_progress: Option<Progress>,
+    progress_limit: RateLimit,
+}
+
+/// Grid dimensions shown while the window is being resized
+struct ResizeOverlay {
+    text: String,
+    hide_at: Instant,
 }
 
 /// Can wakeup the render loop from other threads
@@ -121,6 +194,14 @@ impl Notifier {
     }
 }
 
+/// Load the system font configuration on another thread
+///
+/// The returned thread should be joined before the display is created, so the configuration is
+/// not loaded twice.
+pub fn preload_fonts() -> thread::JoinHandle<()> {
+    crate::util::thread::spawn_named("font preload", font::preload)
+}
+
 impl Display {
     pub fn notifier(&self) -> Notifier {
         Notifier(self.window.create_window_proxy())
@@ -128,6 +209,15 @@ impl Display {
 
     pub fn update_config(&mut self, config: &Config) {
         self.render_timer = config.render_timer();
+        self.render_config = RenderConfig::new(config, self.renderer.capabilities());
+        self.pacer.set_max_fps(config.render.max_fps());
+
+        // Glyphs have to be rasterized again when they are drawn differently
+        if config.font.rendering_mode != self.glyph_cache.rendering_mode()
+            || config.font.builtin_box_drawing() != self.glyph_cache.builtin_box_drawing()
+        {
+            self.update_glyph_cache(config);
+        }
     }
 
     /// Get size info about the display
@@ -135,6 +225,24 @@ impl Display {
         &self.size_info
     }
 
+    /// Size of the terminal, without the columns reserved for row timestamps
+    fn term_size(&self) -> SizeInfo {
+        let mut size = self.size_info;
+        if self.timestamps && size.cols().0 > TIMESTAMP_COLUMNS {
+            size.width -= size.cell_width * TIMESTAMP_COLUMNS as f32;
+        }
+        size
+    }
+
+    /// Size of the PTY, without the line reserved for the status line
+    pub fn pty_size(&self) -> SizeInfo {
+        let mut pty_size = self.term_size();
+        if self.status_line {
+            pty_size.height -= pty_size.cell_height;
+        }
+        pty_size
+    }
+
     pub fn new(config: &Config) -> Result<Display, Error> {
         // Extract some properties from config
         let render_timer = config.render_timer();
@@ -145,7 +253,7 @@ impl Display {
             event_loop.get_available_monitors().next().map(|m| m.get_hidpi_factor()).unwrap_or(1.);
 
         // Guess the target window dimensions
-        let metrics = GlyphCache::static_metrics(config, estimated_dpr as f32)?;
+        let metrics = GlyphCache::static_metrics(&config.font, estimated_dpr as f32)?;
         let (cell_width, cell_height) = Self::compute_cell_size(config, &metrics);
         let dimensions = Self::calculate_dimensions(config, estimated_dpr, cell_width, cell_height);
 
@@ -165,14 +273,17 @@ impl Display {
             window.inner_size_pixels().expect("glutin returns window size").to_physical(dpr);
 
         // Create renderer
-        let mut renderer = QuadRenderer::new()?;
+        let mut renderer = QuadRenderer::new(window.has_alpha())?;
+
+        let capabilities = renderer.capabilities();
+        info!("Renderer capabilities: {:?}", capabilities);
+        if !capabilities.transparency && config.background_opacity() < 1. {
+            warn!("Window transparency is not supported, ignoring background_opacity");
+        }
 
         let (glyph_cache, cell_width, cell_height) =
             Self::new_glyph_cache(dpr, &mut renderer, config)?;
 
-        let mut padding_x = f64::from(config.window.padding.x) * dpr;
-        let mut padding_y = f64::from(config.window.padding.y) * dpr;
-
         if let Some((width, height)) =
             Self::calculate_dimensions(config, dpr, cell_width, cell_height)
         {
@@ -182,31 +293,33 @@ impl Display {
                 viewport_size = PhysicalSize::new(width, height);
                 window.set_inner_size(viewport_size.to_logical(dpr));
             }
-        } else if config.window.dynamic_padding {
-            // Make sure additional padding is spread evenly
-            let cw = f64::from(cell_width);
-            let ch = f64::from(cell_height);
-            padding_x = padding_x + (viewport_size.width - 2. * padding_x) % cw / 2.;
-            padding_y = padding_y + (viewport_size.height - 2. * padding_y) % ch / 2.;
         }
 
-        padding_x = padding_x.floor();
-        padding_y = padding_y.floor();
+        let (padding_x, padding_y) = compute_padding(
+            config,
+            dpr,
+            viewport_size.width as f32,
+            viewport_size.height as f32,
+            cell_width,
+            cell_height,
+        );
 
         // Update OpenGL projection
-        renderer.resize(viewport_size, padding_x as f32, padding_y as f32);
+        renderer.resize(viewport_size, padding_x, padding_y);
 
         info!("Cell Size: {} x {}", cell_width, cell_height);
         info!("Padding: {} x {}", padding_x, padding_y);
 
+        window.set_size_hints(&config.window, cell_width, cell_height, dpr);
+
         let size_info = SizeInfo {
             dpr,
             width: viewport_size.width as f32,
             height: viewport_size.height as f32,
             cell_width: cell_width as f32,
             cell_height: cell_height as f32,
-            padding_x: padding_x as f32,
-            padding_y: padding_y as f32,
+            padding_x,
+            padding_y,
         };
 
         // Channel for resize events
@@ -218,23 +331,41 @@ impl Display {
         // need to be in the callback.
         let (tx, rx) = mpsc::channel();
 
-        // Clear screen
+        // Present a cleared frame right away, the window would otherwise show undefined content
+        // until the shell's first output is drawn
+        let render_config = RenderConfig::new(config, capabilities);
         let background_color = config.colors.primary.background;
-        renderer.with_api(config, &size_info, |api| {
+        renderer.with_api(&render_config, &size_info, |api| {
             api.clear(background_color);
         });
+        window.swap_buffers()?;
+
+        let rects = Rects::new(glyph_cache.font_metrics(), size_info);
 
         Ok(Display {
             window,
             renderer,
+            render_config,
             glyph_cache,
             render_timer,
             tx,
             rx,
-            meter: Meter::new(),
+            stats: RenderStats::default(),
+            pacer: FramePacer::new(config.render.max_fps()),
             font_size: config.font.size,
             size_info,
             last_message: None,
+            status_line: config.status_line.enabled,
+            timestamps: false,
+            resize_overlay: None,
+            last_frame_hash: None,
+            line_cells: Vec::new(),
+            rects,
+            pending_title: None,
+            title_limit: RateLimit::new(MAX_TITLE_UPDATES, Duration::from_secs(1)),
+            pending_progress: None,
+            shown_progress: None,
+            progress_limit: RateLimit::new(MAX_PROGRESS_UPDATES, Duration::from_secs(1)),
         })
     }
 
@@ -280,7 +411,7 @@ impl Display {
             let init_start = ::std::time::Instant::now();
 
             let cache =
-                renderer.with_loader(|mut api| GlyphCache::new(rasterizer, &font, &mut api))?;
+                renderer.with_loader(|mut api| GlyphCache::new(rasterizer, &font, dpr, &mut api))?;
 
             let stop = init_start.elapsed();
             let stop_f = stop.as_secs() as f64 + f64::from(stop.subsec_nanos()) / 1_000_000_000f64;
@@ -349,6 +480,7 @@ impl Display {
         while let Ok(size) = self.rx.try_recv() {
             new_size = Some(size);
         }
+        let window_resized = new_size.is_some();
 
         // Update the DPR
         let dpr = self.window.hidpi_factor();
@@ -357,9 +489,14 @@ impl Display {
         let font_changed =
             terminal.font_size != self.font_size || (dpr - self.size_info.dpr).abs() > f64::EPSILON;
 
+        // Status line or timestamps have been toggled
+        let status_line_changed = self.status_line != config.status_line.enabled
+            || self.timestamps != terminal.timestamps_visible();
+
         // Skip resize if nothing changed
         if let Some(new_size) = new_size {
             if !font_changed
+                && !status_line_changed
                 && (new_size.width - f64::from(self.size_info.width)).abs() < f64::EPSILON
                 && (new_size.height - f64::from(self.size_info.height)).abs() < f64::EPSILON
             {
@@ -370,7 +507,7 @@ impl Display {
         // Message bar update detected
         let message_bar_changed = self.last_message != terminal.message_buffer_mut().message();
 
-        if font_changed || message_bar_changed {
+        if font_changed || message_bar_changed || status_line_changed {
             if new_size == None {
                 // Force a resize to refresh things
                 new_size = Some(PhysicalSize::new(
@@ -381,11 +518,16 @@ impl Display {
 
             self.font_size = terminal.font_size;
             self.last_message = terminal.message_buffer_mut().message();
+            self.status_line = config.status_line.enabled;
+            self.timestamps = terminal.timestamps_visible();
             self.size_info.dpr = dpr;
         }
 
         if font_changed {
             self.update_glyph_cache(config);
+
+            let SizeInfo { cell_width, cell_height, .. } = self.size_info;
+            self.window.set_size_hints(&config.window, cell_width, cell_height, dpr);
         }
 
         if let Some(psize) = new_size.take() {
@@ -397,34 +539,40 @@ impl Display {
             self.size_info.width = width;
             self.size_info.height = height;
 
-            let mut padding_x = f32::from(config.window.padding.x) * dpr as f32;
-            let mut padding_y = f32::from(config.window.padding.y) * dpr as f32;
-
-            if config.window.dynamic_padding {
-                padding_x = padding_x + ((width - 2. * padding_x) % cell_width) / 2.;
-                padding_y = padding_y + ((height - 2. * padding_y) % cell_height) / 2.;
-            }
+            let (padding_x, padding_y) =
+                compute_padding(config, dpr, width, height, cell_width, cell_height);
+            self.size_info.padding_x = padding_x;
+            self.size_info.padding_y = padding_y;
 
-            self.size_info.padding_x = padding_x.floor();
-            self.size_info.padding_y = padding_y.floor();
-
-            let size = &self.size_info;
+            let size = &self.term_size();
             terminal.resize(size);
             processor_resize_handle.on_resize(size);
 
-            // Subtract message bar lines for pty size
-            let mut pty_size = *size;
+            // Subtract message bar and status line for pty size
+            let mut pty_size = self.pty_size();
             if let Some(message) = terminal.message_buffer_mut().message() {
                 pty_size.height -= pty_size.cell_height * message.text(&size).len() as f32;
             }
 
             if message_bar_changed
+                || status_line_changed
                 || previous_cols != pty_size.cols()
                 || previous_lines != pty_size.lines()
             {
                 pty_resize_handle.on_resize(&pty_size);
             }
 
+            // Show the new grid dimensions while the user is resizing the window
+            if window_resized
+                && !font_changed
+                && (previous_cols != pty_size.cols() || previous_lines != pty_size.lines())
+            {
+                self.resize_overlay = Some(ResizeOverlay {
+                    text: format!(" {}x{} ", pty_size.cols(), pty_size.lines()),
+                    hide_at: Instant::now() + RESIZE_OVERLAY_DURATION,
+                });
+            }
+
             self.window.resize(psize);
             self.renderer.resize(psize, self.size_info.padding_x, self.size_info.padding_y);
         }
@@ -436,6 +584,13 @@ impl Display {
     ///
     /// This call may block if vsync is enabled
     pub fn draw(&mut self, terminal: &FairMutex<Term>, config: &Config) {
+        enter_span!("draw");
+
+        // Output arriving while waiting is drawn with this frame
+        self.pacer.set_vblank(self.window.vblank());
+        self.pacer.wait();
+
+        let frame_start = Instant::now();
         let mut terminal = terminal.lock();
         let size_info = *terminal.size_info();
         let visual_bell_intensity = terminal.visual_bell.intensity();
@@ -443,23 +598,163 @@ impl Display {
         let metrics = self.glyph_cache.font_metrics();
 
         let window_focused = self.window.is_focused;
-        let grid_cells: Vec<RenderableCell> =
-            terminal.renderable_cells(config, window_focused).collect();
+        let force_redraw = mem::replace(&mut terminal.force_redraw, false);
+        let mut frame_hasher = terminal.frame_hasher();
+        {
+            enter_span!("renderable_cells");
+
+            // Rebuild all lines when the cached cells can't be trusted
+            let num_lines = terminal.grid().num_lines();
+            let mut damage = terminal.take_damage();
+            if force_redraw || self.line_cells.len() != num_lines.0 {
+                damage = LineDamage::new(num_lines);
+                self.line_cells = vec![Vec::new(); num_lines.0];
+            }
+
+            for (line, cells) in self.line_cells.iter_mut().enumerate() {
+                if damage.is_damaged(Line(line)) {
+                    cells.clear();
+                }
+            }
+
+            for cell in terminal.renderable_cells(config).damaged(&damage) {
+                self.line_cells[cell.line.0].push(cell);
+            }
+
+            for cell in self.line_cells.iter().flatten() {
+                frame_hasher.cell(cell);
+            }
+        }
+
+        // The selection is drawn below the cells, which only change their text color
+        let selection_rects = terminal.visible_selection_rects();
+        let selection_color = terminal.selection_background(config);
+        for rect in &selection_rects {
+            for line in rect.top.0..=rect.bottom.0 {
+                frame_hasher.highlight(Line(line), rect.start, rect.end, selection_color);
+            }
+        }
+
+        // Images are drawn on top of the cells they cover
+        let image_cells = if self.renderer.capabilities().images {
+            terminal.visible_image_cells()
+        } else {
+            Vec::new()
+        };
+        let mut images: Vec<Arc<Image>> = Vec::new();
+        for cell in &image_cells {
+            frame_hasher.image(cell);
+            if !images.iter().any(|image| image.id == cell.image) {
+                images.extend(terminal.image(cell.image));
+            }
+        }
+
+        // The cursor is drawn on top of the cached cells, so moving it doesn't damage any lines
+        let cursor_cells = terminal.renderable_cursor(config, window_focused);
+        for cell in &cursor_cells {
+            frame_hasher.cell(cell);
+        }
 
         // Get message from terminal to ignore modifications after lock is dropped
         let message_buffer = terminal.message_buffer_mut().message();
+        if let Some(ref message) = message_buffer {
+            for (i, text) in message.text(&size_info).iter().enumerate() {
+                frame_hasher.text(Line(i), text);
+            }
+            frame_hasher.color(message.color(), 0.);
+        }
+
+        // The status line is drawn directly below the grid
+        let status_line =
+            terminal.status_line_text().map(|text| (terminal.grid().num_lines(), text));
+
+        let timestamps = terminal.visible_timestamps();
+
+        // The search bar covers the last line of the grid
+        let search_bar = terminal
+            .search_bar_text()
+            .map(|text| (Line(terminal.grid().num_lines().saturating_sub(1)), text));
+        if let Some((line, ref text)) = search_bar {
+            frame_hasher.text(line, text);
+        }
+
+        let search_matches: Vec<_> = terminal
+            .visible_search_matches()
+            .into_iter()
+            .map(|m| {
+                let color = if m.focused { SEARCH_FOCUSED_MATCH_COLOR } else { SEARCH_MATCH_COLOR };
+                frame_hasher.highlight(m.line, m.start, m.end, color);
+                (m, color)
+            })
+            .collect();
+
+        let marks = terminal.visible_marks();
+        for line in &marks {
+            frame_hasher.highlight(*line, Column(0), Column(0), MARK_COLOR);
+        }
+
+        let hint_labels = terminal.visible_hint_labels();
+        for (line, col, label) in &hint_labels {
+            frame_hasher.label(*line, *col, label);
+        }
+
+        // Drop the resize overlay once it has timed out
+        let now = Instant::now();
+        if self.resize_overlay.as_ref().map_or(false, |overlay| overlay.hide_at <= now) {
+            self.resize_overlay = None;
+        }
+
+        if let Some(ref overlay) = self.resize_overlay {
+            frame_hasher.text(Line(size_info.lines().0 / 2), &overlay.text);
+        }
 
-        // Clear dirty flag
-        terminal.dirty = !terminal.visual_bell.completed();
+        // Skip rendering when the frame on screen is still up to date
+        let frame_hash = frame_hasher.finish();
+        let unchanged =
+            !force_redraw && !self.render_timer && self.last_frame_hash == Some(frame_hash);
+        self.last_frame_hash = Some(frame_hash);
 
+        // Clear dirty flag, keep redrawing until the resize overlay is hidden
+        terminal.dirty = !terminal.visual_bell.completed() || self.resize_overlay.is_some();
+
+        // Only the latest title is applied once the rate limit allows it again
         if let Some(title) = terminal.get_next_title() {
-            self.window.set_title(&title);
+            self.pending_title = Some(title);
+        }
+        if self.pending_title.is_some() {
+            if self.title_limit.allow() {
+                let title = self.pending_title.take().unwrap();
+                self.window.set_title(&title);
+            } else {
+                terminal.dirty = true;
+            }
+        }
+
+        terminal.flush_clipboard();
+        if terminal.has_pending_clipboard() {
+            terminal.dirty = true;
         }
 
         if let Some(mouse_cursor) = terminal.get_next_mouse_cursor() {
             self.window.set_mouse_cursor(mouse_cursor);
         }
 
+        // Only the latest progress is shown, and only if it differs from the one on display
+        if let Some(progress) = terminal.get_next_progress() {
+            self.pending_progress = Some(progress);
+        }
+        if let Some(progress) = self.pending_progress {
+            if progress == self.shown_progress {
+                self.pending_progress = None;
+            } else if self.progress_limit.allow() {
+                self.pending_progress = None;
+                self.shown_progress = progress;
+                self.window.set_progress(progress);
+            } else {
+                terminal.dirty = true;
+            }
+        }
+
         if let Some(is_urgent) = terminal.next_is_urgent.take() {
             // We don't need to set the urgent flag if we already have the
             // user's attention.
@@ -479,46 +774,120 @@ impl Display {
         // handling and rendering.
         drop(terminal);
 
-        self.renderer.with_api(config, &size_info, |api| {
-            api.clear(background_color);
+        if unchanged {
+            trace!("Skipping identical frame");
+            return;
+        }
+
+        self.renderer.begin_frame(self.render_timer);
+
+        let render_config = self.render_config;
+        self.renderer.with_api(&render_config, &size_info, |api| {
+            match render_config.padding_color {
+                Some(padding) if padding != background_color => {
+                    api.clear(padding);
+                    api.clear_grid(background_color, &size_info);
+                },
+                _ => api.clear(background_color),
+            }
         });
 
         {
             let glyph_cache = &mut self.glyph_cache;
-            let mut rects = Rects::new(&metrics, &size_info);
+            let line_cells = &self.line_cells;
+            let rects = &mut self.rects;
+            rects.reset(metrics, size_info);
+
+            for rect in &selection_rects {
+                let x = size_info.padding_x + rect.start.0 as f32 * size_info.cell_width;
+                let y = size_info.padding_y + rect.top.0 as f32 * size_info.cell_height;
+                let width = (rect.end.0 + 1 - rect.start.0) as f32 * size_info.cell_width;
+                let height = (rect.bottom.0 + 1 - rect.top.0) as f32 * size_info.cell_height;
+                rects.push(Rect::new(x, y, width, height), selection_color, RectKind::Selection);
+            }
+            if !selection_rects.is_empty() {
+                self.renderer.draw_rects(&size_info, rects.of_kind(RectKind::Selection));
+            }
+
+            let mut bell_color = render_config.visual_bell_color;
+            bell_color.a = (visual_bell_intensity * f64::from(bell_color.a)) as u8;
+            let bell = Rect::new(0., 0., size_info.width, size_info.height);
+            rects.push(bell, bell_color, RectKind::VisualBell);
 
             // Draw grid
             {
-                let _sampler = self.meter.sampler();
+                enter_span!("render_grid");
 
-                self.renderer.with_api(config, &size_info, |mut api| {
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
                     // Iterate over all non-empty cells in the grid
-                    for cell in grid_cells {
+                    for cell in line_cells.iter().flatten() {
                         // Update underline/strikeout
-                        rects.update_lines(&size_info, &cell);
+                        rects.update_lines(&size_info, cell);
 
                         // Draw the cell
+                        api.render_cell(cell.clone(), glyph_cache);
+                    }
+                });
+            }
+
+            {
+                enter_span!("render_images");
+                self.renderer.draw_images(&size_info, &images, &image_cells);
+            }
+
+            if !cursor_cells.is_empty() {
+                enter_span!("render_cursor");
+
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    for cell in cursor_cells {
+                        rects.update_lines(&size_info, &cell);
                         api.render_cell(cell, glyph_cache);
                     }
                 });
             }
 
-            if let Some(message) = message_buffer {
-                let text = message.text(&size_info);
+            enter_span!("render_overlays");
 
+            for (m, color) in search_matches {
+                let x = size_info.padding_x + m.start.0 as f32 * size_info.cell_width;
+                let y = size_info.padding_y + m.line.0 as f32 * size_info.cell_height;
+                let width = (m.end.0 + 1 - m.start.0) as f32 * size_info.cell_width;
+                let rect = Rect::new(x, y, width, size_info.cell_height);
+                rects.push(rect, color, RectKind::Overlay);
+            }
+
+            for line in &marks {
+                let y = size_info.padding_y + line.0 as f32 * size_info.cell_height;
+                let width = MARK_WIDTH * size_info.dpr as f32;
+                let rect = Rect::new(0., y, width, size_info.cell_height);
+                rects.push(rect, MARK_COLOR, RectKind::Mark);
+            }
+
+            for (line, col, label) in &hint_labels {
+                let x = size_info.padding_x + col.0 as f32 * size_info.cell_width;
+                let y = size_info.padding_y + line.0 as f32 * size_info.cell_height;
+                let width = label.chars().count() as f32 * size_info.cell_width;
+                let rect = Rect::new(x, y, width, size_info.cell_height);
+                rects.push(rect, HINT_LABEL_COLOR, RectKind::HintLabel);
+            }
+
+            let message_text = message_buffer.as_ref().map(|message| message.text(&size_info));
+            if let (Some(message), Some(text)) = (&message_buffer, &message_text) {
                 // Create a new rectangle for the background
                 let start_line = size_info.lines().0 - text.len();
                 let y = size_info.padding_y + size_info.cell_height * start_line as f32;
                 let rect = Rect::new(0., y, size_info.width, size_info.height - y);
-                rects.push(rect, message.color());
+                rects.push(rect, message.color(), RectKind::Overlay);
+            }
 
-                // Draw rectangles including the new background
-                self.renderer.draw_rects(config, &size_info, visual_bell_intensity, rects);
+            // Draw everything but the selection on top of the cells
+            self.renderer.draw_rects(&size_info, rects.except_kind(RectKind::Selection));
 
+            if let Some(text) = message_text {
                 // Relay messages to the user
                 let mut offset = 1;
                 for message_text in text.iter().rev() {
-                    self.renderer.with_api(config, &size_info, |mut api| {
+                    self.renderer.with_api(&render_config, &size_info, |mut api| {
                         api.render_string(
                             &message_text,
                             Line(size_info.lines().saturating_sub(offset)),
@@ -528,21 +897,75 @@ impl Display {
                     });
                     offset += 1;
                 }
-            } else {
-                // Draw rectangles
-                self.renderer.draw_rects(config, &size_info, visual_bell_intensity, rects);
             }
 
-            // Draw render timer
+            if let Some((line, text)) = search_bar {
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    api.render_string(&text, line, glyph_cache, Some(SEARCH_BAR_COLOR));
+                });
+            }
+
+            if let Some((line, text)) = status_line {
+                let color = config.status_line.background;
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    api.render_string(&text, line, glyph_cache, Some(color));
+                });
+            }
+
+            // Draw hint labels on top of the start of their hints
+            if !hint_labels.is_empty() {
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    for (line, col, label) in &hint_labels {
+                        api.render_string_at(label, *line, *col, glyph_cache, None);
+                    }
+                });
+            }
+
+            // Draw row timestamps in the columns reserved for them
+            if !timestamps.is_empty() {
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    for (line, text) in &timestamps {
+                        let column = Column(size_info.cols().0.saturating_sub(text.len()));
+                        let color = Some(TIMESTAMP_COLOR);
+                        api.render_string_at(text, *line, column, glyph_cache, color);
+                    }
+                });
+            }
+
+            // Draw grid dimensions centered on top of the terminal content
+            if let Some(ref overlay) = self.resize_overlay {
+                let len = overlay.text.chars().count();
+                let line = Line(size_info.lines().0 / 2);
+                let column = Column(size_info.cols().0.saturating_sub(len) / 2);
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    api.render_string_at(
+                        &overlay.text,
+                        line,
+                        column,
+                        glyph_cache,
+                        Some(RESIZE_OVERLAY_COLOR),
+                    );
+                });
+            }
+
+            // Draw statistics of the previous frames
             if self.render_timer {
-                let timing = format!("{:.3} usec", self.meter.average());
-                let color = Rgb { r: 0xd5, g: 0x4e, b: 0x53 };
-                self.renderer.with_api(config, &size_info, |mut api| {
-                    api.render_string(&timing[..], size_info.lines() - 2, glyph_cache, Some(color));
+                let summary = self.stats.summary();
+                let color = Rgb::new(0xd5, 0x4e, 0x53);
+                self.renderer.with_api(&render_config, &size_info, |mut api| {
+                    api.render_string(&summary, size_info.lines() - 2, glyph_cache, Some(color));
                 });
             }
         }
 
+        let frame_stats = self.renderer.end_frame();
+        if self.render_timer {
+            self.stats.record(frame_start.elapsed(), frame_stats);
+        }
+
+        self.pacer.record_frame_time(frame_start.elapsed());
+
+        enter_span!("present");
         self.window.swap_buffers().expect("swap buffers");
     }
 
@@ -568,3 +991,35 @@ impl Display {
         self.window.get_wayland_display()
     }
 }
+
+/// Compute the padding around the grid on both axes
+///
+/// With dynamic padding, the pixels which don't fit a whole cell are split between both sides
+/// of the axis. If the remainder is odd, the extra pixel is left at the right/bottom edge.
+fn compute_padding(
+    config: &Config,
+    dpr: f64,
+    width: f32,
+    height: f32,
+    cell_width: f32,
+    cell_height: f32,
+) -> (f32, f32) {
+    let padding_x = (f32::from(config.window.padding.x) * dpr as f32).floor();
+    let padding_y = (f32::from(config.window.padding.y) * dpr as f32).floor();
+
+    if !config.window.dynamic_padding {
+        return (padding_x, padding_y);
+    }
+
+    (
+        padding_x + centering_offset(width, padding_x, cell_width),
+        padding_y + centering_offset(height, padding_y, cell_height),
+    )
+}
+
+/// Additional padding required to center whole cells within `size`
+fn centering_offset(size: f32, padding: f32, cell_size: f32) -> f32 {
+    let available = (size - 2. * padding).max(0.);
+    let remainder = available - (available / cell_size).floor() * cell_size;
+    (remainder / 2.).floor()
+}
@@ -1,8 +1,6 @@
 //! Process window events
 use std::borrow::Cow;
 use std::env;
-#[cfg(unix)]
-use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::sync::mpsc;
@@ -18,7 +16,7 @@ use crate::config::{self, Config};
 use crate::display::OnResize;
 use crate::grid::Scroll;
 use crate::index::{Column, Line, Point, Side};
-use crate::input::{self, KeyBinding, MouseBinding};
+use crate::input::{self, Action, KeyBinding, MouseBinding};
 use crate::selection::Selection;
 use crate::sync::FairMutex;
 use crate::term::cell::Cell;
@@ -76,6 +74,14 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         }
     }
 
+    fn copy_selection_as_rich_text(&mut self) {
+        if let Some(selected) = self.terminal.selection_to_rich_text() {
+            if !selected.text.is_empty() {
+                self.terminal.clipboard().store_rich_text(selected);
+            }
+        }
+    }
+
     fn selection_is_empty(&self) -> bool {
         self.terminal.selection().as_ref().map(Selection::is_empty).unwrap_or(true)
     }
@@ -169,12 +175,9 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
 
         #[cfg(unix)]
         let args = {
-            #[cfg(not(target_os = "freebsd"))]
-            let proc_prefix = "";
-            #[cfg(target_os = "freebsd")]
-            let proc_prefix = "/compat/linux";
-            let link_path = format!("{}/proc/{}/cwd", proc_prefix, tty::child_pid());
-            if let Ok(path) = fs::read_link(link_path) {
+            // Fall back to our own directory when the shell's can't be read
+            if let Some(path) = tty::child_working_directory().or_else(|| env::current_dir().ok())
+            {
                 vec!["--working-directory".into(), path]
             } else {
                 Vec::new()
@@ -299,6 +302,8 @@ pub struct Processor<N> {
     alt_send_esc: bool,
     is_fullscreen: bool,
     is_simple_fullscreen: bool,
+    file_drop: config::FileDrop,
+    dropped_files: Vec<String>,
 }
 
 /// Notify that the terminal was resized
@@ -344,6 +349,8 @@ impl<N: Notify> Processor<N> {
             alt_send_esc: config.alt_send_esc(),
             is_fullscreen: false,
             is_simple_fullscreen: false,
+            file_drop: config.file_drop.to_owned(),
+            dropped_files: Vec::new(),
         }
     }
 
@@ -357,6 +364,7 @@ impl<N: Notify> Processor<N> {
         resize_tx: &mpsc::Sender<PhysicalSize>,
         hide_mouse: &mut bool,
         window_is_focused: &mut bool,
+        dropped_files: &mut Vec<String>,
     ) {
         match event {
             // Pass on device events
@@ -435,6 +443,7 @@ impl<N: Notify> Processor<N> {
                     },
                     Refresh => {
                         processor.ctx.terminal.dirty = true;
+                        processor.ctx.terminal.force_redraw = true;
                     },
                     Focused(is_focused) => {
                         *window_is_focused = is_focused;
@@ -451,13 +460,13 @@ impl<N: Notify> Processor<N> {
                         processor.on_focus_change(is_focused);
                     },
                     DroppedFile(path) => {
-                        use crate::input::ActionContext;
-                        let path: String = path.to_string_lossy().into();
-                        processor.ctx.write_to_pty(path.into_bytes());
+                        // Files dropped together are pasted once all events are processed
+                        dropped_files.push(path.to_string_lossy().into());
                     },
                     HiDpiFactorChanged(new_dpr) => {
                         processor.ctx.size_info.dpr = new_dpr;
                         processor.ctx.terminal.dirty = true;
+                        processor.ctx.terminal.force_redraw = true;
                     },
                     _ => (),
                 }
@@ -532,6 +541,7 @@ impl<N: Notify> Processor<N> {
             // ends.
             {
                 let hide_mouse = &mut self.hide_mouse;
+                let dropped_files = &mut self.dropped_files;
                 let mut process = |event| {
                     if print_events {
                         info!("glutin event: {:?}", event);
@@ -543,6 +553,7 @@ impl<N: Notify> Processor<N> {
                         resize_tx,
                         hide_mouse,
                         &mut window_is_focused,
+                        dropped_files,
                     );
                 };
 
@@ -553,6 +564,14 @@ impl<N: Notify> Processor<N> {
                 window.poll_events(process);
             }
 
+            if !self.dropped_files.is_empty() {
+                let paths = self.file_drop.format(self.dropped_files.iter().map(String::as_str));
+                if !paths.is_empty() {
+                    Action::Paste.paste(&mut processor.ctx, &paths);
+                }
+                self.dropped_files.clear();
+            }
+
             if self.hide_mouse_when_typing {
                 window.set_mouse_visible(!self.hide_mouse);
             }
@@ -589,5 +608,6 @@ impl<N: Notify> Processor<N> {
         self.mouse_config = config.mouse.to_owned();
         self.save_to_clipboard = config.selection.save_to_clipboard;
         self.alt_send_esc = config.alt_send_esc();
+        self.file_drop = config.file_drop.to_owned();
     }
 }
@@ -1,10 +1,12 @@
 //! The main event loop which performs I/O on the pseudoterminal
 use std::borrow::Cow;
+use std::cmp::min;
 use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{self, ErrorKind, Read, Write};
 use std::marker::Send;
 use std::sync::Arc;
+use std::time::Duration;
 
 use mio::{self, Events, PollOpt, Ready};
 use mio_extras::channel::{self, Receiver, Sender};
@@ -20,6 +22,15 @@ use crate::term::Term;
 use crate::tty;
 use crate::util::thread;
 
+/// Bytes parsed at once, while the terminal is locked
+pub(crate) const READ_BUFFER_SIZE: usize = 0x4000;
+
+/// Bytes parsed before other events are handled
+const MAX_READ: usize = 0x1_0000;
+
+/// Bytes read ahead while the terminal is locked by the renderer
+const MAX_UNPROCESSED: usize = 0x10_0000;
+
 /// Messages that may be sent to the `EventLoop`
 #[derive(Debug)]
 pub enum Msg {
@@ -50,6 +61,16 @@ struct Writing {
     written: usize,
 }
 
+/// Output read from the PTY which is waiting to be parsed
+///
+/// Once the buffer is full, reading stops until the parser caught up, so the kernel buffer of
+/// the PTY pushes back on the child instead of queueing its output in memory.
+struct ReadBuffer {
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+}
+
 /// Indicates the result of draining the mio channel
 #[derive(Debug)]
 enum DrainResult {
@@ -78,6 +99,7 @@ pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
     parser: ansi::Processor,
+    read_buffer: ReadBuffer,
 }
 
 pub struct Notifier(pub Sender<Msg>);
@@ -100,7 +122,12 @@ impl event::Notify for Notifier {
 
 impl Default for State {
     fn default() -> State {
-        State { write_list: VecDeque::new(), parser: ansi::Processor::new(), writing: None }
+        State {
+            write_list: VecDeque::new(),
+            parser: ansi::Processor::new(),
+            writing: None,
+            read_buffer: ReadBuffer::new(MAX_UNPROCESSED),
+        }
     }
 }
 
@@ -133,6 +160,55 @@ impl State {
     }
 }
 
+impl ReadBuffer {
+    fn new(size: usize) -> ReadBuffer {
+        ReadBuffer { buf: vec![0u8; size].into_boxed_slice(), start: 0, end: 0 }
+    }
+
+    /// Space for reading up to `max` more bytes, empty once the buffer is full
+    fn space(&mut self, max: usize) -> &mut [u8] {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = 0;
+        } else if self.end == self.buf.len() {
+            self.buf.rotate_left(self.start);
+            self.end -= self.start;
+            self.start = 0;
+        }
+
+        let end = min(self.end + max, self.buf.len());
+        &mut self.buf[self.end..end]
+    }
+
+    /// Mark bytes written to the start of `space` as read
+    #[inline]
+    fn fill(&mut self, count: usize) {
+        self.end += count;
+    }
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.end - self.start == self.buf.len()
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Up to `max` of the bytes waiting to be parsed
+    #[inline]
+    fn unparsed(&self, max: usize) -> &[u8] {
+        &self.buf[self.start..min(self.start + max, self.end)]
+    }
+
+    /// Mark bytes at the start of `unparsed` as parsed
+    #[inline]
+    fn consume(&mut self, count: usize) {
+        self.start += count;
+    }
+}
+
 impl Writing {
     #[inline]
     fn new(c: Cow<'static, [u8]>) -> Writing {
@@ -222,72 +298,84 @@ where
     }
 
     #[inline]
-    fn pty_read<X>(
-        &mut self,
-        state: &mut State,
-        buf: &mut [u8],
-        mut writer: Option<&mut X>,
-    ) -> io::Result<()>
+    fn pty_read<X>(&mut self, state: &mut State, mut writer: Option<&mut X>) -> io::Result<()>
     where
         X: Write,
     {
-        const MAX_READ: usize = 0x1_0000;
         let mut processed = 0;
-        let mut terminal = None;
-
-        // Flag to keep track if wakeup has already been sent
-        let mut send_wakeup = false;
 
         loop {
-            match self.pty.reader().read(&mut buf[..]) {
-                Ok(0) => break,
-                Ok(got) => {
-                    // Record bytes read; used to limit time spent in pty_read.
-                    processed += got;
-
-                    // Send a copy of bytes read to a subscriber. Used for
-                    // example with ref test recording.
-                    writer = writer.map(|w| {
-                        w.write_all(&buf[..got]).unwrap();
-                        w
-                    });
-
-                    // Get reference to terminal. Lock is acquired on initial
-                    // iteration and held until there's no bytes left to parse
-                    // or we've reached MAX_READ.
-                    let terminal = if terminal.is_none() {
-                        terminal = Some(self.terminal.lock());
-                        let terminal = terminal.as_mut().unwrap();
-                        send_wakeup = !terminal.dirty;
-                        terminal
-                    } else {
-                        terminal.as_mut().unwrap()
-                    };
-
-                    // Run the parser
-                    for byte in &buf[..got] {
-                        state.parser.advance(&mut **terminal, *byte, &mut self.pty.writer());
-                    }
+            // Read ahead of the parser, so it isn't blocked by the reads
+            let mut drained = false;
+            let mut error = None;
+            loop {
+                let space = state.read_buffer.space(READ_BUFFER_SIZE);
+                if space.is_empty() {
+                    break;
+                }
 
-                    // Exit if we've processed enough bytes
-                    if processed > MAX_READ {
+                match self.pty.reader().read(space) {
+                    Ok(0) => {
+                        drained = true;
                         break;
-                    }
-                },
-                Err(err) => match err.kind() {
-                    ErrorKind::Interrupted | ErrorKind::WouldBlock => {
+                    },
+                    Ok(got) => state.read_buffer.fill(got),
+                    Err(err) => {
+                        match err.kind() {
+                            ErrorKind::Interrupted | ErrorKind::WouldBlock => (),
+                            _ => error = Some(err),
+                        }
+                        drained = true;
                         break;
                     },
-                    _ => return Err(err),
-                },
+                }
+            }
+
+            if !state.read_buffer.is_empty() {
+                // Keep reading while the renderer holds the terminal, until the PTY is drained
+                // or the buffer is full
+                let mut terminal = match self.terminal.try_lock() {
+                    Some(terminal) => terminal,
+                    None if !drained && !state.read_buffer.is_full() => continue,
+                    None => self.terminal.lock(),
+                };
+
+                // The lock is released after every chunk, so the renderer never waits for
+                // more than one of them
+                let chunk = state.read_buffer.unparsed(READ_BUFFER_SIZE);
+
+                // Send a copy of bytes read to a subscriber. Used for
+                // example with ref test recording.
+                writer = writer.map(|w| {
+                    w.write_all(chunk).unwrap();
+                    w
+                });
+
+                enter_span!("parse", bytes = chunk.len());
+                for byte in chunk {
+                    state.parser.advance(&mut *terminal, *byte, &mut self.pty.writer());
+                }
+
+                // Only request a draw if one hasn't already been requested.
+                if !terminal.dirty {
+                    self.display.notify();
+                    terminal.dirty = true;
+                }
+
+                processed += chunk.len();
+                state.read_buffer.consume(chunk.len());
+            }
+
+            // Errors are only reported once everything read before them has been parsed
+            match error {
+                Some(err) if state.read_buffer.is_empty() => return Err(err),
+                _ => (),
             }
-        }
 
-        // Only request a draw if one hasn't already been requested.
-        if let Some(mut terminal) = terminal {
-            if send_wakeup {
-                self.display.notify();
-                terminal.dirty = true;
+            // Exit once there is nothing left to parse, or if we've processed enough bytes.
+            // Anything left over is parsed on the next iteration of the event loop.
+            if (drained && state.read_buffer.is_empty()) || processed >= MAX_READ {
+                break;
             }
         }
 
@@ -329,7 +417,6 @@ where
     pub fn spawn(mut self, state: Option<State>) -> thread::JoinHandle<(Self, State)> {
         thread::spawn_named("pty reader", move || {
             let mut state = state.unwrap_or_else(Default::default);
-            let mut buf = [0u8; 0x1000];
 
             let mut tokens = (0..).map(Into::into);
 
@@ -350,13 +437,18 @@ where
             };
 
             'event_loop: loop {
-                if let Err(err) = self.poll.poll(&mut events, None) {
+                // Don't wait for the PTY while output read ahead of the parser is left over
+                let timeout =
+                    if state.read_buffer.is_empty() { None } else { Some(Duration::from_secs(0)) };
+
+                if let Err(err) = self.poll.poll(&mut events, timeout) {
                     match err.kind() {
                         ErrorKind::Interrupted => continue,
                         _ => panic!("EventLoop polling error: {:?}", err),
                     }
                 }
 
+                let mut readable = false;
                 for event in events.iter() {
                     match event.token() {
                         token if token == channel_token => {
@@ -386,24 +478,7 @@ where
                                 }
                             }
 
-                            if event.readiness().is_readable() {
-                                if let Err(e) = self.pty_read(&mut state, &mut buf, pipe.as_mut()) {
-                                    #[cfg(target_os = "linux")]
-                                    {
-                                        // On Linux, a `read` on the master side of a PTY can fail
-                                        // with `EIO` if the client side hangs up.  In that case,
-                                        // just loop back round for the inevitable `Exited` event.
-                                        // This sucks, but checking the process is either racy or
-                                        // blocking.
-                                        if e.kind() == ErrorKind::Other {
-                                            continue;
-                                        }
-                                    }
-
-                                    error!("Error reading from PTY in event loop: {}", e);
-                                    break 'event_loop;
-                                }
-                            }
+                            readable |= event.readiness().is_readable();
 
                             if event.readiness().is_writable() {
                                 if let Err(e) = self.pty_write(&mut state) {
@@ -416,6 +491,24 @@ where
                     }
                 }
 
+                if readable || !state.read_buffer.is_empty() {
+                    if let Err(e) = self.pty_read(&mut state, pipe.as_mut()) {
+                        // On Linux, a `read` on the master side of a PTY can fail with `EIO` if
+                        // the client side hangs up.  In that case, just loop back round for the
+                        // inevitable `Exited` event. This sucks, but checking the process is
+                        // either racy or blocking.
+                        #[cfg(target_os = "linux")]
+                        let hung_up = e.kind() == ErrorKind::Other;
+                        #[cfg(not(target_os = "linux"))]
+                        let hung_up = false;
+
+                        if !hung_up {
+                            error!("Error reading from PTY in event loop: {}", e);
+                            break 'event_loop;
+                        }
+                    }
+                }
+
                 // Register write interest if necessary
                 let mut interest = Ready::readable();
                 if state.needs_write() {
@@ -434,3 +527,126 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ReadBuffer;
+
+    /// Fill all space available for reads with increasing bytes
+    fn fill(buffer: &mut ReadBuffer, next: &mut u8) -> usize {
+        let space = buffer.space(4);
+        for byte in space.iter_mut() {
+            *byte = *next;
+            *next += 1;
+        }
+
+        let len = space.len();
+        buffer.fill(len);
+        len
+    }
+
+    #[test]
+    fn full_read_buffer_stops_reading() {
+        let mut buffer = ReadBuffer::new(8);
+        let mut next = 0;
+
+        assert_eq!(fill(&mut buffer, &mut next), 4);
+        assert_eq!(fill(&mut buffer, &mut next), 4);
+        assert!(buffer.is_full());
+        assert_eq!(fill(&mut buffer, &mut next), 0);
+
+        // Reading resumes once the parser caught up
+        assert_eq!(buffer.unparsed(3), &[0, 1, 2]);
+        buffer.consume(3);
+        assert!(!buffer.is_full());
+        assert_eq!(fill(&mut buffer, &mut next), 3);
+        assert!(buffer.is_full());
+
+        assert_eq!(buffer.unparsed(16), &[3, 4, 5, 6, 7, 8, 9, 10]);
+        buffer.consume(8);
+        assert!(buffer.is_empty());
+        assert_eq!(fill(&mut buffer, &mut next), 4);
+    }
+}
+
+#[cfg(all(test, feature = "bench"))]
+mod benches {
+    extern crate serde_json as json;
+    extern crate test;
+
+    use std::fs;
+    use std::io;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::ansi;
+    use crate::clipboard::Clipboard;
+    use crate::config::Config;
+    use crate::message_bar::MessageBuffer;
+    use crate::sync::FairMutex;
+    use crate::term::{SizeInfo, Term};
+
+    use super::{MAX_READ, READ_BUFFER_SIZE};
+
+    /// Lock the terminal to iterate over its cells, while another thread parses PTY output
+    ///
+    /// The other thread parses a recorded vim session over and over, holding the lock for
+    /// `chunk_size` bytes at a time.
+    fn render_while_parsing(b: &mut test::Bencher, chunk_size: usize) {
+        let recording = fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/ref/vim_large_window_scroll/alacritty.recording"
+        ))
+        .unwrap();
+        let size = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/ref/vim_large_window_scroll/size.json"
+        ))
+        .unwrap();
+        let size: SizeInfo = json::from_str(&size).unwrap();
+
+        let config = Config::default();
+        let terminal = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let terminal = Arc::new(FairMutex::new(terminal));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let parser_thread = {
+            let terminal = Arc::clone(&terminal);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut parser = ansi::Processor::new();
+                while !done.load(Ordering::Relaxed) {
+                    for chunk in recording.chunks(chunk_size) {
+                        let mut terminal = terminal.lock();
+                        for byte in chunk {
+                            parser.advance(&mut *terminal, *byte, &mut io::sink());
+                        }
+                    }
+                }
+            })
+        };
+
+        b.iter(|| {
+            let terminal = terminal.lock();
+            for cell in terminal.renderable_cells(&config) {
+                test::black_box(cell);
+            }
+        });
+
+        done.store(true, Ordering::Relaxed);
+        parser_thread.join().unwrap();
+    }
+
+    /// Parsing one read buffer per lock, like the PTY reader does
+    #[bench]
+    fn render_under_load(b: &mut test::Bencher) {
+        render_while_parsing(b, READ_BUFFER_SIZE);
+    }
+
+    /// Parsing everything read before handling other events with a single lock
+    #[bench]
+    fn render_under_load_single_lock(b: &mut test::Bencher) {
+        render_while_parsing(b, MAX_READ);
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracking of the lines which changed since the last frame
+use crate::index::Line;
+
+/// Set of damaged lines
+///
+/// Lines outside of the tracked range are always considered damaged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDamage {
+    /// Every line is damaged
+    full: bool,
+
+    lines: Vec<bool>,
+}
+
+impl Default for LineDamage {
+    fn default() -> LineDamage {
+        LineDamage { full: true, lines: Vec::new() }
+    }
+}
+
+impl LineDamage {
+    /// Create fully damaged state for a number of lines
+    pub fn new(lines: Line) -> LineDamage {
+        LineDamage { full: true, lines: vec![false; lines.0] }
+    }
+
+    #[inline]
+    pub fn damage_line(&mut self, line: Line) {
+        if let Some(damaged) = self.lines.get_mut(line.0) {
+            *damaged = true;
+        }
+    }
+
+    #[inline]
+    pub fn damage_all(&mut self) {
+        self.full = true;
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    #[inline]
+    pub fn is_damaged(&self, line: Line) -> bool {
+        self.full || self.lines.get(line.0).cloned().unwrap_or(true)
+    }
+
+    /// Track a different number of lines, damaging all of them
+    pub fn resize(&mut self, lines: Line) {
+        *self = LineDamage::new(lines);
+    }
+
+    /// Take the current damage, leaving all lines undamaged
+    ///
+    /// The returned damage is moved down by `offset` lines, to match the lines of the viewport.
+    pub fn take(&mut self, offset: usize) -> LineDamage {
+        let len = self.lines.len();
+        let mut damage = LineDamage { full: self.full, lines: vec![false; len] };
+        for (line, damaged) in self.lines.iter_mut().enumerate() {
+            if line + offset < len {
+                damage.lines[line + offset] = *damaged;
+            }
+            *damaged = false;
+        }
+        self.full = false;
+        damage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineDamage;
+    use crate::index::Line;
+
+    #[test]
+    fn take_damage() {
+        let mut damage = LineDamage::new(Line(3));
+        assert!(damage.take(0).is_full());
+
+        damage.damage_line(Line(1));
+        let taken = damage.take(0);
+        assert!(!taken.is_full());
+        assert!(!taken.is_damaged(Line(0)));
+        assert!(taken.is_damaged(Line(1)));
+        assert!(!taken.is_damaged(Line(2)));
+
+        assert!(!damage.take(0).is_damaged(Line(1)));
+    }
+
+    #[test]
+    fn take_scrolled_damage() {
+        let mut damage = LineDamage::new(Line(3));
+        damage.take(0);
+
+        damage.damage_line(Line(0));
+        damage.damage_line(Line(2));
+        let taken = damage.take(1);
+        assert!(!taken.is_damaged(Line(0)));
+        assert!(taken.is_damaged(Line(1)));
+        assert!(!taken.is_damaged(Line(2)));
+    }
+
+    #[test]
+    fn untracked_lines_are_damaged() {
+        let mut damage = LineDamage::default();
+        damage.take(0);
+        assert!(damage.is_damaged(Line(5)));
+    }
+}
@@ -20,6 +20,9 @@ use std::ops::{Deref, Index, IndexMut, Range, RangeFrom, RangeFull, RangeInclusi
 use crate::index::{self, Column, IndexRange, Line, Point};
 use crate::selection::Selection;
 
+mod damage;
+pub use self::damage::LineDamage;
+
 mod row;
 pub use self::row::Row;
 
@@ -108,6 +111,10 @@ pub struct Grid<T> {
     /// Range for URL hover highlights
     #[serde(default)]
     pub url_highlight: Option<RangeInclusive<index::Linear>>,
+
+    /// Lines of the active area modified since the last frame
+    #[serde(skip)]
+    damage: LineDamage,
 }
 
 #[derive(Copy, Clone)]
@@ -138,6 +145,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
             selection: None,
             max_scroll_limit: scrollback,
             url_highlight: None,
+            damage: LineDamage::new(lines),
         }
     }
 
@@ -162,6 +170,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
 
     /// Update the size of the scrollback history
     pub fn update_history(&mut self, history_size: usize, template: &T) {
+        self.damage.damage_all();
         self.raw.update_history(history_size, Row::new(self.cols, &template));
         self.max_scroll_limit = history_size;
         self.scroll_limit = min(self.scroll_limit, history_size);
@@ -169,6 +178,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
     }
 
     pub fn scroll_display(&mut self, scroll: Scroll) {
+        let display_offset = self.display_offset;
         match scroll {
             Scroll::Lines(count) => {
                 self.display_offset = min(
@@ -185,6 +195,10 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
             Scroll::Top => self.display_offset = self.scroll_limit,
             Scroll::Bottom => self.display_offset = 0,
         }
+
+        if self.display_offset != display_offset {
+            self.damage.damage_all();
+        }
     }
 
     pub fn resize(
@@ -210,6 +224,8 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
             Ordering::Greater => self.shrink_cols(cols, template),
             Ordering::Equal => (),
         }
+
+        self.damage.resize(lines);
     }
 
     fn increase_scroll_limit(&mut self, count: usize, template: &T) {
@@ -279,6 +295,11 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
                 self.raw[i].append(&mut cells);
 
                 if self.raw[i - 1].is_empty() {
+                    // The line now ends in this row, so it was completed with the drained one
+                    if let Some(timestamp) = self.raw[i - 1].timestamp() {
+                        self.raw[i].set_timestamp(timestamp);
+                    }
+
                     // Remove following line if all cells have been drained
                     self.raw.remove(i - 1);
 
@@ -350,7 +371,12 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
                     if occ < cols.0 {
                         new_row.append(&mut vec![*template; cols.0 - occ]);
                     }
-                    let row = Row::from_vec(new_row, occ);
+                    let mut row = Row::from_vec(new_row, occ);
+
+                    // The new row continues the same line, so it was completed at the same time
+                    if let Some(timestamp) = self.raw[i].timestamp() {
+                        row.set_timestamp(timestamp);
+                    }
 
                     // Add new row with all removed cells
                     self.raw.insert(i, row, max_lines);
@@ -407,6 +433,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
         // changing the start index.
         //
         // To accommodate scroll regions, rows are reordered at the end.
+        self.damage.damage_all();
         if region.start == Line(0) {
             // Rotate the entire line buffer. If there's a scrolling region
             // active, the bottom lines are restored in the next step.
@@ -444,6 +471,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
     ///
     /// This is the performance-sensitive part of scrolling.
     pub fn scroll_up(&mut self, region: &Range<index::Line>, positions: index::Line, template: &T) {
+        self.damage.damage_all();
         if region.start == Line(0) {
             // Update display offset when not pinned to active area
             if self.display_offset != 0 {
@@ -505,6 +533,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
         self.display_offset = 0;
         self.selection = None;
         self.url_highlight = None;
+        self.damage.damage_all();
     }
 }
 
@@ -526,6 +555,30 @@ impl<T> Grid<T> {
 
     pub fn clear_history(&mut self) {
         self.scroll_limit = 0;
+        self.damage.damage_all();
+    }
+
+    /// Mark a line of the active area as modified
+    #[inline]
+    pub fn damage_line(&mut self, line: index::Line) {
+        self.damage.damage_line(line);
+    }
+
+    /// Mark the entire viewport as modified
+    #[inline]
+    pub fn damage_all(&mut self) {
+        self.damage.damage_all();
+    }
+
+    fn damage_lines(&mut self, lines: Range<index::Line>) {
+        for line in IndexRange(lines) {
+            self.damage.damage_line(line);
+        }
+    }
+
+    /// Take the damage since the last call, in lines of the viewport
+    pub fn take_damage(&mut self) -> LineDamage {
+        self.damage.take(self.display_offset)
     }
 
     #[inline]
@@ -643,6 +696,7 @@ impl<T> Index<usize> for Grid<T> {
 impl<T> IndexMut<index::Line> for Grid<T> {
     #[inline]
     fn index_mut(&mut self, index: index::Line) -> &mut Row<T> {
+        self.damage.damage_line(index);
         &mut self.raw[index]
     }
 }
@@ -650,6 +704,8 @@ impl<T> IndexMut<index::Line> for Grid<T> {
 impl<T> IndexMut<usize> for Grid<T> {
     #[inline]
     fn index_mut(&mut self, index: usize) -> &mut Row<T> {
+        // Buffer offsets can point anywhere in the history
+        self.damage.damage_all();
         &mut self.raw[index]
     }
 }
@@ -723,6 +779,7 @@ impl<T> IndexRegion<Range<Line>, T> for Grid<T> {
         assert!(index.start < self.num_lines());
         assert!(index.end <= self.num_lines());
         assert!(index.start <= index.end);
+        self.damage_lines(index.start..index.end);
         RegionMut { start: index.start, end: index.end, raw: &mut self.raw }
     }
 }
@@ -735,6 +792,7 @@ impl<T> IndexRegion<RangeTo<Line>, T> for Grid<T> {
 
     fn region_mut(&mut self, index: RangeTo<Line>) -> RegionMut<'_, T> {
         assert!(index.end <= self.num_lines());
+        self.damage_lines(Line(0)..index.end);
         RegionMut { start: Line(0), end: index.end, raw: &mut self.raw }
     }
 }
@@ -747,6 +805,7 @@ impl<T> IndexRegion<RangeFrom<Line>, T> for Grid<T> {
 
     fn region_mut(&mut self, index: RangeFrom<Line>) -> RegionMut<'_, T> {
         assert!(index.start < self.num_lines());
+        self.damage_lines(index.start..self.num_lines());
         RegionMut { start: index.start, end: self.num_lines(), raw: &mut self.raw }
     }
 }
@@ -757,6 +816,7 @@ impl<T> IndexRegion<RangeFull, T> for Grid<T> {
     }
 
     fn region_mut(&mut self, _: RangeFull) -> RegionMut<'_, T> {
+        self.damage.damage_all();
         RegionMut { start: Line(0), end: self.num_lines(), raw: &mut self.raw }
     }
 }
@@ -853,6 +913,17 @@ impl<'a, T: 'a> DisplayIter<'a, T> {
     pub fn line(&self) -> Line {
         self.line
     }
+
+    /// Skip the remaining cells of the current line
+    pub fn skip_line(&mut self) {
+        if self.offset == self.limit {
+            self.col = self.grid.num_cols();
+        } else {
+            self.offset -= 1;
+            self.col = Column(0);
+            self.line = Line(*self.grid.lines - 1 - (self.offset - self.limit));
+        }
+    }
 }
 
 impl<'a, T: Copy + 'a> Iterator for DisplayIter<'a, T> {
@@ -18,6 +18,7 @@ use std::cmp::{max, min};
 use std::ops::{Index, IndexMut};
 use std::ops::{Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::slice;
+use std::time::Instant;
 
 use crate::grid::GridCell;
 use crate::index::Column;
@@ -36,6 +37,10 @@ pub struct Row<T> {
     /// - 1 means there is a value at index zero, but nowhere else
     /// - `occ == inner.len` means every value is occupied
     pub(crate) occ: usize,
+
+    /// Time the cursor last left this row
+    #[serde(skip)]
+    timestamp: Option<Instant>,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -46,7 +51,7 @@ impl<T: PartialEq> PartialEq for Row<T> {
 
 impl<T: Copy> Row<T> {
     pub fn new(columns: Column, template: &T) -> Row<T> {
-        Row { inner: vec![*template; *columns], occ: 0 }
+        Row { inner: vec![*template; *columns], occ: 0, timestamp: None }
     }
 
     pub fn grow(&mut self, cols: Column, template: &T) {
@@ -86,6 +91,7 @@ impl<T: Copy> Row<T> {
             *item = *other;
         }
         self.occ = 0;
+        self.timestamp = None;
     }
 }
 
@@ -93,7 +99,17 @@ impl<T: Copy> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row { inner: vec, occ, timestamp: None }
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> Option<Instant> {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn set_timestamp(&mut self, timestamp: Instant) {
+        self.timestamp = Some(timestamp);
     }
 
     #[inline]
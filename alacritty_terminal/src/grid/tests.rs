@@ -14,6 +14,8 @@
 
 //! Tests for the Grid
 
+use std::time::Instant;
+
 use super::{BidirectionalIterator, Grid};
 use crate::grid::GridCell;
 use crate::index::{Column, Line, Point};
@@ -157,6 +159,23 @@ fn shrink_reflow() {
     assert_eq!(grid[0][Column(1)], Cell::default());
 }
 
+#[test]
+fn reflow_keeps_timestamps() {
+    let timestamp = Instant::now();
+    let mut grid = Grid::new(Line(2), Column(4), 2, Cell::default());
+    grid[Line(1)][Column(0)] = cell('1');
+    grid[Line(1)][Column(3)] = cell('4');
+    grid[Line(1)].set_timestamp(timestamp);
+
+    grid.resize(Line(2), Column(2), &mut Point::new(Line(1), Column(0)), &Cell::default());
+    assert_eq!(grid[1].timestamp(), Some(timestamp));
+    assert_eq!(grid[0].timestamp(), Some(timestamp));
+
+    grid.resize(Line(2), Column(4), &mut Point::new(Line(1), Column(0)), &Cell::default());
+    assert_eq!(grid[0][Column(3)], cell('4'));
+    assert_eq!(grid[0].timestamp(), Some(timestamp));
+}
+
 #[test]
 fn shrink_reflow_twice() {
     let mut grid = Grid::new(Line(1), Column(5), 2, cell('x'));
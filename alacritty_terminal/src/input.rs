@@ -19,27 +19,33 @@
 //! needs to be tracked. Additionally, we need a bit of a state machine to
 //! determine what to do when a non-modifier key is pressed.
 use std::borrow::Cow;
+use std::env;
+use std::fmt;
 use std::mem;
 use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use glutin::{
     ElementState, KeyboardInput, ModifiersState, MouseButton, MouseCursor, MouseScrollDelta,
-    TouchPhase,
+    TouchPhase, VirtualKeyCode,
 };
 use unicode_width::UnicodeWidthStr;
 
 use crate::ansi::{ClearMode, Handler};
 use crate::clipboard::ClipboardType;
-use crate::config::{self, Key};
+use crate::config::{self, HintAction, Key};
 use crate::event::{ClickState, Mouse};
 use crate::grid::Scroll;
 use crate::index::{Column, Line, Linear, Point, Side};
 use crate::message_bar::{self, Message};
+use crate::term::hints::{Hint, HintKind};
 use crate::term::mode::TermMode;
+use crate::term::search::{Direction, ScrollbackSearch};
 use crate::term::{Search, SizeInfo, Term};
 use crate::url::Url;
-use crate::util::start_daemon;
+use crate::tty;
+use crate::util::{start_daemon, start_with_input};
 
 pub const FONT_SIZE_STEP: f32 = 0.5;
 
@@ -63,6 +69,7 @@ pub trait ActionContext {
     fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, _: B);
     fn size_info(&self) -> SizeInfo;
     fn copy_selection(&mut self, _: ClipboardType);
+    fn copy_selection_as_rich_text(&mut self);
     fn clear_selection(&mut self);
     fn update_selection(&mut self, point: Point, side: Side);
     fn simple_selection(&mut self, point: Point, side: Side);
@@ -164,6 +171,42 @@ impl<T: Eq> Binding<T> {
             && self.notmode == binding.notmode
             && self.mods == binding.mods
     }
+
+    /// Check if both bindings are triggered by the same input in at least one terminal mode
+    pub fn conflicts_with(&self, binding: &Binding<T>) -> bool {
+        // A binding can be active if one of its required modes is excluded by neither binding
+        let excluded = self.notmode | binding.notmode;
+        let can_activate = |mode: TermMode| mode.is_empty() || !(mode - excluded).is_empty();
+
+        self.trigger == binding.trigger
+            && self.mods == binding.mods
+            && can_activate(self.mode)
+            && can_activate(binding.mode)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for Binding<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mods = [
+            (self.mods.logo, "Super"),
+            (self.mods.ctrl, "Control"),
+            (self.mods.alt, "Alt"),
+            (self.mods.shift, "Shift"),
+        ];
+        for (_, name) in mods.iter().filter(|(enabled, _)| *enabled) {
+            write!(f, "{}+", name)?;
+        }
+        write!(f, "{:?}", self.trigger)?;
+
+        if !self.mode.is_empty() {
+            write!(f, " in {:?}", self.mode)?;
+        }
+        if !self.notmode.is_empty() {
+            write!(f, " not in {:?}", self.notmode)?;
+        }
+
+        write!(f, ": {:?}", self.action)
+    }
 }
 
 impl<T> Binding<T> {
@@ -206,9 +249,18 @@ pub enum Action {
     // Store current selection into clipboard
     Copy,
 
+    /// Store current selection with its colors into clipboard
+    CopyAsHtml,
+
     /// Paste contents of selection buffer
     PasteSelection,
 
+    /// Store current selection into the secondary selection
+    CopySecondary,
+
+    /// Paste contents of the secondary selection
+    PasteSecondary,
+
     /// Increase font size
     IncreaseFontSize,
 
@@ -236,6 +288,12 @@ pub enum Action {
     /// Scroll all the way to the bottom
     ScrollToBottom,
 
+    /// Scroll to the previous line marked by a watch rule
+    ScrollToPreviousMark,
+
+    /// Scroll to the next line marked by a watch rule
+    ScrollToNextMark,
+
     /// Clear the display buffer(s) to remove history
     ClearHistory,
 
@@ -262,6 +320,24 @@ pub enum Action {
     #[cfg(target_os = "macos")]
     ToggleSimpleFullscreen,
 
+    /// Switch to the next color scheme.
+    CycleColorScheme,
+
+    /// Toggle ignoring escape sequences which leak data.
+    ToggleParanoidMode,
+
+    /// Toggle the output filters.
+    ToggleOutputFilters,
+
+    /// Toggle showing the time every row was completed at.
+    ToggleTimestamps,
+
+    /// Search through the grid and scrollback history.
+    Search,
+
+    /// Label the visible URLs and paths, typing a label opens its target.
+    ShowHints,
+
     /// No action.
     None,
 }
@@ -283,6 +359,9 @@ impl Action {
             Action::Copy => {
                 ctx.copy_selection(ClipboardType::Clipboard);
             },
+            Action::CopyAsHtml => {
+                ctx.copy_selection_as_rich_text();
+            },
             Action::Paste => {
                 let text = ctx.terminal_mut().clipboard().load(ClipboardType::Clipboard);
                 self.paste(ctx, &text);
@@ -294,6 +373,13 @@ impl Action {
                     self.paste(ctx, &text);
                 }
             },
+            Action::CopySecondary => {
+                ctx.copy_selection(ClipboardType::Secondary);
+            },
+            Action::PasteSecondary => {
+                let text = ctx.terminal_mut().clipboard().load(ClipboardType::Secondary);
+                self.paste(ctx, &text);
+            },
             Action::Command(ref program, ref args) => {
                 trace!("Running command {} with args {:?}", program, args);
 
@@ -342,6 +428,16 @@ impl Action {
             Action::ScrollToBottom => {
                 ctx.scroll(Scroll::Bottom);
             },
+            Action::ScrollToPreviousMark => {
+                if let Some(scroll) = ctx.terminal().mark_scroll(Direction::Up) {
+                    ctx.scroll(scroll);
+                }
+            },
+            Action::ScrollToNextMark => {
+                if let Some(scroll) = ctx.terminal().mark_scroll(Direction::Down) {
+                    ctx.scroll(scroll);
+                }
+            },
             Action::ClearHistory => {
                 ctx.terminal_mut().clear_screen(ClearMode::Saved);
             },
@@ -351,12 +447,41 @@ impl Action {
             Action::SpawnNewInstance => {
                 ctx.spawn_new_instance();
             },
+            Action::CycleColorScheme => {
+                ctx.terminal_mut().cycle_color_scheme();
+            },
+            Action::ToggleParanoidMode => {
+                ctx.terminal_mut().toggle_paranoid_mode();
+            },
+            Action::ToggleOutputFilters => {
+                ctx.terminal_mut().toggle_output_filters();
+            },
+            Action::ToggleTimestamps => {
+                ctx.terminal_mut().toggle_timestamps();
+            },
+            Action::Search => {
+                ctx.clear_selection();
+                ctx.terminal_mut().start_search();
+            },
+            Action::ShowHints => ctx.terminal_mut().start_hint_mode(),
             Action::None => (),
         }
     }
 
-    fn paste<A: ActionContext>(&self, ctx: &mut A, contents: &str) {
-        if ctx.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
+    pub(crate) fn paste<A: ActionContext>(&self, ctx: &mut A, contents: &str) {
+        let bracketed = ctx.terminal().mode().contains(TermMode::BRACKETED_PASTE);
+        if !ctx.terminal_mut().confirm_paste(contents, bracketed) {
+            return;
+        }
+
+        let contents = if ctx.terminal().paste_config().filter_control_chars() {
+            filter_control_chars(contents)
+        } else {
+            Cow::Borrowed(contents)
+        };
+
+        if bracketed {
+            // Escapes are always removed, so the paste can't end the bracketed paste early
             ctx.write_to_pty(&b"\x1b[200~"[..]);
             ctx.write_to_pty(contents.replace("\x1b", "").into_bytes());
             ctx.write_to_pty(&b"\x1b[201~"[..]);
@@ -396,8 +521,8 @@ impl From<&'static str> for Action {
 impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     #[inline]
     pub fn mouse_moved(&mut self, x: usize, y: usize, modifiers: ModifiersState) {
-        self.ctx.mouse_mut().x = x;
-        self.ctx.mouse_mut().y = y;
+        let prev_x = mem::replace(&mut self.ctx.mouse_mut().x, x);
+        let prev_y = mem::replace(&mut self.ctx.mouse_mut().y, y);
 
         let size_info = self.ctx.size_info();
         let point = size_info.pixels_to_coords(x, y);
@@ -414,8 +539,16 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             prev_line != self.ctx.mouse().line || prev_col != self.ctx.mouse().column;
         let mouse_moved = cell_changed || prev_side != cell_side;
 
+        // Pixel reports include motion within a cell
+        let pixel_changed = self.ctx.terminal().mode().contains(TermMode::SGR_PIXELS)
+            && (prev_x != x || prev_y != y);
+        let mouse_moved = mouse_moved || pixel_changed;
+
         // Only report motions when cell changed and mouse is not over the message bar
-        if self.message_at_point(Some(point)).is_some() || !mouse_moved {
+        if self.message_at_point(Some(point)).is_some()
+            || self.is_status_line(Some(point))
+            || !mouse_moved
+        {
             return;
         }
 
@@ -433,7 +566,7 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             self.ctx.update_selection(Point { line: point.line, col: point.col }, cell_side);
         } else if self.ctx.terminal().mode().intersects(motion_mode)
             && size_info.contains_point(x, y, false)
-            && cell_changed
+            && (cell_changed || pixel_changed)
         {
             if self.ctx.mouse().left_button_state == ElementState::Pressed {
                 self.mouse_report(32, ElementState::Pressed, modifiers);
@@ -453,16 +586,34 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             TermMode::MOUSE_MOTION | TermMode::MOUSE_DRAG | TermMode::MOUSE_REPORT_CLICK;
 
         // Only show URLs as launchable when all required modifiers are pressed
-        let url = if self.mouse_config.url.mods().relaxed_eq(modifiers)
+        let launchable = self.mouse_config.url.mods().relaxed_eq(modifiers)
             && (!self.ctx.terminal().mode().intersects(mouse_mode) || modifiers.shift)
-            && self.mouse_config.url.launcher.is_some()
-        {
+            && self.mouse_config.url.launcher.is_some();
+
+        // Explicit hyperlinks take precedence over URLs found in the text
+        let hyperlink = if launchable {
+            self.ctx.terminal().hyperlink_at(point).map(|(index, _)| index)
+        } else {
+            None
+        };
+        let url = if launchable && hyperlink.is_none() {
             self.ctx.terminal().url_search(point.into())
         } else {
             None
         };
 
-        if let Some(Url { origin, text }) = url {
+        // Paths are not found by the URL search, only by the hint detector
+        let hint = if url.is_none() && launchable {
+            self.ctx.terminal().hint_at(point).filter(|hint| hint.kind == HintKind::Path)
+        } else {
+            None
+        };
+
+        if let Some(index) = hyperlink {
+            self.ctx.terminal_mut().set_hyperlink_highlight(index);
+            self.ctx.terminal_mut().set_mouse_cursor(MouseCursor::Hand);
+            self.ctx.terminal_mut().dirty = true;
+        } else if let Some(Url { origin, text }) = url {
             let cols = self.ctx.size_info().cols().0;
 
             // Calculate the URL's start position
@@ -488,6 +639,12 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             self.ctx.terminal_mut().set_url_highlight(RangeInclusive::new(start, end));
             self.ctx.terminal_mut().set_mouse_cursor(MouseCursor::Hand);
             self.ctx.terminal_mut().dirty = true;
+        } else if let Some(range) =
+            hint.and_then(|hint| hint.visible_range(self.ctx.terminal().grid()))
+        {
+            self.ctx.terminal_mut().set_url_highlight(range);
+            self.ctx.terminal_mut().set_mouse_cursor(MouseCursor::Hand);
+            self.ctx.terminal_mut().dirty = true;
         } else {
             self.ctx.terminal_mut().reset_url_highlight();
         }
@@ -532,13 +689,18 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     }
 
     pub fn sgr_mouse_report(&mut self, button: u8, state: ElementState) {
-        let (line, column) = (self.ctx.mouse().line, self.ctx.mouse().column);
+        let (x, y) = if self.ctx.terminal().mode().contains(TermMode::SGR_PIXELS) {
+            let (mouse_x, mouse_y) = (self.ctx.mouse().x, self.ctx.mouse().y);
+            self.ctx.size_info().pixels_to_text_area(mouse_x, mouse_y)
+        } else {
+            (self.ctx.mouse().column.0, self.ctx.mouse().line.0)
+        };
         let c = match state {
             ElementState::Pressed => 'M',
             ElementState::Released => 'm',
         };
 
-        let msg = format!("\x1b[<{};{};{}{}", button, column + 1, line + 1, c);
+        let msg = format!("\x1b[<{};{};{}{}", button, x + 1, y + 1, c);
         self.ctx.write_to_pty(msg.into_bytes());
     }
 
@@ -556,7 +718,7 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         }
 
         // Report mouse events
-        if self.ctx.terminal().mode().contains(TermMode::SGR_MOUSE) {
+        if self.ctx.terminal().mode().intersects(TermMode::SGR_MOUSE | TermMode::SGR_PIXELS) {
             self.sgr_mouse_report(button + mods, state);
         } else if let ElementState::Released = state {
             self.normal_mouse_report(3 + mods);
@@ -664,7 +826,7 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         self.copy_selection();
     }
 
-    // Spawn URL launcher when clicking on URLs
+    // Spawn URL launcher when clicking on hyperlinks, URLs or paths
     fn launch_url(&self, modifiers: ModifiersState, point: Point) -> Option<()> {
         if !self.mouse_config.url.mods().relaxed_eq(modifiers)
             || self.ctx.mouse().block_url_launcher
@@ -672,11 +834,71 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             return None;
         }
 
-        let text = self.ctx.terminal().url_search(point.into())?.text;
+        if let Some((_, uri)) = self.ctx.terminal().hyperlink_at(point) {
+            return self.open(uri.to_owned());
+        }
+
+        let text = match self.ctx.terminal().url_search(point.into()) {
+            Some(url) => url.text,
+            None => {
+                let hint = self.ctx.terminal().hint_at(point)?;
+                if hint.kind != HintKind::Path {
+                    return None;
+                }
+                hint_target(&hint)
+            },
+        };
 
+        self.open(text)
+    }
+
+    /// Open a hint selected in hint mode, or run the action of its configured regex
+    fn run_hint(&mut self, hint: Hint) {
+        let rule = match hint.kind {
+            HintKind::Custom(ref rule) => rule,
+            _ => {
+                self.open(hint_target(&hint));
+                return;
+            },
+        };
+
+        let result = match (rule.action, &rule.command) {
+            (HintAction::Copy, _) => {
+                let clipboard = self.ctx.terminal_mut().clipboard();
+                clipboard.store(ClipboardType::Clipboard, hint.text.as_str());
+                Ok(())
+            },
+            (HintAction::Paste, _) => {
+                Action::Paste.paste(&mut self.ctx, &hint.text);
+                Ok(())
+            },
+            (HintAction::Open, None) => {
+                self.open(hint.text.clone());
+                Ok(())
+            },
+            (HintAction::Open, Some(command)) => {
+                let args = command.args.iter().map(String::as_str).chain(Some(hint.text.as_str()));
+                start_daemon(&command.program, args)
+            },
+            (HintAction::Pipe, Some(command)) => {
+                start_with_input(&command.program, &command.args, hint.text.clone().into_bytes())
+            },
+            (HintAction::Pipe, None) => {
+                warn!("Hint regex {:?} has no command to pipe to", rule.regex);
+                Ok(())
+            },
+        };
+
+        if let Err(err) = result {
+            warn!("Unable to run hint command for {:?}: {}", rule.regex, err);
+        }
+    }
+
+    /// Pass a URL or path to the URL launcher
+    fn open(&self, target: String) -> Option<()> {
         let launcher = self.mouse_config.url.launcher.as_ref()?;
         let mut args = launcher.args().to_vec();
-        args.push(text);
+        args.push(target);
 
         match start_daemon(launcher.program(), &args) {
             Ok(_) => debug!("Launched {} with args {:?}", launcher.program(), args),
@@ -786,6 +1008,8 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             // Message should never be `Some` if point is `None`
             debug_assert!(point.is_some());
             self.on_message_bar_click(state, point.unwrap(), message);
+        } else if state == ElementState::Pressed && self.is_status_line(point) {
+            // Clicks on the status line are ignored
         } else {
             match state {
                 ElementState::Pressed => {
@@ -807,7 +1031,15 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
                 *self.ctx.received_count() = 0;
                 *self.ctx.suppress_chars() = false;
 
-                if self.process_key_bindings(input) {
+                if self.ctx.terminal().search().is_some() {
+                    *self.ctx.suppress_chars() = self.process_search_key(input);
+                } else if self.ctx.terminal().is_hint_mode() {
+                    // Only characters select hints, key bindings stay disabled
+                    if input.virtual_keycode == Some(VirtualKeyCode::Escape) {
+                        self.ctx.terminal_mut().stop_hint_mode();
+                        *self.ctx.suppress_chars() = true;
+                    }
+                } else if self.process_key_bindings(input) {
                     *self.ctx.suppress_chars() = true;
                 }
             },
@@ -821,29 +1053,58 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             return;
         }
 
-        self.ctx.scroll(Scroll::Bottom);
-        self.ctx.clear_selection();
-
-        let utf8_len = c.len_utf8();
-        let mut bytes = Vec::with_capacity(utf8_len);
-        unsafe {
-            bytes.set_len(utf8_len);
-            c.encode_utf8(&mut bytes[..]);
+        if let Some(confirmed) = self.ctx.terminal().search().map(ScrollbackSearch::is_confirmed) {
+            let terminal = self.ctx.terminal_mut();
+            match c {
+                'n' if confirmed => terminal.search_next(Direction::Up),
+                'N' if confirmed => terminal.search_next(Direction::Down),
+                c if !confirmed && !c.is_control() => terminal.search_push(c),
+                _ => (),
+            }
+            return;
         }
 
-        if self.alt_send_esc
-            && *self.ctx.received_count() == 0
-            && self.ctx.last_modifiers().alt
-            && utf8_len == 1
-        {
-            bytes.insert(0, b'\x1b');
+        if self.ctx.terminal().is_hint_mode() {
+            if c.is_control() {
+                return;
+            }
+
+            if let Some(hint) = self.ctx.terminal_mut().hint_input(c) {
+                self.run_hint(hint);
+            }
+            return;
         }
 
+        self.ctx.scroll(Scroll::Bottom);
+        self.ctx.clear_selection();
+
+        let alt_send_esc = self.alt_send_esc && *self.ctx.received_count() == 0;
+        let bytes = encode_char(c, *self.ctx.last_modifiers(), alt_send_esc);
+
         self.ctx.write_to_pty(bytes);
 
         *self.ctx.received_count() += 1;
     }
 
+    /// Handle the keys editing the search query or ending the search
+    ///
+    /// Key bindings are not triggered while searching, so nothing is sent to the PTY.
+    ///
+    /// Returns true if the key was handled.
+    fn process_search_key(&mut self, input: KeyboardInput) -> bool {
+        let confirmed = self.ctx.terminal().search().map_or(false, ScrollbackSearch::is_confirmed);
+        let terminal = self.ctx.terminal_mut();
+        match input.virtual_keycode {
+            Some(VirtualKeyCode::Escape) => terminal.stop_search(),
+            Some(VirtualKeyCode::Return) if confirmed => terminal.stop_search(),
+            Some(VirtualKeyCode::Return) => terminal.search_confirm(),
+            Some(VirtualKeyCode::Back) if !confirmed => terminal.search_pop(),
+            _ => return false,
+        }
+
+        true
+    }
+
     /// Attempts to find a binding and execute its action
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
@@ -924,6 +1185,15 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         None
     }
 
+    /// Check if the specified point is on the status line
+    fn is_status_line(&self, point: Option<Point>) -> bool {
+        let terminal = self.ctx.terminal();
+        match point {
+            Some(point) => terminal.has_status_line() && point.line == terminal.grid().num_lines(),
+            None => false,
+        }
+    }
+
     /// Handle clicks on the message bar.
     fn on_message_bar_click(&mut self, button_state: ElementState, point: Point, message: Message) {
         match button_state {
@@ -950,6 +1220,56 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     }
 }
 
+/// Encode a character received from the keyboard for the PTY
+///
+/// Windows reports AltGr as Control+Alt, so printable characters typed with both held are
+/// considered to be AltGr combinations and sent without the escape prefix. Characters
+/// composed from dead keys are sent the same as if they were typed directly.
+/// Text passed to the launcher for a hint
+///
+/// The launcher runs in Alacritty's working directory, so paths are resolved against the one
+/// of the shell.
+fn hint_target(hint: &Hint) -> String {
+    if hint.kind == HintKind::Url {
+        return hint.text.clone();
+    }
+
+    let path = Path::new(&hint.text);
+    let resolved = match path.strip_prefix("~") {
+        Ok(rest) => env::var_os("HOME").map(|home| PathBuf::from(home).join(rest)),
+        Err(_) if path.is_relative() => tty::child_working_directory().map(|dir| dir.join(path)),
+        Err(_) => None,
+    };
+
+    resolved.map_or_else(|| hint.text.clone(), |path| path.to_string_lossy().into_owned())
+}
+
+/// Remove the control characters from pasted text, except for tabs and line breaks
+///
+/// This covers C1 controls as well, which some applications interpret like escape sequences.
+fn filter_control_chars(text: &str) -> Cow<'_, str> {
+    let allowed = |c: char| !c.is_control() || c == '\t' || c == '\n' || c == '\r';
+    if text.chars().all(allowed) {
+        return Cow::Borrowed(text);
+    }
+
+    debug!("Removing control characters from paste");
+    Cow::Owned(text.chars().filter(|&c| allowed(c)).collect())
+}
+
+fn encode_char(c: char, mods: ModifiersState, alt_send_esc: bool) -> Vec<u8> {
+    let mut bytes = vec![0; c.len_utf8()];
+    c.encode_utf8(&mut bytes[..]);
+
+    // Windows reports AltGr as Control+Alt, other platforms don't set modifiers for it
+    let alt_gr = cfg!(windows) && mods.ctrl && mods.alt && !c.is_control();
+    if alt_send_esc && mods.alt && !alt_gr && bytes.len() == 1 {
+        bytes.insert(0, b'\x1b');
+    }
+
+    bytes
+}
+
 #[cfg(test)]
 mod tests {
     use std::borrow::Cow;
@@ -966,7 +1286,7 @@ mod tests {
     use crate::selection::Selection;
     use crate::term::{SizeInfo, Term, TermMode};
 
-    use super::{Action, Binding, Processor};
+    use super::{encode_char, filter_control_chars, Action, Binding, Processor};
 
     const KEY: VirtualKeyCode = VirtualKeyCode::Key0;
 
@@ -1000,6 +1320,8 @@ mod tests {
 
         fn copy_selection(&mut self, _: ClipboardType) {}
 
+        fn copy_selection_as_rich_text(&mut self) {}
+
         fn clear_selection(&mut self) {}
 
         fn hide_window(&mut self) {}
@@ -1290,4 +1612,85 @@ mod tests {
         mode: TermMode::NONE,
         mods: ModifiersState { shift: false, ctrl: false, alt: true, logo: true }
     }
+
+    const NO_MODS: ModifiersState =
+        ModifiersState { shift: false, ctrl: false, alt: false, logo: false };
+    const SHIFT: ModifiersState =
+        ModifiersState { shift: true, ctrl: false, alt: false, logo: false };
+    const ALT: ModifiersState =
+        ModifiersState { shift: false, ctrl: false, alt: true, logo: false };
+    const CTRL_ALT: ModifiersState =
+        ModifiersState { shift: false, ctrl: true, alt: true, logo: false };
+
+    #[test]
+    fn encode_char_layouts() {
+        // Modifiers held when the key was pressed, received character, expected bytes
+        let matrix: &[(ModifiersState, char, &[u8])] = &[
+            (NO_MODS, 'a', b"a"),
+            (ALT, 'a', b"\x1ba"),
+            (CTRL_ALT, '\x01', b"\x1b\x01"),
+            (NO_MODS, 'ß', "ß".as_bytes()),
+            // AltGr as reported on X11 and Wayland
+            (NO_MODS, '@', b"@"),
+            (SHIFT, 'Å', "Å".as_bytes()),
+            // Dead key compositions
+            (NO_MODS, 'ê', "ê".as_bytes()),
+            (SHIFT, 'ë', "ë".as_bytes()),
+            (NO_MODS, '^', b"^"),
+            (ALT, 'á', "á".as_bytes()),
+        ];
+
+        for &(mods, c, expected) in matrix {
+            assert_eq!(encode_char(c, mods, true), expected, "{:?} {:?}", mods, c);
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn encode_char_windows_alt_gr() {
+        for &c in &['@', '{', '$', '€', 'ą'] {
+            let mut bytes = [0; 4];
+            assert_eq!(encode_char(c, CTRL_ALT, true), c.encode_utf8(&mut bytes).as_bytes());
+        }
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn encode_char_control_alt_chords() {
+        assert_eq!(encode_char('@', CTRL_ALT, true), b"\x1b@");
+        assert_eq!(encode_char('{', CTRL_ALT, true), b"\x1b{");
+    }
+
+    #[test]
+    fn encode_char_without_alt_send_esc() {
+        assert_eq!(encode_char('a', ALT, false), b"a");
+        assert_eq!(encode_char('@', CTRL_ALT, false), b"@");
+    }
+
+    #[test]
+    fn paste_control_chars_are_filtered() {
+        assert_eq!(filter_control_chars("ls -l\tfoo\r\n"), "ls -l\tfoo\r\n");
+        assert_eq!(filter_control_chars("a\x1b[201~b\x03c\u{9b}d"), "a[201~bcd");
+    }
+
+    #[test]
+    fn binding_conflicts() {
+        let binding = |mode, notmode| Binding {
+            mods: NO_MODS,
+            action: Action::None,
+            mode,
+            notmode,
+            trigger: KEY,
+        };
+
+        let any = binding(TermMode::NONE, TermMode::NONE);
+        let app_cursor = binding(TermMode::APP_CURSOR, TermMode::NONE);
+        let not_app_cursor = binding(TermMode::NONE, TermMode::APP_CURSOR);
+        let alt_screen = binding(TermMode::ALT_SCREEN, TermMode::NONE);
+
+        assert!(any.conflicts_with(&app_cursor));
+        assert!(app_cursor.conflicts_with(&alt_screen));
+        assert!(!app_cursor.conflicts_with(&not_app_cursor));
+        assert!(!not_app_cursor.conflicts_with(&Binding { mods: SHIFT, ..any.clone() }));
+    }
 }
@@ -29,6 +29,7 @@ extern crate objc;
 #[macro_use]
 pub mod macros;
 pub mod ansi;
+pub mod appearance;
 pub mod clipboard;
 pub mod config;
 mod cursor;
@@ -41,6 +42,7 @@ pub mod input;
 pub mod locale;
 pub mod message_bar;
 pub mod meter;
+pub mod pacing;
 pub mod panic;
 pub mod renderer;
 pub mod selection;
@@ -49,6 +51,13 @@ pub mod term;
 pub mod tty;
 mod url;
 pub mod util;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd"
+))]
+mod vblank;
 pub mod window;
 
 pub use crate::grid::Grid;
@@ -12,6 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+/// Enter a tracing span until the end of the current scope
+///
+/// Without the `tracing` feature, this expands to nothing.
+#[cfg(feature = "tracing")]
+macro_rules! enter_span {
+    ($($arg:tt)*) => {
+        let _span = tracing::trace_span!($($arg)*).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! enter_span {
+    ($($arg:tt)*) => {};
+}
+
 #[macro_export]
 macro_rules! die {
     ($($arg:tt)*) => {{
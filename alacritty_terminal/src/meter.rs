@@ -94,8 +94,8 @@ impl Meter {
 
     /// Add a sample
     ///
-    /// Used by Sampler::drop.
-    fn add_sample(&mut self, sample: Duration) {
+    /// Used by Sampler::drop and for durations which weren't measured by a sampler.
+    pub fn add_sample(&mut self, sample: Duration) {
         let mut usec = 0f64;
 
         usec += f64::from(sample.subsec_nanos()) / 1e3;
@@ -0,0 +1,189 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Frame pacing
+//!
+//! Limits the rate frames are drawn at, independent of the renderer and its swap interval.
+//! Redraw requests which arrive while waiting for the next frame are drawn together with it.
+//!
+//! When the display's vblank timing is known, frames start just early enough to be presented
+//! at the next vblank, so they contain the latest output without tearing.
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time between the expected end of a frame and the vblank it is presented at
+const VBLANK_MARGIN: Duration = Duration::from_millis(2);
+
+/// Number of recent frames, whose slowest render time is expected for the next frame
+const FRAME_TIME_SAMPLES: usize = 16;
+
+/// Time of a past vertical blank of the display and its refresh period
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vblank {
+    pub time: Instant,
+    pub period: Duration,
+}
+
+impl Vblank {
+    /// Latest start of a frame taking `frame_time`, to be presented at the first vblank it can
+    /// make after `earliest`
+    fn frame_start(&self, earliest: Instant, frame_time: Duration) -> Instant {
+        let lead = frame_time + VBLANK_MARGIN;
+        let ready = earliest + lead;
+        if ready <= self.time || self.period == Duration::from_secs(0) {
+            return earliest;
+        }
+
+        let periods = nanos(ready - self.time) / nanos(self.period) + 1;
+        let vblank = self.time + self.period * periods as u32;
+        vblank - lead
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
+#[derive(Debug, Default)]
+pub struct FramePacer {
+    /// Shortest time between the start of two frames
+    interval: Option<Duration>,
+
+    /// Start of the last frame
+    last_frame: Option<Instant>,
+
+    /// Latest vblank timing of the display, when it is known
+    vblank: Option<Vblank>,
+
+    /// Render times of the last frames
+    frame_times: VecDeque<Duration>,
+}
+
+impl FramePacer {
+    pub fn new(max_fps: Option<u32>) -> FramePacer {
+        let mut pacer = FramePacer::default();
+        pacer.set_max_fps(max_fps);
+        pacer
+    }
+
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        self.interval = max_fps.map(|fps| Duration::from_secs(1) / fps.max(1));
+    }
+
+    pub fn set_vblank(&mut self, vblank: Option<Vblank>) {
+        self.vblank = vblank;
+    }
+
+    /// Record how long rendering a frame took, until it was ready to be presented
+    pub fn record_frame_time(&mut self, frame_time: Duration) {
+        self.frame_times.push_back(frame_time);
+        if self.frame_times.len() > FRAME_TIME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+    }
+
+    /// Time left until the next frame should start
+    pub fn remaining(&self, now: Instant) -> Duration {
+        let remaining = self.interval_remaining(now);
+
+        match self.vblank {
+            Some(vblank) => {
+                let frame_time = self.frame_times.iter().max().cloned().unwrap_or_default();
+                let start = vblank.frame_start(now + remaining, frame_time);
+                start.duration_since(now)
+            },
+            None => remaining,
+        }
+    }
+
+    /// Time left until the frame rate limit allows the next frame
+    fn interval_remaining(&self, now: Instant) -> Duration {
+        match (self.interval, self.last_frame) {
+            (Some(interval), Some(last_frame)) => {
+                let elapsed = now.duration_since(last_frame);
+                if elapsed < interval {
+                    interval - elapsed
+                } else {
+                    Duration::from_secs(0)
+                }
+            },
+            _ => Duration::from_secs(0),
+        }
+    }
+
+    /// Wait until the next frame may start, then record it as started
+    pub fn wait(&mut self) {
+        let remaining = self.remaining(Instant::now());
+        if remaining > Duration::from_secs(0) {
+            thread::sleep(remaining);
+        }
+
+        self.last_frame = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{FramePacer, Vblank};
+
+    #[test]
+    fn unlimited() {
+        let mut pacer = FramePacer::new(None);
+        pacer.wait();
+        assert_eq!(pacer.remaining(Instant::now()), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn limited() {
+        let mut pacer = FramePacer::new(Some(10));
+        let start = Instant::now();
+        pacer.last_frame = Some(start);
+
+        assert_eq!(pacer.remaining(start), Duration::from_millis(100));
+        assert_eq!(pacer.remaining(start + Duration::from_millis(40)), Duration::from_millis(60));
+        assert_eq!(pacer.remaining(start + Duration::from_millis(200)), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn starts_before_next_vblank() {
+        let mut pacer = FramePacer::new(None);
+        let vblank = Instant::now();
+        let period = Duration::from_millis(16);
+        pacer.set_vblank(Some(Vblank { time: vblank, period }));
+        pacer.record_frame_time(Duration::from_millis(3));
+        pacer.record_frame_time(Duration::from_millis(4));
+
+        // The slowest recent frame and the margin are subtracted from the next vblank
+        let now = vblank + Duration::from_millis(1);
+        assert_eq!(pacer.remaining(now), Duration::from_millis(9));
+
+        // Vblanks which can't be made anymore are skipped
+        let now = vblank + Duration::from_millis(12);
+        assert_eq!(pacer.remaining(now), Duration::from_millis(14));
+    }
+
+    #[test]
+    fn vblank_after_rate_limit() {
+        let mut pacer = FramePacer::new(Some(50));
+        let start = Instant::now();
+        pacer.last_frame = Some(start);
+        pacer.set_vblank(Some(Vblank { time: start, period: Duration::from_millis(8) }));
+
+        // The limit allows a frame after 20ms, the vblank at 24ms is the first one after it
+        assert_eq!(pacer.remaining(start), Duration::from_millis(22));
+    }
+}
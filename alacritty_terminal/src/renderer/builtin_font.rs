@@ -0,0 +1,551 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Glyphs drawn without the font
+//!
+//! Box drawing characters, block elements and powerline symbols are supposed to connect with
+//! the glyphs in neighbouring cells. Fonts rarely fill the cell exactly, so these glyphs are
+//! generated to match the cell size instead.
+use std::cmp;
+
+use font::{BitmapBuffer, Metrics, RasterizedGlyph};
+
+use crate::config::Delta;
+
+/// Line going from the center of the cell to one of its edges
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Arm {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+/// Arms of the lines in U+2500..=U+254F, as up, right, down and left
+const LINES: [&[u8; 4]; 80] = [
+    b"0101", b"0202", b"1010", b"2020", b"0101", b"0202", b"1010", b"2020", b"0101", b"0202",
+    b"1010", b"2020", b"0110", b"0210", b"0120", b"0220", b"0011", b"0012", b"0021", b"0022",
+    b"1100", b"1200", b"2100", b"2200", b"1001", b"1002", b"2001", b"2002", b"1110", b"1210",
+    b"2110", b"1120", b"2120", b"2210", b"1220", b"2220", b"1011", b"1012", b"2011", b"1021",
+    b"2021", b"2012", b"1022", b"2022", b"0111", b"0112", b"0211", b"0212", b"0121", b"0122",
+    b"0221", b"0222", b"1101", b"1102", b"1201", b"1202", b"2101", b"2102", b"2201", b"2202",
+    b"1111", b"1112", b"1211", b"1212", b"2111", b"1121", b"2121", b"2112", b"2211", b"1122",
+    b"1221", b"2212", b"1222", b"2122", b"2221", b"2222", b"0101", b"0202", b"1010", b"2020",
+];
+
+/// Arms of the double lines in U+2550..=U+256C
+const DOUBLE_LINES: [&[u8; 4]; 29] = [
+    b"0303", b"3030", b"0310", b"0130", b"0330", b"0013", b"0031", b"0033", b"1300", b"3100",
+    b"3300", b"1003", b"3001", b"3003", b"1310", b"3130", b"3330", b"1013", b"3031", b"3033",
+    b"0313", b"0131", b"0333", b"1303", b"3101", b"3303", b"1313", b"3131", b"3333",
+];
+
+/// Arms of the half lines in U+2574..=U+257F
+const HALF_LINES: [&[u8; 4]; 12] = [
+    b"0001", b"1000", b"0100", b"0010", b"0002", b"2000", b"0200", b"0020", b"0201", b"1020",
+    b"0102", b"2010",
+];
+
+/// Rasterize `c` to fill a whole cell, if it is drawn without the font
+pub fn builtin_glyph(c: char, metrics: &Metrics, offset: Delta<i8>) -> Option<RasterizedGlyph> {
+    let width = cmp::max(1, (metrics.average_advance + f64::from(offset.x)).floor() as i32);
+    let height = cmp::max(1, (metrics.line_height + f64::from(offset.y)).floor() as i32);
+    let light = cmp::max(1, metrics.underline_thickness.round() as i32);
+
+    let mut canvas = Canvas::new(width, height, light);
+    match c as u32 {
+        // Dashed lines
+        0x2504..=0x250b | 0x254c..=0x254f => {
+            let dashes = match c as u32 {
+                0x2504..=0x2507 => 3,
+                0x2508..=0x250b => 4,
+                _ => 2,
+            };
+            canvas.draw_dashes(arms(LINES[c as usize - 0x2500]), dashes);
+        },
+        0x2500..=0x254f => canvas.draw_arms(arms(LINES[c as usize - 0x2500])),
+        0x2550..=0x256c => canvas.draw_arms(arms(DOUBLE_LINES[c as usize - 0x2550])),
+        // Rounded corners
+        0x256d => canvas.draw_arc(1., 1.),
+        0x256e => canvas.draw_arc(-1., 1.),
+        0x256f => canvas.draw_arc(-1., -1.),
+        0x2570 => canvas.draw_arc(1., -1.),
+        // Diagonals
+        0x2571 => canvas.draw_diagonal(false),
+        0x2572 => canvas.draw_diagonal(true),
+        0x2573 => {
+            canvas.draw_diagonal(false);
+            canvas.draw_diagonal(true);
+        },
+        0x2574..=0x257f => canvas.draw_arms(arms(HALF_LINES[c as usize - 0x2574])),
+        0x2580..=0x259f => canvas.draw_block(c as u32),
+        // Powerline triangles and arrows
+        0xe0b0 => canvas.draw_triangle(false),
+        0xe0b1 => canvas.draw_arrow(false),
+        0xe0b2 => canvas.draw_triangle(true),
+        0xe0b3 => canvas.draw_arrow(true),
+        _ => return None,
+    }
+
+    Some(canvas.into_glyph(c))
+}
+
+fn arms(digits: &[u8; 4]) -> [Arm; 4] {
+    let mut arms = [Arm::None; 4];
+    for (arm, digit) in arms.iter_mut().zip(digits.iter()) {
+        *arm = match digit {
+            b'1' => Arm::Light,
+            b'2' => Arm::Heavy,
+            b'3' => Arm::Double,
+            _ => Arm::None,
+        };
+    }
+    arms
+}
+
+/// Where a line stops at the center of the cell
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stop {
+    /// Covering the lines crossing the center
+    Center,
+
+    /// At the closer line of a perpendicular double line
+    Near,
+
+    /// At the farther line of a perpendicular double line
+    Far,
+}
+
+/// Coverage of every pixel in a cell
+struct Canvas {
+    width: i32,
+    height: i32,
+
+    /// Thickness of light lines, heavy lines are twice as thick
+    light: i32,
+
+    buf: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: i32, height: i32, light: i32) -> Canvas {
+        Canvas { width, height, light, buf: vec![0; (width * height) as usize] }
+    }
+
+    fn into_glyph(self, c: char) -> RasterizedGlyph {
+        let mut buf = Vec::with_capacity(self.buf.len() * 3);
+        for coverage in self.buf {
+            buf.extend_from_slice(&[coverage; 3]);
+        }
+
+        RasterizedGlyph {
+            c,
+            width: self.width,
+            height: self.height,
+            top: self.height,
+            left: 0,
+            buf: BitmapBuffer::RGB(buf),
+        }
+    }
+
+    /// Fill the pixels from `(x0, y0)` up to but not including `(x1, y1)`
+    fn fill_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, coverage: u8) {
+        for y in cmp::max(0, y0)..cmp::min(self.height, y1) {
+            for x in cmp::max(0, x0)..cmp::min(self.width, x1) {
+                let pixel = &mut self.buf[(y * self.width + x) as usize];
+                *pixel = cmp::max(*pixel, coverage);
+            }
+        }
+    }
+
+    /// Fill every pixel with the coverage from 0 to 1 at its center
+    fn fill_with<F: Fn(f32, f32) -> f32>(&mut self, coverage: F) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let value = coverage(x as f32 + 0.5, y as f32 + 0.5).max(0.).min(1.);
+                let pixel = &mut self.buf[(y * self.width + x) as usize];
+                *pixel = cmp::max(*pixel, (value * 255.).round() as u8);
+            }
+        }
+    }
+
+    fn thickness(&self, arm: Arm) -> i32 {
+        match arm {
+            Arm::Heavy => self.light * 2,
+            _ => self.light,
+        }
+    }
+
+    /// Start of a line with `thickness` centered across `size` pixels
+    #[inline]
+    fn centered(size: i32, thickness: i32) -> i32 {
+        (size - thickness) / 2
+    }
+
+    /// Draw lines from the center to the edges
+    ///
+    /// Double lines are joined like the outlines of single lines, so corners and crossings
+    /// keep the gap between both lines free.
+    fn draw_arms(&mut self, arms: [Arm; 4]) {
+        let widest = arms.iter().filter(|&&arm| arm != Arm::None).map(|&arm| self.thickness(arm));
+        let widest = widest.max().unwrap_or(self.light);
+
+        for direction in 0..4 {
+            let arm = arms[direction];
+            let opposite = arms[(direction + 2) % 4];
+            let sides = [arms[(direction + 3) % 4], arms[(direction + 1) % 4]];
+
+            match arm {
+                Arm::None => (),
+                Arm::Double => {
+                    for (side, &offset) in [-1, 1].iter().enumerate() {
+                        let (towards, away) = (sides[side], sides[1 - side]);
+                        let stop = match (towards, opposite, away) {
+                            (Arm::Double, ..) => Stop::Near,
+                            (Arm::None, Arm::None, Arm::Double) => Stop::Far,
+                            _ => Stop::Center,
+                        };
+
+                        let offset = offset * self.light;
+                        self.draw_arm(direction, self.light, offset, stop, widest);
+                    }
+                },
+                _ => {
+                    let doubles = sides.iter().filter(|&&side| side == Arm::Double).count();
+                    let stop = match (opposite, doubles) {
+                        (Arm::None, 2) => Stop::Near,
+                        (Arm::None, 1) => Stop::Far,
+                        _ => Stop::Center,
+                    };
+
+                    self.draw_arm(direction, self.thickness(arm), 0, stop, widest);
+                },
+            }
+        }
+    }
+
+    /// Draw a single line from the center towards the edge in `direction`
+    ///
+    /// Directions start at the top and go clockwise. Negative offsets move the line towards
+    /// the previous direction, positive offsets towards the next one.
+    fn draw_arm(
+        &mut self,
+        direction: usize,
+        thickness: i32,
+        offset: i32,
+        stop: Stop,
+        widest: i32,
+    ) {
+        let vertical = direction % 2 == 0;
+        let (across, along) =
+            if vertical { (self.width, self.height) } else { (self.height, self.width) };
+
+        // The previous direction is on the right for lines going down, and below for lines
+        // going left
+        let offset = if direction >= 2 { -offset } else { offset };
+        let start = Self::centered(across, thickness) + offset;
+
+        // Start of lines going right or down, and end of lines going left or up
+        let gap = self.light;
+        let center = Self::centered(along, self.light);
+        let (after, before) = match stop {
+            Stop::Center => {
+                let center = Self::centered(along, widest);
+                (center, center + widest)
+            },
+            Stop::Near => (center + gap, center - gap + self.light),
+            Stop::Far => (center - gap, center + gap + self.light),
+        };
+
+        let (from, to) =
+            if direction == 1 || direction == 2 { (after, along) } else { (0, before) };
+
+        if vertical {
+            self.fill_rect(start, from, start + thickness, to, 255);
+        } else {
+            self.fill_rect(from, start, to, start + thickness, 255);
+        }
+    }
+
+    /// Draw a straight line interrupted by `dashes` gaps
+    fn draw_dashes(&mut self, arms: [Arm; 4], dashes: i32) {
+        let vertical = arms[1] == Arm::None;
+        let thickness = self.thickness(if vertical { arms[0] } else { arms[1] });
+        let along = if vertical { self.height } else { self.width };
+        let across = if vertical { self.width } else { self.height };
+        let start = Self::centered(across, thickness);
+
+        // Gaps are split between both ends of a dash, so dashes stay even across cells
+        let dash = along as f32 / dashes as f32;
+        let gap = (dash / 3.).max(1.);
+        for i in 0..dashes {
+            let from = (i as f32 * dash + gap / 2.).round() as i32;
+            let to = ((i + 1) as f32 * dash - gap / 2.).round() as i32;
+            if vertical {
+                self.fill_rect(start, from, start + thickness, to, 255);
+            } else {
+                self.fill_rect(from, start, to, start + thickness, 255);
+            }
+        }
+    }
+
+    /// Draw a rounded corner connecting the edges in the horizontal and vertical direction
+    ///
+    /// A positive `dx` points to the right edge, a positive `dy` to the bottom edge.
+    fn draw_arc(&mut self, dx: f32, dy: f32) {
+        let thickness = self.light;
+        let x_start = Self::centered(self.width, thickness);
+        let y_start = Self::centered(self.height, thickness);
+
+        let half = thickness as f32 / 2.;
+        let x_center = x_start as f32 + half;
+        let y_center = y_start as f32 + half;
+        let radius_x = if dx > 0. { self.width as f32 - x_center } else { x_center };
+        let radius_y = if dy > 0. { self.height as f32 - y_center } else { y_center };
+        let radius = radius_x.min(radius_y);
+
+        let circle_x = x_center + dx * radius;
+        let circle_y = y_center + dy * radius;
+        self.fill_with(|x, y| {
+            if (x - circle_x) * dx > 0. || (y - circle_y) * dy > 0. {
+                return 0.;
+            }
+
+            let distance = ((x - circle_x).powi(2) + (y - circle_y).powi(2)).sqrt();
+            half - (distance - radius).abs() + 0.5
+        });
+
+        // Straight lines from the end of the arc to the edges
+        let circle_x = circle_x.round() as i32;
+        let circle_y = circle_y.round() as i32;
+        let x_end = x_start + thickness;
+        let y_end = y_start + thickness;
+        if dy > 0. {
+            self.fill_rect(x_start, circle_y, x_end, self.height, 255);
+        } else {
+            self.fill_rect(x_start, 0, x_end, circle_y, 255);
+        }
+        if dx > 0. {
+            self.fill_rect(circle_x, y_start, self.width, y_end, 255);
+        } else {
+            self.fill_rect(0, y_start, circle_x, y_end, 255);
+        }
+    }
+
+    /// Draw a line between opposite corners, starting at the top left when `falling` is set
+    fn draw_diagonal(&mut self, falling: bool) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let half = self.light as f32 / 2.;
+        let (start, end) = if falling {
+            ((0., 0.), (width, height))
+        } else {
+            ((width, 0.), (0., height))
+        };
+
+        self.fill_with(|x, y| half - segment_distance((x, y), start, end) + 0.5);
+    }
+
+    /// Draw U+2580..=U+259F
+    fn draw_block(&mut self, c: u32) {
+        let (width, height) = (self.width, self.height);
+        let x_eighth = |eighths: i32| (width as f32 * eighths as f32 / 8.).round() as i32;
+        let y_eighth = |eighths: i32| (height as f32 * eighths as f32 / 8.).round() as i32;
+        let (x_half, y_half) = (x_eighth(4), y_eighth(4));
+
+        match c {
+            0x2580 => self.fill_rect(0, 0, width, y_half, 255),
+            0x2581..=0x2588 => {
+                let eighths = (c - 0x2580) as i32;
+                self.fill_rect(0, y_eighth(8 - eighths), width, height, 255);
+            },
+            0x2589..=0x258f => {
+                let eighths = (0x2590 - c) as i32;
+                self.fill_rect(0, 0, x_eighth(eighths), height, 255);
+            },
+            0x2590 => self.fill_rect(x_half, 0, width, height, 255),
+            // Shades
+            0x2591..=0x2593 => {
+                let coverage = (c - 0x2590) as u8 * 0x40;
+                self.fill_rect(0, 0, width, height, coverage);
+            },
+            0x2594 => self.fill_rect(0, 0, width, y_eighth(1), 255),
+            0x2595 => self.fill_rect(x_eighth(7), 0, width, height, 255),
+            // Quadrants
+            _ => {
+                let (upper_left, upper_right, lower_left, lower_right) = match c {
+                    0x2596 => (false, false, true, false),
+                    0x2597 => (false, false, false, true),
+                    0x2598 => (true, false, false, false),
+                    0x2599 => (true, false, true, true),
+                    0x259a => (true, false, false, true),
+                    0x259b => (true, true, true, false),
+                    0x259c => (true, true, false, true),
+                    0x259d => (false, true, false, false),
+                    0x259e => (false, true, true, false),
+                    _ => (false, true, true, true),
+                };
+
+                if upper_left {
+                    self.fill_rect(0, 0, x_half, y_half, 255);
+                }
+                if upper_right {
+                    self.fill_rect(x_half, 0, width, y_half, 255);
+                }
+                if lower_left {
+                    self.fill_rect(0, y_half, x_half, height, 255);
+                }
+                if lower_right {
+                    self.fill_rect(x_half, y_half, width, height, 255);
+                }
+            },
+        }
+    }
+
+    /// Draw a solid triangle pointing right, or left when `mirrored` is set
+    fn draw_triangle(&mut self, mirrored: bool) {
+        let (width, height) = (self.width as f32, self.height as f32);
+
+        // Average over a few samples per pixel to smooth the slanted edges
+        const SAMPLES: usize = 4;
+        self.fill_with(|x, y| {
+            let mut inside = 0;
+            for i in 0..SAMPLES {
+                for j in 0..SAMPLES {
+                    let sample_x = x - 0.5 + (i as f32 + 0.5) / SAMPLES as f32;
+                    let sample_y = y - 0.5 + (j as f32 + 0.5) / SAMPLES as f32;
+
+                    let sample_x = if mirrored { width - sample_x } else { sample_x };
+                    if sample_x <= width * (1. - (2. * sample_y / height - 1.).abs()) {
+                        inside += 1;
+                    }
+                }
+            }
+            inside as f32 / (SAMPLES * SAMPLES) as f32
+        });
+    }
+
+    /// Draw the outline of a triangle pointing right, or left when `mirrored` is set
+    fn draw_arrow(&mut self, mirrored: bool) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let half = self.light as f32 / 2.;
+        let (edge, tip) = if mirrored { (width, 0.) } else { (0., width) };
+
+        self.fill_with(|x, y| {
+            let upper = segment_distance((x, y), (edge, 0.), (tip, height / 2.));
+            let lower = segment_distance((x, y), (tip, height / 2.), (edge, height));
+            half - upper.min(lower) + 0.5
+        });
+    }
+}
+
+/// Distance between `point` and the line segment from `start` to `end`
+fn segment_distance(point: (f32, f32), start: (f32, f32), end: (f32, f32)) -> f32 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0. {
+        0.
+    } else {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length).max(0.).min(1.)
+    };
+
+    let (x, y) = (start.0 + t * dx, start.1 + t * dy);
+    ((point.0 - x).powi(2) + (point.1 - y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use font::{BitmapBuffer, Metrics, RasterizedGlyph};
+
+    use super::builtin_glyph;
+    use crate::config::Delta;
+
+    fn metrics() -> Metrics {
+        Metrics {
+            average_advance: 9.,
+            line_height: 18.,
+            descent: -4.,
+            underline_position: -2.,
+            underline_thickness: 1.,
+            strikeout_position: 4.,
+            strikeout_thickness: 1.,
+        }
+    }
+
+    fn glyph(c: char) -> RasterizedGlyph {
+        builtin_glyph(c, &metrics(), Delta::default()).unwrap()
+    }
+
+    /// Coverage of the pixel in the given column and row
+    fn pixel(glyph: &RasterizedGlyph, x: i32, y: i32) -> u8 {
+        match &glyph.buf {
+            BitmapBuffer::RGB(buf) => buf[((y * glyph.width + x) * 3) as usize],
+            BitmapBuffer::RGBA(_) => panic!("builtin glyphs are not colored"),
+        }
+    }
+
+    #[test]
+    fn fills_cell() {
+        let glyph = glyph('█');
+        assert_eq!((glyph.width, glyph.height, glyph.top, glyph.left), (9, 18, 18, 0));
+        assert!(glyph.buf.bytes().iter().all(|&coverage| coverage == 255));
+    }
+
+    #[test]
+    fn cell_offset() {
+        let glyph = builtin_glyph('─', &metrics(), Delta { x: 2, y: -1 }).unwrap();
+        assert_eq!((glyph.width, glyph.height), (11, 17));
+    }
+
+    #[test]
+    fn lines_reach_edges() {
+        let horizontal = glyph('─');
+        assert_eq!(pixel(&horizontal, 0, 8), 255);
+        assert_eq!(pixel(&horizontal, 8, 8), 255);
+        assert_eq!(pixel(&horizontal, 0, 0), 0);
+
+        let vertical = glyph('│');
+        assert_eq!(pixel(&vertical, 4, 0), 255);
+        assert_eq!(pixel(&vertical, 4, 17), 255);
+        assert_eq!(pixel(&vertical, 0, 0), 0);
+    }
+
+    #[test]
+    fn crossings_line_up() {
+        let cross = glyph('┼');
+        let vertical = glyph('│');
+        let horizontal = glyph('─');
+        for y in 0..18 {
+            for x in 0..9 {
+                let expected = pixel(&vertical, x, y).max(pixel(&horizontal, x, y));
+                assert_eq!(pixel(&cross, x, y), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn double_corner_keeps_gap() {
+        let corner = glyph('╔');
+
+        // Outer line goes around the corner, the inner one starts after the gap
+        assert_eq!(pixel(&corner, 3, 7), 255);
+        assert_eq!(pixel(&corner, 4, 8), 0);
+        assert_eq!(pixel(&corner, 5, 9), 255);
+    }
+
+    #[test]
+    fn unsupported_characters() {
+        assert!(builtin_glyph('a', &metrics(), Delta::default()).is_none());
+        assert!(builtin_glyph('\u{2600}', &metrics(), Delta::default()).is_none());
+    }
+}
@@ -11,37 +11,47 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::hash::BuildHasherDefault;
 use std::io::{self, Read};
-use std::mem::size_of;
+use std::mem::{self, size_of};
 use std::path::PathBuf;
 use std::ptr;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::time::Duration;
 
 use fnv::FnvHasher;
-use font::{self, FontDesc, FontKey, GlyphKey, Rasterize, RasterizedGlyph, Rasterizer};
+use font::{
+    self, BitmapBuffer, FontDesc, FontKey, GlyphKey, Rasterize, RasterizedGlyph, Rasterizer,
+};
 use glutin::dpi::PhysicalSize;
 use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
 
-use crate::config::{self, Config, Delta};
+use crate::config::{self, Config, Delta, RenderingMode};
 use crate::cursor::{get_cursor_glyph, CursorKey};
 use crate::gl;
 use crate::gl::types::*;
 use crate::index::{Column, Line};
-use crate::renderer::rects::{Rect, Rects};
+use crate::renderer::packing::{Packer, SkylinePacker};
+use crate::renderer::rects::{Rect, RenderRect};
+use crate::renderer::stats::{FrameStats, GpuTimer};
 use crate::term::color::Rgb;
+use crate::term::images::{Image, ImageCell, ImageId};
 use crate::term::{self, cell, RenderableCell, RenderableCellContent};
 
+mod builtin_font;
+mod packing;
 pub mod rects;
+pub mod stats;
 
 // Shader paths for live reload
 static TEXT_SHADER_F_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/text.f.glsl");
 static TEXT_SHADER_V_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/text.v.glsl");
 static RECT_SHADER_F_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.f.glsl");
 static RECT_SHADER_V_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.v.glsl");
+static IMAGE_SHADER_F_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/image.f.glsl");
+static IMAGE_SHADER_V_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/image.v.glsl");
 
 // Shader source which is used when live-shader-reload feature is disable
 static TEXT_SHADER_F: &str =
@@ -52,11 +62,18 @@ static RECT_SHADER_F: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.f.glsl"));
 static RECT_SHADER_V: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.v.glsl"));
+static IMAGE_SHADER_F: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/image.f.glsl"));
+static IMAGE_SHADER_V: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/image.v.glsl"));
 
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory
 pub trait LoadGlyph {
+    /// Handle to a loaded glyph, describing where the backend stored it
+    type Glyph;
+
     /// Load the rasterized glyph into GPU memory
-    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph;
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Self::Glyph;
 
     /// Clear any state accumulated from previous loaded glyphs
     ///
@@ -134,6 +151,14 @@ pub struct RectShaderProgram {
     u_color: GLint,
 }
 
+/// Program drawing textured quads of images
+#[derive(Debug)]
+pub struct ImageShaderProgram {
+    // Program id
+    id: GLuint,
+}
+
+/// Glyph loaded into one of the OpenGL texture atlases
 #[derive(Copy, Debug, Clone)]
 pub struct Glyph {
     tex_id: GLuint,
@@ -145,18 +170,38 @@ pub struct Glyph {
     uv_left: f32,
     uv_width: f32,
     uv_height: f32,
+
+    /// Glyph has its own colors instead of being drawn in the text color
+    colored: bool,
 }
 
+/// Rasterized glyphs which can be uploaded again without the rasterizer
+type RasterizedGlyphs = HashMap<GlyphKey, RasterizedGlyph, BuildHasherDefault<FnvHasher>>;
+
 /// Naïve glyph cache
 ///
 /// Currently only keyed by `char`, and thus not possible to hold different
 /// representations of the same code point.
-pub struct GlyphCache {
+///
+/// The cached glyphs are the handles returned by the backend's `LoadGlyph` implementation.
+pub struct GlyphCache<G = Glyph> {
     /// Cache of buffered glyphs
-    cache: HashMap<GlyphKey, Glyph, BuildHasherDefault<FnvHasher>>,
+    cache: HashMap<GlyphKey, G, BuildHasherDefault<FnvHasher>>,
+
+    /// Rasterized glyphs of the current font size
+    rasterized: RasterizedGlyphs,
+
+    /// Rasterized glyphs of recently used font sizes and DPRs, most recent last
+    recent_sizes: VecDeque<((font::Size, u64), RasterizedGlyphs)>,
+
+    /// Font config the rasterized glyphs were created with
+    font: config::Font,
+
+    /// Device pixel ratio the current glyphs are rasterized for
+    dpr: f64,
 
     /// Cache of buffered cursor glyphs
-    cursor_cache: HashMap<CursorKey, Glyph, BuildHasherDefault<FnvHasher>>,
+    cursor_cache: HashMap<CursorKey, G, BuildHasherDefault<FnvHasher>>,
 
     /// Rasterizer for loading new glyphs
     rasterizer: Rasterizer,
@@ -170,6 +215,11 @@ pub struct GlyphCache {
     /// bold font
     bold_key: FontKey,
 
+    /// Fallback fonts of the regular, bold and italic font, in the order they are tried
+    ///
+    /// These are only loaded once the first glyph is missing from the primary fonts.
+    fallback_keys: Option<HashMap<FontKey, Vec<FontKey>, BuildHasherDefault<FnvHasher>>>,
+
     /// font size
     font_size: font::Size,
 
@@ -179,14 +229,15 @@ pub struct GlyphCache {
     metrics: ::font::Metrics,
 }
 
-impl GlyphCache {
+impl<G> GlyphCache<G> {
     pub fn new<L>(
         mut rasterizer: Rasterizer,
         font: &config::Font,
+        dpr: f64,
         loader: &mut L,
-    ) -> Result<GlyphCache, font::Error>
+    ) -> Result<GlyphCache<G>, font::Error>
     where
-        L: LoadGlyph,
+        L: LoadGlyph<Glyph = G>,
     {
         let (regular, bold, italic) = Self::compute_font_keys(font, &mut rasterizer)?;
 
@@ -199,12 +250,17 @@ impl GlyphCache {
 
         let mut cache = GlyphCache {
             cache: HashMap::default(),
+            rasterized: HashMap::default(),
+            recent_sizes: VecDeque::new(),
+            font: font.clone(),
+            dpr,
             cursor_cache: HashMap::default(),
             rasterizer,
             font_size: font.size,
             font_key: regular,
             bold_key: bold,
             italic_key: italic,
+            fallback_keys: None,
             glyph_offset: font.glyph_offset,
             metrics,
         };
@@ -216,7 +272,10 @@ impl GlyphCache {
         Ok(cache)
     }
 
-    fn load_glyphs_for_font<L: LoadGlyph>(&mut self, font: FontKey, loader: &mut L) {
+    fn load_glyphs_for_font<L>(&mut self, font: FontKey, loader: &mut L)
+    where
+        L: LoadGlyph<Glyph = G>,
+    {
         let size = self.font_size;
         for i in 32u8..=128u8 {
             self.get(GlyphKey { font_key: font, c: i as char, size }, loader);
@@ -259,6 +318,36 @@ impl GlyphCache {
         Ok((regular, bold, italic))
     }
 
+    /// Load the fallback fonts for each of the regular, bold and italic font
+    fn compute_fallback_keys(
+        font: &config::Font,
+        size: font::Size,
+        rasterizer: &mut Rasterizer,
+        (regular, bold, italic): (FontKey, FontKey, FontKey),
+    ) -> HashMap<FontKey, Vec<FontKey>, BuildHasherDefault<FnvHasher>> {
+        let styles = [
+            (regular, font::Slant::Normal, font::Weight::Normal),
+            (bold, font::Slant::Normal, font::Weight::Bold),
+            (italic, font::Slant::Italic, font::Weight::Normal),
+        ];
+
+        let mut fallback_keys = HashMap::default();
+        for &(primary, slant, weight) in &styles {
+            let mut keys = Vec::new();
+            for desc in font.fallback() {
+                let desc = Self::make_desc(desc, slant, weight);
+                match rasterizer.load_font(&desc, size) {
+                    Ok(key) if key != primary && !keys.contains(&key) => keys.push(key),
+                    Ok(_) => (),
+                    Err(err) => warn!("Unable to load fallback font with {}: {}", desc, err),
+                }
+            }
+            fallback_keys.insert(primary, keys);
+        }
+
+        fallback_keys
+    }
+
     fn make_desc(
         desc: &config::FontDescription,
         slant: font::Slant,
@@ -269,7 +358,17 @@ impl GlyphCache {
         } else {
             font::Style::Description { slant, weight }
         };
-        FontDesc::new(desc.family.clone(), style)
+        FontDesc::new(desc.family.clone(), style).with_variations(desc.variations.0.clone())
+    }
+
+    /// Rendering mode of the cached glyphs
+    pub fn rendering_mode(&self) -> RenderingMode {
+        self.font.rendering_mode
+    }
+
+    /// Whether box drawing characters are generated instead of taken from the font
+    pub fn builtin_box_drawing(&self) -> bool {
+        self.font.builtin_box_drawing()
     }
 
     pub fn font_metrics(&self) -> font::Metrics {
@@ -278,43 +377,113 @@ impl GlyphCache {
             .expect("metrics load since font is loaded at glyph cache creation")
     }
 
-    pub fn get<'a, L>(&'a mut self, glyph_key: GlyphKey, loader: &mut L) -> &'a Glyph
+    pub fn get<'a, L>(&'a mut self, glyph_key: GlyphKey, loader: &mut L) -> &'a G
     where
-        L: LoadGlyph,
+        L: LoadGlyph<Glyph = G>,
     {
         let glyph_offset = self.glyph_offset;
+        let rendering_mode = self.font.rendering_mode;
+        let builtin_box_drawing = self.font.builtin_box_drawing();
+        let cell_offset = self.font.offset;
         let rasterizer = &mut self.rasterizer;
+        let rasterized_glyphs = &mut self.rasterized;
+        let fallback_keys = &mut self.fallback_keys;
+        let font = &self.font;
+        let font_size = self.font_size;
+        let primary_keys = (self.font_key, self.bold_key, self.italic_key);
         let metrics = &self.metrics;
         self.cache.entry(glyph_key).or_insert_with(|| {
-            let mut rasterized =
-                rasterizer.get_glyph(glyph_key).unwrap_or_else(|_| Default::default());
+            let rasterized = rasterized_glyphs.entry(glyph_key).or_insert_with(|| {
+                // Glyphs connecting to their neighbours are generated to fill the cell
+                let builtin = if builtin_box_drawing {
+                    builtin_font::builtin_glyph(glyph_key.c, metrics, cell_offset)
+                } else {
+                    None
+                };
+                if let Some(builtin) = builtin {
+                    return builtin;
+                }
+
+                // Configured fallback fonts are tried before the system's fallback
+                let font_key = if font.fallback().is_empty()
+                    || rasterizer.has_glyph(glyph_key.font_key, glyph_key.c)
+                {
+                    glyph_key.font_key
+                } else {
+                    let fallback_keys = fallback_keys.get_or_insert_with(|| {
+                        Self::compute_fallback_keys(font, font_size, rasterizer, primary_keys)
+                    });
+                    fallback_keys
+                        .get(&glyph_key.font_key)
+                        .and_then(|keys| {
+                            keys.iter().cloned().find(|&key| rasterizer.has_glyph(key, glyph_key.c))
+                        })
+                        .unwrap_or(glyph_key.font_key)
+                };
+
+                let mut rasterized = rasterizer
+                    .get_glyph(GlyphKey { font_key, ..glyph_key })
+                    .unwrap_or_else(|_| Default::default());
+
+                rasterized.left += i32::from(glyph_offset.x);
+                rasterized.top += i32::from(glyph_offset.y);
+                rasterized.top -= metrics.descent as i32;
+
+                // Colored glyphs are not drawn as coverage masks
+                if let (RenderingMode::Grayscale, BitmapBuffer::RGB(buf)) =
+                    (rendering_mode, &mut rasterized.buf)
+                {
+                    to_grayscale(buf);
+                }
 
-            rasterized.left += i32::from(glyph_offset.x);
-            rasterized.top += i32::from(glyph_offset.y);
-            rasterized.top -= metrics.descent as i32;
+                rasterized
+            });
 
-            loader.load_glyph(&rasterized)
+            loader.load_glyph(rasterized)
         })
     }
 
-    pub fn update_font_size<L: LoadGlyph>(
+    pub fn update_font_size<L>(
         &mut self,
         font: &config::Font,
         size: font::Size,
         dpr: f64,
         loader: &mut L,
-    ) -> Result<(), font::Error> {
+    ) -> Result<(), font::Error>
+    where
+        L: LoadGlyph<Glyph = G>,
+    {
         // Clear currently cached data in both GL and the registry
         loader.clear();
         self.cache = HashMap::default();
         self.cursor_cache = HashMap::default();
 
+        // Keep the rasterized glyphs of the previous size, unless the font itself changed
+        let rasterized = mem::replace(&mut self.rasterized, HashMap::default());
+        if *font == self.font {
+            self.recent_sizes.push_back(((self.font_size, self.dpr.to_bits()), rasterized));
+            if self.recent_sizes.len() > CACHED_FONT_SIZES {
+                self.recent_sizes.pop_front();
+            }
+        } else {
+            self.recent_sizes.clear();
+            self.font = font.clone();
+        }
+
+        // Reuse the glyphs rasterized for this size before
+        let key = (size, dpr.to_bits());
+        if let Some(index) = self.recent_sizes.iter().position(|(recent, _)| *recent == key) {
+            self.rasterized = self.recent_sizes.remove(index).unwrap().1;
+        }
+
         // Update dpi scaling
         self.rasterizer.update_dpr(dpr as f32);
+        self.dpr = dpr;
 
         // Recompute font keys
         let font = font.to_owned().with_size(size);
         let (regular, bold, italic) = Self::compute_font_keys(&font, &mut self.rasterizer)?;
+        self.fallback_keys = None;
 
         self.rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
         let metrics = self.rasterizer.metrics(regular, size)?;
@@ -333,16 +502,16 @@ impl GlyphCache {
 
         Ok(())
     }
+}
 
+impl GlyphCache {
     // Calculate font metrics without access to a glyph cache
     //
     // This should only be used *before* OpenGL is initialized and the glyph cache can be filled.
-    pub fn static_metrics(config: &Config, dpr: f32) -> Result<font::Metrics, font::Error> {
-        let font = config.font.clone();
-
-        let mut rasterizer = font::Rasterizer::new(dpr, config.font.use_thin_strokes())?;
+    pub fn static_metrics(font: &config::Font, dpr: f32) -> Result<font::Metrics, font::Error> {
+        let mut rasterizer = font::Rasterizer::new(dpr, font.use_thin_strokes())?;
         let regular_desc =
-            GlyphCache::make_desc(&font.normal(), font::Slant::Normal, font::Weight::Normal);
+            Self::make_desc(&font.normal(), font::Slant::Normal, font::Weight::Normal);
         let regular = rasterizer.load_font(&regular_desc, font.size)?;
         rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
 
@@ -350,6 +519,19 @@ impl GlyphCache {
     }
 }
 
+/// Replace the coverage of every color channel with their average
+///
+/// The buffer contains one byte per color channel, with three channels per pixel.
+fn to_grayscale(buf: &mut [u8]) {
+    for pixel in buf.chunks_mut(3) {
+        let sum: u16 = pixel.iter().map(|&channel| u16::from(channel)).sum();
+        let average = (sum / pixel.len() as u16) as u8;
+        for channel in pixel.iter_mut() {
+            *channel = average;
+        }
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 struct InstanceData {
@@ -372,6 +554,8 @@ struct InstanceData {
     r: f32,
     g: f32,
     b: f32,
+    // whether the glyph is colored, ignoring the text color
+    colored: f32,
     // background color
     bg_r: f32,
     bg_g: f32,
@@ -379,6 +563,59 @@ struct InstanceData {
     bg_a: f32,
 }
 
+/// Configuration options used by the renderer
+///
+/// This allows rendering without access to the complete configuration.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderConfig {
+    pub background_opacity: f32,
+
+    /// Color of the window padding, the grid background is used if unset
+    pub padding_color: Option<Rgb>,
+
+    pub visual_bell_color: Rgb,
+
+    /// Offset of the cursor glyphs within their cell
+    pub glyph_offset: Delta<i8>,
+}
+
+impl RenderConfig {
+    pub fn new(config: &Config, capabilities: RendererCapabilities) -> RenderConfig {
+        // Without transparency the window contents are composited as if they were opaque
+        let background_opacity =
+            if capabilities.transparency { config.background_opacity() } else { 1. };
+
+        RenderConfig {
+            background_opacity,
+            padding_color: config.colors.primary.padding,
+            visual_bell_color: config.visual_bell.color,
+            glyph_offset: config.font.offset,
+        }
+    }
+}
+
+/// Features supported by a renderer backend
+///
+/// Higher layers check these to fall back gracefully, instead of assuming what the
+/// backend is able to do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RendererCapabilities {
+    /// The window is blended with whatever is behind it
+    pub transparency: bool,
+
+    /// Glyphs are blended separately for every color channel
+    pub subpixel_antialiasing: bool,
+
+    /// Images can be drawn in addition to glyphs and rectangles
+    pub images: bool,
+
+    /// Only the damaged parts of a frame need to be presented
+    pub incremental_present: bool,
+
+    /// Colors outside of the sRGB range can be presented
+    pub hdr: bool,
+}
+
 #[derive(Debug)]
 pub struct QuadRenderer {
     program: TextShaderProgram,
@@ -388,11 +625,23 @@ pub struct QuadRenderer {
     vbo_instance: GLuint,
     rect_vao: GLuint,
     rect_vbo: GLuint,
+    image_program: ImageShaderProgram,
+    image_vao: GLuint,
+    image_vbo: GLuint,
+
+    /// Textures of the images drawn in the last frame
+    image_textures: HashMap<ImageId, GLuint>,
     atlas: Vec<Atlas>,
     current_atlas: usize,
     active_tex: GLuint,
     batch: Batch,
     rx: mpsc::Receiver<Msg>,
+    capabilities: RendererCapabilities,
+    frame_stats: FrameStats,
+
+    /// Timer for the GPU time of frames, only created while frames are timed
+    gpu_timer: Option<GpuTimer>,
+    timing: bool,
 }
 
 #[derive(Debug)]
@@ -402,7 +651,8 @@ pub struct RenderApi<'a> {
     atlas: &'a mut Vec<Atlas>,
     current_atlas: &'a mut usize,
     program: &'a mut TextShaderProgram,
-    config: &'a Config,
+    config: &'a RenderConfig,
+    stats: &'a mut FrameStats,
 }
 
 #[derive(Debug)]
@@ -452,11 +702,12 @@ impl Batch {
             r: f32::from(cell.fg.r),
             g: f32::from(cell.fg.g),
             b: f32::from(cell.fg.b),
+            colored: if glyph.colored { 1. } else { 0. },
 
             bg_r: f32::from(cell.bg.r),
             bg_g: f32::from(cell.bg.g),
             bg_b: f32::from(cell.bg.b),
-            bg_a: cell.bg_alpha,
+            bg_a: cell.bg_alpha * cell.bg.alpha(),
         });
     }
 
@@ -495,10 +746,17 @@ impl Batch {
 const BATCH_MAX: usize = 0x1_0000;
 const ATLAS_SIZE: i32 = 1024;
 
+/// Number of previous font sizes whose rasterized glyphs are kept around
+const CACHED_FONT_SIZES: usize = 3;
+
 impl QuadRenderer {
-    pub fn new() -> Result<QuadRenderer, Error> {
+    /// Create a renderer for the current OpenGL context
+    ///
+    /// The `transparency` flag indicates if the context's framebuffer has an alpha channel.
+    pub fn new(transparency: bool) -> Result<QuadRenderer, Error> {
         let program = TextShaderProgram::new()?;
         let rect_program = RectShaderProgram::new()?;
+        let image_program = ImageShaderProgram::new()?;
 
         let mut vao: GLuint = 0;
         let mut ebo: GLuint = 0;
@@ -509,6 +767,9 @@ impl QuadRenderer {
         let mut rect_vbo: GLuint = 0;
         let mut rect_ebo: GLuint = 0;
 
+        let mut image_vao: GLuint = 0;
+        let mut image_vbo: GLuint = 0;
+
         unsafe {
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
@@ -581,7 +842,7 @@ impl QuadRenderer {
             // color
             gl::VertexAttribPointer(
                 3,
-                3,
+                4,
                 gl::FLOAT,
                 gl::FALSE,
                 size_of::<InstanceData>() as i32,
@@ -596,7 +857,7 @@ impl QuadRenderer {
                 gl::FLOAT,
                 gl::FALSE,
                 size_of::<InstanceData>() as i32,
-                (13 * size_of::<f32>()) as *const _,
+                (14 * size_of::<f32>()) as *const _,
             );
             gl::EnableVertexAttribArray(4);
             gl::VertexAttribDivisor(4, 1);
@@ -615,6 +876,24 @@ impl QuadRenderer {
                 gl::STATIC_DRAW,
             );
 
+            // Image setup, every vertex has a position and texture coordinates
+            gl::GenVertexArrays(1, &mut image_vao);
+            gl::GenBuffers(1, &mut image_vbo);
+            gl::BindVertexArray(image_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, image_vbo);
+            let stride = (4 * size_of::<f32>()) as i32;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (2 * size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
             // Cleanup
             gl::BindVertexArray(0);
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
@@ -660,11 +939,26 @@ impl QuadRenderer {
             vbo_instance,
             rect_vao,
             rect_vbo,
+            image_program,
+            image_vao,
+            image_vbo,
+            image_textures: HashMap::new(),
             atlas: Vec::new(),
             current_atlas: 0,
             active_tex: 0,
             batch: Batch::new(),
             rx: msg_rx,
+            capabilities: RendererCapabilities {
+                transparency,
+                // The text shader uses dual source blending with per channel coverage
+                subpixel_antialiasing: true,
+                images: true,
+                incremental_present: false,
+                hdr: false,
+            },
+            frame_stats: FrameStats::default(),
+            gpu_timer: None,
+            timing: false,
         };
 
         let atlas = Atlas::new(ATLAS_SIZE);
@@ -673,14 +967,41 @@ impl QuadRenderer {
         Ok(renderer)
     }
 
+    #[inline]
+    pub fn capabilities(&self) -> RendererCapabilities {
+        self.capabilities
+    }
+
+    /// Start collecting the statistics of a new frame
+    ///
+    /// The GPU time is only measured when `timed` is set.
+    pub fn begin_frame(&mut self, timed: bool) {
+        self.frame_stats = FrameStats::default();
+        self.timing = timed;
+
+        if timed {
+            self.gpu_timer.get_or_insert_with(GpuTimer::new).begin();
+        } else {
+            self.gpu_timer = None;
+        }
+    }
+
+    /// Finish the current frame and return its statistics
+    pub fn end_frame(&mut self) -> FrameStats {
+        if let (true, Some(timer)) = (self.timing, self.gpu_timer.as_mut()) {
+            timer.end();
+            self.frame_stats.gpu_time = timer.elapsed();
+        }
+        self.timing = false;
+
+        self.frame_stats
+    }
+
     // Draw all rectangles simultaneously to prevent excessive program swaps
-    pub fn draw_rects(
-        &mut self,
-        config: &Config,
-        props: &term::SizeInfo,
-        visual_bell_intensity: f64,
-        cell_line_rects: Rects,
-    ) {
+    pub fn draw_rects<'r, I>(&mut self, props: &term::SizeInfo, rects: I)
+    where
+        I: IntoIterator<Item = &'r RenderRect>,
+    {
         // Swap to rectangle rendering program
         unsafe {
             // Swap program
@@ -708,14 +1029,9 @@ impl QuadRenderer {
             gl::EnableVertexAttribArray(0);
         }
 
-        // Draw visual bell
-        let color = config.visual_bell.color;
-        let rect = Rect::new(0., 0., props.width, props.height);
-        self.render_rect(&rect, color, visual_bell_intensity as f32, props);
-
-        // Draw underlines and strikeouts
-        for cell_line_rect in cell_line_rects.rects() {
-            self.render_rect(&cell_line_rect.0, cell_line_rect.1, 255., props);
+        // Draw the rects using the opacity of their color
+        for rect in rects {
+            self.render_rect(&rect.rect, rect.color, 1., props);
         }
 
         // Deactivate rectangle program again
@@ -738,7 +1054,92 @@ impl QuadRenderer {
         }
     }
 
-    pub fn with_api<F, T>(&mut self, config: &Config, props: &term::SizeInfo, func: F) -> T
+    /// Draw the parts of the images shown by the cells
+    ///
+    /// Images are uploaded when they are first drawn and released once they are not part of
+    /// a frame anymore.
+    pub fn draw_images(
+        &mut self,
+        props: &term::SizeInfo,
+        images: &[Arc<Image>],
+        cells: &[ImageCell],
+    ) {
+        let visible: HashSet<ImageId> = images.iter().map(|image| image.id).collect();
+        self.image_textures.retain(|id, texture| {
+            let retain = visible.contains(id);
+            if !retain {
+                unsafe { gl::DeleteTextures(1, texture) };
+            }
+            retain
+        });
+
+        if cells.is_empty() {
+            return;
+        }
+
+        unsafe {
+            gl::UseProgram(self.image_program.id);
+
+            // Remove padding from viewport
+            gl::Viewport(0, 0, props.width as i32, props.height as i32);
+
+            // Pixels are not premultiplied
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindVertexArray(self.image_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.image_vbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        let mut vertices: Vec<f32> = Vec::new();
+        for image in images {
+            vertices.clear();
+            for cell in cells.iter().filter(|cell| cell.image == image.id) {
+                push_image_quad(&mut vertices, props, image, cell);
+            }
+            if vertices.is_empty() {
+                continue;
+            }
+
+            let texture =
+                *self.image_textures.entry(image.id).or_insert_with(|| upload_image(image));
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (size_of::<f32>() * vertices.len()) as _,
+                    vertices.as_ptr() as *const _,
+                    gl::STREAM_DRAW,
+                );
+                gl::DrawArrays(gl::TRIANGLES, 0, (vertices.len() / 4) as i32);
+            }
+
+            self.frame_stats.draw_calls += 1;
+            self.frame_stats.quads += vertices.len() / 24;
+        }
+
+        unsafe {
+            // Reset blending strategy
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            // The glyph atlas has to be bound again
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            self.active_tex = 0;
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+
+            let padding_x = props.padding_x as i32;
+            let padding_y = props.padding_y as i32;
+            let width = props.width as i32;
+            let height = props.height as i32;
+            gl::Viewport(padding_x, padding_y, width - 2 * padding_x, height - 2 * padding_y);
+
+            gl::UseProgram(0);
+        }
+    }
+
+    pub fn with_api<F, T>(&mut self, config: &RenderConfig, props: &term::SizeInfo, func: F) -> T
     where
         F: FnOnce(RenderApi<'_>) -> T,
     {
@@ -765,6 +1166,7 @@ impl QuadRenderer {
             current_atlas: &mut self.current_atlas,
             program: &mut self.program,
             config,
+            stats: &mut self.frame_stats,
         });
 
         unsafe {
@@ -795,9 +1197,10 @@ impl QuadRenderer {
 
     pub fn reload_shaders(&mut self, props: &term::SizeInfo) {
         info!("Reloading shaders...");
-        let result = (TextShaderProgram::new(), RectShaderProgram::new());
-        let (program, rect_program) = match result {
-            (Ok(program), Ok(rect_program)) => {
+        let result =
+            (TextShaderProgram::new(), RectShaderProgram::new(), ImageShaderProgram::new());
+        let (program, rect_program, image_program) = match result {
+            (Ok(program), Ok(rect_program), Ok(image_program)) => {
                 unsafe {
                     gl::UseProgram(program.id);
                     program.update_projection(
@@ -810,9 +1213,9 @@ impl QuadRenderer {
                 }
 
                 info!("... successfully reloaded shaders");
-                (program, rect_program)
+                (program, rect_program, image_program)
             },
-            (Err(err), _) | (_, Err(err)) => {
+            (Err(err), ..) | (_, Err(err), _) | (.., Err(err)) => {
                 error!("{}", err);
                 return;
             },
@@ -821,6 +1224,7 @@ impl QuadRenderer {
         self.active_tex = 0;
         self.program = program;
         self.rect_program = rect_program;
+        self.image_program = image_program;
     }
 
     pub fn resize(&mut self, size: PhysicalSize, padding_x: f32, padding_y: f32) {
@@ -851,7 +1255,7 @@ impl QuadRenderer {
     // This requires the rectangle program to be activated
     fn render_rect(&mut self, rect: &Rect<f32>, color: Rgb, alpha: f32, size: &term::SizeInfo) {
         // Do nothing when alpha is fully transparent
-        if alpha == 0. {
+        if alpha == 0. || color.a == 0 {
             return;
         }
 
@@ -881,12 +1285,15 @@ impl QuadRenderer {
             // Draw the rectangle
             gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
         }
+
+        self.frame_stats.draw_calls += 1;
+        self.frame_stats.quads += 1;
     }
 }
 
 impl<'a> RenderApi<'a> {
     pub fn clear(&self, color: Rgb) {
-        let alpha = self.config.background_opacity();
+        let alpha = self.config.background_opacity * color.alpha();
         unsafe {
             gl::ClearColor(
                 (f32::from(color.r) / 255.0).min(1.0) * alpha,
@@ -898,6 +1305,26 @@ impl<'a> RenderApi<'a> {
         }
     }
 
+    /// Clear only the grid, leaving the padding around it untouched
+    pub fn clear_grid(&self, color: Rgb, props: &term::SizeInfo) {
+        let width = props.cols().0 as f32 * props.cell_width;
+        let height = props.lines().0 as f32 * props.cell_height;
+
+        // Scissor coordinates start at the bottom of the window
+        let y = props.height - props.padding_y - height;
+
+        unsafe {
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(props.padding_x as i32, y as i32, width as i32, height as i32);
+        }
+
+        self.clear(color);
+
+        unsafe {
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+    }
+
     fn render_batch(&mut self) {
         unsafe {
             gl::BufferSubData(
@@ -935,6 +1362,10 @@ impl<'a> RenderApi<'a> {
             );
         }
 
+        // Background and foreground are drawn in separate passes
+        self.stats.draw_calls += 2;
+        self.stats.quads += 2 * self.batch.len();
+
         self.batch.clear();
     }
 
@@ -946,9 +1377,20 @@ impl<'a> RenderApi<'a> {
         line: Line,
         glyph_cache: &mut GlyphCache,
         color: Option<Rgb>,
+    ) {
+        self.render_string_at(string, line, Column(0), glyph_cache, color);
+    }
+
+    /// Render a string starting at an arbitrary column
+    pub fn render_string_at(
+        &mut self,
+        string: &str,
+        line: Line,
+        col: Column,
+        glyph_cache: &mut GlyphCache,
+        color: Option<Rgb>,
     ) {
         let bg_alpha = color.map(|_| 1.0).unwrap_or(0.0);
-        let col = Column(0);
 
         let cells = string
             .chars()
@@ -961,8 +1403,8 @@ impl<'a> RenderApi<'a> {
                     chars[0] = c;
                     chars
                 }),
-                bg: color.unwrap_or(Rgb { r: 0, g: 0, b: 0 }),
-                fg: Rgb { r: 0, g: 0, b: 0 },
+                bg: color.unwrap_or(Rgb::new(0, 0, 0)),
+                fg: Rgb::new(0, 0, 0),
                 flags: cell::Flags::empty(),
                 bg_alpha,
             })
@@ -994,8 +1436,8 @@ impl<'a> RenderApi<'a> {
                 // Raw cell pixel buffers like cursors don't need to go through font lookup
                 let metrics = glyph_cache.metrics;
                 let glyph = glyph_cache.cursor_cache.entry(cursor_key).or_insert_with(|| {
-                    let offset_x = self.config.font.offset.x;
-                    let offset_y = self.config.font.offset.y;
+                    let offset_x = self.config.glyph_offset.x;
+                    let offset_y = self.config.glyph_offset.y;
 
                     self.load_glyph(&get_cursor_glyph(
                         cursor_key.style,
@@ -1073,6 +1515,8 @@ fn load_glyph(
         Err(AtlasInsertError::Full) => {
             *current_atlas += 1;
             if *current_atlas == atlas.len() {
+                let occupancy = atlas[*current_atlas - 1].occupancy() * 100.;
+                debug!("Atlas full at {:.1}% occupancy, creating a new one", occupancy);
                 let new = Atlas::new(ATLAS_SIZE);
                 *active_tex = 0; // Atlas::new binds a texture. Ugh this is sloppy.
                 atlas.push(new);
@@ -1089,6 +1533,7 @@ fn load_glyph(
             uv_left: 0.0,
             uv_width: 0.0,
             uv_height: 0.0,
+            colored: false,
         },
     }
 }
@@ -1102,6 +1547,8 @@ fn clear_atlas(atlas: &mut Vec<Atlas>, current_atlas: &mut usize) {
 }
 
 impl<'a> LoadGlyph for LoaderApi<'a> {
+    type Glyph = Glyph;
+
     fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
         load_glyph(self.active_tex, self.atlas, self.current_atlas, rasterized)
     }
@@ -1112,6 +1559,8 @@ impl<'a> LoadGlyph for LoaderApi<'a> {
 }
 
 impl<'a> LoadGlyph for RenderApi<'a> {
+    type Glyph = Glyph;
+
     fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
         load_glyph(self.active_tex, self.atlas, self.current_atlas, rasterized)
     }
@@ -1265,7 +1714,7 @@ impl RectShaderProgram {
                 f32::from(color.r) / 255.,
                 f32::from(color.g) / 255.,
                 f32::from(color.b) / 255.,
-                alpha,
+                alpha * color.alpha(),
             );
         }
     }
@@ -1279,6 +1728,105 @@ impl Drop for RectShaderProgram {
     }
 }
 
+impl ImageShaderProgram {
+    pub fn new() -> Result<Self, ShaderCreationError> {
+        let (vertex_src, fragment_src) = if cfg!(feature = "live-shader-reload") {
+            (None, None)
+        } else {
+            (Some(IMAGE_SHADER_V), Some(IMAGE_SHADER_F))
+        };
+        let vertex_shader = create_shader(IMAGE_SHADER_V_PATH, gl::VERTEX_SHADER, vertex_src)?;
+        let fragment_shader =
+            create_shader(IMAGE_SHADER_F_PATH, gl::FRAGMENT_SHADER, fragment_src)?;
+        let program = create_program(vertex_shader, fragment_shader)?;
+
+        unsafe {
+            gl::DeleteShader(fragment_shader);
+            gl::DeleteShader(vertex_shader);
+        }
+
+        Ok(ImageShaderProgram { id: program })
+    }
+}
+
+impl Drop for ImageShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
+/// Upload the pixels of an image into a new texture
+fn upload_image(image: &Image) -> GLuint {
+    let mut pixels = Vec::with_capacity(image.pixels.len() * 4);
+    for pixel in &image.pixels {
+        pixels.extend_from_slice(&[pixel.r, pixel.g, pixel.b, pixel.a]);
+    }
+
+    let mut texture: GLuint = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as i32,
+            image.width as i32,
+            image.height as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_ptr() as *const _,
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+    }
+
+    texture
+}
+
+/// Add the two triangles covering the part of an image shown by a cell
+fn push_image_quad(
+    vertices: &mut Vec<f32>,
+    size: &term::SizeInfo,
+    image: &Image,
+    cell: &ImageCell,
+) {
+    // Part of the image in pixels, cells at the right and bottom edge are only partly covered
+    let src_x = cell.offset as f32 * size.cell_width;
+    let src_y = cell.row as f32 * size.cell_height;
+    let width = size.cell_width.min(image.width as f32 - src_x);
+    let height = size.cell_height.min(image.height as f32 - src_y);
+    if width <= 0. || height <= 0. {
+        return;
+    }
+
+    let x = size.padding_x + cell.col.0 as f32 * size.cell_width;
+    let y = size.padding_y + cell.line.0 as f32 * size.cell_height;
+    let left = x / size.width * 2. - 1.;
+    let right = (x + width) / size.width * 2. - 1.;
+    let top = 1. - y / size.height * 2.;
+    let bottom = 1. - (y + height) / size.height * 2.;
+
+    let u_left = src_x / image.width as f32;
+    let u_right = (src_x + width) / image.width as f32;
+    let v_top = src_y / image.height as f32;
+    let v_bottom = (src_y + height) / image.height as f32;
+
+    #[rustfmt::skip]
+    vertices.extend_from_slice(&[
+        left, top, u_left, v_top,
+        right, top, u_right, v_top,
+        left, bottom, u_left, v_bottom,
+        right, top, u_right, v_top,
+        right, bottom, u_right, v_bottom,
+        left, bottom, u_left, v_bottom,
+    ]);
+}
+
 fn create_program(vertex: GLuint, fragment: GLuint) -> Result<GLuint, ShaderCreationError> {
     unsafe {
         let program = gl::CreateProgram();
@@ -1442,22 +1990,8 @@ impl From<io::Error> for ShaderCreationError {
 
 /// Manages a single texture atlas
 ///
-/// The strategy for filling an atlas looks roughly like this:
-///
-/// ```ignore
-///                           (width, height)
-///   ┌─────┬─────┬─────┬─────┬─────┐
-///   │ 10  │     │     │     │     │ <- Empty spaces; can be filled while
-///   │     │     │     │     │     │    glyph_height < height - row_baseline
-///   ├⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┤
-///   │ 5   │ 6   │ 7   │ 8   │ 9   │
-///   │     │     │     │     │     │
-///   ├⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┴⎼⎼⎼⎼⎼┤ <- Row height is tallest glyph in row; this is
-///   │ 1   │ 2   │ 3   │ 4         │    used as the baseline for the following row.
-///   │     │     │     │           │ <- Row considered full when next glyph doesn't
-///   └─────┴─────┴─────┴───────────┘    fit in the row.
-/// (0, 0)  x->
-/// ```
+/// Glyphs are placed by a skyline packer, since it wastes less space than packing glyphs into
+/// rows when glyphs of different heights are mixed. The `packing` benchmarks compare both.
 #[derive(Debug)]
 struct Atlas {
     /// Texture id for this atlas
@@ -1469,19 +2003,8 @@ struct Atlas {
     /// Height of atlas
     height: i32,
 
-    /// Left-most free pixel in a row.
-    ///
-    /// This is called the extent because it is the upper bound of used pixels
-    /// in a row.
-    row_extent: i32,
-
-    /// Baseline for glyphs in the current row
-    row_baseline: i32,
-
-    /// Tallest glyph in current row
-    ///
-    /// This is used as the advance when end of row is reached
-    row_tallest: i32,
+    /// Allocator for the space within the atlas
+    packer: Box<dyn Packer>,
 }
 
 /// Error that can happen when inserting a texture to the Atlas
@@ -1503,11 +2026,11 @@ impl Atlas {
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGB as i32,
+                gl::RGBA as i32,
                 size,
                 size,
                 0,
-                gl::RGB,
+                gl::RGBA,
                 gl::UNSIGNED_BYTE,
                 ptr::null(),
             );
@@ -1520,13 +2043,17 @@ impl Atlas {
             gl::BindTexture(gl::TEXTURE_2D, 0);
         }
 
-        Atlas { id, width: size, height: size, row_extent: 0, row_baseline: 0, row_tallest: 0 }
+        let packer = Box::new(SkylinePacker::new(size, size));
+        Atlas { id, width: size, height: size, packer }
     }
 
     pub fn clear(&mut self) {
-        self.row_extent = 0;
-        self.row_baseline = 0;
-        self.row_tallest = 0;
+        self.packer.clear();
+    }
+
+    /// Fraction of the atlas which is covered by glyphs
+    pub fn occupancy(&self) -> f32 {
+        self.packer.occupancy()
     }
 
     /// Insert a RasterizedGlyph into the texture atlas
@@ -1539,31 +2066,27 @@ impl Atlas {
             return Err(AtlasInsertError::GlyphTooLarge);
         }
 
-        // If there's not enough room in current row, go onto next one
-        if !self.room_in_row(glyph) {
-            self.advance_row()?;
-        }
-
-        // If there's still not room, there's nothing that can be done here.
-        if !self.room_in_row(glyph) {
-            return Err(AtlasInsertError::Full);
+        match self.packer.pack(glyph.width, glyph.height) {
+            Some(offset) => Ok(self.insert_inner(glyph, offset, active_tex)),
+            None => Err(AtlasInsertError::Full),
         }
-
-        // There appears to be room; load the glyph.
-        Ok(self.insert_inner(glyph, active_tex))
     }
 
-    /// Insert the glyph without checking for room
-    ///
-    /// Internal function for use once atlas has been checked for space. GL
-    /// errors could still occur at this point if we were checking for them;
-    /// hence, the Result.
-    fn insert_inner(&mut self, glyph: &RasterizedGlyph, active_tex: &mut u32) -> Glyph {
-        let offset_y = self.row_baseline;
-        let offset_x = self.row_extent;
+    /// Upload the glyph at an offset reserved by the packer
+    fn insert_inner(
+        &mut self,
+        glyph: &RasterizedGlyph,
+        (offset_x, offset_y): (i32, i32),
+        active_tex: &mut u32,
+    ) -> Glyph {
         let height = glyph.height as i32;
         let width = glyph.width as i32;
 
+        let (format, buf) = match &glyph.buf {
+            BitmapBuffer::RGB(buf) => (gl::RGB, buf),
+            BitmapBuffer::RGBA(buf) => (gl::RGBA, buf),
+        };
+
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
 
@@ -1575,21 +2098,15 @@ impl Atlas {
                 offset_y,
                 width,
                 height,
-                gl::RGB,
+                format,
                 gl::UNSIGNED_BYTE,
-                glyph.buf.as_ptr() as *const _,
+                buf.as_ptr() as *const _,
             );
 
             gl::BindTexture(gl::TEXTURE_2D, 0);
             *active_tex = 0;
         }
 
-        // Update Atlas state
-        self.row_extent = offset_x + width;
-        if height > self.row_tallest {
-            self.row_tallest = height;
-        }
-
         // Generate UV coordinates
         let uv_bot = offset_y as f32 / self.height as f32;
         let uv_left = offset_x as f32 / self.width as f32;
@@ -1606,29 +2123,7 @@ impl Atlas {
             uv_left,
             uv_width,
             uv_height,
+            colored: glyph.buf.is_colored(),
         }
     }
-
-    /// Check if there's room in the current row for given glyph
-    fn room_in_row(&self, raw: &RasterizedGlyph) -> bool {
-        let next_extent = self.row_extent + raw.width as i32;
-        let enough_width = next_extent <= self.width;
-        let enough_height = (raw.height as i32) < (self.height - self.row_baseline);
-
-        enough_width && enough_height
-    }
-
-    /// Mark current row as finished and prepare to insert into the next row
-    fn advance_row(&mut self) -> Result<(), AtlasInsertError> {
-        let advance_to = self.row_baseline + self.row_tallest;
-        if self.height - advance_to <= 0 {
-            return Err(AtlasInsertError::Full);
-        }
-
-        self.row_baseline = advance_to;
-        self.row_extent = 0;
-        self.row_tallest = 0;
-
-        Ok(())
-    }
 }
@@ -0,0 +1,341 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strategies for packing glyph rectangles into a texture atlas
+use std::fmt::Debug;
+
+/// Allocator for rectangles within a fixed size area
+pub trait Packer: Debug {
+    /// Reserve space for a rectangle and return its bottom-left corner
+    ///
+    /// Returns `None` when there is no room left for the rectangle.
+    fn pack(&mut self, width: i32, height: i32) -> Option<(i32, i32)>;
+
+    /// Release all rectangles
+    fn clear(&mut self);
+
+    /// Fraction of the area which is covered by packed rectangles
+    fn occupancy(&self) -> f32;
+}
+
+/// Packs rectangles into rows, each as tall as its tallest rectangle
+///
+/// ```ignore
+///                           (width, height)
+///   ┌─────┬─────┬─────┬─────┬─────┐
+///   │ 10  │     │     │     │     │ <- Empty spaces; can be filled while
+///   │     │     │     │     │     │    glyph_height < height - row_baseline
+///   ├⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┤
+///   │ 5   │ 6   │ 7   │ 8   │ 9   │
+///   │     │     │     │     │     │
+///   ├⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┼⎼⎼⎼⎼⎼┴⎼⎼⎼⎼⎼┤ <- Row height is tallest glyph in row; this is
+///   │ 1   │ 2   │ 3   │ 4         │    used as the baseline for the following row.
+///   │     │     │     │           │ <- Row considered full when next glyph doesn't
+///   └─────┴─────┴─────┴───────────┘    fit in the row.
+/// (0, 0)  x->
+/// ```
+///
+/// Rows with glyphs of mixed heights waste the space above the shorter glyphs, so this is only
+/// kept as a reference for the tests and benchmarks.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct RowPacker {
+    width: i32,
+    height: i32,
+
+    /// Left-most free pixel in a row.
+    ///
+    /// This is called the extent because it is the upper bound of used pixels
+    /// in a row.
+    row_extent: i32,
+
+    /// Baseline for glyphs in the current row
+    row_baseline: i32,
+
+    /// Tallest glyph in current row
+    ///
+    /// This is used as the advance when end of row is reached
+    row_tallest: i32,
+
+    /// Area covered by packed rectangles
+    used: i64,
+}
+
+#[cfg(test)]
+impl RowPacker {
+    pub fn new(width: i32, height: i32) -> RowPacker {
+        RowPacker { width, height, row_extent: 0, row_baseline: 0, row_tallest: 0, used: 0 }
+    }
+
+    /// Check if there's room in the current row for given rectangle
+    fn room_in_row(&self, width: i32, height: i32) -> bool {
+        let next_extent = self.row_extent + width;
+        let enough_width = next_extent <= self.width;
+        let enough_height = height < (self.height - self.row_baseline);
+
+        enough_width && enough_height
+    }
+
+    /// Mark current row as finished and prepare to insert into the next row
+    fn advance_row(&mut self) -> bool {
+        let advance_to = self.row_baseline + self.row_tallest;
+        if self.height - advance_to <= 0 {
+            return false;
+        }
+
+        self.row_baseline = advance_to;
+        self.row_extent = 0;
+        self.row_tallest = 0;
+
+        true
+    }
+}
+
+#[cfg(test)]
+impl Packer for RowPacker {
+    fn pack(&mut self, width: i32, height: i32) -> Option<(i32, i32)> {
+        // If there's not enough room in current row, go onto next one
+        if !self.room_in_row(width, height) && !self.advance_row() {
+            return None;
+        }
+
+        // If there's still not room, there's nothing that can be done here.
+        if !self.room_in_row(width, height) {
+            return None;
+        }
+
+        let offset = (self.row_extent, self.row_baseline);
+        self.row_extent += width;
+        self.row_tallest = self.row_tallest.max(height);
+        self.used += i64::from(width) * i64::from(height);
+
+        Some(offset)
+    }
+
+    fn clear(&mut self) {
+        *self = RowPacker::new(self.width, self.height);
+    }
+
+    fn occupancy(&self) -> f32 {
+        self.used as f32 / (i64::from(self.width) * i64::from(self.height)) as f32
+    }
+}
+
+/// Horizontal segment of the skyline
+#[derive(Debug, Copy, Clone)]
+struct Segment {
+    x: i32,
+    y: i32,
+    width: i32,
+}
+
+/// Packs rectangles on top of a skyline of the rectangles placed so far
+///
+/// Every rectangle is placed at the lowest position it fits, so short glyphs can fill the
+/// space besides taller ones instead of wasting the rest of a row.
+#[derive(Debug)]
+pub struct SkylinePacker {
+    width: i32,
+    height: i32,
+
+    /// Segments ordered by their x coordinate, covering the entire width
+    skyline: Vec<Segment>,
+
+    /// Area covered by packed rectangles
+    used: i64,
+}
+
+impl SkylinePacker {
+    pub fn new(width: i32, height: i32) -> SkylinePacker {
+        SkylinePacker {
+            width,
+            height,
+            skyline: vec![Segment { x: 0, y: 0, width }],
+            used: 0,
+        }
+    }
+
+    /// Lowest y coordinate a rectangle starting at segment `index` can be placed at
+    fn fit(&self, index: usize, width: i32, height: i32) -> Option<i32> {
+        let x = self.skyline[index].x;
+        if x + width > self.width {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut remaining = width;
+        for segment in &self.skyline[index..] {
+            y = y.max(segment.y);
+            if y + height > self.height {
+                return None;
+            }
+
+            remaining -= segment.width;
+            if remaining <= 0 {
+                break;
+            }
+        }
+
+        Some(y)
+    }
+
+    /// Raise the skyline below a newly placed rectangle
+    fn place(&mut self, index: usize, width: i32, top: i32) {
+        let x = self.skyline[index].x;
+        self.skyline.insert(index, Segment { x, y: top, width });
+
+        // Shrink or remove all segments covered by the new one
+        let end = x + width;
+        let next = index + 1;
+        while next < self.skyline.len() && self.skyline[next].x < end {
+            let segment = &mut self.skyline[next];
+            let segment_end = segment.x + segment.width;
+            if segment_end <= end {
+                self.skyline.remove(next);
+            } else {
+                segment.width = segment_end - end;
+                segment.x = end;
+                break;
+            }
+        }
+
+        // Merge neighbours at the same height
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+impl Packer for SkylinePacker {
+    fn pack(&mut self, width: i32, height: i32) -> Option<(i32, i32)> {
+        if width > self.width || height > self.height {
+            return None;
+        }
+
+        // Lowest position, left-most on ties
+        let (index, y) = (0..self.skyline.len())
+            .filter_map(|index| self.fit(index, width, height).map(|y| (index, y)))
+            .min_by_key(|&(index, y)| (y, self.skyline[index].x))?;
+
+        let x = self.skyline[index].x;
+        self.place(index, width, y + height);
+        self.used += i64::from(width) * i64::from(height);
+
+        Some((x, y))
+    }
+
+    fn clear(&mut self) {
+        *self = SkylinePacker::new(self.width, self.height);
+    }
+
+    fn occupancy(&self) -> f32 {
+        self.used as f32 / (i64::from(self.width) * i64::from(self.height)) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Packer, RowPacker, SkylinePacker};
+
+    /// Glyph sizes alternating between regular glyphs and short ones like punctuation
+    fn mixed_sizes() -> impl Iterator<Item = (i32, i32)> {
+        (0..).map(|i| if i % 2 == 0 { (9, 18) } else { (9, 5) })
+    }
+
+    fn fill(packer: &mut dyn Packer) -> usize {
+        mixed_sizes().take_while(|&(width, height)| packer.pack(width, height).is_some()).count()
+    }
+
+    fn overlaps(a: (i32, i32, i32, i32), b: (i32, i32, i32, i32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn skyline_does_not_overlap() {
+        let mut packer = SkylinePacker::new(64, 64);
+        let mut placed = Vec::new();
+        for (width, height) in mixed_sizes().take(40) {
+            if let Some((x, y)) = packer.pack(width, height) {
+                assert!(x + width <= 64 && y + height <= 64);
+                let rect = (x, y, width, height);
+                assert!(placed.iter().all(|other| !overlaps(rect, *other)));
+                placed.push(rect);
+            }
+        }
+    }
+
+    #[test]
+    fn skyline_packs_mixed_heights_denser() {
+        let mut row = RowPacker::new(128, 128);
+        let mut skyline = SkylinePacker::new(128, 128);
+
+        let rows = fill(&mut row);
+        let skylines = fill(&mut skyline);
+
+        assert!(skylines > rows);
+        assert!(skyline.occupancy() > row.occupancy());
+    }
+
+    #[test]
+    fn clear_releases_space() {
+        let mut packer = SkylinePacker::new(16, 16);
+        assert_eq!(packer.pack(16, 16), Some((0, 0)));
+        assert_eq!(packer.pack(1, 1), None);
+
+        packer.clear();
+        assert!(packer.occupancy() < 0.01);
+        assert_eq!(packer.pack(1, 1), Some((0, 0)));
+    }
+}
+
+#[cfg(all(test, feature = "bench"))]
+mod benches {
+    extern crate test;
+
+    use super::{Packer, RowPacker, SkylinePacker};
+
+    /// Pack a mix of glyph sizes until the atlas is full
+    fn fill(packer: &mut dyn Packer) {
+        let sizes = [(9, 18), (9, 5), (18, 18), (7, 12), (9, 3)];
+        for &(width, height) in sizes.iter().cycle() {
+            if packer.pack(width, height).is_none() {
+                break;
+            }
+        }
+    }
+
+    #[bench]
+    fn row_packer_fill(b: &mut test::Bencher) {
+        let mut packer = RowPacker::new(1024, 1024);
+        b.iter(|| {
+            packer.clear();
+            fill(&mut packer);
+        });
+    }
+
+    #[bench]
+    fn skyline_packer_fill(b: &mut test::Bencher) {
+        let mut packer = SkylinePacker::new(1024, 1024);
+        b.iter(|| {
+            packer.clear();
+            fill(&mut packer);
+        });
+    }
+}
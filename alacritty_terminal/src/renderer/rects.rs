@@ -44,23 +44,74 @@ impl Line {
     }
 }
 
+/// What a rect is drawn for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RectKind {
+    Underline,
+    Strikeout,
+
+    /// Drawn below the cells instead of on top of them
+    Selection,
+
+    VisualBell,
+    HintLabel,
+
+    /// Gutter next to rows marked by a watch rule
+    Mark,
+
+    /// Search matches and message bar backgrounds
+    Overlay,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RenderRect {
+    pub rect: Rect<f32>,
+    pub color: Rgb,
+    pub kind: RectKind,
+}
+
+impl RenderRect {
+    /// Thickness of underlines and strikeouts, which are as high as they are thick
+    #[inline]
+    pub fn thickness(&self) -> f32 {
+        self.rect.height
+    }
+}
+
 /// Rects for underline, strikeout and more.
-pub struct Rects<'a> {
-    inner: Vec<(Rect<f32>, Rgb)>,
+///
+/// The buffer is kept for the next frame, so drawing doesn't allocate once it is large enough.
+pub struct Rects {
+    inner: Vec<RenderRect>,
     active_lines: Vec<Line>,
-    metrics: &'a Metrics,
-    size: &'a SizeInfo,
+    metrics: Metrics,
+    size: SizeInfo,
 }
 
-impl<'a> Rects<'a> {
-    pub fn new(metrics: &'a Metrics, size: &'a SizeInfo) -> Self {
-        let active_lines = vec![Line::new(Flags::UNDERLINE), Line::new(Flags::STRIKEOUT)];
+impl Rects {
+    pub fn new(metrics: Metrics, size: SizeInfo) -> Self {
+        let active_lines = vec![Line::new(Flags::ALL_UNDERLINES), Line::new(Flags::STRIKEOUT)];
         Self { inner: Vec::new(), active_lines, metrics, size }
     }
 
-    /// Convert the stored rects to rectangles for the renderer.
-    pub fn rects(&self) -> &Vec<(Rect<f32>, Rgb)> {
-        &self.inner
+    /// Remove all rects for the next frame, keeping the allocated buffer
+    pub fn reset(&mut self, metrics: Metrics, size: SizeInfo) {
+        self.inner.clear();
+        for line in &mut self.active_lines {
+            line.range = None;
+        }
+        self.metrics = metrics;
+        self.size = size;
+    }
+
+    /// Rects of one kind, in the order they were added
+    pub fn of_kind(&self, kind: RectKind) -> impl Iterator<Item = &RenderRect> {
+        self.inner.iter().filter(move |rect| rect.kind == kind)
+    }
+
+    /// Rects of all kinds except one, in the order they were added
+    pub fn except_kind(&self, kind: RectKind) -> impl Iterator<Item = &RenderRect> {
+        self.inner.iter().filter(move |rect| rect.kind != kind)
     }
 
     /// Update the stored lines with the next cell info.
@@ -71,7 +122,7 @@ impl<'a> Rects<'a> {
                 Some((ref mut start, ref mut end)) => {
                     // No change in line
                     if cell.line == start.line
-                        && cell.flags.contains(line.flag)
+                        && cell.flags.intersects(line.flag)
                         && cell.fg == start.fg
                         && cell.column == end.col + 1
                     {
@@ -95,7 +146,7 @@ impl<'a> Rects<'a> {
                     self.inner.push(create_rect(start, *end, line.flag, &self.metrics, &self.size));
 
                     // Start a new line if the flag is present
-                    if cell.flags.contains(line.flag) {
+                    if cell.flags.intersects(line.flag) {
                         *start = cell.clone();
                         *end = cell.into();
                     } else {
@@ -104,7 +155,7 @@ impl<'a> Rects<'a> {
                 },
                 // Check for new start of line
                 None => {
-                    if cell.flags.contains(line.flag) {
+                    if cell.flags.intersects(line.flag) {
                         line.range = Some((cell.clone(), cell.into()));
                     }
                 },
@@ -113,8 +164,8 @@ impl<'a> Rects<'a> {
     }
 
     // Add a rectangle
-    pub fn push(&mut self, rect: Rect<f32>, color: Rgb) {
-        self.inner.push((rect, color));
+    pub fn push(&mut self, rect: Rect<f32>, color: Rgb, kind: RectKind) {
+        self.inner.push(RenderRect { rect, color, kind });
     }
 }
 
@@ -126,14 +177,19 @@ fn create_rect(
     flag: Flags,
     metrics: &Metrics,
     size: &SizeInfo,
-) -> (Rect<f32>, Rgb) {
+) -> RenderRect {
     let start_x = start.column.0 as f32 * size.cell_width;
     let end_x = (end.col.0 + 1) as f32 * size.cell_width;
     let width = end_x - start_x;
 
-    let (position, mut height) = match flag {
-        Flags::UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
-        Flags::STRIKEOUT => (metrics.strikeout_position, metrics.strikeout_thickness),
+    let (kind, position, mut height) = match flag {
+        // Undercurls are drawn as straight underlines
+        Flags::ALL_UNDERLINES => {
+            (RectKind::Underline, metrics.underline_position, metrics.underline_thickness)
+        },
+        Flags::STRIKEOUT => {
+            (RectKind::Strikeout, metrics.strikeout_position, metrics.strikeout_thickness)
+        },
         _ => unimplemented!("Invalid flag for cell line drawing specified"),
     };
 
@@ -152,5 +208,82 @@ fn create_rect(
     let rect =
         Rect::new(start_x + size.padding_x, y.round() + size.padding_y, width, height.round());
 
-    (rect, start.fg)
+    RenderRect { rect, color: start.fg, kind }
+}
+
+#[cfg(test)]
+mod tests {
+    use font::Metrics;
+
+    use super::{Rect, RectKind, Rects};
+    use crate::index::{Column, Line};
+    use crate::term::cell::{Flags, MAX_ZEROWIDTH_CHARS};
+    use crate::term::color::Rgb;
+    use crate::term::{RenderableCell, RenderableCellContent, SizeInfo};
+
+    fn rects() -> Rects {
+        let metrics = Metrics {
+            average_advance: 10.,
+            line_height: 20.,
+            descent: -4.,
+            underline_position: -2.,
+            underline_thickness: 2.,
+            strikeout_position: 5.,
+            strikeout_thickness: 1.,
+        };
+        let size = SizeInfo {
+            width: 100.,
+            height: 100.,
+            cell_width: 10.,
+            cell_height: 20.,
+            padding_x: 0.,
+            padding_y: 0.,
+            dpr: 1.,
+        };
+        Rects::new(metrics, size)
+    }
+
+    fn cell(column: usize, flags: Flags) -> RenderableCell {
+        RenderableCell {
+            line: Line(0),
+            column: Column(column),
+            inner: RenderableCellContent::Chars([' '; MAX_ZEROWIDTH_CHARS + 1]),
+            fg: Rgb::new(0xff, 0, 0),
+            bg: Rgb::new(0, 0, 0),
+            bg_alpha: 0.,
+            flags,
+        }
+    }
+
+    #[test]
+    fn underlines_are_merged_and_tagged() {
+        let mut rects = rects();
+        let size = rects.size;
+        for column in 0..3 {
+            rects.update_lines(&size, &cell(column, Flags::UNDERLINE));
+        }
+        rects.update_lines(&size, &cell(3, Flags::empty()));
+        rects.push(Rect::new(0., 0., 10., 10.), Rgb::new(0, 0, 0xff), RectKind::Selection);
+
+        let underlines: Vec<_> = rects.of_kind(RectKind::Underline).collect();
+        assert_eq!(underlines.len(), 1);
+        assert_eq!(underlines[0].rect.width, 30.);
+        assert_eq!(underlines[0].thickness(), 2.);
+        assert_eq!(underlines[0].color, Rgb::new(0xff, 0, 0));
+        assert_eq!(rects.except_kind(RectKind::Selection).count(), 1);
+    }
+
+    #[test]
+    fn reset_keeps_buffer() {
+        let mut rects = rects();
+        for _ in 0..10 {
+            rects.push(Rect::new(0., 0., 1., 1.), Rgb::new(0, 0, 0), RectKind::Overlay);
+        }
+        let capacity = rects.inner.capacity();
+
+        let (metrics, size) = (rects.metrics, rects.size);
+        rects.reset(metrics, size);
+        assert_eq!(rects.of_kind(RectKind::Overlay).count(), 0);
+        assert_eq!(rects.inner.capacity(), capacity);
+    }
 }
@@ -0,0 +1,224 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Render statistics
+//!
+//! Collects CPU and GPU frame times together with draw call and quad counts, which are shown
+//! on screen and logged periodically while the render timer is enabled.
+use std::time::{Duration, Instant};
+
+use crate::gl;
+use crate::gl::types::*;
+use crate::meter::Meter;
+
+/// Number of timer queries in flight, so reading a result never waits for the GPU
+const TIMER_QUERIES: usize = 3;
+
+/// Time between two statistics summaries in the log
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Work submitted to the GPU during a single frame
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FrameStats {
+    pub draw_calls: usize,
+    pub quads: usize,
+
+    /// GPU time of the most recent frame whose timer query has finished
+    pub gpu_time: Option<Duration>,
+}
+
+/// Measures GPU time with OpenGL timer queries
+///
+/// Results are only read once they are available, so the reported time lags a few frames
+/// behind the frame which is currently being drawn.
+#[derive(Debug)]
+pub struct GpuTimer {
+    queries: [GLuint; TIMER_QUERIES],
+
+    /// Queries which have been submitted but not read yet
+    pending: [bool; TIMER_QUERIES],
+
+    /// Next query to be used, which is also the oldest one
+    index: usize,
+
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    pub fn new() -> GpuTimer {
+        let mut queries = [0; TIMER_QUERIES];
+        unsafe {
+            gl::GenQueries(TIMER_QUERIES as GLsizei, queries.as_mut_ptr());
+        }
+
+        GpuTimer { queries, pending: [false; TIMER_QUERIES], index: 0, last: None }
+    }
+
+    pub fn begin(&mut self) {
+        self.poll();
+
+        // Pending results of the reused query are discarded
+        self.pending[self.index] = false;
+        unsafe {
+            gl::BeginQuery(gl::TIME_ELAPSED, self.queries[self.index]);
+        }
+    }
+
+    pub fn end(&mut self) {
+        unsafe {
+            gl::EndQuery(gl::TIME_ELAPSED);
+        }
+
+        self.pending[self.index] = true;
+        self.index = (self.index + 1) % TIMER_QUERIES;
+    }
+
+    /// Time of the latest frame whose result is available
+    pub fn elapsed(&mut self) -> Option<Duration> {
+        self.poll();
+        self.last
+    }
+
+    /// Read the results of all finished queries, from oldest to newest
+    fn poll(&mut self) {
+        for offset in 0..TIMER_QUERIES {
+            let index = (self.index + offset) % TIMER_QUERIES;
+            if !self.pending[index] {
+                continue;
+            }
+
+            let query = self.queries[index];
+            let mut available = 0;
+            unsafe {
+                gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+            }
+            if available == 0 {
+                continue;
+            }
+
+            let mut nanos = 0;
+            unsafe {
+                gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut nanos);
+            }
+            self.last = Some(Duration::from_nanos(nanos));
+            self.pending[index] = false;
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteQueries(TIMER_QUERIES as GLsizei, self.queries.as_ptr());
+        }
+    }
+}
+
+/// Statistics over the frames drawn while the render timer is enabled
+pub struct RenderStats {
+    /// CPU time spent on a frame, from locking the terminal until presenting it
+    cpu: Meter,
+
+    /// GPU time of the frames, as far as the results are available
+    gpu: Meter,
+
+    /// Statistics of the last frame
+    last: FrameStats,
+
+    /// Frames drawn since the last log summary
+    frames: usize,
+
+    /// Start of the current log interval
+    interval_start: Instant,
+}
+
+impl Default for RenderStats {
+    fn default() -> RenderStats {
+        RenderStats {
+            cpu: Meter::new(),
+            gpu: Meter::new(),
+            last: FrameStats::default(),
+            frames: 0,
+            interval_start: Instant::now(),
+        }
+    }
+}
+
+impl RenderStats {
+    /// Record a finished frame, logging a summary once per interval
+    pub fn record(&mut self, cpu_time: Duration, frame: FrameStats) {
+        self.cpu.add_sample(cpu_time);
+        if let Some(gpu_time) = frame.gpu_time {
+            self.gpu.add_sample(gpu_time);
+        }
+        self.last = frame;
+        self.frames += 1;
+
+        let elapsed = self.interval_start.elapsed();
+        if elapsed >= LOG_INTERVAL {
+            let secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+            info!("Render stats: {}, {:.1} fps", self.summary(), self.frames as f64 / secs);
+
+            self.frames = 0;
+            self.interval_start = Instant::now();
+        }
+    }
+
+    /// Single line description of the frame times and the last frame's workload
+    pub fn summary(&self) -> String {
+        let gpu = match self.last.gpu_time {
+            Some(_) => format!("{:.3} usec", self.gpu.average()),
+            None => String::from("n/a"),
+        };
+
+        format!(
+            "cpu {:.3} usec, gpu {}, {} draws, {} quads",
+            self.cpu.average(),
+            gpu,
+            self.last.draw_calls,
+            self.last.quads
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{FrameStats, RenderStats};
+
+    #[test]
+    fn summary_without_gpu_time() {
+        let mut stats = RenderStats::default();
+        stats.record(Duration::from_micros(100), FrameStats {
+            draw_calls: 3,
+            quads: 120,
+            gpu_time: None,
+        });
+
+        assert_eq!(stats.summary(), "cpu 10.000 usec, gpu n/a, 3 draws, 120 quads");
+    }
+
+    #[test]
+    fn summary_with_gpu_time() {
+        let mut stats = RenderStats::default();
+        stats.record(Duration::from_micros(100), FrameStats {
+            draw_calls: 1,
+            quads: 1,
+            gpu_time: Some(Duration::from_micros(50)),
+        });
+
+        assert_eq!(stats.summary(), "cpu 10.000 usec, gpu 5.000 usec, 1 draws, 1 quads");
+    }
+}
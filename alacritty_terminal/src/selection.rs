@@ -18,6 +18,7 @@
 //! finalized when the button is released. The selection should be cleared
 //! when text is added/removed/scrolled on the screen. The selection should
 //! also be cleared if the user clicks off of the selection.
+use std::cmp::min;
 use std::ops::Range;
 
 use crate::index::{Column, Line, Point, Side};
@@ -375,6 +376,53 @@ impl SelectionRange {
             && (self.start.col <= col || (self.start.line != line && !self.is_block))
             && (self.end.col >= col || (self.end.line != line && !self.is_block))
     }
+
+    /// Fewest rectangles covering exactly the selected cells
+    pub fn rects(&self, num_cols: Column) -> Vec<SelectionRect> {
+        let last_col = num_cols - 1;
+        let end_col = min(self.end.col, last_col);
+
+        if self.is_block || self.start.line == self.end.line {
+            if self.start.col > end_col {
+                return Vec::new();
+            }
+
+            let rect = SelectionRect {
+                top: self.start.line,
+                bottom: self.end.line,
+                start: self.start.col,
+                end: end_col,
+            };
+            return vec![rect];
+        }
+
+        // Partially selected first and last lines, with the full lines between them
+        let mut rects = Vec::with_capacity(3);
+        let mut top = self.start.line;
+        let mut bottom = self.end.line;
+        if self.start.col != Column(0) {
+            rects.push(SelectionRect { top, bottom: top, start: self.start.col, end: last_col });
+            top += 1;
+        }
+        if end_col != last_col {
+            rects.push(SelectionRect { top: bottom, bottom, start: Column(0), end: end_col });
+            bottom -= 1;
+        }
+        if top <= bottom {
+            rects.push(SelectionRect { top, bottom, start: Column(0), end: last_col });
+        }
+
+        rects
+    }
+}
+
+/// Cells from the `start` to the `end` column of every line from `top` to `bottom`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SelectionRect {
+    pub top: Line,
+    pub bottom: Line,
+    pub start: Column,
+    pub end: Column,
 }
 
 /// Tests for selection
@@ -390,7 +438,7 @@ impl SelectionRange {
 mod test {
     use std::mem;
 
-    use super::{Selection, Span};
+    use super::{Selection, SelectionRange, SelectionRect, Span};
     use crate::clipboard::Clipboard;
     use crate::grid::Grid;
     use crate::index::{Column, Line, Point, Side};
@@ -589,4 +637,29 @@ mod test {
             is_block: false,
         });
     }
+
+    #[test]
+    fn selection_rects() {
+        let rect = |top, bottom, start, end| SelectionRect {
+            top: Line(top),
+            bottom: Line(bottom),
+            start: Column(start),
+            end: Column(end),
+        };
+        let rects = |start: (usize, usize), end: (usize, usize), is_block| {
+            let start = Point::new(Line(start.0), Column(start.1));
+            let end = Point::new(Line(end.0), Column(end.1));
+            SelectionRange::new(start, end, is_block).rects(Column(10))
+        };
+
+        assert_eq!(rects((1, 2), (1, 5), false), vec![rect(1, 1, 2, 5)]);
+        assert_eq!(rects((0, 3), (3, 2), false), vec![
+            rect(0, 0, 3, 9),
+            rect(3, 3, 0, 2),
+            rect(1, 2, 0, 9),
+        ]);
+        assert_eq!(rects((0, 0), (2, 9), false), vec![rect(0, 2, 0, 9)]);
+        assert_eq!(rects((0, 5), (1, 9), false), vec![rect(0, 0, 5, 9), rect(1, 1, 0, 9)]);
+        assert_eq!(rects((1, 2), (4, 6), true), vec![rect(1, 4, 2, 6)]);
+    }
 }
@@ -41,4 +41,10 @@ impl<T> FairMutex<T> {
         let _next = self.next.lock();
         self.data.lock()
     }
+
+    /// Lock the mutex only if it's free and no other thread is waiting for it
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        let _next = self.next.try_lock()?;
+        self.data.try_lock()
+    }
 }
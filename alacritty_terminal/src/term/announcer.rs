@@ -0,0 +1,192 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Forwarding of completed output lines to an external announcement command
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::config::Announcements;
+
+/// Length of the window used for rate limiting
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+pub struct Announcer {
+    tx: mpsc::Sender<String>,
+
+    /// Text of the current line, which can span multiple wrapped rows
+    line: String,
+
+    ignore: Vec<String>,
+}
+
+impl Announcer {
+    /// Start the announcement command
+    ///
+    /// Returns `None` when no command is configured or it could not be started.
+    pub fn new(config: &Announcements) -> Option<Announcer> {
+        let shell = config.command.as_ref()?;
+
+        let child = Command::new(&*shell.program)
+            .args(&shell.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) => {
+                error!("Unable to start announcement command {}: {}", shell.program, err);
+                return None;
+            },
+        };
+
+        let mut stdin = child.stdin.take()?;
+        let (tx, rx) = mpsc::channel::<String>();
+        let mut throttle = Throttle::new(config.max_lines_per_second(), Instant::now());
+
+        crate::util::thread::spawn_named("line announcer", move || {
+            loop {
+                // Wake up without new lines too, so skipped lines are reported once output stops
+                let line = match rx.recv_timeout(RATE_WINDOW) {
+                    Ok(line) => Some(line),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
+                let summary = throttle.summary(Instant::now());
+                let line = line.filter(|_| throttle.admit());
+                if summary.into_iter().chain(line).any(|text| writeln!(stdin, "{}", text).is_err())
+                {
+                    break;
+                }
+            }
+
+            // Closing stdin lets the command terminate
+            drop(stdin);
+            let _ = child.wait();
+        });
+
+        Some(Announcer { tx, line: String::new(), ignore: config.ignore.clone() })
+    }
+
+    /// Add the text of a row to the current line
+    ///
+    /// The line is announced once a row which was not wrapped is added.
+    pub fn push(&mut self, text: &str, wrapped: bool) {
+        self.line.push_str(text);
+        if wrapped {
+            return;
+        }
+
+        let line = self.line.trim_end().to_owned();
+        self.line.clear();
+
+        if line.trim().is_empty() || self.ignore.iter().any(|ignore| line.contains(&ignore[..])) {
+            return;
+        }
+
+        let _ = self.tx.send(line);
+    }
+}
+
+/// Rate limit of the announced lines, which summarizes the dropped ones
+struct Throttle {
+    max_lines: usize,
+
+    /// Start of the current rate limiting window
+    window_start: Instant,
+
+    /// Lines announced in the current window
+    window_lines: usize,
+
+    /// Lines dropped since the last announcement
+    skipped: usize,
+}
+
+impl Throttle {
+    fn new(max_lines: usize, now: Instant) -> Throttle {
+        Throttle { max_lines, window_start: now, window_lines: 0, skipped: 0 }
+    }
+
+    /// Start a new window once the current one is over, summarizing the lines it dropped
+    fn summary(&mut self, now: Instant) -> Option<String> {
+        if now.duration_since(self.window_start) < RATE_WINDOW {
+            return None;
+        }
+
+        self.window_start = now;
+        self.window_lines = 0;
+
+        if self.skipped == 0 {
+            return None;
+        }
+
+        let summary = format!("{} lines skipped", self.skipped);
+        self.skipped = 0;
+        Some(summary)
+    }
+
+    /// Count a line against the current window, returning whether it may be announced
+    fn admit(&mut self) -> bool {
+        if self.window_lines >= self.max_lines {
+            self.skipped += 1;
+            return false;
+        }
+
+        self.window_lines += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    use super::{Announcer, Throttle, RATE_WINDOW};
+
+    #[test]
+    fn wrapped_rows_are_one_line() {
+        let (tx, rx) = mpsc::channel();
+        let mut announcer =
+            Announcer { tx, line: String::new(), ignore: vec![String::from("secret")] };
+
+        announcer.push("make: ", true);
+        announcer.push("done   ", false);
+        announcer.push("   ", false);
+        announcer.push("no secret", false);
+
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![String::from("make: done")]);
+    }
+
+    #[test]
+    fn throttle_summarizes_skipped_lines() {
+        let start = Instant::now();
+        let mut throttle = Throttle::new(2, start);
+
+        assert_eq!(throttle.summary(start), None);
+        let admitted = (0..5).filter(|_| throttle.admit()).count();
+        assert_eq!(admitted, 2);
+
+        // The summary waits for the end of the window, even if no line follows
+        assert_eq!(throttle.summary(start + RATE_WINDOW / 2), None);
+        assert_eq!(throttle.summary(start + RATE_WINDOW), Some(String::from("3 lines skipped")));
+        assert_eq!(throttle.summary(start + RATE_WINDOW * 2), None);
+
+        assert!(throttle.admit());
+        assert!(throttle.admit());
+        assert!(!throttle.admit());
+    }
+}
@@ -16,24 +16,33 @@ use bitflags::bitflags;
 use crate::ansi::{Color, NamedColor};
 use crate::grid::{self, GridCell};
 use crate::index::Column;
+use crate::term::metadata::MetadataId;
 
 // Maximum number of zerowidth characters which will be stored per cell.
 pub const MAX_ZEROWIDTH_CHARS: usize = 5;
 
 bitflags! {
+    /// Cell attributes
+    ///
+    /// The bits are stored in ref tests, so existing flags must keep their values.
     #[derive(Serialize, Deserialize)]
     pub struct Flags: u16 {
-        const INVERSE           = 0b00_0000_0001;
-        const BOLD              = 0b00_0000_0010;
-        const ITALIC            = 0b00_0000_0100;
-        const UNDERLINE         = 0b00_0000_1000;
-        const WRAPLINE          = 0b00_0001_0000;
-        const WIDE_CHAR         = 0b00_0010_0000;
-        const WIDE_CHAR_SPACER  = 0b00_0100_0000;
-        const DIM               = 0b00_1000_0000;
-        const DIM_BOLD          = 0b00_1000_0010;
-        const HIDDEN            = 0b01_0000_0000;
-        const STRIKEOUT         = 0b10_0000_0000;
+        const INVERSE           = 0b0000_0000_0000_0001;
+        const BOLD              = 0b0000_0000_0000_0010;
+        const ITALIC            = 0b0000_0000_0000_0100;
+        const UNDERLINE         = 0b0000_0000_0000_1000;
+        const WRAPLINE          = 0b0000_0000_0001_0000;
+        const WIDE_CHAR         = 0b0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER  = 0b0000_0000_0100_0000;
+        const DIM               = 0b0000_0000_1000_0000;
+        const DIM_BOLD          = 0b0000_0000_1000_0010;
+        const HIDDEN            = 0b0000_0001_0000_0000;
+        const STRIKEOUT         = 0b0000_0010_0000_0000;
+        const UNDERCURL         = 0b0000_0100_0000_0000;
+        const BLINK             = 0b0000_1000_0000_0000;
+        /// Protected from selective erase (DECSCA)
+        const PROTECTED         = 0b0001_0000_0000_0000;
+        const ALL_UNDERLINES    = Self::UNDERLINE.bits | Self::UNDERCURL.bits;
     }
 }
 
@@ -49,6 +58,8 @@ pub struct Cell {
     pub flags: Flags,
     #[serde(default = "default_extra")]
     pub extra: [char; MAX_ZEROWIDTH_CHARS],
+    #[serde(default)]
+    pub metadata: MetadataId,
 }
 
 impl Default for Cell {
@@ -63,9 +74,9 @@ impl GridCell for Cell {
         (self.c == ' ' || self.c == '\t')
             && self.extra[0] == ' '
             && self.bg == Color::Named(NamedColor::Background)
-            && !self
-                .flags
-                .intersects(Flags::INVERSE | Flags::UNDERLINE | Flags::STRIKEOUT | Flags::WRAPLINE)
+            && !self.flags.intersects(
+                Flags::INVERSE | Flags::ALL_UNDERLINES | Flags::STRIKEOUT | Flags::WRAPLINE,
+            )
     }
 
     #[inline]
@@ -124,8 +135,30 @@ impl Cell {
         self.flags.contains(Flags::DIM)
     }
 
+    #[inline]
+    pub fn underlined(&self) -> bool {
+        self.flags.intersects(Flags::ALL_UNDERLINES)
+    }
+
+    #[inline]
+    pub fn blink(&self) -> bool {
+        self.flags.contains(Flags::BLINK)
+    }
+
+    #[inline]
+    pub fn protected(&self) -> bool {
+        self.flags.contains(Flags::PROTECTED)
+    }
+
     pub fn new(c: char, fg: Color, bg: Color) -> Cell {
-        Cell { extra: [' '; MAX_ZEROWIDTH_CHARS], c, bg, fg, flags: Flags::empty() }
+        Cell {
+            extra: [' '; MAX_ZEROWIDTH_CHARS],
+            c,
+            bg,
+            fg,
+            flags: Flags::empty(),
+            metadata: MetadataId::NONE,
+        }
     }
 
     #[inline]
@@ -161,7 +194,7 @@ impl Cell {
 
 #[cfg(test)]
 mod tests {
-    use super::{Cell, LineLength};
+    use super::{Cell, Flags, LineLength};
 
     use crate::grid::Row;
     use crate::index::Column;
@@ -183,6 +216,23 @@ mod tests {
 
         assert_eq!(row.line_length(), Column(10));
     }
+
+    #[test]
+    fn flags_keep_ref_test_bits() {
+        let flags: Flags = serde_json::from_str(r#"{"bits":514}"#).unwrap();
+        assert_eq!(flags, Flags::BOLD | Flags::STRIKEOUT);
+
+        let flags = Flags::UNDERCURL | Flags::PROTECTED;
+        let serialized = serde_json::to_string(&flags).unwrap();
+        assert_eq!(serde_json::from_str::<Flags>(&serialized).unwrap(), flags);
+    }
+
+    #[test]
+    fn undercurl_is_underlined() {
+        let mut cell = Cell::default();
+        cell.flags.insert(Flags::UNDERCURL);
+        assert!(cell.underlined());
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -10,14 +10,37 @@ use crate::config::Colors;
 
 pub const COUNT: usize = 270;
 
-pub const RED: Rgb = Rgb { r: 0xff, g: 0x0, b: 0x0 };
-pub const YELLOW: Rgb = Rgb { r: 0xff, g: 0xff, b: 0x0 };
+pub const RED: Rgb = Rgb::new(0xff, 0x0, 0x0);
+pub const YELLOW: Rgb = Rgb::new(0xff, 0xff, 0x0);
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Serialize)]
 pub struct Rgb {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+
+    /// Opacity, `0xff` is fully opaque
+    pub a: u8,
+}
+
+impl Default for Rgb {
+    fn default() -> Rgb {
+        Rgb::new(0, 0, 0)
+    }
+}
+
+impl Rgb {
+    /// Create an opaque color
+    #[inline]
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b, a: 0xff }
+    }
+
+    /// Opacity as a fraction between `0.` and `1.`
+    #[inline]
+    pub fn alpha(self) -> f32 {
+        f32::from(self.a) / 255.
+    }
 }
 
 // a multiply function for Rgb, as the default dim is just *2/3
@@ -29,6 +52,7 @@ impl Mul<f32> for Rgb {
             r: (f32::from(self.r) * rhs).max(0.0).min(255.0) as u8,
             g: (f32::from(self.g) * rhs).max(0.0).min(255.0) as u8,
             b: (f32::from(self.b) * rhs).max(0.0).min(255.0) as u8,
+            a: self.a,
         };
 
         trace!("Scaling RGB by {} from {:?} to {:?}", rhs, self, result);
@@ -37,7 +61,11 @@ impl Mul<f32> for Rgb {
     }
 }
 
-/// Deserialize an Rgb from a hex string
+fn opaque() -> u8 {
+    0xff
+}
+
+/// Deserialize an Rgb from a hex string or color name
 ///
 /// This is *not* the deserialize impl for Rgb since we want a symmetric
 /// serialize/deserialize impl for ref tests.
@@ -54,21 +82,27 @@ impl<'de> Deserialize<'de> for Rgb {
             r: u8,
             g: u8,
             b: u8,
+            #[serde(default = "opaque")]
+            a: u8,
         }
 
         impl<'a> Visitor<'a> for RgbVisitor {
             type Value = Rgb;
 
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("hex color like 0xff00ff")
+                f.write_str("hex color like 0xff00ff or #ff00ff80, or a CSS color name")
             }
 
             fn visit_str<E>(self, value: &str) -> ::std::result::Result<Rgb, E>
             where
                 E: ::serde::de::Error,
             {
-                Rgb::from_str(&value[..])
-                    .map_err(|_| E::custom("failed to parse rgb; expected hex color like 0xff00ff"))
+                Rgb::from_str(&value[..]).map_err(|_| {
+                    E::custom(
+                        "failed to parse rgb; expected hex color like 0xff00ff or #ff00ff80, or \
+                         a CSS color name",
+                    )
+                })
             }
         }
 
@@ -76,11 +110,11 @@ impl<'de> Deserialize<'de> for Rgb {
         let value = serde_yaml::Value::deserialize(deserializer)?;
 
         // Attempt to deserialize from struct form
-        if let Ok(RgbDerivedDeser { r, g, b }) = RgbDerivedDeser::deserialize(value.clone()) {
-            return Ok(Rgb { r, g, b });
+        if let Ok(RgbDerivedDeser { r, g, b, a }) = RgbDerivedDeser::deserialize(value.clone()) {
+            return Ok(Rgb { r, g, b, a });
         }
 
-        // Deserialize from hex notation (either 0xff00ff or #ff00ff)
+        // Deserialize from hex notation or a color name
         match value.deserialize_str(RgbVisitor) {
             Ok(rgb) => Ok(rgb),
             Err(err) => {
@@ -94,40 +128,36 @@ impl<'de> Deserialize<'de> for Rgb {
 impl FromStr for Rgb {
     type Err = ();
 
+    /// Parse `0xrrggbb`, `#rrggbb`, both with an optional alpha component, or a CSS color name
     fn from_str(s: &str) -> ::std::result::Result<Rgb, ()> {
-        let mut chars = s.chars();
-        let mut rgb = Rgb::default();
-
-        macro_rules! component {
-            ($($c:ident),*) => {
-                $(
-                    match chars.next().and_then(|c| c.to_digit(16)) {
-                        Some(val) => rgb.$c = (val as u8) << 4,
-                        None => return Err(())
-                    }
-
-                    match chars.next().and_then(|c| c.to_digit(16)) {
-                        Some(val) => rgb.$c |= val as u8,
-                        None => return Err(())
-                    }
-                )*
-            }
-        }
+        let hex = if s.starts_with("0x") {
+            &s[2..]
+        } else if s.starts_with('#') {
+            &s[1..]
+        } else {
+            return css_color(s).ok_or(());
+        };
 
-        match chars.next() {
-            Some('0') => {
-                if chars.next() != Some('x') {
-                    return Err(());
-                }
-            },
-            Some('#') => (),
-            _ => return Err(()),
+        if (hex.len() != 6 && hex.len() != 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(());
         }
 
-        component!(r, g, b);
+        let value = u32::from_str_radix(hex, 16).map_err(|_| ())?;
+        let (rgb, a) = if hex.len() == 8 { (value >> 8, value as u8) } else { (value, 0xff) };
+        Ok(Rgb { r: (rgb >> 16) as u8, g: (rgb >> 8) as u8, b: rgb as u8, a })
+    }
+}
 
-        Ok(rgb)
+/// Look up a CSS color name, ignoring case
+fn css_color(name: &str) -> Option<Rgb> {
+    let name = name.to_ascii_lowercase();
+    if name == "transparent" {
+        return Some(Rgb { a: 0, ..Rgb::default() });
     }
+
+    let index = CSS_COLORS.binary_search_by(|(css_name, _)| css_name.cmp(&name.as_str())).ok()?;
+    let rgb = CSS_COLORS[index].1;
+    Some(Rgb::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
 }
 
 /// List of indexed colors
@@ -227,11 +257,11 @@ impl List {
                     {
                         self[index] = indexed_color.color;
                     } else {
-                        self[index] = Rgb {
-                            r: if r == 0 { 0 } else { r * 40 + 55 },
-                            b: if b == 0 { 0 } else { b * 40 + 55 },
-                            g: if g == 0 { 0 } else { g * 40 + 55 },
-                        };
+                        self[index] = Rgb::new(
+                            if r == 0 { 0 } else { r * 40 + 55 },
+                            if g == 0 { 0 } else { g * 40 + 55 },
+                            if b == 0 { 0 } else { b * 40 + 55 },
+                        );
                     }
                     index += 1;
                 }
@@ -258,7 +288,7 @@ impl List {
             }
 
             let value = i * 10 + 8;
-            self[index] = Rgb { r: value, g: value, b: value };
+            self[index] = Rgb::new(value, value, value);
             index += 1;
         }
 
@@ -319,3 +349,193 @@ impl IndexMut<u8> for List {
         &mut self.0[idx as usize]
     }
 }
+
+/// Named colors of CSS, sorted by name
+const CSS_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::Rgb;
+
+    #[test]
+    fn parse_hex_colors() {
+        assert_eq!(Rgb::from_str("0xff8000"), Ok(Rgb::new(0xff, 0x80, 0)));
+        assert_eq!(Rgb::from_str("#ff8000"), Ok(Rgb::new(0xff, 0x80, 0)));
+        assert_eq!(Rgb::from_str("#ff800080"), Ok(Rgb { a: 0x80, ..Rgb::new(0xff, 0x80, 0) }));
+        assert_eq!(Rgb::from_str("#ff80"), Err(()));
+        assert_eq!(Rgb::from_str("#+f8000"), Err(()));
+    }
+
+    #[test]
+    fn parse_named_colors() {
+        assert_eq!(Rgb::from_str("rebeccapurple"), Ok(Rgb::new(0x66, 0x33, 0x99)));
+        assert_eq!(Rgb::from_str("AliceBlue"), Ok(Rgb::new(0xf0, 0xf8, 0xff)));
+        assert_eq!(Rgb::from_str("transparent").map(|rgb| rgb.a), Ok(0));
+        assert_eq!(Rgb::from_str("notacolor"), Err(()));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let rgb = Rgb { a: 0x40, ..Rgb::new(1, 2, 3) };
+        let serialized = serde_yaml::to_string(&rgb).unwrap();
+        assert_eq!(serde_yaml::from_str::<Rgb>(&serialized).unwrap(), rgb);
+
+        // Ref tests were recorded without alpha
+        let rgb: Rgb = serde_json::from_str(r#"{"r":1,"g":2,"b":3}"#).unwrap();
+        assert_eq!(rgb, Rgb::new(1, 2, 3));
+
+        let rgb: Rgb = serde_yaml::from_str("'#01020340'").unwrap();
+        assert_eq!(rgb.a, 0x40);
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hashing of the logical inputs of a frame
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::index::{Column, Line};
+use crate::term::color::Rgb;
+use crate::term::images::ImageCell;
+use crate::term::{RenderableCell, RenderableCellContent, SizeInfo};
+
+/// Every item is prefixed with a tag, so different item sequences never hash the same input
+#[derive(Hash)]
+enum Tag {
+    Size,
+    Font,
+    Cell,
+    Cursor,
+    Text,
+    Color,
+    Highlight,
+    Label,
+    Image,
+}
+
+/// Hasher over everything which ends up in a rendered frame
+///
+/// Two frames with the same hash render the same pixels, as long as the font and the
+/// renderer configuration didn't change in between.
+#[derive(Default)]
+pub struct FrameHasher(DefaultHasher);
+
+impl FrameHasher {
+    pub fn size(&mut self, size: &SizeInfo) {
+        Tag::Size.hash(&mut self.0);
+        for value in &[size.width, size.height, size.padding_x, size.padding_y] {
+            value.to_bits().hash(&mut self.0);
+        }
+    }
+
+    pub fn font_size(&mut self, size: font::Size) {
+        Tag::Font.hash(&mut self.0);
+        size.hash(&mut self.0);
+    }
+
+    pub fn cell(&mut self, cell: &RenderableCell) {
+        match cell.inner {
+            RenderableCellContent::Chars(chars) => {
+                Tag::Cell.hash(&mut self.0);
+                chars.hash(&mut self.0);
+            },
+            RenderableCellContent::Cursor(key) => {
+                Tag::Cursor.hash(&mut self.0);
+                key.hash(&mut self.0);
+            },
+        }
+        cell.line.0.hash(&mut self.0);
+        cell.column.0.hash(&mut self.0);
+        cell.fg.hash(&mut self.0);
+        cell.bg.hash(&mut self.0);
+        cell.bg_alpha.to_bits().hash(&mut self.0);
+        cell.flags.hash(&mut self.0);
+    }
+
+    /// Text drawn on top of the grid, like messages or the status line
+    pub fn text(&mut self, line: Line, text: &str) {
+        Tag::Text.hash(&mut self.0);
+        line.0.hash(&mut self.0);
+        text.hash(&mut self.0);
+    }
+
+    /// Text drawn on top of the grid starting at a specific cell, like hint labels
+    pub fn label(&mut self, line: Line, col: Column, text: &str) {
+        Tag::Label.hash(&mut self.0);
+        line.0.hash(&mut self.0);
+        col.0.hash(&mut self.0);
+        text.hash(&mut self.0);
+    }
+
+    /// Colored overlay across the cells `start` to `end` of a line
+    pub fn highlight(&mut self, line: Line, start: Column, end: Column, color: Rgb) {
+        Tag::Highlight.hash(&mut self.0);
+        line.0.hash(&mut self.0);
+        start.0.hash(&mut self.0);
+        end.0.hash(&mut self.0);
+        color.hash(&mut self.0);
+    }
+
+    /// Part of an image drawn on top of a cell
+    pub fn image(&mut self, cell: &ImageCell) {
+        Tag::Image.hash(&mut self.0);
+        cell.line.0.hash(&mut self.0);
+        cell.col.0.hash(&mut self.0);
+        cell.image.hash(&mut self.0);
+        cell.row.hash(&mut self.0);
+        cell.offset.hash(&mut self.0);
+    }
+
+    /// Colors and intensities which are not part of any cell
+    pub fn color(&mut self, color: Rgb, intensity: f64) {
+        Tag::Color.hash(&mut self.0);
+        color.hash(&mut self.0);
+        intensity.to_bits().hash(&mut self.0);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameHasher;
+    use crate::index::{Column, Line};
+    use crate::term::cell::{Flags, MAX_ZEROWIDTH_CHARS};
+    use crate::term::color::Rgb;
+    use crate::term::{RenderableCell, RenderableCellContent};
+
+    fn cell(line: usize, column: usize, c: char) -> RenderableCell {
+        let mut chars = [' '; MAX_ZEROWIDTH_CHARS + 1];
+        chars[0] = c;
+        RenderableCell {
+            line: Line(line),
+            column: Column(column),
+            inner: RenderableCellContent::Chars(chars),
+            fg: Rgb::new(0xff, 0xff, 0xff),
+            bg: Rgb::default(),
+            bg_alpha: 0.,
+            flags: Flags::empty(),
+        }
+    }
+
+    fn hash(cells: &[RenderableCell]) -> u64 {
+        let mut hasher = FrameHasher::default();
+        for cell in cells {
+            hasher.cell(cell);
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn identical_frames() {
+        let cells = [cell(0, 0, 'a'), cell(0, 1, 'b')];
+        assert_eq!(hash(&cells), hash(&cells.clone()));
+    }
+
+    #[test]
+    fn changed_frames() {
+        let cells = [cell(0, 0, 'a'), cell(0, 1, 'b')];
+        assert_ne!(hash(&cells), hash(&[cell(0, 0, 'a'), cell(0, 1, 'c')]));
+        assert_ne!(hash(&cells), hash(&[cell(0, 0, 'a'), cell(1, 1, 'b')]));
+
+        let mut bold = cells.clone();
+        bold[1].flags = Flags::BOLD;
+        assert_ne!(hash(&cells), hash(&bold));
+    }
+
+    #[test]
+    fn text_is_not_a_cell() {
+        let mut text = FrameHasher::default();
+        text.text(Line(0), "a");
+        assert_ne!(text.finish(), hash(&[cell(0, 0, 'a')]));
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Graphics attributes reported through XTSMGRAPHICS
+use std::cmp::min;
+use std::fmt::Write;
+
+use crate::ansi::{GraphicsAction, GraphicsItem};
+use crate::term::SizeInfo;
+
+/// Number of color registers used until an application requests a different amount
+pub const DEFAULT_COLOR_REGISTERS: usize = 256;
+
+/// Largest number of color registers which can be requested
+pub const MAX_COLOR_REGISTERS: usize = 1024;
+
+/// Largest sixel image geometry in pixels, independent of the window size
+pub const MAX_SIXEL_GEOMETRY: (usize, usize) = (4096, 4096);
+
+/// Status reported in XTSMGRAPHICS replies
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Status {
+    Success = 0,
+    InvalidItem = 1,
+    InvalidAction = 2,
+    Failure = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicsAttributes {
+    color_registers: usize,
+
+    /// Sixel geometry requested by the application, the window size is used if unset
+    sixel_geometry: Option<(usize, usize)>,
+}
+
+impl Default for GraphicsAttributes {
+    fn default() -> GraphicsAttributes {
+        GraphicsAttributes { color_registers: DEFAULT_COLOR_REGISTERS, sixel_geometry: None }
+    }
+}
+
+impl GraphicsAttributes {
+    /// Handle an XTSMGRAPHICS request and build its reply
+    pub fn request(
+        &mut self,
+        size: &SizeInfo,
+        item: Option<GraphicsItem>,
+        action: Option<GraphicsAction>,
+        values: &[i64],
+    ) -> String {
+        // ReGIS is not supported
+        let item = match item {
+            Some(GraphicsItem::RegisGeometry) | None => {
+                return reply(item, Status::InvalidItem, &[0]);
+            },
+            Some(item) => item,
+        };
+        let action = match action {
+            Some(action) => action,
+            None => return reply(Some(item), Status::InvalidAction, &[0]),
+        };
+
+        match (item, action) {
+            (GraphicsItem::ColorRegisters, GraphicsAction::Set) => match values.get(0) {
+                Some(&registers) if registers > 0 => {
+                    self.color_registers = min(registers as usize, MAX_COLOR_REGISTERS);
+                },
+                _ => return reply(Some(item), Status::Failure, &[0]),
+            },
+            (GraphicsItem::SixelGeometry, GraphicsAction::Set) => match values {
+                [width, height, ..] if *width > 0 && *height > 0 => {
+                    self.sixel_geometry = Some((*width as usize, *height as usize));
+                },
+                _ => return reply(Some(item), Status::Failure, &[0]),
+            },
+            (GraphicsItem::ColorRegisters, GraphicsAction::Reset) => {
+                self.color_registers = DEFAULT_COLOR_REGISTERS;
+            },
+            (GraphicsItem::SixelGeometry, GraphicsAction::Reset) => self.sixel_geometry = None,
+            _ => (),
+        }
+
+        let values = match (item, action) {
+            (GraphicsItem::ColorRegisters, GraphicsAction::ReadMaximum) => {
+                vec![MAX_COLOR_REGISTERS]
+            },
+            (GraphicsItem::SixelGeometry, GraphicsAction::ReadMaximum) => {
+                let (width, height) = max_sixel_geometry(size);
+                vec![width, height]
+            },
+            (GraphicsItem::SixelGeometry, _) => {
+                let (width, height) = self.sixel_geometry(size);
+                vec![width, height]
+            },
+            _ => vec![self.color_registers],
+        };
+
+        reply(Some(item), Status::Success, &values)
+    }
+
+    /// Current sixel geometry, limited to the text area of the window
+    pub fn sixel_geometry(&self, size: &SizeInfo) -> (usize, usize) {
+        let (max_width, max_height) = max_sixel_geometry(size);
+        match self.sixel_geometry {
+            Some((width, height)) => (min(width, max_width), min(height, max_height)),
+            None => (max_width, max_height),
+        }
+    }
+}
+
+/// Size of the text area in pixels, limited to the largest supported geometry
+fn max_sixel_geometry(size: &SizeInfo) -> (usize, usize) {
+    let width = (size.width - 2. * size.padding_x).max(0.) as usize;
+    let height = (size.height - 2. * size.padding_y).max(0.) as usize;
+    (min(width, MAX_SIXEL_GEOMETRY.0), min(height, MAX_SIXEL_GEOMETRY.1))
+}
+
+fn reply(item: Option<GraphicsItem>, status: Status, values: &[usize]) -> String {
+    let item = item.map_or(0, |item| item as u8);
+    let mut reply = format!("\x1b[?{};{}", item, status as u8);
+    for value in values {
+        let _ = write!(reply, ";{}", value);
+    }
+    reply.push('S');
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphicsAttributes;
+    use crate::ansi::{GraphicsAction, GraphicsItem};
+    use crate::term::SizeInfo;
+
+    fn size() -> SizeInfo {
+        SizeInfo {
+            width: 804.0,
+            height: 604.0,
+            cell_width: 8.0,
+            cell_height: 16.0,
+            padding_x: 2.0,
+            padding_y: 2.0,
+            dpr: 1.0,
+        }
+    }
+
+    fn request(attributes: &mut GraphicsAttributes, args: &[i64]) -> String {
+        let item = GraphicsItem::from_primitive(args[0]);
+        let action = GraphicsAction::from_primitive(args[1]);
+        attributes.request(&size(), item, action, &args[2..])
+    }
+
+    #[test]
+    fn color_registers() {
+        let mut attributes = GraphicsAttributes::default();
+        assert_eq!(request(&mut attributes, &[1, 1]), "\x1b[?1;0;256S");
+        assert_eq!(request(&mut attributes, &[1, 4]), "\x1b[?1;0;1024S");
+        assert_eq!(request(&mut attributes, &[1, 3, 2048]), "\x1b[?1;0;1024S");
+        assert_eq!(request(&mut attributes, &[1, 2]), "\x1b[?1;0;256S");
+    }
+
+    #[test]
+    fn sixel_geometry_follows_window() {
+        let mut attributes = GraphicsAttributes::default();
+        assert_eq!(request(&mut attributes, &[2, 1]), "\x1b[?2;0;800;600S");
+        assert_eq!(request(&mut attributes, &[2, 3, 400, 1000]), "\x1b[?2;0;400;600S");
+        assert_eq!(request(&mut attributes, &[2, 2]), "\x1b[?2;0;800;600S");
+    }
+
+    #[test]
+    fn invalid_requests() {
+        let mut attributes = GraphicsAttributes::default();
+        assert_eq!(request(&mut attributes, &[3, 1]), "\x1b[?3;1;0S");
+        assert_eq!(request(&mut attributes, &[9, 1]), "\x1b[?0;1;0S");
+        assert_eq!(request(&mut attributes, &[1, 9]), "\x1b[?1;2;0S");
+        assert_eq!(request(&mut attributes, &[2, 3, 0]), "\x1b[?2;3;0S");
+    }
+}
@@ -0,0 +1,446 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of URLs, file paths and configured regexes in the visible part of the grid
+//!
+//! In hint mode every match is labeled with a few characters, typing a label opens its match
+//! or runs the action of its regex without using the mouse. When matches overlap, URLs and
+//! paths take precedence over configured regexes, which take precedence in the order they are
+//! configured.
+use std::cmp::min;
+use std::ops::RangeInclusive;
+
+use regex::Regex;
+
+use crate::config::{HintRule, Hints};
+use crate::grid::{Grid, ViewportPosition};
+use crate::index::{Column, Line, Linear, Point};
+use crate::term::cell::{Cell, Flags, LineLength};
+use crate::term::search::{clamped_line_bounds, viewport_context};
+
+/// Characters the labels are made of, the easiest to reach come first
+const LABEL_ALPHABET: &str = "jfkdlsahgurieowpq";
+
+/// URLs with one of the supported schemes, or paths
+///
+/// Paths have to start with `/`, `~/`, `./` or `../`, or end in a file extension, so text like
+/// `and/or` is not taken for one.
+const HINT_REGEX: &str = concat!(
+    r#"(?P<url>(?:https?://|mailto:|file://|git://|ssh://|ftp://|news:)[^\s<>"{}|\\^`]+)"#,
+    r#"|(?:^|[\s'"(\[=])(?P<path>(?:~|\.{1,2})?(?:/[\w.@%+~-]+)+/?"#,
+    r#"|[\w.-]+(?:/[\w.@%+~-]+)*/[\w@%+~-]+\.\w+)"#,
+);
+
+/// Characters which are not considered part of a hint when they end it
+const DENY_END_CHARS: [char; 8] = ['.', ',', ';', ':', '?', '!', '(', '\''];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HintKind {
+    Url,
+    Path,
+
+    /// Match of a configured regex
+    Custom(HintRule),
+}
+
+/// Text which can be acted on, from its first to its last cell in buffer coordinates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub kind: HintKind,
+    pub text: String,
+    pub start: Point<usize>,
+    pub end: Point<usize>,
+}
+
+impl Hint {
+    /// Cells of the hint which are visible in the viewport
+    pub fn visible_range(&self, grid: &Grid<Cell>) -> Option<RangeInclusive<Linear>> {
+        let num_lines = grid.num_lines().0;
+        let num_cols = grid.num_cols();
+
+        let start = match grid.buffer_line_to_visible(self.start.line) {
+            ViewportPosition::Visible(line) => Point::new(line.0, self.start.col),
+            ViewportPosition::Above => Point::new(0, Column(0)),
+            ViewportPosition::Below => return None,
+        };
+        let end = match grid.buffer_line_to_visible(self.end.line) {
+            ViewportPosition::Visible(line) => Point::new(line.0, self.end.col),
+            ViewportPosition::Below => Point::new(num_lines - 1, num_cols - 1),
+            ViewportPosition::Above => return None,
+        };
+
+        let start = Linear::from_point(num_cols, start);
+        let end = Linear::from_point(num_cols, end);
+        Some(RangeInclusive::new(start, end))
+    }
+
+    /// First visible cell of the hint
+    fn label_position(&self, grid: &Grid<Cell>) -> Option<(Line, Column)> {
+        let range = self.visible_range(grid)?;
+        let num_cols = grid.num_cols().0;
+        let start = range.start().0;
+        Some((Line(start / num_cols), Column(start % num_cols)))
+    }
+}
+
+/// Finds the hints in the grid
+pub struct HintDetector {
+    regex: Regex,
+    rules: Vec<(Regex, HintRule)>,
+    config: Hints,
+}
+
+impl Default for HintDetector {
+    fn default() -> HintDetector {
+        HintDetector::new(&Hints::default())
+    }
+}
+
+impl HintDetector {
+    pub fn new(config: &Hints) -> HintDetector {
+        HintDetector {
+            regex: Regex::new(HINT_REGEX).expect("valid hint regex"),
+            rules: compile(config),
+            config: config.clone(),
+        }
+    }
+
+    pub fn update_config(&mut self, config: &Hints) {
+        if self.config != *config {
+            *self = HintDetector::new(config);
+        }
+    }
+
+    /// Hints which are at least partially visible, from the top of the viewport to its bottom
+    pub fn visible_hints(&self, grid: &Grid<Cell>) -> Vec<Hint> {
+        let viewport_bottom = grid.display_offset();
+        let viewport_top = viewport_bottom + grid.num_lines().0 - 1;
+        let (max_top, min_bottom) = viewport_context(grid);
+
+        let mut hints = Vec::new();
+        let mut line = min(viewport_top, grid.len() - 1);
+        loop {
+            let (top, bottom) = clamped_line_bounds(grid, line, max_top, min_bottom);
+            hints.extend(self.find(grid, top, bottom));
+
+            if bottom <= viewport_bottom {
+                break;
+            }
+            line = bottom - 1;
+        }
+
+        hints
+    }
+
+    /// Hint covering the visible cell at `point`
+    pub fn hint_at(&self, grid: &Grid<Cell>, point: Point) -> Option<Hint> {
+        let num_cols = grid.num_cols();
+        let index = Linear::from_point(num_cols, Point::new(point.line.0, point.col));
+
+        let line = grid.visible_line_to_buffer(point.line);
+        let (max_top, min_bottom) = viewport_context(grid);
+        let (top, bottom) = clamped_line_bounds(grid, line, max_top, min_bottom);
+        self.find(grid, top, bottom).into_iter().find(|hint| {
+            hint.visible_range(grid).map_or(false, |range| {
+                *range.start() <= index && index <= *range.end()
+            })
+        })
+    }
+
+    /// Hints within the rows `top` to `bottom`, in reading order
+    fn find(&self, grid: &Grid<Cell>, top: usize, bottom: usize) -> Vec<Hint> {
+        let mut text = String::new();
+        let mut points = Vec::new();
+        for line in (bottom..=top).rev() {
+            let row = &grid[line];
+            for col in 0..row.line_length().0 {
+                let cell = &row[Column(col)];
+                if !cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    points.push((text.len(), Point::new(line, Column(col))));
+                    text.push(cell.c);
+                }
+            }
+        }
+
+        let point_at = |byte: usize| {
+            let index = match points.binary_search_by_key(&byte, |&(offset, _)| offset) {
+                Ok(index) => index,
+                Err(index) => index - 1,
+            };
+            points[index].1
+        };
+
+        // Byte ranges of the matches in the text, in order of precedence
+        let mut found = Vec::new();
+        for captures in self.regex.captures_iter(&text) {
+            let (kind, m) = match (captures.name("url"), captures.name("path")) {
+                (Some(url), _) => (HintKind::Url, url),
+                (None, Some(path)) => (HintKind::Path, path),
+                (None, None) => continue,
+            };
+
+            let len = trim_end(m.as_str()).len();
+            found.push((m.start(), m.start() + len, kind));
+        }
+        for (regex, rule) in &self.rules {
+            for m in regex.find_iter(&text) {
+                let overlaps =
+                    found.iter().any(|&(start, end, _)| m.start() < end && start < m.end());
+                if !overlaps {
+                    found.push((m.start(), m.end(), HintKind::Custom(rule.clone())));
+                }
+            }
+        }
+        found.retain(|&(start, end, _)| start < end);
+        found.sort_by_key(|&(start, ..)| start);
+
+        found
+            .into_iter()
+            .map(|(start_byte, end_byte, kind)| {
+                let start = point_at(start_byte);
+                let mut end = point_at(end_byte - 1);
+                if grid[end.line][end.col].flags.contains(Flags::WIDE_CHAR) {
+                    end.col += Column(1);
+                }
+
+                Hint { kind, text: text[start_byte..end_byte].to_owned(), start, end }
+            })
+            .collect()
+    }
+}
+
+/// Compile the regexes of all configured hints, invalid ones are dropped
+fn compile(config: &Hints) -> Vec<(Regex, HintRule)> {
+    config
+        .enabled
+        .iter()
+        .filter_map(|rule| match Regex::new(&rule.regex) {
+            Ok(regex) => Some((regex, rule.clone())),
+            Err(err) => {
+                error!("Invalid hint regex {:?}: {}", rule.regex, err);
+                None
+            },
+        })
+        .collect()
+}
+
+/// Remove punctuation and unbalanced closing brackets from the end of a hint
+fn trim_end(mut text: &str) -> &str {
+    loop {
+        let last = match text.chars().last() {
+            Some(last) => last,
+            None => return text,
+        };
+
+        let unbalanced = match last {
+            ')' => text.matches('(').count() < text.matches(')').count(),
+            ']' => text.matches('[').count() < text.matches(']').count(),
+            _ => false,
+        };
+
+        if !unbalanced && !DENY_END_CHARS.contains(&last) {
+            return text;
+        }
+        text = &text[..text.len() - last.len_utf8()];
+    }
+}
+
+/// Result of typing a character in hint mode
+#[derive(Debug, PartialEq, Eq)]
+pub enum HintInput {
+    /// More characters are required to identify the hint
+    Pending,
+
+    /// The typed characters are the complete label of this hint
+    Selected(Hint),
+
+    /// No label starts with the typed characters
+    NoMatch,
+}
+
+/// Visible hints with their labels and the characters typed so far
+#[derive(Debug)]
+pub struct HintMode {
+    hints: Vec<(String, Hint)>,
+    typed: String,
+}
+
+impl HintMode {
+    pub fn new(hints: Vec<Hint>) -> HintMode {
+        let labels = labels(hints.len());
+        HintMode { hints: labels.into_iter().zip(hints).collect(), typed: String::new() }
+    }
+
+    /// Type the next character of a label
+    pub fn input(&mut self, c: char) -> HintInput {
+        self.typed.push(c);
+
+        let mut candidates = self.hints.iter().filter(|(label, _)| label.starts_with(&self.typed));
+        match candidates.next() {
+            Some((label, hint)) if *label == self.typed => HintInput::Selected(hint.clone()),
+            Some(_) => HintInput::Pending,
+            None => HintInput::NoMatch,
+        }
+    }
+
+    /// Untyped remainders of the labels which still match, at the start of their hints
+    pub fn visible_labels(&self, grid: &Grid<Cell>) -> Vec<(Line, Column, String)> {
+        self.hints
+            .iter()
+            .filter(|(label, _)| label.starts_with(&self.typed))
+            .filter_map(|(label, hint)| {
+                let (line, col) = hint.label_position(grid)?;
+                let remainder = &label[self.typed.len()..];
+
+                // Keep labels at the right edge inside the grid
+                let last_start = grid.num_cols().0.saturating_sub(remainder.chars().count());
+                Some((line, min(col, Column(last_start)), remainder.to_owned()))
+            })
+            .collect()
+    }
+}
+
+/// Labels of equal length for `count` hints
+fn labels(count: usize) -> Vec<String> {
+    let alphabet: Vec<char> = LABEL_ALPHABET.chars().collect();
+
+    let mut len = 1;
+    let mut capacity = alphabet.len();
+    while capacity < count {
+        len += 1;
+        capacity *= alphabet.len();
+    }
+
+    (0..count)
+        .map(|mut index| {
+            let mut label = vec![alphabet[0]; len];
+            for c in label.iter_mut().rev() {
+                *c = alphabet[index % alphabet.len()];
+                index /= alphabet.len();
+            }
+            label.into_iter().collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{HintRule, Hints};
+    use crate::grid::Grid;
+    use crate::index::{Column, Line, Point};
+    use crate::term::cell::{Cell, Flags};
+
+    use super::{labels, HintDetector, HintInput, HintKind, HintMode};
+
+    fn grid(lines: &[&str]) -> Grid<Cell> {
+        let mut grid = Grid::new(Line(lines.len()), Column(30), 0, Cell::default());
+        for (line, text) in lines.iter().enumerate() {
+            for (col, c) in text.chars().enumerate() {
+                grid[Line(line)][Column(col)].c = c;
+            }
+        }
+        grid
+    }
+
+    fn texts(grid: &Grid<Cell>) -> Vec<(HintKind, String)> {
+        HintDetector::default()
+            .visible_hints(grid)
+            .into_iter()
+            .map(|hint| (hint.kind, hint.text))
+            .collect()
+    }
+
+    #[test]
+    fn detects_urls_and_paths() {
+        let grid = grid(&["see https://example.org.", "error: src/main.rs:12:5", "a (~/notes)"]);
+
+        assert_eq!(texts(&grid), vec![
+            (HintKind::Url, String::from("https://example.org")),
+            (HintKind::Path, String::from("src/main.rs")),
+            (HintKind::Path, String::from("~/notes")),
+        ]);
+    }
+
+    #[test]
+    fn relative_paths_need_a_prefix_or_extension() {
+        let paths = grid(&["and/or x=1/2", "./run ../up a.b/c"]);
+        assert_eq!(texts(&paths), vec![
+            (HintKind::Path, String::from("./run")),
+            (HintKind::Path, String::from("../up")),
+        ]);
+
+
+        let url = grid(&["https://example.org/a/b"]);
+        assert_eq!(texts(&url), vec![(HintKind::Url, String::from("https://example.org/a/b"))]);
+    }
+
+    #[test]
+    fn configured_regexes() {
+        let rule = |regex: &str| HintRule { regex: regex.into(), ..HintRule::default() };
+        let config = Hints { enabled: vec![rule(r"\b[0-9a-f]{7}\b"), rule(r"\d+"), rule("(")] };
+        let detector = HintDetector::new(&config);
+
+        let grid = grid(&["3f2a9c1 /tmp/42 17"]);
+        let hints: Vec<_> = detector
+            .visible_hints(&grid)
+            .into_iter()
+            .map(|hint| (hint.kind, hint.text))
+            .collect();
+        assert_eq!(hints, vec![
+            (HintKind::Custom(rule(r"\b[0-9a-f]{7}\b")), String::from("3f2a9c1")),
+            (HintKind::Path, String::from("/tmp/42")),
+            (HintKind::Custom(rule(r"\d+")), String::from("17")),
+        ]);
+    }
+
+    #[test]
+    fn hint_across_wrapped_rows() {
+        let mut grid = grid(&["                   http://exam", "ple.org"]);
+        grid[Line(0)][Column(29)].flags.insert(Flags::WRAPLINE);
+
+        let hints = HintDetector::default().visible_hints(&grid);
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].text, "http://example.org");
+        assert_eq!(hints[0].start, Point::new(1, Column(19)));
+        assert_eq!(hints[0].end, Point::new(0, Column(6)));
+
+        let hint = HintDetector::default().hint_at(&grid, Point::new(Line(1), Column(2)));
+        assert_eq!(hint.map(|hint| hint.text), Some(String::from("http://example.org")));
+    }
+
+    #[test]
+    fn labels_have_equal_length() {
+        assert_eq!(labels(3), vec!["j", "f", "k"]);
+
+        let labels = labels(20);
+        assert!(labels.iter().all(|label| label.len() == 2));
+        assert_eq!(labels[0], "jj");
+        assert_eq!(labels[19], "fk");
+    }
+
+    #[test]
+    fn typing_a_label_selects_its_hint() {
+        let grid = grid(&["/tmp/a /tmp/b"]);
+        let mut mode = HintMode::new(HintDetector::default().visible_hints(&grid));
+
+        assert_eq!(mode.visible_labels(&grid).len(), 2);
+        match mode.input('f') {
+            HintInput::Selected(hint) => assert_eq!(hint.text, "/tmp/b"),
+            input => panic!("unexpected input result {:?}", input),
+        }
+
+        let mut mode = HintMode::new(HintDetector::default().visible_hints(&grid));
+        assert_eq!(mode.input('x'), HintInput::NoMatch);
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! URIs of the hyperlinks started with `OSC 8`
+//!
+//! Cells refer to their hyperlink through the `hyperlink` index of their metadata. Links with
+//! the same ID and URI share one index, so a link written in several parts, like one which is
+//! wrapped by a pager, is hovered and opened as a whole. Links without an ID share the index of
+//! their URI.
+use std::collections::HashMap;
+
+use crate::ansi::Hyperlink;
+
+/// Maximum number of hyperlinks, the metadata table can't refer to more anyway
+const MAX_HYPERLINKS: usize = 0xffff;
+
+#[derive(Default)]
+pub struct Hyperlinks {
+    links: Vec<Option<Hyperlink>>,
+    indices: HashMap<Hyperlink, u32>,
+    free: Vec<u32>,
+
+    /// Inserts since the last collection, including dropped ones
+    inserted: usize,
+
+    /// Number of links which survived the last collection
+    live: usize,
+}
+
+impl Hyperlinks {
+    /// Get the index of a hyperlink, adding it if necessary
+    ///
+    /// Once the table is full, new links are dropped and `None` is returned.
+    pub fn insert(&mut self, hyperlink: Hyperlink) -> Option<u32> {
+        if let Some(index) = self.indices.get(&hyperlink) {
+            return Some(*index);
+        }
+
+        self.inserted += 1;
+
+        let index = if let Some(index) = self.free.pop() {
+            self.links[index as usize] = Some(hyperlink.clone());
+            index
+        } else if self.links.len() < MAX_HYPERLINKS {
+            self.links.push(Some(hyperlink.clone()));
+            self.links.len() as u32 - 1
+        } else {
+            warn!("Too many hyperlinks, dropping {:?}", hyperlink.uri);
+            return None;
+        };
+
+        self.indices.insert(hyperlink, index);
+
+        Some(index)
+    }
+
+    /// URI of a hyperlink
+    #[inline]
+    pub fn uri(&self, index: u32) -> Option<&str> {
+        let link = self.links.get(index as usize)?.as_ref()?;
+        Some(&link.uri)
+    }
+
+    /// Whether the table should be collected before inserting more links
+    ///
+    /// Like the metadata table, a full table is only collected again after as many inserts as
+    /// there were links left by the previous collection.
+    #[inline]
+    pub fn needs_collection(&self) -> bool {
+        self.free.is_empty() && self.links.len() >= MAX_HYPERLINKS && self.inserted >= self.live
+    }
+
+    /// Free all links which are not in `used`
+    pub fn collect<I: IntoIterator<Item = u32>>(&mut self, used: I) {
+        let mut marked = vec![false; self.links.len()];
+        for index in used {
+            if let Some(mark) = marked.get_mut(index as usize) {
+                *mark = true;
+            }
+        }
+
+        for (index, link) in self.links.iter_mut().enumerate() {
+            if marked[index] {
+                continue;
+            }
+
+            if let Some(link) = link.take() {
+                self.indices.remove(&link);
+                self.free.push(index as u32);
+            }
+        }
+
+        self.live = self.indices.len();
+        self.inserted = 0;
+
+        debug!("Collected hyperlinks, {} still in use", self.live);
+    }
+
+    /// Remove all links
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hyperlinks, MAX_HYPERLINKS};
+    use crate::ansi::Hyperlink;
+
+    fn link(id: Option<&str>, uri: &str) -> Hyperlink {
+        Hyperlink { id: id.map(String::from), uri: String::from(uri) }
+    }
+
+    #[test]
+    fn links_with_same_id_are_deduplicated() {
+        let mut hyperlinks = Hyperlinks::default();
+
+        let first = hyperlinks.insert(link(Some("a"), "https://a.org"));
+        let second = hyperlinks.insert(link(Some("a"), "https://a.org"));
+        let other_uri = hyperlinks.insert(link(Some("a"), "https://b.org"));
+
+        assert_eq!(first, second);
+        assert_ne!(first, other_uri);
+        assert_eq!(hyperlinks.uri(other_uri.unwrap()), Some("https://b.org"));
+    }
+
+    #[test]
+    fn links_without_id_are_deduplicated_by_uri() {
+        let mut hyperlinks = Hyperlinks::default();
+
+        let first = hyperlinks.insert(link(None, "https://a.org"));
+        let second = hyperlinks.insert(link(None, "https://a.org"));
+        let with_id = hyperlinks.insert(link(Some("a"), "https://a.org"));
+
+        assert_eq!(first, second);
+        assert_ne!(first, with_id);
+        assert_eq!(hyperlinks.uri(first.unwrap()), Some("https://a.org"));
+    }
+
+    #[test]
+    fn full_table_is_recycled() {
+        let mut hyperlinks = Hyperlinks::default();
+        for i in 0..MAX_HYPERLINKS {
+            hyperlinks.insert(link(None, &i.to_string()));
+        }
+
+        assert!(hyperlinks.needs_collection());
+        assert_eq!(hyperlinks.insert(link(None, "dropped")), None);
+
+        hyperlinks.collect(vec![3]);
+        assert_eq!(hyperlinks.uri(3), Some("3"));
+        assert_eq!(hyperlinks.uri(4), None);
+
+        let index = hyperlinks.insert(link(None, "new")).unwrap();
+        assert_eq!(hyperlinks.uri(index), Some("new"));
+        assert_eq!(hyperlinks.insert(link(None, "3")), Some(3));
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Images placed on the grid
+//!
+//! Every cell covered by an image carries an `ImageTile` in its metadata, so images scroll with
+//! the text and the parts of an image overwritten by text disappear. The pixels are stored once
+//! in the `ImageStore`, until the oldest images are evicted to make room for new ones.
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::index::{Column, Line};
+use crate::term::color::Rgb;
+
+/// Pixels of all stored images, beyond which the oldest images are dropped
+const MAX_STORED_PIXELS: usize = 64 * 1024 * 1024;
+
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Hash)]
+pub struct ImageId(u32);
+
+/// Row of an image which is drawn across a row of cells
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ImageTile {
+    pub image: ImageId,
+    pub row: u16,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Image {
+    pub id: ImageId,
+    pub width: usize,
+    pub height: usize,
+
+    /// Pixels from the top left to the bottom right
+    pub pixels: Vec<Rgb>,
+
+    /// Column of the cell the left edge of the image was placed on
+    pub col: Column,
+}
+
+/// Visible cell showing a part of an image
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImageCell {
+    pub line: Line,
+    pub col: Column,
+    pub image: ImageId,
+
+    /// Row and column of the cell within the image
+    pub row: usize,
+    pub offset: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    /// Images from the oldest to the newest
+    images: VecDeque<Arc<Image>>,
+    pixels: usize,
+    next_id: u32,
+}
+
+impl ImageStore {
+    /// Store the pixels of an image placed at `col`
+    pub fn insert(
+        &mut self,
+        width: usize,
+        height: usize,
+        pixels: Vec<Rgb>,
+        col: Column,
+    ) -> ImageId {
+        let id = ImageId(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1);
+
+        self.pixels += pixels.len();
+        self.images.push_back(Arc::new(Image { id, width, height, pixels, col }));
+
+        while self.pixels > MAX_STORED_PIXELS && self.images.len() > 1 {
+            if let Some(image) = self.images.pop_front() {
+                self.pixels -= image.pixels.len();
+            }
+        }
+
+        id
+    }
+
+    /// Look up a stored image, evicted images are not drawn anymore
+    pub fn get(&self, id: ImageId) -> Option<&Arc<Image>> {
+        self.images.iter().rev().find(|image| image.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.pixels = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageStore, MAX_STORED_PIXELS};
+    use crate::index::Column;
+    use crate::term::color::Rgb;
+
+    #[test]
+    fn evicts_oldest_images() {
+        let mut store = ImageStore::default();
+        let half = vec![Rgb::default(); MAX_STORED_PIXELS / 2];
+
+        let first = store.insert(1, half.len(), half.clone(), Column(0));
+        let second = store.insert(1, half.len(), half.clone(), Column(0));
+        assert!(store.get(first).is_some());
+
+        let third = store.insert(1, 1, vec![Rgb::default()], Column(3));
+        assert!(store.get(first).is_none());
+        assert!(store.get(second).is_some());
+        assert_eq!(store.get(third).map(|image| image.col), Some(Column(3)));
+    }
+}
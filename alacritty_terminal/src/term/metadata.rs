@@ -0,0 +1,231 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Side table for cell attributes which are rarely set
+//!
+//! Instead of growing every `Cell`, each distinct combination of these attributes is stored
+//! once in a `MetadataTable` and cells only carry the small `MetadataId` referring to it.
+use std::collections::HashMap;
+
+use crate::term::images::ImageTile;
+
+/// Reference to an entry of the metadata table
+///
+/// The default ID is used for cells without any metadata.
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct MetadataId(u16);
+
+impl MetadataId {
+    pub const NONE: MetadataId = MetadataId(0);
+
+    #[inline]
+    pub fn is_none(self) -> bool {
+        self == MetadataId::NONE
+    }
+}
+
+/// Attributes attached to a cell
+#[derive(Clone, Default, Debug, Eq, PartialEq, Hash)]
+pub struct Metadata {
+    /// Command which produced the cell
+    pub command: Option<u32>,
+
+    /// Hyperlink the cell is part of
+    pub hyperlink: Option<u32>,
+
+    /// Mark set by the application, like a shell prompt
+    pub mark: Option<u32>,
+
+    /// Part of an image drawn on the cell
+    pub image: Option<ImageTile>,
+}
+
+impl Metadata {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.command.is_none()
+            && self.hyperlink.is_none()
+            && self.mark.is_none()
+            && self.image.is_none()
+    }
+}
+
+/// Maximum number of entries, ID zero is reserved for cells without metadata
+const MAX_ENTRIES: usize = u16::max_value() as usize;
+
+/// Interned metadata of all cells in the terminal
+///
+/// Entries are not reference counted, since cells are copied around freely. Instead the table
+/// is collected once it is full, freeing every entry which is no longer used by any cell.
+#[derive(Default)]
+pub struct MetadataTable {
+    entries: Vec<Option<Metadata>>,
+    ids: HashMap<Metadata, MetadataId>,
+    free: Vec<MetadataId>,
+
+    /// Interns since the last collection, including dropped ones
+    interned: usize,
+
+    /// Number of entries which survived the last collection
+    live: usize,
+}
+
+impl MetadataTable {
+    /// Get the ID of the metadata, adding it to the table if necessary
+    ///
+    /// Once the table is full, new metadata is dropped and `MetadataId::NONE` is returned.
+    pub fn intern(&mut self, metadata: Metadata) -> MetadataId {
+        if metadata.is_empty() {
+            return MetadataId::NONE;
+        }
+
+        if let Some(id) = self.ids.get(&metadata) {
+            return *id;
+        }
+
+        self.interned += 1;
+
+        let id = if let Some(id) = self.free.pop() {
+            self.entries[usize::from(id.0) - 1] = Some(metadata.clone());
+            id
+        } else if self.entries.len() < MAX_ENTRIES {
+            self.entries.push(Some(metadata.clone()));
+            MetadataId(self.entries.len() as u16)
+        } else {
+            warn!("Cell metadata table is full, dropping {:?}", metadata);
+            return MetadataId::NONE;
+        };
+
+        self.ids.insert(metadata, id);
+
+        id
+    }
+
+    /// Look up the metadata of an ID
+    #[inline]
+    pub fn get(&self, id: MetadataId) -> Option<&Metadata> {
+        if id.is_none() {
+            None
+        } else {
+            self.entries.get(usize::from(id.0) - 1).and_then(Option::as_ref)
+        }
+    }
+
+    /// All metadata in the table
+    pub fn iter(&self) -> impl Iterator<Item = &Metadata> {
+        self.entries.iter().filter_map(Option::as_ref)
+    }
+
+    /// Whether the table should be collected before interning more metadata
+    ///
+    /// To keep the cost of scanning all cells low, a full table is only collected again after
+    /// as many interns as there were entries left by the previous collection.
+    #[inline]
+    pub fn needs_collection(&self) -> bool {
+        self.free.is_empty() && self.entries.len() >= MAX_ENTRIES && self.interned >= self.live
+    }
+
+    /// Free all entries which are not in `used`
+    pub fn collect<I: IntoIterator<Item = MetadataId>>(&mut self, used: I) {
+        let mut marked = vec![false; self.entries.len()];
+        for id in used.into_iter().filter(|id| !id.is_none()) {
+            if let Some(mark) = marked.get_mut(usize::from(id.0) - 1) {
+                *mark = true;
+            }
+        }
+
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            if marked[index] {
+                continue;
+            }
+
+            if let Some(metadata) = entry.take() {
+                self.ids.remove(&metadata);
+                self.free.push(MetadataId(index as u16 + 1));
+            }
+        }
+
+        self.live = self.ids.len();
+        self.interned = 0;
+
+        debug!("Collected cell metadata, {} entries still in use", self.live);
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metadata, MetadataId, MetadataTable, MAX_ENTRIES};
+
+    #[test]
+    fn empty_metadata_is_not_stored() {
+        let mut table = MetadataTable::default();
+        assert_eq!(table.intern(Metadata::default()), MetadataId::NONE);
+        assert_eq!(table.get(MetadataId::NONE), None);
+    }
+
+    #[test]
+    fn intern_identical_metadata_once() {
+        let mut table = MetadataTable::default();
+        let link = Metadata { hyperlink: Some(3), ..Default::default() };
+        let mark = Metadata { mark: Some(1), ..Default::default() };
+
+        let link_id = table.intern(link.clone());
+        let mark_id = table.intern(mark.clone());
+
+        assert_ne!(link_id, mark_id);
+        assert_eq!(table.intern(link.clone()), link_id);
+        assert_eq!(table.get(link_id), Some(&link));
+        assert_eq!(table.get(mark_id), Some(&mark));
+    }
+
+    #[test]
+    fn full_table_is_recycled() {
+        let mut table = MetadataTable::default();
+        let link = |index| Metadata { hyperlink: Some(index), ..Default::default() };
+
+        let ids: Vec<_> = (0..MAX_ENTRIES as u32).map(|i| table.intern(link(i))).collect();
+        assert!(ids.iter().all(|id| !id.is_none()));
+        assert_eq!(table.intern(link(MAX_ENTRIES as u32)), MetadataId::NONE);
+        assert!(table.needs_collection());
+
+        // Only keep the first entry alive
+        table.collect(vec![ids[0]]);
+        assert!(!table.needs_collection());
+        assert_eq!(table.get(ids[0]), Some(&link(0)));
+        assert_eq!(table.get(ids[1]), None);
+
+        let recycled = table.intern(link(MAX_ENTRIES as u32));
+        assert!(!recycled.is_none());
+        assert_ne!(recycled, ids[0]);
+        assert_eq!(table.get(recycled), Some(&link(MAX_ENTRIES as u32)));
+        assert_eq!(table.intern(link(0)), ids[0]);
+
+        // Refill the table with entries which are all still in use
+        let mut used = vec![ids[0], recycled];
+        for i in 1..MAX_ENTRIES as u32 - 1 {
+            used.push(table.intern(link(MAX_ENTRIES as u32 + i)));
+        }
+        assert!(table.needs_collection());
+        table.collect(used);
+
+        // Nothing was freed, so the next collection waits for more interns
+        assert_eq!(table.intern(link(u32::max_value())), MetadataId::NONE);
+        assert!(!table.needs_collection());
+    }
+}
@@ -15,40 +15,83 @@
 //! Exports the `Term` type which is a high-level API for the Grid
 use std::cmp::{max, min};
 use std::ops::{Index, IndexMut, Range, RangeInclusive};
-use std::time::{Duration, Instant};
-use std::{io, mem, ptr};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use std::{io, iter, mem, ptr};
 
 use font::{self, Size};
 use glutin::MouseCursor;
 use unicode_width::UnicodeWidthChar;
 
 use crate::ansi::{
-    self, Attr, CharsetIndex, Color, CursorStyle, Handler, NamedColor, StandardCharset,
+    self, Attr, CharsetIndex, Color, CursorStyle, GraphicsAction, GraphicsItem, Handler, Hyperlink,
+    NamedColor, Progress, StandardCharset,
+};
+use crate::clipboard::{Clipboard, ClipboardType, RichText};
+use crate::config::{
+    Announcements, Config, Paste, SecurityMode, StatusLine, VisualBellAnimation,
 };
-use crate::clipboard::{Clipboard, ClipboardType};
-use crate::config::{Config, VisualBellAnimation};
 use crate::cursor::CursorKey;
 use crate::grid::{
-    BidirectionalIterator, DisplayIter, Grid, GridCell, IndexRegion, Indexed, Scroll,
-    ViewportPosition,
+    BidirectionalIterator, DisplayIter, Grid, GridCell, IndexRegion, Indexed, LineDamage,
+    Scroll, ViewportPosition,
 };
 use crate::index::{self, Column, Contains, IndexRange, Line, Linear, Point};
 use crate::input::FONT_SIZE_STEP;
-use crate::message_bar::MessageBuffer;
-use crate::selection::{self, Selection, SelectionRange, Span};
+use crate::message_bar::{Message, MessageBuffer};
+use crate::selection::{self, Selection, SelectionRange, SelectionRect, Span};
+use crate::term::announcer::Announcer;
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Rgb;
+use crate::term::frame_hash::FrameHasher;
+use crate::term::graphics::GraphicsAttributes;
+use crate::term::hints::{Hint, HintDetector, HintInput, HintMode};
+use crate::term::hyperlinks::Hyperlinks;
+use crate::term::images::{Image, ImageCell, ImageId, ImageStore, ImageTile};
+use crate::term::metadata::{Metadata, MetadataId, MetadataTable};
+use crate::term::notifications::Notifications;
+use crate::term::output_filter::OutputFilters;
+use crate::term::rich_text::StyledText;
+use crate::term::search::{Direction, ScrollbackSearch, VisibleMatch};
+use crate::term::sixel::SixelImage;
+use crate::term::watcher::Watcher;
 use crate::url::{Url, UrlParser};
+use crate::util::{format_utc_time, RateLimit};
 
 #[cfg(windows)]
 use crate::tty;
 
+mod announcer;
 pub mod cell;
 pub mod color;
+pub mod frame_hash;
+mod graphics;
+pub mod hints;
+mod hyperlinks;
+pub mod images;
+pub mod metadata;
+mod notifications;
+mod output_filter;
+pub mod rich_text;
+pub mod search;
+pub mod sixel;
+mod watcher;
 
 /// Used to match equal brackets, when performing a bracket-pair selection.
 const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
 
+/// Topic of the message shown while a paste is held back
+const PASTE_TOPIC: &str = "paste";
+
+/// Maximum number of clipboard writes per second requested by escape sequences
+const MAX_CLIPBOARD_WRITES: usize = 5;
+
+/// Maximum number of bells per second
+const MAX_BELLS: usize = 10;
+
+/// Maximum number of cells scanned in each direction when looking for a URL
+const MAX_URL_LENGTH: usize = 2048;
+
 /// A type that can expand a given point to a region
 ///
 /// Usually this is implemented for some 2-D array type since
@@ -125,20 +168,33 @@ impl Search for Term {
 
         // Find URLs
         let mut url_parser = UrlParser::new();
+        let mut length = 0;
         while let Some(cell) = iterb.prev() {
             if (iterb.cur.col == last_col && !cell.flags.contains(cell::Flags::WRAPLINE))
                 || url_parser.advance_left(cell)
             {
                 break;
             }
+
+            // Give up on runs of URL characters too long to be a link
+            length += 1;
+            if length > MAX_URL_LENGTH {
+                return None;
+            }
         }
 
+        length = 0;
         while let Some(cell) = iterf.next() {
             if url_parser.advance_right(cell)
                 || (iterf.cur.col == last_col && !cell.flags.contains(cell::Flags::WRAPLINE))
             {
                 break;
             }
+
+            length += 1;
+            if length > MAX_URL_LENGTH {
+                return None;
+            }
         }
         url_parser.url()
     }
@@ -201,106 +257,88 @@ impl selection::Dimensions for Term {
 /// Iterator that yields cells needing render
 ///
 /// Yields cells that require work to be displayed (that is, not a an empty
-/// background cell). Additionally, this manages some state of the grid only
-/// relevant for rendering like temporarily changing the cell with the cursor.
-///
-/// This manages the cursor during a render. The cursor location is inverted to
-/// draw it, and reverted after drawing to maintain state.
+/// background cell). The cursor is not part of these cells, it is drawn on top
+/// of them from `Term::renderable_cursor`.
 pub struct RenderableCellsIter<'a> {
     inner: DisplayIter<'a, Cell>,
     grid: &'a Grid<Cell>,
-    cursor: &'a Point,
-    cursor_offset: usize,
-    cursor_key: Option<CursorKey>,
-    cursor_style: CursorStyle,
     config: &'a Config,
     colors: &'a color::List,
     selection: Option<SelectionRange>,
     url_highlight: &'a Option<RangeInclusive<index::Linear>>,
+    hyperlink_highlight: Option<u32>,
+    metadata: &'a MetadataTable,
+    damage: Option<&'a LineDamage>,
 }
 
 impl<'a> RenderableCellsIter<'a> {
     /// Create the renderable cells iterator
-    ///
-    /// The cursor and terminal mode are required for properly displaying the
-    /// cursor.
     fn new<'b>(
         term: &'b Term,
         config: &'b Config,
         selection: Option<Span>,
-        mut cursor_style: CursorStyle,
     ) -> RenderableCellsIter<'b> {
         let grid = &term.grid;
 
-        let cursor_offset = grid.line_to_offset(term.cursor.point.line);
-        let inner = grid.display_iter();
-
-        let selection_range = selection.and_then(|span| {
-            // Get on-screen lines of the selection's locations
-            let start_line = grid.buffer_line_to_visible(span.start.line);
-            let end_line = grid.buffer_line_to_visible(span.end.line);
-
-            // Limit block selection columns to within start/end points
-            let (limit_start, limit_end) =
-                if span.is_block { (span.start.col, span.end.col) } else { (Column(0), Column(0)) };
-
-            // Get start/end locations based on what part of selection is on screen
-            let locations = match (start_line, end_line) {
-                (ViewportPosition::Visible(start_line), ViewportPosition::Visible(end_line)) => {
-                    Some((start_line, span.start.col, end_line, span.end.col))
-                },
-                (ViewportPosition::Visible(start_line), ViewportPosition::Above) => {
-                    Some((start_line, span.start.col, Line(0), limit_end))
-                },
-                (ViewportPosition::Below, ViewportPosition::Visible(end_line)) => {
-                    Some((grid.num_lines(), limit_start, end_line, span.end.col))
-                },
-                (ViewportPosition::Below, ViewportPosition::Above) => {
-                    Some((grid.num_lines(), limit_start, Line(0), limit_end))
-                },
-                _ => None,
-            };
-
-            locations.map(|(start_line, start_col, end_line, end_col)| {
-                // start and end *lines* are swapped as we switch from buffer to
-                // Line coordinates.
-                let mut end = Point { line: start_line, col: start_col };
-                let mut start = Point { line: end_line, col: end_col };
-
-                if start > end {
-                    ::std::mem::swap(&mut start, &mut end);
-                }
-
-                SelectionRange::new(start, end, span.is_block)
-            })
-        });
-
-        // Load cursor glyph
-        let cursor = &term.cursor.point;
-        let cursor_visible = term.mode.contains(TermMode::SHOW_CURSOR) && grid.contains(cursor);
-        let cursor_key = if cursor_visible {
-            let is_wide = grid[cursor].flags.contains(cell::Flags::WIDE_CHAR)
-                && (cursor.col + 1) < grid.num_cols();
-            Some(CursorKey { style: cursor_style, is_wide })
-        } else {
-            // Use hidden cursor so text will not get inverted
-            cursor_style = CursorStyle::Hidden;
-            None
-        };
-
         RenderableCellsIter {
-            cursor,
-            cursor_offset,
             grid,
-            inner,
-            selection: selection_range,
+            inner: grid.display_iter(),
+            selection: selection.and_then(|span| visible_selection(grid, span)),
             url_highlight: &grid.url_highlight,
+            hyperlink_highlight: term.hyperlink_highlight,
+            metadata: &term.metadata,
             config,
             colors: &term.colors,
-            cursor_key,
-            cursor_style,
+            damage: None,
         }
     }
+
+    /// Only yield the cells of damaged lines
+    pub fn damaged(mut self, damage: &'a LineDamage) -> RenderableCellsIter<'a> {
+        self.damage = Some(damage);
+        self
+    }
+}
+
+/// Part of the selection which is on screen, in viewport coordinates
+fn visible_selection(grid: &Grid<Cell>, span: Span) -> Option<SelectionRange> {
+    // Get on-screen lines of the selection's locations
+    let start_line = grid.buffer_line_to_visible(span.start.line);
+    let end_line = grid.buffer_line_to_visible(span.end.line);
+
+    // Limit block selection columns to within start/end points
+    let (limit_start, limit_end) =
+        if span.is_block { (span.start.col, span.end.col) } else { (Column(0), Column(0)) };
+
+    // Get start/end locations based on what part of selection is on screen
+    let locations = match (start_line, end_line) {
+        (ViewportPosition::Visible(start_line), ViewportPosition::Visible(end_line)) => {
+            Some((start_line, span.start.col, end_line, span.end.col))
+        },
+        (ViewportPosition::Visible(start_line), ViewportPosition::Above) => {
+            Some((start_line, span.start.col, Line(0), limit_end))
+        },
+        (ViewportPosition::Below, ViewportPosition::Visible(end_line)) => {
+            Some((grid.num_lines(), limit_start, end_line, span.end.col))
+        },
+        (ViewportPosition::Below, ViewportPosition::Above) => {
+            Some((grid.num_lines(), limit_start, Line(0), limit_end))
+        },
+        _ => None,
+    };
+
+    locations.map(|(start_line, start_col, end_line, end_col)| {
+        // start and end *lines* are swapped as we switch from buffer to
+        // Line coordinates.
+        let mut end = Point { line: start_line, col: start_col };
+        let mut start = Point { line: end_line, col: end_col };
+
+        if start > end {
+            ::std::mem::swap(&mut start, &mut end);
+        }
+
+        SelectionRange::new(start, end, span.is_block)
+    })
 }
 
 #[derive(Clone, Debug)]
@@ -327,11 +365,27 @@ impl RenderableCell {
         let mut fg_rgb = Self::compute_fg_rgb(config, colors, cell.fg, cell.flags);
         let mut bg_rgb = Self::compute_bg_rgb(colors, cell.bg);
 
-        let selection_background = config.colors.selection.background;
-        if let (true, Some(col)) = (selected, selection_background) {
-            // Override selection background with config colors
-            bg_rgb = col;
-        } else if selected ^ cell.inverse() {
+        if selected {
+            // The selection background is drawn below the cells, only the text color changes
+            let selection = config.colors.selection;
+            match (selection.text, selection.background) {
+                (Some(text), _) => fg_rgb = text,
+                (None, None) => fg_rgb = colors[NamedColor::Background],
+                (None, Some(_)) => (),
+            }
+
+            return RenderableCell {
+                line: cell.line,
+                column: cell.column,
+                inner: RenderableCellContent::Chars(cell.chars()),
+                fg: fg_rgb,
+                bg: bg_rgb,
+                bg_alpha: 0.,
+                flags: cell.flags,
+            };
+        }
+
+        if cell.inverse() {
             if fg_rgb == bg_rgb && !cell.flags.contains(Flags::HIDDEN) {
                 // Reveal inversed text when fg/bg is the same
                 fg_rgb = colors[NamedColor::Background];
@@ -342,11 +396,6 @@ impl RenderableCell {
             }
         }
 
-        // Override selection text with config colors
-        if let (true, Some(col)) = (selected, config.colors.selection.text) {
-            fg_rgb = col;
-        }
-
         RenderableCell {
             line: cell.line,
             column: cell.column,
@@ -424,64 +473,38 @@ impl<'a> Iterator for RenderableCellsIter<'a> {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.cursor_offset == self.inner.offset() && self.inner.column() == self.cursor.col {
-                let selected = self
-                    .selection
-                    .as_ref()
-                    .map(|range| range.contains(self.cursor.col, self.cursor.line))
-                    .unwrap_or(false);
-
-                // Handle cursor
-                if let Some(cursor_key) = self.cursor_key.take() {
-                    let cell = Indexed {
-                        inner: self.grid[self.cursor],
-                        column: self.cursor.col,
-                        line: self.cursor.line,
-                    };
-
-                    let mut renderable_cell =
-                        RenderableCell::new(self.config, self.colors, cell, selected);
-
-                    renderable_cell.inner = RenderableCellContent::Cursor(cursor_key);
-
-                    if let Some(color) = self.config.colors.cursor.cursor {
-                        renderable_cell.fg = color;
-                    }
+            // Skip lines which are still up to date
+            if let Some(damage) = self.damage {
+                if self.inner.column() == Column(0) && !damage.is_damaged(self.inner.line()) {
+                    self.inner.skip_line();
+                    continue;
+                }
+            }
 
-                    return Some(renderable_cell);
-                } else {
-                    let mut cell =
-                        RenderableCell::new(self.config, self.colors, self.inner.next()?, selected);
+            let mut cell = self.inner.next()?;
 
-                    if self.cursor_style == CursorStyle::Block {
-                        std::mem::swap(&mut cell.bg, &mut cell.fg);
+            let selected = self
+                .selection
+                .as_ref()
+                .map(|range| range.contains(cell.column, cell.line))
+                .unwrap_or(false);
 
-                        if let Some(color) = self.config.colors.cursor.text {
-                            cell.fg = color;
-                        }
-                    }
+            // Underline URL highlights
+            let index = Linear::new(self.grid.num_cols(), cell.column, cell.line);
+            if self.url_highlight.as_ref().map(|range| range.contains_(index)).unwrap_or(false) {
+                cell.inner.flags.insert(Flags::UNDERLINE);
+            }
 
-                    return Some(cell);
-                }
-            } else {
-                let mut cell = self.inner.next()?;
-
-                let selected = self
-                    .selection
-                    .as_ref()
-                    .map(|range| range.contains(cell.column, cell.line))
-                    .unwrap_or(false);
-
-                // Underline URL highlights
-                let index = Linear::new(self.grid.num_cols(), cell.column, cell.line);
-                if self.url_highlight.as_ref().map(|range| range.contains_(index)).unwrap_or(false)
-                {
+            // Underline all parts of hovered hyperlinks
+            if let Some(hyperlink) = self.hyperlink_highlight {
+                let metadata = self.metadata.get(cell.inner.metadata);
+                if metadata.and_then(|metadata| metadata.hyperlink) == Some(hyperlink) {
                     cell.inner.flags.insert(Flags::UNDERLINE);
                 }
+            }
 
-                if !cell.is_empty() || selected {
-                    return Some(RenderableCell::new(self.config, self.colors, cell, selected));
-                }
+            if !cell.is_empty() {
+                return Some(RenderableCell::new(self.config, self.colors, cell, selected));
             }
         }
     }
@@ -492,21 +515,22 @@ pub mod mode {
 
     bitflags! {
         pub struct TermMode: u16 {
-            const SHOW_CURSOR         = 0b00_0000_0000_0001;
-            const APP_CURSOR          = 0b00_0000_0000_0010;
-            const APP_KEYPAD          = 0b00_0000_0000_0100;
-            const MOUSE_REPORT_CLICK  = 0b00_0000_0000_1000;
-            const BRACKETED_PASTE     = 0b00_0000_0001_0000;
-            const SGR_MOUSE           = 0b00_0000_0010_0000;
-            const MOUSE_MOTION        = 0b00_0000_0100_0000;
-            const LINE_WRAP           = 0b00_0000_1000_0000;
-            const LINE_FEED_NEW_LINE  = 0b00_0001_0000_0000;
-            const ORIGIN              = 0b00_0010_0000_0000;
-            const INSERT              = 0b00_0100_0000_0000;
-            const FOCUS_IN_OUT        = 0b00_1000_0000_0000;
-            const ALT_SCREEN          = 0b01_0000_0000_0000;
-            const MOUSE_DRAG          = 0b10_0000_0000_0000;
-            const ANY                 = 0b11_1111_1111_1111;
+            const SHOW_CURSOR         = 0b000_0000_0000_0001;
+            const APP_CURSOR          = 0b000_0000_0000_0010;
+            const APP_KEYPAD          = 0b000_0000_0000_0100;
+            const MOUSE_REPORT_CLICK  = 0b000_0000_0000_1000;
+            const BRACKETED_PASTE     = 0b000_0000_0001_0000;
+            const SGR_MOUSE           = 0b000_0000_0010_0000;
+            const MOUSE_MOTION        = 0b000_0000_0100_0000;
+            const LINE_WRAP           = 0b000_0000_1000_0000;
+            const LINE_FEED_NEW_LINE  = 0b000_0001_0000_0000;
+            const ORIGIN              = 0b000_0010_0000_0000;
+            const INSERT              = 0b000_0100_0000_0000;
+            const FOCUS_IN_OUT        = 0b000_1000_0000_0000;
+            const ALT_SCREEN          = 0b001_0000_0000_0000;
+            const MOUSE_DRAG          = 0b010_0000_0000_0000;
+            const SGR_PIXELS          = 0b100_0000_0000_0000;
+            const ANY                 = 0b111_1111_1111_1111;
             const NONE                = 0;
         }
     }
@@ -721,6 +745,101 @@ impl VisualBell {
     }
 }
 
+/// Receiver for the text of a selection
+trait SelectionAppend {
+    fn push_cell(&mut self, cell: &Cell, c: char);
+
+    fn push_newline(&mut self);
+
+    fn append(
+        &mut self,
+        append_newline: bool,
+        grid: &Grid<Cell>,
+        tabs: &TabStops,
+        mut line: usize,
+        cols: Range<Column>,
+    ) {
+        // Select until last line still within the buffer
+        line = min(line, grid.len() - 1);
+
+        let grid_line = &grid[line];
+        let line_length = grid_line.line_length();
+        let line_end = min(line_length, cols.end + 1);
+
+        if cols.start < line_end {
+            let mut tab_mode = false;
+
+            for col in IndexRange::from(cols.start..line_end) {
+                let cell = grid_line[col];
+
+                if tab_mode {
+                    // Skip over whitespace until next tab-stop once a tab was found
+                    if tabs[col] {
+                        tab_mode = false;
+                    } else if cell.c == ' ' {
+                        continue;
+                    }
+                }
+
+                if !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+                    self.push_cell(&cell, cell.c);
+                    for c in (&cell.chars()[1..]).iter().filter(|c| **c != ' ') {
+                        self.push_cell(&cell, *c);
+                    }
+                }
+
+                if cell.c == '\t' {
+                    tab_mode = true;
+                }
+            }
+        }
+
+        if append_newline
+            || (cols.end >= grid.num_cols() - 1
+                && (line_end == Column(0)
+                    || !grid[line][line_end - 1].flags.contains(cell::Flags::WRAPLINE)))
+        {
+            self.push_newline();
+        }
+    }
+}
+
+impl SelectionAppend for String {
+    fn push_cell(&mut self, _: &Cell, c: char) {
+        self.push(c);
+    }
+
+    fn push_newline(&mut self) {
+        self.push('\n');
+    }
+}
+
+/// Collects the selection with the colors of every cell
+struct StyledTextAppender<'a> {
+    text: StyledText,
+    term: &'a Term,
+}
+
+impl<'a> SelectionAppend for StyledTextAppender<'a> {
+    fn push_cell(&mut self, cell: &Cell, c: char) {
+        let (fg, bg) = self.term.rich_text_colors(cell);
+        self.text.push(c, fg, bg, cell.flags);
+    }
+
+    fn push_newline(&mut self) {
+        let colors = &self.term.colors;
+        self.text.push_newline(colors[NamedColor::Foreground], colors[NamedColor::Background]);
+    }
+}
+
+/// State of the last frame which is drawn on top of the grid contents
+#[derive(Default)]
+struct FrameState {
+    selection: Option<Span>,
+    url_highlight: Option<RangeInclusive<index::Linear>>,
+    hyperlink_highlight: Option<u32>,
+}
+
 pub struct Term {
     /// The grid
     grid: Grid<Cell>,
@@ -740,6 +859,93 @@ pub struct Term {
     /// Got a request to set the mouse cursor; it's buffered here until the next draw
     next_mouse_cursor: Option<MouseCursor>,
 
+    /// Got a request to switch the color scheme; it's buffered here until the next draw
+    next_color_scheme: Option<String>,
+
+    /// Names of the configured color schemes
+    color_schemes: Vec<String>,
+
+    /// Name of the active color scheme
+    color_scheme: Option<String>,
+
+    /// Receiver of completed output lines
+    announcer: Option<Announcer>,
+    announcements: Announcements,
+
+    /// Last title requested by the application
+    title: Option<String>,
+
+    /// Line reserved below the grid
+    status_line: StatusLine,
+
+    /// Progress reported by the running command
+    progress: Option<Progress>,
+
+    /// Desktop notifications requested by the running command
+    notifications: Notifications,
+
+    /// Rewriting and highlighting of completed rows
+    output_filters: OutputFilters,
+
+    /// Line which received input since the hooks for completed lines last ran on it
+    unfinished_line: Option<Line>,
+
+    /// Rules triggered by completed lines
+    watcher: Watcher,
+
+    /// Show the time every row was completed at
+    timestamps: bool,
+
+    /// Active search through the grid and scrollback history
+    search: Option<ScrollbackSearch>,
+
+    /// Finds URLs and paths for hint mode and mouse hovering
+    hint_detector: HintDetector,
+
+    /// Labels of the visible hints, while hint mode is active
+    hint_mode: Option<HintMode>,
+
+    /// Reference for converting row timestamps to the system time
+    epoch: (Instant, SystemTime),
+
+    /// Metadata of all cells in the grids
+    metadata: MetadataTable,
+
+    /// Metadata attached to new cells written at the cursor
+    cursor_metadata: MetadataId,
+
+    /// URIs of the hyperlinks referred to by the cell metadata
+    hyperlinks: Hyperlinks,
+
+    /// Hyperlink underlined while it is hovered
+    hyperlink_highlight: Option<u32>,
+
+    /// Ignore escapes which leak data or act outside of the terminal
+    paranoid: bool,
+    security_mode: SecurityMode,
+
+    /// Filtering and confirmation of pasted text
+    paste: Paste,
+
+    /// Text held back until it is pasted again
+    pending_paste: Option<String>,
+
+    /// Reply to ENQ
+    answerback: String,
+
+    /// Allow applications to read the clipboard with OSC 52
+    clipboard_read: bool,
+
+    /// Limits for escapes with expensive side effects
+    clipboard_limit: RateLimit,
+    bell_limit: RateLimit,
+
+    /// Latest clipboard write of every clipboard held back by the rate limit
+    pending_clipboard: Vec<(ClipboardType, String)>,
+
+    /// Progress change buffered until the next draw
+    next_progress: Option<Option<Progress>>,
+
     /// Alternate grid
     alt_grid: Grid<Cell>,
 
@@ -769,8 +975,20 @@ pub struct Term {
     /// Size
     size_info: SizeInfo,
 
+    /// Graphics attributes negotiated through XTSMGRAPHICS
+    graphics: GraphicsAttributes,
+
+    /// Pixels of the images placed on the grids
+    images: ImageStore,
+
     pub dirty: bool,
 
+    /// Redraw even if the next frame is identical, since the window contents were lost
+    pub force_redraw: bool,
+
+    /// Changes which are not recorded as grid damage are detected against the last frame
+    last_frame: FrameState,
+
     pub visual_bell: VisualBell,
     pub next_is_urgent: Option<bool>,
 
@@ -800,6 +1018,9 @@ pub struct Term {
     /// Whether to permit updating the terminal title
     dynamic_title: bool,
 
+    /// Bold text uses the bright color variants
+    draw_bold_text_with_bright_colors: bool,
+
     /// Number of spaces in one tab
     tabspaces: usize,
 
@@ -864,6 +1085,19 @@ impl SizeInfo {
         }
     }
 
+    /// Convert window pixels to pixels within the text area, excluding the padding
+    ///
+    /// Positions in the padding are clamped to the closest edge of the text area.
+    pub fn pixels_to_text_area(&self, x: usize, y: usize) -> (usize, usize) {
+        let max_x = (self.width - 2. * self.padding_x).max(1.) as usize - 1;
+        let max_y = (self.height - 2. * self.padding_y).max(1.) as usize - 1;
+
+        let x = x.saturating_sub(self.padding_x as usize);
+        let y = y.saturating_sub(self.padding_y as usize);
+
+        (min(x, max_x), min(y, max_y))
+    }
+
     pub fn pixels_to_coords(&self, x: usize, y: usize) -> Point {
         let col = Column(x.saturating_sub(self.padding_x as usize) / (self.cell_width as usize));
         let line = Line(y.saturating_sub(self.padding_y as usize) / (self.cell_height as usize));
@@ -901,6 +1135,171 @@ impl Term {
         self.next_mouse_cursor.take()
     }
 
+    #[inline]
+    pub fn get_next_color_scheme(&mut self) -> Option<String> {
+        self.next_color_scheme.take()
+    }
+
+    #[inline]
+    pub fn get_next_progress(&mut self) -> Option<Option<Progress>> {
+        self.next_progress.take()
+    }
+
+    /// Store clipboard writes held back by the rate limit, as far as it allows
+    pub fn flush_clipboard(&mut self) {
+        while !self.pending_clipboard.is_empty() && self.clipboard_limit.allow() {
+            let (ty, text) = self.pending_clipboard.remove(0);
+            self.clipboard.store(ty, text);
+        }
+    }
+
+    #[inline]
+    pub fn has_pending_clipboard(&self) -> bool {
+        !self.pending_clipboard.is_empty()
+    }
+
+    /// Attach metadata to all cells written from now on
+    pub fn set_cursor_metadata(&mut self, metadata: Metadata) {
+        self.cursor_metadata = self.intern_metadata(metadata);
+    }
+
+    /// Add metadata to the table, freeing entries no cell uses anymore once it is full
+    fn intern_metadata(&mut self, metadata: Metadata) -> MetadataId {
+        if self.metadata.needs_collection() {
+            self.collect_metadata();
+        }
+
+        self.metadata.intern(metadata)
+    }
+
+    /// Free all metadata which isn't attached to a cell or the cursor
+    fn collect_metadata(&mut self) {
+        let grids = [&self.grid, &self.alt_grid];
+        let used = grids
+            .iter()
+            .flat_map(|grid| (0..grid.len()).flat_map(move |index| grid[index][..].iter()))
+            .map(|cell| cell.metadata)
+            .chain(iter::once(self.cursor_metadata));
+        self.metadata.collect(used);
+    }
+
+    /// Get the index of a hyperlink, freeing links which are no longer used if necessary
+    fn insert_hyperlink(&mut self, hyperlink: Hyperlink) -> Option<u32> {
+        if self.hyperlinks.needs_collection() {
+            // Links are only referred to by metadata, so unused metadata is freed first
+            self.collect_metadata();
+            let used = self.metadata.iter().filter_map(|metadata| metadata.hyperlink);
+            self.hyperlinks.collect(used);
+
+            if self.hyperlink_highlight.and_then(|index| self.hyperlinks.uri(index)).is_none() {
+                self.hyperlink_highlight = None;
+            }
+        }
+
+        self.hyperlinks.insert(hyperlink)
+    }
+
+    /// Metadata attached to a cell
+    #[inline]
+    pub fn cell_metadata(&self, cell: &Cell) -> Option<&Metadata> {
+        self.metadata.get(cell.metadata)
+    }
+
+    /// Index and URI of the hyperlink at a point of the viewport
+    pub fn hyperlink_at(&self, point: Point) -> Option<(u32, &str)> {
+        let point = self.grid.visible_to_buffer(point);
+        let index = self.cell_metadata(&self.grid[point.line][point.col])?.hyperlink?;
+        Some((index, self.hyperlinks.uri(index)?))
+    }
+
+    #[inline]
+    pub fn has_status_line(&self) -> bool {
+        self.status_line.enabled
+    }
+
+    /// Contents of the status line, if it is enabled
+    ///
+    /// The text is padded or truncated to the width of the grid.
+    pub fn status_line_text(&self) -> Option<String> {
+        if !self.has_status_line() {
+            return None;
+        }
+
+        let num_cols = self.grid.num_cols().0;
+        let title = self.title.as_ref().map_or("", String::as_str);
+        let left = self.status_line.expand(title, num_cols, self.grid.num_lines().0);
+
+        let mut segments = Vec::new();
+        match self.progress {
+            Some(Progress::Normal(value)) => segments.push(format!(" {}%", value)),
+            Some(Progress::Error(value)) => segments.push(format!(" failed {}%", value)),
+            Some(Progress::Paused(value)) => segments.push(format!(" paused {}%", value)),
+            Some(Progress::Indeterminate) => segments.push(String::from(" busy")),
+            None => (),
+        }
+
+        if self.paranoid {
+            segments.push(String::from(" [paranoid]"));
+        }
+
+        let offset = self.grid.display_offset();
+        if offset != 0 {
+            segments.push(format!(" [{}/{}]", offset, self.grid.scroll_limit()));
+        }
+
+        // Without enough room, the scroll position at the end is kept the longest
+        let mut right_len: usize = segments.iter().map(|segment| segment.chars().count()).sum();
+        while right_len > num_cols && segments.len() > 1 {
+            right_len -= segments.remove(0).chars().count();
+        }
+        let mut right = segments.concat();
+        if right_len > num_cols {
+            right = right.chars().skip(right_len - num_cols).collect();
+            right_len = num_cols;
+        }
+
+        let mut text: String = left
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(num_cols.saturating_sub(right_len))
+            .collect();
+        let padding = num_cols.saturating_sub(text.chars().count() + right_len);
+        text.extend(::std::iter::repeat(' ').take(padding));
+        text.push_str(&right);
+
+        Some(text)
+    }
+
+    #[inline]
+    pub fn paranoid(&self) -> bool {
+        self.paranoid
+    }
+
+    /// Switch between the normal and paranoid security mode
+    pub fn toggle_paranoid_mode(&mut self) {
+        self.paranoid = !self.paranoid;
+        info!("Paranoid mode {}", if self.paranoid { "enabled" } else { "disabled" });
+        self.dirty = true;
+    }
+
+    /// Request a switch to the color scheme following the active one
+    pub fn cycle_color_scheme(&mut self) {
+        if self.color_schemes.is_empty() {
+            return;
+        }
+
+        let next = match self.color_scheme {
+            Some(ref active) => match self.color_schemes.iter().position(|name| name == active) {
+                Some(index) => (index + 1) % self.color_schemes.len(),
+                None => 0,
+            },
+            None => 0,
+        };
+
+        self.next_color_scheme = Some(self.color_schemes[next].clone());
+        self.dirty = true;
+    }
+
     pub fn new(
         config: &Config,
         size: SizeInfo,
@@ -908,7 +1307,11 @@ impl Term {
         clipboard: Clipboard,
     ) -> Term {
         let num_cols = size.cols();
-        let num_lines = size.lines();
+        let mut num_lines = size.lines();
+
+        if config.status_line.enabled {
+            num_lines = Line(num_lines.saturating_sub(1));
+        }
 
         let history_size = config.scrolling.history() as usize;
         let grid = Grid::new(num_lines, num_cols, history_size, Cell::default());
@@ -924,7 +1327,40 @@ impl Term {
         Term {
             next_title: None,
             next_mouse_cursor: None,
+            next_color_scheme: None,
+            color_schemes: config.color_schemes(),
+            color_scheme: config.colors.scheme.clone(),
+            announcer: Announcer::new(&config.announcements),
+            announcements: config.announcements.clone(),
+            title: None,
+            status_line: config.status_line.clone(),
+            progress: None,
+            next_progress: None,
+            notifications: Notifications::new(&config.notifications),
+            output_filters: OutputFilters::new(&config.output_filters),
+            unfinished_line: None,
+            watcher: Watcher::new(&config.watch),
+            timestamps: false,
+            search: None,
+            hint_detector: HintDetector::new(&config.hints),
+            hint_mode: None,
+            epoch: (Instant::now(), SystemTime::now()),
+            metadata: MetadataTable::default(),
+            cursor_metadata: MetadataId::NONE,
+            hyperlinks: Hyperlinks::default(),
+            hyperlink_highlight: None,
+            paranoid: config.security.mode == SecurityMode::Paranoid,
+            security_mode: config.security.mode,
+            paste: config.paste.clone(),
+            pending_paste: None,
+            answerback: config.security.answerback.clone(),
+            clipboard_read: config.security.clipboard_read,
+            clipboard_limit: RateLimit::new(MAX_CLIPBOARD_WRITES, Duration::from_secs(1)),
+            bell_limit: RateLimit::new(MAX_BELLS, Duration::from_secs(1)),
+            pending_clipboard: Vec::new(),
             dirty: false,
+            force_redraw: false,
+            last_frame: Default::default(),
             visual_bell: VisualBell::new(config),
             next_is_urgent: None,
             input_needs_wrap: false,
@@ -941,6 +1377,8 @@ impl Term {
             mode: Default::default(),
             scroll_region,
             size_info: size,
+            graphics: Default::default(),
+            images: Default::default(),
             colors,
             color_modified: [false; color::COUNT],
             original_colors: colors,
@@ -948,6 +1386,7 @@ impl Term {
             cursor_style: None,
             default_cursor_style: config.cursor.style,
             dynamic_title: config.dynamic_title(),
+            draw_bold_text_with_bright_colors: config.draw_bold_text_with_bright_colors(),
             tabspaces,
             auto_scroll: config.scrolling.auto_scroll,
             message_buffer,
@@ -978,88 +1417,421 @@ impl Term {
                 self.colors[i] = self.original_colors[i];
             }
         }
+        self.grid.damage_all();
+        self.color_schemes = config.color_schemes();
+        self.color_scheme = config.colors.scheme.clone();
+        if self.announcements != config.announcements {
+            self.announcer = Announcer::new(&config.announcements);
+            self.announcements = config.announcements.clone();
+        }
+        self.status_line = config.status_line.clone();
+        self.notifications.update_config(&config.notifications);
+        self.output_filters.update_config(&config.output_filters);
+        self.watcher.update_config(&config.watch);
+        self.hint_detector.update_config(&config.hints);
+        self.answerback = config.security.answerback.clone();
+        self.paste = config.paste.clone();
+        self.clipboard_read = config.security.clipboard_read;
+        if self.security_mode != config.security.mode {
+            self.security_mode = config.security.mode;
+            self.paranoid = self.security_mode == SecurityMode::Paranoid;
+            self.dirty = true;
+        }
         self.visual_bell.update_config(config);
         self.default_cursor_style = config.cursor.style;
         self.dynamic_title = config.dynamic_title();
+        self.draw_bold_text_with_bright_colors = config.draw_bold_text_with_bright_colors();
         self.auto_scroll = config.scrolling.auto_scroll;
         self.grid.update_history(config.scrolling.history() as usize, &self.cursor.template);
     }
 
+    /// Enable or disable the output filters for this session
+    pub fn toggle_output_filters(&mut self) {
+        self.output_filters.toggle();
+        let state = if self.output_filters.enabled() { "enabled" } else { "disabled" };
+        info!("Output filters {}", state);
+    }
+
+    /// Run the hooks for completed lines on a row the cursor is done with
+    ///
+    /// Besides linefeeds and wraps, this also happens once input is written to another line or
+    /// the screen scrolls, so lines which are never terminated are processed as well.
+    fn complete_line(&mut self, line: Line) {
+        if self.unfinished_line == Some(line) {
+            self.unfinished_line = None;
+        }
+
+        if line < self.grid.num_lines() {
+            self.grid[line].set_timestamp(Instant::now());
+            self.filter_line(line);
+            self.announce_line(line);
+            self.watch_line(line);
+        }
+    }
+
+    /// Complete the line which received input, unless the cursor is still on it
+    fn complete_left_line(&mut self) {
+        match self.unfinished_line {
+            Some(line) if line != self.cursor.point.line => self.complete_line(line),
+            _ => (),
+        }
+    }
+
+    /// Complete the line which received input, even if the cursor is still on it
+    fn complete_unfinished_line(&mut self) {
+        if let Some(line) = self.unfinished_line {
+            self.complete_line(line);
+        }
+    }
+
+    /// Apply the output filters to a line
+    ///
+    /// The alternate screen is not filtered, since its applications redraw rows arbitrarily.
+    fn filter_line(&mut self, line: Line) {
+        if !self.alt {
+            self.output_filters.apply(&mut self.grid[line]);
+        }
+    }
+
+    /// Show or hide the time every visible row was completed at
+    pub fn toggle_timestamps(&mut self) {
+        self.timestamps = !self.timestamps;
+        self.dirty = true;
+    }
+
     #[inline]
-    pub fn needs_draw(&self) -> bool {
-        self.dirty
+    pub fn timestamps_visible(&self) -> bool {
+        self.timestamps
     }
 
-    pub fn selection_to_string(&self) -> Option<String> {
-        trait Append {
-            fn append(
-                &mut self,
-                append_newline: bool,
-                grid: &Grid<Cell>,
-                tabs: &TabStops,
-                line: usize,
-                cols: Range<Column>,
-            );
-        }
-
-        impl Append for String {
-            fn append(
-                &mut self,
-                append_newline: bool,
-                grid: &Grid<Cell>,
-                tabs: &TabStops,
-                mut line: usize,
-                cols: Range<Column>,
-            ) {
-                // Select until last line still within the buffer
-                line = min(line, grid.len() - 1);
-
-                let grid_line = &grid[line];
-                let line_length = grid_line.line_length();
-                let line_end = min(line_length, cols.end + 1);
-
-                if cols.start < line_end {
-                    let mut tab_mode = false;
-
-                    for col in IndexRange::from(cols.start..line_end) {
-                        let cell = grid_line[col];
-
-                        if tab_mode {
-                            // Skip over whitespace until next tab-stop once a tab was found
-                            if tabs[col] {
-                                tab_mode = false;
-                            } else if cell.c == ' ' {
-                                continue;
-                            }
-                        }
-
-                        if !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
-                            self.push(cell.c);
-                            for c in (&cell.chars()[1..]).iter().filter(|c| **c != ' ') {
-                                self.push(*c);
-                            }
-                        }
-
-                        if cell.c == '\t' {
-                            tab_mode = true;
-                        }
-                    }
+    /// Formatted timestamps of all visible rows which have been completed
+    pub fn visible_timestamps(&self) -> Vec<(Line, String)> {
+        if !self.timestamps {
+            return Vec::new();
+        }
+
+        let (instant, system_time) = self.epoch;
+        IndexRange(Line(0)..self.grid.num_lines())
+            .filter_map(|line| {
+                let buffer_line = self.grid.visible_to_buffer(Point::new(line, Column(0))).line;
+                let timestamp = self.grid[buffer_line].timestamp()?;
+                let time = system_time + timestamp.duration_since(instant);
+                Some((line, format_utc_time(time)))
+            })
+            .collect()
+    }
+
+    /// Lines of the viewport marked by a watch rule
+    pub fn visible_marks(&self) -> Vec<Line> {
+        IndexRange(Line(0)..self.grid.num_lines())
+            .filter(|&line| self.is_marked(self.grid.visible_line_to_buffer(line)))
+            .collect()
+    }
+
+    /// Scroll which moves the next marked line in `direction` to the top of the viewport
+    pub fn mark_scroll(&self, direction: Direction) -> Option<Scroll> {
+        let num_lines = self.grid.num_lines().0;
+        let display_offset = self.grid.display_offset();
+        let top = display_offset + num_lines - 1;
+
+        let line = match direction {
+            Direction::Up => (top + 1..self.grid.scroll_limit() + num_lines)
+                .find(|&line| self.is_marked(line))?,
+            Direction::Down => (0..top).rev().find(|&line| self.is_marked(line))?,
+        };
+
+        let target = min(line.saturating_sub(num_lines - 1), self.grid.scroll_limit());
+        Some(Scroll::Lines(target as isize - display_offset as isize))
+    }
+
+    fn is_marked(&self, buffer_line: usize) -> bool {
+        let cell = &self.grid[buffer_line][Column(0)];
+        self.cell_metadata(cell).map_or(false, |metadata| metadata.mark.is_some())
+    }
+
+    /// Cells of the viewport showing a part of an image
+    pub fn visible_image_cells(&self) -> Vec<ImageCell> {
+        let mut cells = Vec::new();
+        for line in IndexRange(Line(0)..self.grid.num_lines()) {
+            let row = &self.grid[self.grid.visible_line_to_buffer(line)];
+            for col in IndexRange(Column(0)..self.grid.num_cols()) {
+                let tile = match self.cell_metadata(&row[col]).and_then(|metadata| metadata.image) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+
+                // Images which were evicted are not drawn anymore
+                match self.images.get(tile.image) {
+                    Some(image) if col >= image.col => cells.push(ImageCell {
+                        line,
+                        col,
+                        image: tile.image,
+                        row: usize::from(tile.row),
+                        offset: (col - image.col).0,
+                    }),
+                    _ => (),
                 }
+            }
+        }
+        cells
+    }
+
+    #[inline]
+    pub fn image(&self, id: ImageId) -> Option<Arc<Image>> {
+        self.images.get(id).cloned()
+    }
+
+    /// Start searching through the grid and the scrollback history
+    pub fn start_search(&mut self) {
+        let origin = Point::new(self.grid.display_offset(), self.grid.num_cols());
+        self.search = Some(ScrollbackSearch::new(origin));
+        self.dirty = true;
+    }
+
+    pub fn stop_search(&mut self) {
+        self.search = None;
+        self.dirty = true;
+    }
+
+    pub fn search(&self) -> Option<&ScrollbackSearch> {
+        self.search.as_ref()
+    }
+
+    /// Append a character to the search query
+    pub fn search_push(&mut self, c: char) {
+        if let Some(search) = self.search.as_mut() {
+            search.push(&self.grid, c);
+        }
+        self.scroll_to_search_focus();
+    }
+
+    /// Remove the last character of the search query
+    pub fn search_pop(&mut self) {
+        if let Some(search) = self.search.as_mut() {
+            search.pop(&self.grid);
+        }
+        self.scroll_to_search_focus();
+    }
+
+    /// Stop editing the search query and start navigating its matches
+    pub fn search_confirm(&mut self) {
+        if let Some(search) = self.search.as_mut() {
+            search.confirm();
+        }
+        self.dirty = true;
+    }
+
+    /// Move to the next search match
+    pub fn search_next(&mut self, direction: Direction) {
+        if let Some(search) = self.search.as_mut() {
+            search.focus_next(&self.grid, direction);
+        }
+        self.scroll_to_search_focus();
+    }
+
+    /// Scroll the viewport to center the focused match, unless it is visible already
+    fn scroll_to_search_focus(&mut self) {
+        self.dirty = true;
+
+        let line = match self.search.as_ref().and_then(ScrollbackSearch::focused) {
+            Some(focused) => focused.start.line,
+            None => return,
+        };
+
+        let num_lines = self.grid.num_lines().0;
+        let display_offset = self.grid.display_offset();
+        if line >= display_offset && line < display_offset + num_lines {
+            return;
+        }
+
+        let target = min(line.saturating_sub(num_lines / 2), self.grid.scroll_limit());
+        self.scroll_display(Scroll::Lines(target as isize - display_offset as isize));
+    }
+
+    /// Text of the search bar, padded to the width of the grid
+    pub fn search_bar_text(&self) -> Option<String> {
+        let search = self.search.as_ref()?;
+
+        let mut text = format!("Search: {}", search.query());
+        if !search.query().is_empty() && search.focused().is_none() {
+            text.push_str("  [no matches]");
+        } else if search.is_confirmed() {
+            text.push_str("  [n/N to navigate]");
+        }
 
-                if append_newline
-                    || (cols.end >= grid.num_cols() - 1
-                        && (line_end == Column(0)
-                            || !grid[line][line_end - 1].flags.contains(cell::Flags::WRAPLINE)))
-                {
-                    self.push('\n');
+        let num_cols = self.grid.num_cols().0;
+        let len = text.chars().count();
+        if len < num_cols {
+            text.extend(std::iter::repeat(' ').take(num_cols - len));
+        }
+
+        Some(text)
+    }
+
+    /// Parts of the search matches which are visible in the viewport
+    pub fn visible_search_matches(&self) -> Vec<VisibleMatch> {
+        self.search.as_ref().map_or_else(Vec::new, |search| search.visible_matches(&self.grid))
+    }
+
+    /// Label every visible URL and path, unless there are none
+    pub fn start_hint_mode(&mut self) {
+        let hints = self.hint_detector.visible_hints(&self.grid);
+        if !hints.is_empty() {
+            self.hint_mode = Some(HintMode::new(hints));
+            self.dirty = true;
+        }
+    }
+
+    pub fn stop_hint_mode(&mut self) {
+        self.hint_mode = None;
+        self.dirty = true;
+    }
+
+    #[inline]
+    pub fn is_hint_mode(&self) -> bool {
+        self.hint_mode.is_some()
+    }
+
+    /// Type the next character of a hint label
+    ///
+    /// Hint mode ends once a hint is selected or no label matches anymore.
+    pub fn hint_input(&mut self, c: char) -> Option<Hint> {
+        let input = self.hint_mode.as_mut()?.input(c);
+        self.dirty = true;
+
+        match input {
+            HintInput::Pending => None,
+            HintInput::Selected(hint) => {
+                self.hint_mode = None;
+                Some(hint)
+            },
+            HintInput::NoMatch => {
+                self.hint_mode = None;
+                None
+            },
+        }
+    }
+
+    /// Remaining characters of the hint labels at the start of their hints
+    pub fn visible_hint_labels(&self) -> Vec<(Line, Column, String)> {
+        self.hint_mode.as_ref().map_or_else(Vec::new, |mode| mode.visible_labels(&self.grid))
+    }
+
+    /// URL or path covering the visible cell at `point`
+    pub fn hint_at(&self, point: Point) -> Option<Hint> {
+        self.hint_detector.hint_at(&self.grid, point)
+    }
+
+    /// Text of a line and whether it wraps into the next line
+    fn line_text(&self, line: Line) -> (String, bool) {
+        let row = &self.grid[line];
+        let text: String = row[..row.line_length()]
+            .iter()
+            .filter(|cell| !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER))
+            .map(|cell| cell.c)
+            .collect();
+
+        (text, row[Column(row.len() - 1)].flags.contains(cell::Flags::WRAPLINE))
+    }
+
+    /// Pass the text of a line to the announcer
+    fn announce_line(&mut self, line: Line) {
+        if self.announcer.is_none() || self.alt {
+            return;
+        }
+
+        let (text, wrapped) = self.line_text(line);
+        if let Some(ref mut announcer) = self.announcer {
+            announcer.push(&text, wrapped);
+        }
+    }
+
+    /// Match a line against the watch rules and run their actions
+    fn watch_line(&mut self, line: Line) {
+        if !self.watcher.has_rules() || self.alt {
+            return;
+        }
+
+        let (text, wrapped) = self.line_text(line);
+        for trigger in self.watcher.push(&text, wrapped) {
+            if trigger.notify {
+                self.notifications.notify(Some("Alacritty"), &trigger.line);
+            }
+
+            if trigger.bell {
+                self.bell();
+            }
+
+            if trigger.mark {
+                for col in IndexRange(Column(0)..self.grid.num_cols()) {
+                    let mut metadata = self
+                        .metadata
+                        .get(self.grid[line][col].metadata)
+                        .cloned()
+                        .unwrap_or_default();
+                    metadata.mark = Some(trigger.rule as u32);
+                    self.grid[line][col].metadata = self.intern_metadata(metadata);
                 }
             }
         }
+    }
 
-        let selection = self.grid.selection.clone()?;
-        let Span { mut start, mut end, is_block } = selection.to_span(self)?;
+    #[inline]
+    pub fn needs_draw(&self) -> bool {
+        self.dirty
+    }
 
+    pub fn selection_to_string(&self) -> Option<String> {
         let mut res = String::new();
+        self.append_selection(&mut res)?;
+        Some(res)
+    }
+
+    /// Selected text with its colors and style
+    pub fn selection_to_rich_text(&self) -> Option<RichText> {
+        let mut styled = StyledTextAppender { text: StyledText::default(), term: self };
+        self.append_selection(&mut styled)?;
+
+        let (fg, bg) = (self.colors[NamedColor::Foreground], self.colors[NamedColor::Background]);
+        Some(RichText {
+            text: styled.text.to_plain(),
+            html: styled.text.to_html(fg, bg),
+            rtf: styled.text.to_rtf(fg, bg),
+        })
+    }
+
+    /// Colors of a cell for rich text, following the colors applied by the renderer
+    fn rich_text_colors(&self, cell: &Cell) -> (Rgb, Rgb) {
+        let bright = self.draw_bold_text_with_bright_colors && cell.flags.contains(Flags::BOLD);
+        let dim = cell.flags.contains(Flags::DIM) && !cell.flags.contains(Flags::BOLD);
+        let mut fg = match cell.fg {
+            Color::Spec(rgb) => rgb,
+            Color::Named(ansi) if bright => self.colors[ansi.to_bright()],
+            Color::Named(ansi) if dim => self.colors[ansi.to_dim()],
+            Color::Named(ansi) => self.colors[ansi],
+            Color::Indexed(idx) if bright && idx < 8 => self.colors[idx as usize + 8],
+            Color::Indexed(idx) => self.colors[idx],
+        };
+        let mut bg = match cell.bg {
+            Color::Spec(rgb) => rgb,
+            Color::Named(ansi) => self.colors[ansi],
+            Color::Indexed(idx) => self.colors[idx],
+        };
+
+        if cell.inverse() {
+            ::std::mem::swap(&mut fg, &mut bg);
+        }
+        if cell.flags.contains(Flags::HIDDEN) {
+            fg = bg;
+        }
+
+        (fg, bg)
+    }
+
+    /// Append all lines of the selection to `res`
+    fn append_selection<A: SelectionAppend>(&self, res: &mut A) -> Option<()> {
+        let selection = self.grid.selection.clone()?;
+        let Span { mut start, mut end, is_block } = selection.to_span(self)?;
 
         if start > end {
             ::std::mem::swap(&mut start, &mut end);
@@ -1101,7 +1873,7 @@ impl Term {
             },
         }
 
-        Some(res)
+        Some(())
     }
 
     pub(crate) fn visible_to_buffer(&self, point: Point) -> Point<usize> {
@@ -1142,20 +1914,123 @@ impl Term {
     /// A renderable cell is any cell which has content other than the default
     /// background color.  Cells with an alternate background color are
     /// considered renderable as are cells with any text content.
-    pub fn renderable_cells<'b>(
-        &'b self,
-        config: &'b Config,
-        window_focused: bool,
-    ) -> RenderableCellsIter<'_> {
+    pub fn renderable_cells<'b>(&'b self, config: &'b Config) -> RenderableCellsIter<'_> {
         let selection = self.grid.selection.as_ref().and_then(|s| s.to_span(self));
+        RenderableCellsIter::new(&self, config, selection)
+    }
 
-        let cursor = if window_focused || !config.cursor.unfocused_hollow() {
+    /// Rectangles covered by the selection in the viewport, drawn below the renderable cells
+    pub fn visible_selection_rects(&self) -> Vec<SelectionRect> {
+        self.grid
+            .selection
+            .as_ref()
+            .and_then(|s| s.to_span(self))
+            .and_then(|span| visible_selection(&self.grid, span))
+            .map_or_else(Vec::new, |range| range.rects(self.grid.num_cols()))
+    }
+
+    /// Color of the selection rectangles
+    ///
+    /// Without a configured background the selection is drawn in the foreground color, with
+    /// the selected text in the background color.
+    pub fn selection_background(&self, config: &Config) -> Rgb {
+        config.colors.selection.background.unwrap_or(self.colors[NamedColor::Foreground])
+    }
+
+    /// Cells of the cursor, in the order they are drawn on top of the grid
+    ///
+    /// The cursor is not part of the renderable cells, so moving it doesn't invalidate the
+    /// cached cells of the lines it moves between. A block cursor is followed by the inverted
+    /// cell it covers.
+    pub fn renderable_cursor(&self, config: &Config, window_focused: bool) -> Vec<RenderableCell> {
+        let style = if window_focused || !config.cursor.unfocused_hollow() {
             self.cursor_style.unwrap_or(self.default_cursor_style)
         } else {
             CursorStyle::HollowBlock
         };
 
-        RenderableCellsIter::new(&self, config, selection, cursor)
+        let grid = &self.grid;
+        let cursor = self.cursor.point;
+        if !self.mode.contains(TermMode::SHOW_CURSOR)
+            || style == CursorStyle::Hidden
+            || !grid.contains(&cursor)
+        {
+            return Vec::new();
+        }
+
+        let line = match grid.buffer_line_to_visible(grid.line_to_offset(cursor.line)) {
+            ViewportPosition::Visible(line) => line,
+            _ => return Vec::new(),
+        };
+
+        let selected = self
+            .grid
+            .selection
+            .as_ref()
+            .and_then(|s| s.to_span(self))
+            .and_then(|span| visible_selection(grid, span))
+            .map_or(false, |range| range.contains(cursor.col, line));
+        let cell = || Indexed { inner: grid[&cursor], column: cursor.col, line };
+
+        let is_wide = grid[&cursor].flags.contains(cell::Flags::WIDE_CHAR)
+            && (cursor.col + 1) < grid.num_cols();
+        let mut cursor_cell = RenderableCell::new(config, &self.colors, cell(), selected);
+        cursor_cell.inner = RenderableCellContent::Cursor(CursorKey { style, is_wide });
+        if let Some(color) = config.colors.cursor.cursor {
+            cursor_cell.fg = color;
+        }
+
+        let mut cells = vec![cursor_cell];
+        if style == CursorStyle::Block {
+            let mut text = RenderableCell::new(config, &self.colors, cell(), selected);
+            mem::swap(&mut text.bg, &mut text.fg);
+            if let Some(color) = config.colors.cursor.text {
+                text.fg = color;
+            }
+            cells.push(text);
+        }
+
+        cells
+    }
+
+    /// Lines of the viewport which changed since the last call
+    pub fn take_damage(&mut self) -> LineDamage {
+        let selection = self.grid.selection.as_ref().and_then(|s| s.to_span(self));
+        if selection != self.last_frame.selection
+            || self.grid.url_highlight != self.last_frame.url_highlight
+            || self.hyperlink_highlight != self.last_frame.hyperlink_highlight
+        {
+            self.grid.damage_all();
+        }
+
+        self.last_frame = FrameState {
+            selection,
+            url_highlight: self.grid.url_highlight.clone(),
+            hyperlink_highlight: self.hyperlink_highlight,
+        };
+
+        self.grid.take_damage()
+    }
+
+    /// Hasher over the terminal state of the next frame, except for the renderable cells
+    ///
+    /// The renderable cells are added by the caller while iterating over them, to avoid
+    /// collecting them twice.
+    pub fn frame_hasher(&self) -> FrameHasher {
+        let mut hasher = FrameHasher::default();
+        hasher.size(&self.size_info);
+        hasher.font_size(self.font_size);
+        hasher.color(self.background_color(), self.visual_bell.intensity());
+
+        if let Some(text) = self.status_line_text() {
+            hasher.text(self.grid.num_lines(), &text);
+        }
+
+        for (line, text) in self.visible_timestamps() {
+            hasher.text(line, &text);
+        }
+
+        hasher
     }
 
     /// Resize terminal to new dimensions
@@ -1169,6 +2044,9 @@ impl Term {
             return;
         }
 
+        // Line indices change, so only the cursor line is left unfinished
+        self.complete_left_line();
+
         let old_cols = self.grid.num_cols();
         let old_lines = self.grid.num_lines();
         let mut num_cols = size.cols();
@@ -1178,6 +2056,10 @@ impl Term {
             num_lines -= message.text(size).len();
         }
 
+        if self.status_line.enabled {
+            num_lines = Line(num_lines.saturating_sub(1));
+        }
+
         self.size_info = *size;
 
         if old_cols == num_cols && old_lines == num_lines {
@@ -1244,6 +2126,10 @@ impl Term {
         self.cursor_save_alt.point.col = min(self.cursor_save_alt.point.col, num_cols - 1);
         self.cursor_save_alt.point.line = min(self.cursor_save_alt.point.line, num_lines - 1);
 
+        if self.unfinished_line.is_some() {
+            self.unfinished_line = Some(self.cursor.point.line);
+        }
+
         // Recreate tabs list
         self.tabs = TabStops::new(self.grid.num_cols(), self.tabspaces);
     }
@@ -1264,6 +2150,8 @@ impl Term {
     }
 
     pub fn swap_alt(&mut self) {
+        self.complete_unfinished_line();
+
         if self.alt {
             let template = &self.cursor.template;
             self.grid.region_mut(..).each(|c| c.reset(template));
@@ -1271,6 +2159,7 @@ impl Term {
 
         self.alt = !self.alt;
         ::std::mem::swap(&mut self.grid, &mut self.alt_grid);
+        self.grid.damage_all();
     }
 
     /// Scroll screen down
@@ -1280,6 +2169,7 @@ impl Term {
     #[inline]
     fn scroll_down_relative(&mut self, origin: Line, mut lines: Line) {
         trace!("Scrolling down relative: origin={}, lines={}", origin, lines);
+        self.complete_unfinished_line();
         lines = min(lines, self.scroll_region.end - self.scroll_region.start);
         lines = min(lines, self.scroll_region.end - origin);
 
@@ -1296,6 +2186,7 @@ impl Term {
     #[inline]
     fn scroll_up_relative(&mut self, origin: Line, lines: Line) {
         trace!("Scrolling up relative: origin={}, lines={}", origin, lines);
+        self.complete_unfinished_line();
         let lines = min(lines, self.scroll_region.end - self.scroll_region.start);
 
         // Scroll from origin to bottom less number of lines
@@ -1330,6 +2221,44 @@ impl Term {
         &self.message_buffer
     }
 
+    #[inline]
+    pub fn paste_config(&self) -> &Paste {
+        &self.paste
+    }
+
+    /// Check whether pasted text may be sent, holding back large and multiline pastes
+    ///
+    /// A held back paste is sent once the same text is pasted again.
+    pub fn confirm_paste(&mut self, text: &str, bracketed: bool) -> bool {
+        let multiline =
+            self.paste.confirm_multiline && !bracketed && text.contains(&['\n', '\r'][..]);
+        let large = self.paste.confirm_bytes > 0 && text.len() > self.paste.confirm_bytes;
+
+        let pending = self.pending_paste.take();
+        if pending.is_some() {
+            self.message_buffer.remove_topic(PASTE_TOPIC);
+        }
+
+        if !(multiline || large) || pending.as_ref().map(String::as_str) == Some(text) {
+            return true;
+        }
+
+        let warning = if large {
+            format!("Paste of {} bytes held back, paste again to send it", text.len())
+        } else {
+            let lines = text.lines().count();
+            format!("Pasting {} lines may run them as commands, paste again to send them", lines)
+        };
+        let mut message = Message::new(warning, color::YELLOW);
+        message.set_topic(PASTE_TOPIC.into());
+        let _ = self.message_buffer.tx().send(message);
+
+        self.pending_paste = Some(text.to_owned());
+        self.dirty = true;
+
+        false
+    }
+
     #[inline]
     pub fn exit(&mut self) {
         self.should_exit = true;
@@ -1343,6 +2272,14 @@ impl Term {
     #[inline]
     pub fn set_url_highlight(&mut self, hl: RangeInclusive<index::Linear>) {
         self.grid.url_highlight = Some(hl);
+        self.hyperlink_highlight = None;
+    }
+
+    /// Underline all cells of a hyperlink
+    #[inline]
+    pub fn set_hyperlink_highlight(&mut self, hyperlink: u32) {
+        self.grid.url_highlight = None;
+        self.hyperlink_highlight = Some(hyperlink);
     }
 
     #[inline]
@@ -1357,6 +2294,7 @@ impl Term {
         self.set_mouse_cursor(mouse_cursor);
 
         self.grid.url_highlight = None;
+        self.hyperlink_highlight = None;
         self.dirty = true;
     }
 
@@ -1381,21 +2319,64 @@ impl ansi::Handler for Term {
     /// Set the window title
     #[inline]
     fn set_title(&mut self, title: &str) {
+        if self.paranoid {
+            debug!("Ignoring title change in paranoid mode");
+            return;
+        }
+
+        self.title = Some(title.to_owned());
+
         if self.dynamic_title {
             self.next_title = Some(title.to_owned());
 
-            #[cfg(windows)]
-            {
-                // cmd.exe in winpty: winpty incorrectly sets the title to ' ' instead of
-                // 'Alacritty' - thus we have to substitute this back to get equivalent
-                // behaviour as conpty.
-                //
-                // The starts_with check is necessary because other shells e.g. bash set a
-                // different title and don't need Alacritty prepended.
-                if !tty::is_conpty() && title.starts_with(' ') {
-                    self.next_title = Some(format!("Alacritty {}", title.trim()));
-                }
-            }
+            #[cfg(windows)]
+            {
+                // cmd.exe in winpty: winpty incorrectly sets the title to ' ' instead of
+                // 'Alacritty' - thus we have to substitute this back to get equivalent
+                // behaviour as conpty.
+                //
+                // The starts_with check is necessary because other shells e.g. bash set a
+                // different title and don't need Alacritty prepended.
+                if !tty::is_conpty() && title.starts_with(' ') {
+                    self.next_title = Some(format!("Alacritty {}", title.trim()));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn notify(&mut self, title: Option<&str>, body: &str) {
+        if self.paranoid {
+            debug!("Ignoring notification in paranoid mode");
+            return;
+        }
+
+        self.notifications.notify(title, body);
+    }
+
+    #[inline]
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        // Links are ended instead of started in paranoid mode, since their target is hidden
+        let hyperlink = match hyperlink {
+            Some(_) if self.paranoid => {
+                debug!("Ignoring hyperlink in paranoid mode");
+                None
+            },
+            Some(hyperlink) => self.insert_hyperlink(hyperlink),
+            None => None,
+        };
+
+        let mut metadata = self.metadata.get(self.cursor_metadata).cloned().unwrap_or_default();
+        metadata.hyperlink = hyperlink;
+        self.cursor_metadata = self.intern_metadata(metadata);
+    }
+
+    #[inline]
+    fn set_progress(&mut self, progress: Option<Progress>) {
+        if self.progress != progress {
+            self.progress = progress;
+            self.next_progress = Some(progress);
+            self.dirty = true;
         }
     }
 
@@ -1430,6 +2411,7 @@ impl ansi::Handler for Term {
             if (self.cursor.point.line + 1) >= self.scroll_region.end {
                 self.linefeed();
             } else {
+                self.complete_line(self.cursor.point.line);
                 self.cursor.point.line += 1;
             }
 
@@ -1437,6 +2419,11 @@ impl ansi::Handler for Term {
             self.input_needs_wrap = false;
         }
 
+        if !self.alt {
+            self.complete_left_line();
+            self.unfinished_line = Some(self.cursor.point.line);
+        }
+
         // Number of cells the char will occupy
         if let Some(width) = c.width() {
             let num_cols = self.grid.num_cols();
@@ -1469,6 +2456,7 @@ impl ansi::Handler for Term {
             let cell = &mut self.grid[&self.cursor.point];
             *cell = self.cursor.template;
             cell.c = self.cursor.charsets[self.active_charset].map(c);
+            cell.metadata = self.cursor_metadata;
 
             // Handle wide chars
             if width == 2 {
@@ -1479,6 +2467,7 @@ impl ansi::Handler for Term {
                     let spacer = &mut self.grid[&self.cursor.point];
                     *spacer = self.cursor.template;
                     spacer.flags.insert(cell::Flags::WIDE_CHAR_SPACER);
+                    spacer.metadata = self.cursor_metadata;
                 }
             }
         }
@@ -1582,7 +2571,18 @@ impl ansi::Handler for Term {
 
     #[inline]
     fn identify_terminal<W: io::Write>(&mut self, writer: &mut W) {
-        let _ = writer.write_all(b"\x1b[?6c");
+        // VT220 with sixel graphics
+        let _ = writer.write_all(b"\x1b[?62;4c");
+    }
+
+    #[inline]
+    fn answerback<W: io::Write>(&mut self, writer: &mut W) {
+        if self.paranoid || self.answerback.is_empty() {
+            trace!("Ignoring ENQ");
+            return;
+        }
+
+        let _ = writer.write_all(self.answerback.as_bytes());
     }
 
     #[inline]
@@ -1601,6 +2601,19 @@ impl ansi::Handler for Term {
         };
     }
 
+    #[inline]
+    fn graphics_attribute<W: io::Write>(
+        &mut self,
+        writer: &mut W,
+        item: Option<GraphicsItem>,
+        action: Option<GraphicsAction>,
+        values: &[i64],
+    ) {
+        trace!("Graphics attribute request: {:?} {:?} {:?}", item, action, values);
+        let reply = self.graphics.request(&self.size_info, item, action, values);
+        let _ = writer.write_all(reply.as_bytes());
+    }
+
     #[inline]
     fn move_down_and_cr(&mut self, lines: Line) {
         trace!("Moving down and cr: {}", lines);
@@ -1665,6 +2678,7 @@ impl ansi::Handler for Term {
     #[inline]
     fn linefeed(&mut self) {
         trace!("Linefeed");
+        self.complete_line(self.cursor.point.line);
         let next = self.cursor.point.line + 1;
         if next == self.scroll_region.end {
             self.scroll_up(Line(1));
@@ -1677,6 +2691,10 @@ impl ansi::Handler for Term {
     #[inline]
     fn bell(&mut self) {
         trace!("Bell");
+        if !self.bell_limit.allow() {
+            return;
+        }
+
         self.visual_bell.ring();
         self.next_is_urgent = Some(true);
     }
@@ -1754,6 +2772,59 @@ impl ansi::Handler for Term {
         }
     }
 
+    /// Place an image at the cursor and continue below it
+    ///
+    /// The image is cropped to the sixel geometry and the right edge of the grid, the cells it
+    /// covers are cleared.
+    fn sixel_image(&mut self, image: SixelImage) {
+        let (max_width, max_height) = self.graphics.sixel_geometry(&self.size_info);
+        let width = min(image.width, max_width);
+        let height = min(image.height, max_height);
+        if width == 0 || height == 0 || self.size_info.cell_width < 1. {
+            return;
+        }
+
+        // Pixels which were never drawn show the background unless the image is transparent
+        let background = self.colors[NamedColor::Background];
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in image.pixels.chunks(image.width).take(height) {
+            pixels.extend(row[..width].iter().map(|&pixel| {
+                if pixel.a == 0 && !image.transparent {
+                    background
+                } else {
+                    pixel
+                }
+            }));
+        }
+
+        let col = self.cursor.point.col;
+        let cols = (width as f32 / self.size_info.cell_width).ceil() as usize;
+        let cols = min(cols, (self.grid.num_cols() - col).0);
+        let rows = (height as f32 / self.size_info.cell_height.max(1.)).ceil() as usize;
+        let id = self.images.insert(width, height, pixels, col);
+
+        let template = self.cursor.template;
+        for row in 0..rows {
+            if row > 0 {
+                self.linefeed();
+            }
+
+            let mut metadata = self.metadata.get(self.cursor_metadata).cloned().unwrap_or_default();
+            metadata.image = Some(ImageTile { image: id, row: row as u16 });
+            let metadata = self.intern_metadata(metadata);
+
+            let line = self.cursor.point.line;
+            for cell in &mut self.grid[line][col..col + Column(cols)] {
+                cell.reset(&template);
+                cell.metadata = metadata;
+            }
+        }
+
+        self.linefeed();
+        self.cursor.point.col = col;
+        self.input_needs_wrap = false;
+    }
+
     #[inline]
     fn erase_chars(&mut self, count: Column) {
         trace!("Erasing chars: count={}, col={}", count, self.cursor.point.col);
@@ -1869,11 +2940,17 @@ impl ansi::Handler for Term {
         trace!("Setting color[{}] = {:?}", index, color);
         self.colors[index] = color;
         self.color_modified[index] = true;
+        self.grid.damage_all();
     }
 
     /// Write a foreground/background color escape sequence with the current color
     #[inline]
     fn dynamic_color_sequence<W: io::Write>(&mut self, writer: &mut W, code: u8, index: usize) {
+        if self.paranoid {
+            debug!("Ignoring query for dynamic color code {} in paranoid mode", code);
+            return;
+        }
+
         trace!("Writing escape sequence for dynamic color code {}: color[{}]", code, index);
         let color = self.colors[index];
         let response = format!(
@@ -1889,12 +2966,55 @@ impl ansi::Handler for Term {
         trace!("Resetting color[{}]", index);
         self.colors[index] = self.original_colors[index];
         self.color_modified[index] = false;
+        self.grid.damage_all();
     }
 
     /// Set the clipboard
     #[inline]
-    fn set_clipboard(&mut self, string: &str) {
-        self.clipboard.store(ClipboardType::Clipboard, string);
+    fn set_clipboard(&mut self, clipboard: u8, string: &str) {
+        if self.paranoid {
+            debug!("Ignoring clipboard write in paranoid mode");
+            return;
+        }
+
+        let ty = match ClipboardType::from_selection_char(clipboard) {
+            Some(ty) => ty,
+            None => {
+                debug!("Ignoring write to unsupported clipboard {:?}", clipboard as char);
+                return;
+            },
+        };
+
+        // Only the latest write is kept once the rate limit is exceeded
+        self.pending_clipboard.retain(|(pending, _)| *pending != ty);
+        if self.clipboard_limit.allow() {
+            self.clipboard.store(ty, string);
+        } else {
+            debug!("Delaying clipboard write, rate limit exceeded");
+            self.pending_clipboard.push((ty, string.to_owned()));
+            self.dirty = true;
+        }
+    }
+
+    /// Reply with the text of the clipboard
+    #[inline]
+    fn write_clipboard<W: io::Write>(&mut self, writer: &mut W, clipboard: u8) {
+        if self.paranoid || !self.clipboard_read {
+            debug!("Ignoring clipboard read");
+            return;
+        }
+
+        let ty = match ClipboardType::from_selection_char(clipboard) {
+            Some(ty) => ty,
+            None => {
+                debug!("Ignoring read of unsupported clipboard {:?}", clipboard as char);
+                return;
+            },
+        };
+
+        let text = self.clipboard.load(ty);
+        let reply = format!("\x1b]52;{};{}\x07", clipboard as char, base64::encode(&text));
+        let _ = writer.write_all(reply.as_bytes());
     }
 
     #[inline]
@@ -1959,6 +3079,10 @@ impl ansi::Handler for Term {
         }
         self.input_needs_wrap = false;
         self.next_title = None;
+        self.title = None;
+        self.set_progress(None);
+        self.cursor_metadata = MetadataId::NONE;
+        self.unfinished_line = None;
         self.next_mouse_cursor = None;
         self.cursor = Default::default();
         self.active_charset = Default::default();
@@ -1970,8 +3094,13 @@ impl ansi::Handler for Term {
         self.colors = self.original_colors;
         self.color_modified = [false; color::COUNT];
         self.cursor_style = None;
+        self.graphics = Default::default();
+        self.images.clear();
         self.grid.reset(&Cell::default());
         self.alt_grid.reset(&Cell::default());
+        self.metadata.clear();
+        self.hyperlinks.clear();
+        self.hyperlink_highlight = None;
         self.scroll_region = Line(0)..self.grid.num_lines();
     }
 
@@ -2009,14 +3138,17 @@ impl ansi::Handler for Term {
             Attr::Italic => self.cursor.template.flags.insert(cell::Flags::ITALIC),
             Attr::CancelItalic => self.cursor.template.flags.remove(cell::Flags::ITALIC),
             Attr::Underscore => self.cursor.template.flags.insert(cell::Flags::UNDERLINE),
-            Attr::CancelUnderline => self.cursor.template.flags.remove(cell::Flags::UNDERLINE),
+            Attr::CancelUnderline => {
+                self.cursor.template.flags.remove(cell::Flags::ALL_UNDERLINES)
+            },
             Attr::Hidden => self.cursor.template.flags.insert(cell::Flags::HIDDEN),
             Attr::CancelHidden => self.cursor.template.flags.remove(cell::Flags::HIDDEN),
             Attr::Strike => self.cursor.template.flags.insert(cell::Flags::STRIKEOUT),
             Attr::CancelStrike => self.cursor.template.flags.remove(cell::Flags::STRIKEOUT),
-            _ => {
-                debug!("Term got unhandled attr: {:?}", attr);
+            Attr::BlinkSlow | Attr::BlinkFast => {
+                self.cursor.template.flags.insert(cell::Flags::BLINK)
             },
+            Attr::CancelBlink => self.cursor.template.flags.remove(cell::Flags::BLINK),
         }
     }
 
@@ -2034,6 +3166,7 @@ impl ansi::Handler for Term {
             },
             ansi::Mode::ShowCursor => self.mode.insert(TermMode::SHOW_CURSOR),
             ansi::Mode::CursorKeys => self.mode.insert(TermMode::APP_CURSOR),
+            ansi::Mode::ApplicationKeypad => self.mode.insert(TermMode::APP_KEYPAD),
             ansi::Mode::ReportMouseClicks => {
                 self.mode.insert(TermMode::MOUSE_REPORT_CLICK);
                 self.set_mouse_cursor(MouseCursor::Default);
@@ -2049,6 +3182,7 @@ impl ansi::Handler for Term {
             ansi::Mode::ReportFocusInOut => self.mode.insert(TermMode::FOCUS_IN_OUT),
             ansi::Mode::BracketedPaste => self.mode.insert(TermMode::BRACKETED_PASTE),
             ansi::Mode::SgrMouse => self.mode.insert(TermMode::SGR_MOUSE),
+            ansi::Mode::SgrPixels => self.mode.insert(TermMode::SGR_PIXELS),
             ansi::Mode::LineWrap => self.mode.insert(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.insert(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.insert(TermMode::ORIGIN),
@@ -2074,6 +3208,7 @@ impl ansi::Handler for Term {
             },
             ansi::Mode::ShowCursor => self.mode.remove(TermMode::SHOW_CURSOR),
             ansi::Mode::CursorKeys => self.mode.remove(TermMode::APP_CURSOR),
+            ansi::Mode::ApplicationKeypad => self.mode.remove(TermMode::APP_KEYPAD),
             ansi::Mode::ReportMouseClicks => {
                 self.mode.remove(TermMode::MOUSE_REPORT_CLICK);
                 self.set_mouse_cursor(MouseCursor::Text);
@@ -2089,6 +3224,7 @@ impl ansi::Handler for Term {
             ansi::Mode::ReportFocusInOut => self.mode.remove(TermMode::FOCUS_IN_OUT),
             ansi::Mode::BracketedPaste => self.mode.remove(TermMode::BRACKETED_PASTE),
             ansi::Mode::SgrMouse => self.mode.remove(TermMode::SGR_MOUSE),
+            ansi::Mode::SgrPixels => self.mode.remove(TermMode::SGR_PIXELS),
             ansi::Mode::LineWrap => self.mode.remove(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.remove(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.remove(TermMode::ORIGIN),
@@ -2176,19 +3312,40 @@ impl IndexMut<Column> for TabStops {
 #[cfg(test)]
 mod tests {
     use std::mem;
+    use std::time::Duration;
 
     use font::Size;
     use serde_json;
 
-    use crate::ansi::{self, CharsetIndex, Handler, StandardCharset};
-    use crate::clipboard::Clipboard;
-    use crate::config::Config;
+    use crate::ansi::{self, CharsetIndex, CursorStyle, Handler, NamedColor, StandardCharset};
+    use crate::clipboard::{Clipboard, ClipboardType};
+    use crate::config::{Config, OutputFilter, SecurityMode, WatchRule};
     use crate::grid::{Grid, Scroll};
     use crate::index::{Column, Line, Point, Side};
     use crate::input::FONT_SIZE_STEP;
     use crate::message_bar::MessageBuffer;
     use crate::selection::Selection;
-    use crate::term::{cell, Cell, SizeInfo, Term};
+    use crate::term::metadata::Metadata;
+    use crate::term::color::Rgb;
+    use crate::term::search::Direction;
+    use crate::term::sixel::SixelImage;
+    use crate::term::{
+        cell, Cell, RenderableCellContent, Search, SizeInfo, Term, MAX_CLIPBOARD_WRITES,
+    };
+    use crate::util::RateLimit;
+
+    /// Size of a grid with 7 columns and 17 lines of 3x3 pixel cells
+    fn test_size_info() -> SizeInfo {
+        SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        }
+    }
 
     #[test]
     fn semantic_selection_works() {
@@ -2261,6 +3418,34 @@ mod tests {
         assert_eq!(term.selection_to_string(), Some(String::from("\"aa\"a\n")));
     }
 
+    #[test]
+    fn selection_to_rich_text() {
+        let size = test_size_info();
+        let mut term =
+            Term::new(&Default::default(), size, MessageBuffer::new(), Clipboard::new_nop());
+        let mut grid: Grid<Cell> = Grid::new(Line(1), Column(3), 0, Cell::default());
+        let red = Rgb::new(0xff, 0, 0);
+        for (i, c) in "a<c".chars().enumerate() {
+            grid[Line(0)][Column(i)].c = c;
+        }
+        grid[Line(0)][Column(1)].fg = ansi::Color::Spec(red);
+        grid[Line(0)][Column(1)].flags.insert(cell::Flags::BOLD);
+
+        mem::swap(&mut term.grid, &mut grid);
+
+        *term.selection_mut() = Some(Selection::lines(Point { line: 0, col: Column(0) }));
+        let rich_text = term.selection_to_rich_text().unwrap();
+        assert_eq!(rich_text.text, "a<c\n");
+
+        let bg = term.colors[NamedColor::Background];
+        let style = format!(
+            "color:#ff0000;background-color:#{:02x}{:02x}{:02x};font-weight:bold",
+            bg.r, bg.g, bg.b
+        );
+        assert!(rich_text.html.contains(&format!("<span style=\"{}\">&lt;</span>", style)));
+        assert!(rich_text.rtf.contains("\\red255\\green0\\blue0;"));
+    }
+
     #[test]
     fn selecting_empty_line() {
         let size = SizeInfo {
@@ -2420,6 +3605,506 @@ mod tests {
         scrolled_grid.scroll_display(Scroll::Top);
         assert_eq!(term.grid, scrolled_grid);
     }
+
+    #[test]
+    fn cursor_metadata_is_attached_to_input() {
+        let size = test_size_info();
+        let mut term =
+            Term::new(&Default::default(), size, MessageBuffer::new(), Clipboard::new_nop());
+
+        let link = Metadata { hyperlink: Some(1), ..Default::default() };
+        term.set_cursor_metadata(link.clone());
+        term.input('a');
+        term.set_cursor_metadata(Metadata::default());
+        term.input('b');
+
+        let first = term.grid[Line(0)][Column(0)];
+        let second = term.grid[Line(0)][Column(1)];
+        assert_eq!(term.cell_metadata(&first), Some(&link));
+        assert_eq!(term.cell_metadata(&second), None);
+    }
+
+    #[test]
+    fn multiline_paste_is_held_back_until_repeated() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        config.paste.confirm_multiline = true;
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        assert!(term.confirm_paste("ls", false));
+        assert!(term.confirm_paste("ls\nrm", true));
+
+        assert!(!term.confirm_paste("ls\nrm", false));
+        assert!(term.message_buffer_mut().message().is_some());
+        assert!(!term.confirm_paste("ls\nrm -r", false));
+        assert!(term.confirm_paste("ls\nrm -r", false));
+        assert!(term.message_buffer_mut().message().is_none());
+    }
+
+    #[test]
+    fn hyperlinks_with_same_id_are_one_target() {
+        let size = test_size_info();
+        let config: Config = Default::default();
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let mut parser = ansi::Processor::new();
+
+        let bytes: &[u8] =
+            b"\x1b]8;id=x;https://a.org\x07ab\x1b]8;;\x07c\x1b]8;id=x;https://a.org\x07d";
+        let mut reply = Vec::new();
+        for byte in bytes {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+
+        let at = |term: &Term, col| {
+            term.hyperlink_at(Point::new(Line(0), Column(col))).map(|(index, uri)| {
+                assert_eq!(uri, "https://a.org");
+                index
+            })
+        };
+        let index = at(&term, 0).unwrap();
+        assert_eq!(at(&term, 1), Some(index));
+        assert_eq!(at(&term, 2), None);
+        assert_eq!(at(&term, 3), Some(index));
+
+        term.set_hyperlink_highlight(index);
+        let underlined: Vec<_> = term
+            .renderable_cells(&config)
+            .filter(|cell| cell.flags.contains(cell::Flags::UNDERLINE))
+            .map(|cell| cell.column.0)
+            .collect();
+        assert_eq!(underlined, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn hyperlinks_are_cleared_on_reset() {
+        let size = test_size_info();
+        let config: Config = Default::default();
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let mut parser = ansi::Processor::new();
+
+        let mut reply = Vec::new();
+        for byte in b"\x1b]8;;https://a.org\x07a" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+        let (index, _) = term.hyperlink_at(Point::new(Line(0), Column(0))).unwrap();
+        term.set_hyperlink_highlight(index);
+
+        for byte in b"\x1bc" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+        assert_eq!(term.hyperlinks.uri(index), None);
+        assert_eq!(term.hyperlink_highlight, None);
+    }
+
+    #[test]
+    fn sixel_image_covers_cells() {
+        let size = test_size_info();
+        let mut term =
+            Term::new(&Default::default(), size, MessageBuffer::new(), Clipboard::new_nop());
+
+        term.input('a');
+        let pixels = vec![Rgb::default(); 5 * 4];
+        term.sixel_image(SixelImage { width: 5, height: 4, pixels, transparent: false });
+
+        let cells: Vec<_> = term
+            .visible_image_cells()
+            .into_iter()
+            .map(|cell| (cell.line, cell.col, cell.row, cell.offset))
+            .collect();
+        assert_eq!(cells, vec![
+            (Line(0), Column(1), 0, 0),
+            (Line(0), Column(2), 0, 1),
+            (Line(1), Column(1), 1, 0),
+            (Line(1), Column(2), 1, 1),
+        ]);
+        assert_eq!(term.cursor.point, Point::new(Line(2), Column(1)));
+
+        // Text replaces the parts of the image it is written over
+        term.goto(Line(0), Column(2));
+        term.input('b');
+        assert_eq!(term.visible_image_cells().len(), 3);
+    }
+
+    #[test]
+    fn status_line_text() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        config.status_line.enabled = true;
+        config.status_line.format = String::from("{columns}x{lines} {title}");
+        let mut term: Term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        // The status line is not part of the grid
+        assert_eq!(term.grid.num_lines(), Line(16));
+        assert_eq!(term.status_line_text(), Some(String::from("7x16   ")));
+
+        term.set_title("a");
+        assert_eq!(term.status_line_text(), Some(String::from("7x16 a ")));
+
+        // Scroll position replaces the end of the text
+        term.grid.scroll_up(&(Line(0)..Line(16)), Line(1), &Cell::default());
+        term.scroll_display(Scroll::Lines(1));
+        assert_eq!(term.status_line_text(), Some(String::from("7 [1/1]")));
+
+        // Segments which don't fit are dropped, starting with the first one
+        term.toggle_paranoid_mode();
+        assert_eq!(term.status_line_text(), Some(String::from("7 [1/1]")));
+        term.scroll_display(Scroll::Bottom);
+        assert_eq!(term.status_line_text(), Some(String::from("ranoid]")));
+
+        config.status_line.enabled = false;
+        term.update_config(&config);
+        assert_eq!(term.status_line_text(), None);
+    }
+
+    #[test]
+    fn paranoid_mode() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        config.security.mode = SecurityMode::Paranoid;
+        let mut term: Term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        let mut reply = Vec::new();
+        term.dynamic_color_sequence(&mut reply, 10, NamedColor::Foreground as usize);
+        term.set_title("spoofed");
+        assert!(reply.is_empty());
+        assert_eq!(term.get_next_title(), None);
+
+        term.toggle_paranoid_mode();
+        term.dynamic_color_sequence(&mut reply, 10, NamedColor::Foreground as usize);
+        term.set_title("a");
+        assert!(!reply.is_empty());
+        assert_eq!(term.get_next_title(), Some(String::from("a")));
+
+        // Reloading an unchanged config keeps the runtime toggle
+        term.update_config(&config);
+        assert!(!term.paranoid());
+    }
+
+    #[test]
+    fn completed_rows_have_timestamps() {
+        let size = test_size_info();
+        let mut term =
+            Term::new(&Default::default(), size, MessageBuffer::new(), Clipboard::new_nop());
+
+        term.linefeed();
+        assert!(term.visible_timestamps().is_empty());
+
+        term.toggle_timestamps();
+        let timestamps = term.visible_timestamps();
+        assert_eq!(timestamps.len(), 1);
+        assert_eq!(timestamps[0].0, Line(0));
+        assert_eq!(timestamps[0].1.len(), "00:00:00".len());
+
+        // Rows wrapping within the screen are completed too
+        for c in "wrapped!".chars() {
+            term.input(c);
+        }
+        let lines: Vec<_> = term.visible_timestamps().into_iter().map(|(line, _)| line).collect();
+        assert_eq!(lines, vec![Line(0), Line(1)]);
+    }
+
+    #[test]
+    fn legacy_queries_without_reply() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        let mut term: Term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let mut parser = ansi::Processor::new();
+
+        // ENQ, window title report, icon label report and clipboard read
+        let queries: &[&[u8]] = &[b"\x05", b"\x1b[21t", b"\x1b[20t", b"\x1b]52;c;?\x07"];
+
+        let mut reply = Vec::new();
+        for query in queries {
+            for byte in query.iter() {
+                parser.advance(&mut term, *byte, &mut reply);
+            }
+        }
+        assert!(reply.is_empty());
+
+        config.security.answerback = String::from("alacritty");
+        term.update_config(&config);
+        parser.advance(&mut term, 0x05, &mut reply);
+        assert_eq!(reply, b"alacritty");
+
+        // Paranoid mode ignores the configured answerback
+        reply.clear();
+        term.toggle_paranoid_mode();
+        parser.advance(&mut term, 0x05, &mut reply);
+        assert!(reply.is_empty());
+    }
+
+    #[test]
+    fn clipboard_read_requires_config() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        let mut term: Term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let mut parser = ansi::Processor::new();
+
+        let mut reply = Vec::new();
+        for byte in b"\x1b]52;c;?\x07" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+        assert!(reply.is_empty());
+
+        config.security.clipboard_read = true;
+        term.update_config(&config);
+        for byte in b"\x1b]52;c;?\x07" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+        assert_eq!(reply, b"\x1b]52;c;\x07");
+
+        reply.clear();
+        for byte in b"\x1b]52;q;?\x07" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+        assert_eq!(reply, b"\x1b]52;q;\x07");
+
+        // Unknown clipboards are not answered
+        reply.clear();
+        for byte in b"\x1b]52;0;?\x07" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+        assert!(reply.is_empty());
+    }
+
+    #[test]
+    fn output_filters_apply_to_wrapped_and_unterminated_lines() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        config.output_filters.rules.push(OutputFilter {
+            regex: String::from("secret"),
+            replacement: Some(String::from("******")),
+            ..OutputFilter::default()
+        });
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let text = |term: &Term, line| -> String {
+            term.grid[Line(line)][..].iter().map(|cell| cell.c).collect()
+        };
+
+        // Rows wrapping within the screen are filtered once the cursor moves past them
+        for c in "secret!x".chars() {
+            term.input(c);
+        }
+        assert_eq!(text(&term, 0), "******!");
+
+        // Rows without linefeed are filtered once input is written to another line
+        term.goto(Line(3), Column(0));
+        for c in "secret".chars() {
+            term.input(c);
+        }
+        assert_eq!(text(&term, 3), "secret ");
+
+        term.goto(Line(5), Column(0));
+        term.input('a');
+        assert_eq!(text(&term, 3), "****** ");
+    }
+
+    #[test]
+    fn watch_lines_wrapped_within_screen() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        let mut rule = WatchRule::default();
+        rule.regex = String::from("^abcdefgh$");
+        rule.mark = true;
+        config.watch.push(rule);
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        for c in "abcdefgh".chars() {
+            term.input(c);
+        }
+        term.linefeed();
+
+        let mark = term.cell_metadata(&term.grid[Line(1)][Column(0)]).and_then(|m| m.mark);
+        assert_eq!(mark, Some(0));
+    }
+
+    #[test]
+    fn scroll_to_watch_marks() {
+        let size = test_size_info();
+        let mut config: Config = Default::default();
+        let mut rule = WatchRule::default();
+        rule.regex = String::from("^err$");
+        rule.mark = true;
+        config.watch.push(rule);
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        for _ in 0..2 {
+            for c in "err".chars() {
+                term.input(c);
+            }
+            term.carriage_return();
+            for _ in 0..40 {
+                term.linefeed();
+            }
+        }
+        assert!(term.visible_marks().is_empty());
+        assert!(term.mark_scroll(Direction::Down).is_none());
+
+        let scroll = term.mark_scroll(Direction::Up).unwrap();
+        term.scroll_display(scroll);
+        assert_eq!(term.visible_marks(), vec![Line(0)]);
+        let newer = term.grid.display_offset();
+
+        let scroll = term.mark_scroll(Direction::Up).unwrap();
+        term.scroll_display(scroll);
+        assert_eq!(term.visible_marks(), vec![Line(0)]);
+        assert!(term.mark_scroll(Direction::Up).is_none());
+
+        let scroll = term.mark_scroll(Direction::Down).unwrap();
+        term.scroll_display(scroll);
+        assert_eq!(term.grid.display_offset(), newer);
+    }
+
+    #[test]
+    fn clipboard_writes_are_coalesced() {
+        let size = test_size_info();
+        let config: Config = Default::default();
+        let mut term: Term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        for i in 0..MAX_CLIPBOARD_WRITES + 3 {
+            term.set_clipboard(b'c', &i.to_string());
+        }
+        term.set_clipboard(b'p', "primary");
+
+        let pending = vec![
+            (ClipboardType::Clipboard, (MAX_CLIPBOARD_WRITES + 2).to_string()),
+            (ClipboardType::Selection, String::from("primary")),
+        ];
+        assert_eq!(term.pending_clipboard, pending);
+
+        // Writes are held back until the rate limit allows them again
+        term.flush_clipboard();
+        assert!(term.has_pending_clipboard());
+
+        term.clipboard_limit = RateLimit::new(MAX_CLIPBOARD_WRITES, Duration::from_secs(0));
+        term.flush_clipboard();
+        assert!(!term.has_pending_clipboard());
+    }
+
+    #[test]
+    fn frame_hash_follows_visible_content() {
+        let size = test_size_info();
+        let config: Config = Default::default();
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+        let frame_hash = |term: &Term| {
+            let mut hasher = term.frame_hasher();
+            for cell in term.renderable_cells(&config) {
+                hasher.cell(&cell);
+            }
+            for cell in term.renderable_cursor(&config, true) {
+                hasher.cell(&cell);
+            }
+            hasher.finish()
+        };
+
+        term.input('a');
+        let hash = frame_hash(&term);
+        assert_eq!(frame_hash(&term), hash);
+
+        // Moving the cursor changes the frame
+        term.goto(Line(2), Column(2));
+        assert_ne!(frame_hash(&term), hash);
+        term.goto(Line(0), Column(1));
+        assert_eq!(frame_hash(&term), hash);
+
+        *term.selection_mut() = Some(Selection::simple(Point::new(16, Column(0)), Side::Left));
+        term.selection_mut().as_mut().unwrap().update(Point::new(16, Column(0)), Side::Right);
+        assert_ne!(frame_hash(&term), hash);
+    }
+
+    #[test]
+    fn damage_follows_writes() {
+        let size = test_size_info();
+        let config: Config = Default::default();
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        // Nothing has been drawn yet
+        assert!(term.take_damage().is_full());
+
+        term.goto(Line(3), Column(0));
+        term.take_damage();
+
+        term.input('a');
+        let damage = term.take_damage();
+        assert!(!damage.is_full());
+        assert!(damage.is_damaged(Line(3)));
+        assert!(!damage.is_damaged(Line(0)));
+        assert!(!damage.is_damaged(Line(5)));
+
+        let cells: Vec<_> = term.renderable_cells(&config).damaged(&damage).collect();
+        assert!(!cells.is_empty());
+        assert!(cells.iter().all(|cell| cell.line == Line(3)));
+
+        // The cursor is drawn separately, moving it doesn't damage its lines
+        term.goto(Line(5), Column(2));
+        assert!(!term.take_damage().is_damaged(Line(5)));
+        let cursor = term.renderable_cursor(&config, true);
+        assert_eq!(cursor.len(), 2);
+        assert!(cursor.iter().all(|cell| cell.line == Line(5) && cell.column == Column(2)));
+        match cursor[0].inner {
+            RenderableCellContent::Cursor(key) => assert_eq!(key.style, CursorStyle::Block),
+            _ => panic!("cursor is drawn first"),
+        }
+
+        // Selections are drawn on top of the grid and damage everything
+        *term.selection_mut() = Some(Selection::simple(Point::new(16, Column(0)), Side::Left));
+        term.selection_mut().as_mut().unwrap().update(Point::new(16, Column(0)), Side::Right);
+        assert!(term.take_damage().is_full());
+        assert!(!term.take_damage().is_full());
+    }
+
+    #[test]
+    fn pixels_to_text_area() {
+        for &dpr in &[1.0f32, 2.0] {
+            let size = SizeInfo {
+                width: 21.0 * dpr,
+                height: 51.0 * dpr,
+                cell_width: 3.0 * dpr,
+                cell_height: 3.0 * dpr,
+                padding_x: 2.0 * dpr,
+                padding_y: 2.0 * dpr,
+                dpr: f64::from(dpr),
+            };
+            let scale = |x: f32| (x * dpr) as usize;
+
+            assert_eq!(size.pixels_to_text_area(0, 0), (0, 0));
+            assert_eq!(size.pixels_to_text_area(scale(5.), scale(7.)), (scale(3.), scale(5.)));
+
+            // Coordinates within the padding are clamped to the text area
+            let (max_x, max_y) = (scale(17.) - 1, scale(47.) - 1);
+            assert_eq!(size.pixels_to_text_area(scale(21.), scale(51.)), (max_x, max_y));
+        }
+    }
+
+    #[test]
+    fn megabyte_line_is_only_scanned_near_viewport() {
+        let size = test_size_info();
+        let config: Config = Default::default();
+        let mut term = Term::new(&config, size, MessageBuffer::new(), Clipboard::new_nop());
+
+        for _ in 0..0x10_0000 {
+            term.input('a');
+        }
+        for c in " https://a.org".chars() {
+            term.input(c);
+        }
+
+        // Rows of the line far from its end are not taken for a link
+        assert_eq!(term.url_search(Point::new(0, Column(3))), None);
+        assert!(term.hint_at(Point::new(Line(0), Column(3))).is_none());
+
+        let line = term.cursor.point.line;
+        let url = term.url_search(Point::new(line.0, Column(0))).map(|url| url.text);
+        assert_eq!(url, Some(String::from("https://a.org")));
+        let hint = term.hint_at(Point::new(line, Column(0))).map(|hint| hint.text);
+        assert_eq!(hint, Some(String::from("https://a.org")));
+
+        term.start_search();
+        for c in "a.org".chars() {
+            term.search_push(c);
+        }
+        assert!(term.visible_search_matches().iter().any(|m| m.line == line));
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -2480,7 +4165,7 @@ mod benches {
         mem::swap(&mut terminal.grid, &mut grid);
 
         b.iter(|| {
-            let iter = terminal.renderable_cells(&config, false);
+            let iter = terminal.renderable_cells(&config);
             for cell in iter {
                 test::black_box(cell);
             }
@@ -0,0 +1,156 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Desktop notifications requested by applications
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::util::RateLimit;
+
+/// Length of the window used for rate limiting
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Maximum number of notifications per minute across all titles
+const MAX_TOTAL_PER_MINUTE: usize = 20;
+
+/// Maximum number of titles with a separate rate limit
+const MAX_ORIGINS: usize = 64;
+
+/// Title of notifications which don't specify one
+const DEFAULT_TITLE: &str = "Alacritty";
+
+pub struct Notifications {
+    config: config::Notifications,
+
+    /// Rate limit of every title
+    origins: HashMap<String, RateLimit>,
+
+    /// Rate limit shared by all titles, so changing the title doesn't bypass the limit
+    total: RateLimit,
+}
+
+impl Notifications {
+    pub fn new(config: &config::Notifications) -> Notifications {
+        Notifications {
+            config: config.clone(),
+            origins: HashMap::new(),
+            total: RateLimit::new(MAX_TOTAL_PER_MINUTE, RATE_WINDOW),
+        }
+    }
+
+    pub fn update_config(&mut self, config: &config::Notifications) {
+        if self.config != *config {
+            self.config = config.clone();
+            self.origins.clear();
+        }
+    }
+
+    /// Show a notification, unless too many were sent with the same title
+    pub fn notify(&mut self, title: Option<&str>, body: &str) {
+        if !self.config.enabled() {
+            return;
+        }
+
+        let title = title.filter(|title| !title.is_empty()).unwrap_or(DEFAULT_TITLE);
+        if !self.allow(title, Instant::now()) {
+            debug!("Dropping notification {:?}, rate limit exceeded", title);
+            return;
+        }
+
+        if let Err(err) = show(title, body) {
+            debug!("Unable to show notification: {}", err);
+        }
+    }
+
+    /// Check the rate limit of an origin, counting the notification if it is allowed
+    fn allow(&mut self, origin: &str, now: Instant) -> bool {
+        // Forget about old titles instead of growing without bounds
+        if self.origins.len() >= MAX_ORIGINS && !self.origins.contains_key(origin) {
+            self.origins.clear();
+        }
+
+        let max = self.config.max_per_minute();
+        let rate_limit = self
+            .origins
+            .entry(origin.to_owned())
+            .or_insert_with(|| RateLimit::new(max, RATE_WINDOW));
+
+        rate_limit.allow_at(now) && self.total.allow_at(now)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn show(title: &str, body: &str) -> ::std::io::Result<()> {
+    crate::util::start_daemon("notify-send", &["--app-name", "Alacritty", "--", title, body])
+}
+
+#[cfg(target_os = "macos")]
+fn show(title: &str, body: &str) -> ::std::io::Result<()> {
+    let script = format!(
+        "display notification {} with title {}",
+        apple_script_string(body),
+        apple_script_string(title)
+    );
+    crate::util::start_daemon("osascript", &["-e", &script])
+}
+
+/// Quote text as an AppleScript string literal
+#[cfg(target_os = "macos")]
+fn apple_script_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(windows)]
+fn show(_title: &str, _body: &str) -> ::std::io::Result<()> {
+    Err(::std::io::Error::new(
+        ::std::io::ErrorKind::Other,
+        "notifications are not supported on Windows",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Notifications, MAX_ORIGINS};
+
+    #[test]
+    fn rate_limit_per_origin() {
+        let mut notifications = Notifications::new(&Default::default());
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            assert!(notifications.allow("build", now));
+        }
+        assert!(!notifications.allow("build", now));
+
+        // Other origins have their own limit
+        assert!(notifications.allow("tests", now));
+
+        // The limit is reset after a minute
+        assert!(notifications.allow("build", now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn origins_are_bounded() {
+        let mut notifications = Notifications::new(&Default::default());
+        let now = Instant::now();
+
+        for i in 0..1000 {
+            notifications.allow(&i.to_string(), now);
+        }
+        assert!(notifications.origins.len() <= MAX_ORIGINS);
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Regex filters rewriting and highlighting rows once the cursor leaves them
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use unicode_width::UnicodeWidthChar;
+
+use crate::ansi::Color;
+use crate::config;
+use crate::grid::Row;
+use crate::index::Column;
+use crate::term::cell::{self, Cell, LineLength, MAX_ZEROWIDTH_CHARS};
+use crate::term::color::Rgb;
+
+/// Time which may be spent filtering within every second
+///
+/// Once it is used up, rows are passed through unmodified until the next second starts, so
+/// expensive expressions can't stall the terminal on large amounts of output.
+const FILTER_BUDGET: Duration = Duration::from_millis(50);
+
+/// Character used for replacement text which is not a single cell wide
+const REPLACEMENT_CHAR: char = '*';
+
+struct Rule {
+    regex: Regex,
+    replacement: Option<String>,
+    foreground: Option<Rgb>,
+    background: Option<Rgb>,
+    whole_line: bool,
+}
+
+pub struct OutputFilters {
+    rules: Vec<Rule>,
+    enabled: bool,
+    config: config::OutputFilters,
+
+    /// Start of the current budget window
+    window_start: Instant,
+
+    /// Time spent filtering in the current window
+    spent: Duration,
+}
+
+impl OutputFilters {
+    pub fn new(config: &config::OutputFilters) -> OutputFilters {
+        OutputFilters {
+            rules: compile(config),
+            enabled: config.enabled(),
+            config: config.clone(),
+            window_start: Instant::now(),
+            spent: Duration::from_secs(0),
+        }
+    }
+
+    pub fn update_config(&mut self, config: &config::OutputFilters) {
+        if self.config != *config {
+            *self = OutputFilters::new(config);
+        }
+    }
+
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable or disable the filters for this session
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Apply all filters to a row
+    ///
+    /// Matches never span multiple rows, since the previous rows of a wrapped line might have
+    /// been moved into the scrollback history already.
+    pub fn apply(&mut self, row: &mut Row<Cell>) {
+        if !self.enabled || self.rules.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.spent = Duration::from_secs(0);
+        } else if self.spent >= FILTER_BUDGET {
+            return;
+        }
+
+        for rule in &self.rules {
+            rule.apply(row);
+        }
+
+        self.spent += now.elapsed();
+        if self.spent >= FILTER_BUDGET {
+            debug!("Output filter budget exceeded, skipping rows until the next second");
+        }
+    }
+}
+
+impl Rule {
+    fn apply(&self, row: &mut Row<Cell>) {
+        // Text of the row and the column each character starts at
+        let line_length = row.line_length();
+        let mut text = String::new();
+        let mut columns = Vec::new();
+        for (col, cell) in row[..line_length].iter().enumerate() {
+            if !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+                columns.push((text.len(), col));
+                text.push(cell.c);
+            }
+        }
+        let column_at = |byte: usize| {
+            let index = columns.binary_search_by_key(&byte, |&(start, _)| start);
+            index.map_or(line_length.0, |index| columns[index].1)
+        };
+
+        let mut highlighted = false;
+        for captures in self.regex.captures_iter(&text) {
+            let matched = captures.get(0).unwrap();
+            if matched.start() == matched.end() {
+                continue;
+            }
+            let (start, end) = (column_at(matched.start()), column_at(matched.end()));
+
+            if let Some(ref replacement) = self.replacement {
+                let mut expanded = String::new();
+                captures.expand(replacement, &mut expanded);
+                let mut chars = expanded.chars();
+
+                for col in start..end {
+                    let cell = &mut row[Column(col)];
+                    cell.c = match chars.next() {
+                        Some(c) if c.width() == Some(1) => c,
+                        Some(_) => REPLACEMENT_CHAR,
+                        None => ' ',
+                    };
+                    cell.extra = [' '; MAX_ZEROWIDTH_CHARS];
+                    cell.flags.remove(cell::Flags::WIDE_CHAR | cell::Flags::WIDE_CHAR_SPACER);
+                }
+            }
+
+            if self.whole_line {
+                highlighted = true;
+            } else {
+                self.highlight(row, start..end);
+            }
+        }
+
+        if highlighted {
+            let len = row.len();
+            self.highlight(row, 0..len);
+        }
+    }
+
+    fn highlight(&self, row: &mut Row<Cell>, columns: Range<usize>) {
+        for col in columns {
+            let cell = &mut row[Column(col)];
+            if let Some(foreground) = self.foreground {
+                cell.fg = Color::Spec(foreground);
+            }
+            if let Some(background) = self.background {
+                cell.bg = Color::Spec(background);
+            }
+        }
+    }
+}
+
+/// Compile the regexes of all rules, invalid rules are dropped
+fn compile(config: &config::OutputFilters) -> Vec<Rule> {
+    config
+        .rules
+        .iter()
+        .filter_map(|rule| match Regex::new(&rule.regex) {
+            Ok(regex) => Some(Rule {
+                regex,
+                replacement: rule.replacement.clone(),
+                foreground: rule.foreground,
+                background: rule.background,
+                whole_line: rule.whole_line,
+            }),
+            Err(err) => {
+                error!("Invalid output filter regex {:?}: {}", rule.regex, err);
+                None
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputFilters;
+    use crate::ansi::{Color, NamedColor};
+    use crate::config::{self, OutputFilter};
+    use crate::grid::Row;
+    use crate::index::Column;
+    use crate::term::cell::Cell;
+    use crate::term::color::Rgb;
+
+    const RED: Rgb = Rgb::new(0xff, 0, 0);
+
+    fn new_row(text: &str) -> Row<Cell> {
+        let mut row = Row::new(Column(12), &Cell::default());
+        for (i, c) in text.chars().enumerate() {
+            row[Column(i)].c = c;
+        }
+        row
+    }
+
+    fn text(row: &Row<Cell>) -> String {
+        row[..].iter().map(|cell| cell.c).collect()
+    }
+
+    fn output_filters(rule: OutputFilter) -> OutputFilters {
+        let mut config = config::OutputFilters::default();
+        config.rules.push(rule);
+        OutputFilters::new(&config)
+    }
+
+    #[test]
+    fn redact_match() {
+        let mut filters = output_filters(OutputFilter {
+            regex: String::from("key=(\\w+)"),
+            replacement: Some(String::from("key=***")),
+            ..OutputFilter::default()
+        });
+
+        let mut row = new_row("a key=secret");
+        filters.apply(&mut row);
+        assert_eq!(text(&row), "a key=***   ");
+    }
+
+    #[test]
+    fn highlight_match_and_line() {
+        let mut filters = output_filters(OutputFilter {
+            regex: String::from("ERROR"),
+            foreground: Some(RED),
+            ..OutputFilter::default()
+        });
+
+        let mut row = new_row("an ERROR");
+        filters.apply(&mut row);
+        assert_eq!(row[Column(2)].fg, Color::Named(NamedColor::Foreground));
+        assert_eq!(row[Column(3)].fg, Color::Spec(RED));
+        assert_eq!(row[Column(7)].fg, Color::Spec(RED));
+
+        let mut filters = output_filters(OutputFilter {
+            regex: String::from("ERROR"),
+            background: Some(RED),
+            whole_line: true,
+            ..OutputFilter::default()
+        });
+        let mut row = new_row("an ERROR");
+        filters.apply(&mut row);
+        assert!(row[..].iter().all(|cell| cell.bg == Color::Spec(RED)));
+    }
+
+    #[test]
+    fn toggle_filters() {
+        let mut filters = output_filters(OutputFilter {
+            regex: String::from("a"),
+            replacement: Some(String::from("b")),
+            ..OutputFilter::default()
+        });
+        filters.toggle();
+
+        let mut row = new_row("a");
+        filters.apply(&mut row);
+        assert_eq!(row[Column(0)].c, 'a');
+    }
+}
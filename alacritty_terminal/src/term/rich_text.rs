@@ -0,0 +1,232 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serialization of styled text for rich text clipboard targets
+use std::fmt::Write;
+
+use crate::term::cell::Flags;
+use crate::term::color::Rgb;
+
+/// Text sharing the same colors and style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledRun {
+    pub text: String,
+    pub fg: Rgb,
+    pub bg: Rgb,
+    pub flags: Flags,
+}
+
+/// Sequence of styled runs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyledText {
+    runs: Vec<StyledRun>,
+}
+
+impl StyledText {
+    /// Append a character, extending the last run when the style is unchanged
+    pub fn push(&mut self, c: char, fg: Rgb, bg: Rgb, flags: Flags) {
+        // Only attributes which are preserved in rich text split runs, undercurls become underlines
+        let underline = flags.intersects(Flags::ALL_UNDERLINES);
+        let mut flags = flags & (Flags::BOLD | Flags::ITALIC | Flags::STRIKEOUT);
+        flags.set(Flags::UNDERLINE, underline);
+        match self.runs.last_mut() {
+            Some(run) if run.fg == fg && run.bg == bg && run.flags == flags => run.text.push(c),
+            _ => self.runs.push(StyledRun { text: c.to_string(), fg, bg, flags }),
+        }
+    }
+
+    /// Append a line break, which keeps the style of the previous run
+    pub fn push_newline(&mut self, fg: Rgb, bg: Rgb) {
+        match self.runs.last_mut() {
+            Some(run) => run.text.push('\n'),
+            None => self.runs.push(StyledRun {
+                text: String::from("\n"),
+                fg,
+                bg,
+                flags: Flags::empty(),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn runs(&self) -> &[StyledRun] {
+        &self.runs
+    }
+
+    /// Text without any styling
+    pub fn to_plain(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+
+    /// HTML fragment with inline styles
+    pub fn to_html(&self, fg: Rgb, bg: Rgb) -> String {
+        let mut html = format!(
+            "<pre style=\"font-family:monospace;color:{};background-color:{}\">",
+            css_color(fg),
+            css_color(bg)
+        );
+
+        for run in &self.runs {
+            let mut style =
+                format!("color:{};background-color:{}", css_color(run.fg), css_color(run.bg));
+            if run.flags.contains(Flags::BOLD) {
+                style.push_str(";font-weight:bold");
+            }
+            if run.flags.contains(Flags::ITALIC) {
+                style.push_str(";font-style:italic");
+            }
+            match (run.flags.contains(Flags::UNDERLINE), run.flags.contains(Flags::STRIKEOUT)) {
+                (true, true) => style.push_str(";text-decoration:underline line-through"),
+                (true, false) => style.push_str(";text-decoration:underline"),
+                (false, true) => style.push_str(";text-decoration:line-through"),
+                (false, false) => (),
+            }
+
+            let _ = write!(html, "<span style=\"{}\">", style);
+            for c in run.text.chars() {
+                match c {
+                    '<' => html.push_str("&lt;"),
+                    '>' => html.push_str("&gt;"),
+                    '&' => html.push_str("&amp;"),
+                    '"' => html.push_str("&quot;"),
+                    c => html.push(c),
+                }
+            }
+            html.push_str("</span>");
+        }
+
+        html.push_str("</pre>");
+        html
+    }
+
+    /// RTF document with a monospace font
+    pub fn to_rtf(&self, fg: Rgb, bg: Rgb) -> String {
+        // Color table indices are one-based, index zero is the automatic color
+        let mut colors = vec![fg, bg];
+        for run in &self.runs {
+            for color in &[run.fg, run.bg] {
+                if !colors.contains(color) {
+                    colors.push(*color);
+                }
+            }
+        }
+        let index = |color: Rgb| colors.iter().position(|c| *c == color).unwrap_or(0) + 1;
+
+        let mut rtf = String::from("{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0\\fmodern Courier New;}}");
+        rtf.push_str("{\\colortbl;");
+        for color in &colors {
+            let _ = write!(rtf, "\\red{}\\green{}\\blue{};", color.r, color.g, color.b);
+        }
+        let _ = write!(rtf, "}}\\f0\\cf{}\\cb{} ", index(fg), index(bg));
+
+        for run in &self.runs {
+            let (fg, bg) = (index(run.fg), index(run.bg));
+            let _ = write!(rtf, "{{\\cf{}\\chcbpat{}\\cb{}", fg, bg, bg);
+            if run.flags.contains(Flags::BOLD) {
+                rtf.push_str("\\b");
+            }
+            if run.flags.contains(Flags::ITALIC) {
+                rtf.push_str("\\i");
+            }
+            if run.flags.contains(Flags::UNDERLINE) {
+                rtf.push_str("\\ul");
+            }
+            if run.flags.contains(Flags::STRIKEOUT) {
+                rtf.push_str("\\strike");
+            }
+            rtf.push(' ');
+
+            for c in run.text.chars() {
+                match c {
+                    '\\' | '{' | '}' => {
+                        rtf.push('\\');
+                        rtf.push(c);
+                    },
+                    '\n' => rtf.push_str("\\line "),
+                    c if c.is_ascii() => rtf.push(c),
+                    // RTF expects UTF-16 code units as signed 16 bit integers
+                    c => {
+                        let mut units = [0; 2];
+                        for unit in c.encode_utf16(&mut units) {
+                            let _ = write!(rtf, "\\u{}?", *unit as i16);
+                        }
+                    },
+                }
+            }
+            rtf.push('}');
+        }
+
+        rtf.push('}');
+        rtf
+    }
+}
+
+fn css_color(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StyledText;
+    use crate::term::cell::Flags;
+    use crate::term::color::Rgb;
+
+    const FG: Rgb = Rgb::new(0xff, 0xff, 0xff);
+    const BG: Rgb = Rgb::new(0, 0, 0);
+    const RED: Rgb = Rgb::new(0xff, 0, 0);
+
+    fn styled_text() -> StyledText {
+        let mut text = StyledText::default();
+        text.push('a', FG, BG, Flags::empty());
+        text.push('<', FG, BG, Flags::WRAPLINE);
+        text.push('b', RED, BG, Flags::BOLD);
+        text.push_newline(FG, BG);
+        text.push('é', FG, BG, Flags::ITALIC);
+        text
+    }
+
+    #[test]
+    fn runs_are_merged() {
+        let text = styled_text();
+        assert_eq!(text.runs().len(), 3);
+        assert_eq!(text.runs()[0].text, "a<");
+        assert_eq!(text.runs()[1].text, "b\n");
+        assert_eq!(text.to_plain(), "a<b\né");
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            styled_text().to_html(FG, BG),
+            "<pre style=\"font-family:monospace;color:#ffffff;background-color:#000000\">\
+             <span style=\"color:#ffffff;background-color:#000000\">a&lt;</span>\
+             <span style=\"color:#ff0000;background-color:#000000;font-weight:bold\">b\n</span>\
+             <span style=\"color:#ffffff;background-color:#000000;font-style:italic\">é</span>\
+             </pre>"
+        );
+    }
+
+    #[test]
+    fn rtf() {
+        assert_eq!(
+            styled_text().to_rtf(FG, BG),
+            "{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0\\fmodern Courier New;}}\
+             {\\colortbl;\\red255\\green255\\blue255;\\red0\\green0\\blue0;\
+             \\red255\\green0\\blue0;}\\f0\\cf1\\cb2 \
+             {\\cf1\\chcbpat2\\cb2 a<}\
+             {\\cf3\\chcbpat2\\cb2\\b b\\line }\
+             {\\cf1\\chcbpat2\\cb2\\i \\u233?}}"
+        );
+    }
+}
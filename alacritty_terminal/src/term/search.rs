@@ -0,0 +1,400 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental search through the grid and the scrollback history
+//!
+//! Rows which wrap into the next row are searched as a single line. The query is matched
+//! literally, ignoring case unless it contains an uppercase character.
+use std::cmp::min;
+
+use crate::grid::{Grid, ViewportPosition};
+use crate::index::{Column, Line, Point};
+use crate::term::cell::{Cell, Flags, LineLength};
+
+/// Rows beyond the viewport which are searched for matches and hints visible in it
+///
+/// Lines wrapping through the whole scrollback are only searched near the viewport, instead of
+/// collecting their text in full for every frame. Matches reaching further are cut off.
+pub(super) const MAX_CONTEXT_ROWS: usize = 100;
+
+/// Direction in which the next match is searched
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards older output
+    Up,
+
+    /// Towards newer output
+    Down,
+}
+
+/// Text matching the query, from its first to its last cell in buffer coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub start: Point<usize>,
+    pub end: Point<usize>,
+}
+
+/// Part of a match which is visible on a single line of the viewport
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VisibleMatch {
+    pub line: Line,
+    pub start: Column,
+    pub end: Column,
+
+    /// Part of the match which is currently navigated to
+    pub focused: bool,
+}
+
+#[derive(Debug)]
+pub struct ScrollbackSearch {
+    query: String,
+
+    /// The query is complete and the matches are navigated
+    confirmed: bool,
+
+    /// Match which is navigated to
+    focused: Option<Match>,
+
+    /// Position the search started at, just past the bottom of the viewport
+    origin: Point<usize>,
+}
+
+impl ScrollbackSearch {
+    pub fn new(origin: Point<usize>) -> ScrollbackSearch {
+        ScrollbackSearch { query: String::new(), confirmed: false, focused: None, origin }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    pub fn focused(&self) -> Option<Match> {
+        self.focused
+    }
+
+    /// Stop editing the query and start navigating the matches
+    pub fn confirm(&mut self) {
+        self.confirmed = true;
+    }
+
+    /// Append a character to the query and focus the closest match above the origin
+    pub fn push(&mut self, grid: &Grid<Cell>, c: char) {
+        self.query.push(c);
+        self.refocus(grid);
+    }
+
+    /// Remove the last character of the query and focus the closest match above the origin
+    pub fn pop(&mut self, grid: &Grid<Cell>) {
+        self.query.pop();
+        self.refocus(grid);
+    }
+
+    fn refocus(&mut self, grid: &Grid<Cell>) {
+        self.focused = self.find_next(grid, self.origin, Direction::Up);
+    }
+
+    /// Move the focus to the next match, wrapping around at the end of the buffer
+    pub fn focus_next(&mut self, grid: &Grid<Cell>, direction: Direction) {
+        let from = self.focused.map_or(self.origin, |focused| focused.start);
+        if let Some(next) = self.find_next(grid, from, direction) {
+            self.focused = Some(next);
+        }
+    }
+
+    /// Closest match starting before or after `from` in reading order
+    fn find_next(
+        &self,
+        grid: &Grid<Cell>,
+        from: Point<usize>,
+        direction: Direction,
+    ) -> Option<Match> {
+        let query = Query::new(&self.query)?;
+
+        let start = logical_line_bounds(grid, min(from.line, grid.len() - 1));
+        let mut bounds = start;
+        let mut first = true;
+        loop {
+            let (top, bottom) = bounds;
+            let matches = query.find(grid, top, bottom);
+
+            // Only the first line has matches on the wrong side of `from`, once the search
+            // wrapped around to it again all of its matches are candidates
+            let found = match direction {
+                Direction::Up => {
+                    matches.into_iter().rev().find(|m| !first || is_before(m.start, from))
+                },
+                Direction::Down => {
+                    matches.into_iter().find(|m| !first || is_before(from, m.start))
+                },
+            };
+
+            if found.is_some() || (!first && bounds == start) {
+                return found;
+            }
+            first = false;
+
+            let line = match direction {
+                Direction::Up if top + 1 < grid.len() => top + 1,
+                Direction::Up => 0,
+                Direction::Down if bottom > 0 => bottom - 1,
+                Direction::Down => grid.len() - 1,
+            };
+            bounds = logical_line_bounds(grid, line);
+        }
+    }
+
+    /// Parts of all matches which are visible in the viewport
+    pub fn visible_matches(&self, grid: &Grid<Cell>) -> Vec<VisibleMatch> {
+        let query = match Query::new(&self.query) {
+            Some(query) => query,
+            None => return Vec::new(),
+        };
+
+        let viewport_bottom = grid.display_offset();
+        let viewport_top = viewport_bottom + grid.num_lines().0 - 1;
+        let (max_top, min_bottom) = viewport_context(grid);
+
+        let mut visible = Vec::new();
+        let mut line = min(viewport_top, grid.len() - 1);
+        loop {
+            let (top, bottom) = clamped_line_bounds(grid, line, max_top, min_bottom);
+            for m in query.find(grid, top, bottom) {
+                let focused = self.focused == Some(m);
+                visible.extend(visible_parts(grid, m, focused));
+            }
+
+            if bottom <= viewport_bottom {
+                break;
+            }
+            line = bottom - 1;
+        }
+
+        visible
+    }
+}
+
+/// Query prepared for comparing it against the characters of the grid
+struct Query {
+    chars: Vec<char>,
+    ignore_case: bool,
+}
+
+impl Query {
+    fn new(query: &str) -> Option<Query> {
+        if query.is_empty() {
+            return None;
+        }
+
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        let chars = query.chars().map(|c| fold_case(c, ignore_case)).collect();
+        Some(Query { chars, ignore_case })
+    }
+
+    /// Non-overlapping matches within the rows `top` to `bottom`, in reading order
+    fn find(&self, grid: &Grid<Cell>, top: usize, bottom: usize) -> Vec<Match> {
+        let mut text = Vec::new();
+        for line in (bottom..=top).rev() {
+            let row = &grid[line];
+            for col in 0..row.line_length().0 {
+                let cell = &row[Column(col)];
+                if !cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    text.push((fold_case(cell.c, self.ignore_case), Point::new(line, Column(col))));
+                }
+            }
+        }
+
+        let mut matches = Vec::new();
+        let mut index = 0;
+        while index + self.chars.len() <= text.len() {
+            let candidate = &text[index..index + self.chars.len()];
+            if candidate.iter().map(|&(c, _)| c).eq(self.chars.iter().cloned()) {
+                let mut end = candidate[candidate.len() - 1].1;
+                if grid[end.line][end.col].flags.contains(Flags::WIDE_CHAR) {
+                    end.col += Column(1);
+                }
+
+                matches.push(Match { start: candidate[0].1, end });
+                index += self.chars.len();
+            } else {
+                index += 1;
+            }
+        }
+
+        matches
+    }
+}
+
+fn fold_case(c: char, ignore_case: bool) -> char {
+    if ignore_case {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+/// Whether `a` comes before `b` when reading the buffer from top to bottom
+fn is_before(a: Point<usize>, b: Point<usize>) -> bool {
+    a.line > b.line || (a.line == b.line && a.col < b.col)
+}
+
+/// First and last row of the line which wraps through the buffer line `line`
+pub(super) fn logical_line_bounds(grid: &Grid<Cell>, line: usize) -> (usize, usize) {
+    clamped_line_bounds(grid, line, grid.len() - 1, 0)
+}
+
+/// First and last row of the line which wraps through `line`, within `max_top` and `min_bottom`
+pub(super) fn clamped_line_bounds(
+    grid: &Grid<Cell>,
+    line: usize,
+    max_top: usize,
+    min_bottom: usize,
+) -> (usize, usize) {
+    let wraps = |line: usize| {
+        let row = &grid[line];
+        row[Column(row.len() - 1)].flags.contains(Flags::WRAPLINE)
+    };
+
+    let mut top = line;
+    while top < max_top && wraps(top + 1) {
+        top += 1;
+    }
+
+    let mut bottom = line;
+    while bottom > min_bottom && wraps(bottom) {
+        bottom -= 1;
+    }
+
+    (top, bottom)
+}
+
+/// Highest and lowest buffer line searched for matches and hints in the viewport
+pub(super) fn viewport_context(grid: &Grid<Cell>) -> (usize, usize) {
+    let viewport_bottom = grid.display_offset();
+    let viewport_top = viewport_bottom + grid.num_lines().0 - 1;
+
+    let max_top = min(viewport_top + MAX_CONTEXT_ROWS, grid.len() - 1);
+    (max_top, viewport_bottom.saturating_sub(MAX_CONTEXT_ROWS))
+}
+
+/// Split a match into its parts on every visible line
+fn visible_parts(grid: &Grid<Cell>, m: Match, focused: bool) -> Vec<VisibleMatch> {
+    let last_col = grid.num_cols() - Column(1);
+
+    (m.end.line..=m.start.line)
+        .rev()
+        .filter_map(|line| match grid.buffer_line_to_visible(line) {
+            ViewportPosition::Visible(visible_line) => {
+                let start = if line == m.start.line { m.start.col } else { Column(0) };
+                let end = if line == m.end.line { m.end.col } else { last_col };
+                Some(VisibleMatch {
+                    line: visible_line,
+                    start,
+                    end: min(end, last_col),
+                    focused,
+                })
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+    use crate::index::{Column, Line, Point};
+    use crate::term::cell::{Cell, Flags};
+
+    use super::{Direction, Match, ScrollbackSearch};
+
+    fn grid(lines: &[&str]) -> Grid<Cell> {
+        let mut grid = Grid::new(Line(lines.len()), Column(10), 0, Cell::default());
+        for (line, text) in lines.iter().enumerate() {
+            for (col, c) in text.chars().enumerate() {
+                grid[Line(line)][Column(col)].c = c;
+            }
+        }
+        grid
+    }
+
+    fn origin(grid: &Grid<Cell>) -> Point<usize> {
+        Point::new(0, grid.num_cols())
+    }
+
+    #[test]
+    fn focuses_closest_match_above_origin() {
+        let grid = grid(&["foo bar", "bar", "baz bar"]);
+        let mut search = ScrollbackSearch::new(origin(&grid));
+        for c in "bar".chars() {
+            search.push(&grid, c);
+        }
+
+        let expected = Match { start: Point::new(0, Column(4)), end: Point::new(0, Column(6)) };
+        assert_eq!(search.focused(), Some(expected));
+    }
+
+    #[test]
+    fn navigates_and_wraps_around() {
+        let grid = grid(&["bar", "foo", "bar"]);
+        let mut search = ScrollbackSearch::new(origin(&grid));
+        for c in "bar".chars() {
+            search.push(&grid, c);
+        }
+        assert_eq!(search.focused().map(|m| m.start.line), Some(0));
+
+        search.focus_next(&grid, Direction::Up);
+        assert_eq!(search.focused().map(|m| m.start.line), Some(2));
+
+        search.focus_next(&grid, Direction::Up);
+        assert_eq!(search.focused().map(|m| m.start.line), Some(0));
+
+        search.focus_next(&grid, Direction::Down);
+        assert_eq!(search.focused().map(|m| m.start.line), Some(2));
+    }
+
+    #[test]
+    fn smart_case() {
+        let grid = grid(&["Foo", "foo"]);
+
+        let mut search = ScrollbackSearch::new(origin(&grid));
+        search.push(&grid, 'f');
+        assert_eq!(search.visible_matches(&grid).len(), 2);
+
+        search.pop(&grid);
+        search.push(&grid, 'F');
+        let matches = search.visible_matches(&grid);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, Line(0));
+    }
+
+    #[test]
+    fn matches_across_wrapped_rows() {
+        let mut grid = grid(&["    foobar", "baz"]);
+        grid[Line(0)][Column(9)].flags.insert(Flags::WRAPLINE);
+
+        let mut search = ScrollbackSearch::new(origin(&grid));
+        for c in "barbaz".chars() {
+            search.push(&grid, c);
+        }
+
+        let matches = search.visible_matches(&grid);
+        assert_eq!(matches.len(), 2);
+        let parts: Vec<_> = matches.iter().map(|m| (m.line, m.start, m.end)).collect();
+        assert_eq!(parts, vec![(Line(0), Column(7), Column(9)), (Line(1), Column(0), Column(2))]);
+        assert!(matches.iter().all(|m| m.focused));
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoder for sixel images, sent as the data of `DCS Ps ; Ps ; Ps q ... ST`
+//!
+//! Every data byte encodes a column of six pixels in the color selected last. The pixel aspect
+//! ratio requested through parameters or raster attributes is ignored, pixels are always square.
+use std::cmp::{max, min};
+use std::mem;
+
+use crate::term::color::Rgb;
+use crate::term::graphics::{MAX_COLOR_REGISTERS, MAX_SIXEL_GEOMETRY};
+
+/// Color registers set before an image defines its own colors, as on the VT340
+const DEFAULT_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Pixel which was never drawn
+const TRANSPARENT: Rgb = Rgb { r: 0, g: 0, b: 0, a: 0 };
+
+/// Decoded sixel image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixelImage {
+    pub width: usize,
+    pub height: usize,
+
+    /// Pixels from the top left to the bottom right, pixels which were never drawn are
+    /// fully transparent
+    pub pixels: Vec<Rgb>,
+
+    /// Pixels which were never drawn keep showing the cells below the image, instead of the
+    /// background color
+    pub transparent: bool,
+}
+
+/// Command whose numeric parameters are being collected
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    /// Sixel data, which takes no parameters
+    Data,
+
+    /// `! Pn`, repeat the next sixel
+    Repeat,
+
+    /// `# Pc ; Pu ; Px ; Py ; Pz`, select or define a color register
+    Color,
+
+    /// `" Pan ; Pad ; Ph ; Pv`, declare the image size
+    Raster,
+}
+
+#[derive(Debug)]
+pub struct SixelParser {
+    command: Command,
+    params: Vec<u32>,
+
+    palette: Vec<Rgb>,
+    color: Rgb,
+
+    /// Rows of pixels, each extended as far as sixels were drawn into it
+    rows: Vec<Vec<Rgb>>,
+    width: usize,
+    height: usize,
+
+    /// Position of the next sixel, `y` is the top row of the current band
+    x: usize,
+    y: usize,
+
+    transparent: bool,
+}
+
+impl SixelParser {
+    /// Start an image with the parameters of its DCS sequence
+    pub fn new(params: &[i64]) -> SixelParser {
+        let mut palette = vec![Rgb::new(0, 0, 0); MAX_COLOR_REGISTERS];
+        for (register, &(r, g, b)) in palette.iter_mut().zip(DEFAULT_PALETTE.iter()) {
+            *register = rgb_percent(u32::from(r), u32::from(g), u32::from(b));
+        }
+        let color = palette[0];
+
+        SixelParser {
+            command: Command::Data,
+            params: Vec::new(),
+            palette,
+            color,
+            rows: Vec::new(),
+            width: 0,
+            height: 0,
+            x: 0,
+            y: 0,
+            transparent: params.get(1) == Some(&1),
+        }
+    }
+
+    pub fn put(&mut self, byte: u8) {
+        if self.command != Command::Data {
+            match byte {
+                b'0'..=b'9' => {
+                    let digit = u32::from(byte - b'0');
+                    if self.params.is_empty() {
+                        self.params.push(0);
+                    }
+                    let param = self.params.last_mut().unwrap();
+                    *param = param.saturating_mul(10).saturating_add(digit);
+                    return;
+                },
+                b';' => {
+                    if self.params.is_empty() {
+                        self.params.push(0);
+                    }
+                    self.params.push(0);
+                    return;
+                },
+                // The byte following a repeat count is the repeated sixel
+                _ if self.finish_command(byte) => return,
+                _ => (),
+            }
+        }
+
+        match byte {
+            b'?'..=b'~' => self.draw(byte - b'?', 1),
+            b'!' => self.command = Command::Repeat,
+            b'#' => self.command = Command::Color,
+            b'"' => self.command = Command::Raster,
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y += 6;
+            },
+            _ => (),
+        }
+    }
+
+    /// Apply the collected parameters, returns whether `byte` was consumed
+    fn finish_command(&mut self, byte: u8) -> bool {
+        let command = self.command;
+        let params = mem::replace(&mut self.params, Vec::new());
+        self.command = Command::Data;
+
+        let param = |index: usize| params.get(index).cloned().unwrap_or(0);
+        match command {
+            Command::Repeat => {
+                if let b'?'..=b'~' = byte {
+                    self.draw(byte - b'?', max(param(0), 1) as usize);
+                    return true;
+                }
+            },
+            Command::Color => {
+                let register = param(0) as usize;
+                if register >= MAX_COLOR_REGISTERS {
+                    return false;
+                }
+
+                if params.len() >= 5 {
+                    let (x, y, z) = (param(2), param(3), param(4));
+                    match param(1) {
+                        1 => self.palette[register] = hls(x, y, z),
+                        2 => self.palette[register] = rgb_percent(x, y, z),
+                        _ => (),
+                    }
+                }
+                self.color = self.palette[register];
+            },
+            Command::Raster => {
+                // Only drawn pixels are stored, the declared size grows the image
+                self.width = min(max(self.width, param(2) as usize), MAX_SIXEL_GEOMETRY.0);
+                self.height = min(max(self.height, param(3) as usize), MAX_SIXEL_GEOMETRY.1);
+            },
+            Command::Data => (),
+        }
+
+        false
+    }
+
+    /// Draw the six bits of a sixel `count` times at the current position
+    fn draw(&mut self, sixel: u8, count: usize) {
+        let start = self.x;
+        let end = min(start.saturating_add(count), MAX_SIXEL_GEOMETRY.0);
+        self.x = start.saturating_add(count);
+        if start >= end || self.y >= MAX_SIXEL_GEOMETRY.1 {
+            return;
+        }
+
+        let band_end = min(self.y + 6, MAX_SIXEL_GEOMETRY.1);
+        self.width = max(self.width, end);
+        self.height = max(self.height, band_end);
+
+        for (bit, y) in (self.y..band_end).enumerate() {
+            if sixel & (1 << bit) == 0 {
+                continue;
+            }
+
+            if self.rows.len() <= y {
+                self.rows.resize(y + 1, Vec::new());
+            }
+            let row = &mut self.rows[y];
+            if row.len() < end {
+                row.resize(end, TRANSPARENT);
+            }
+            for pixel in &mut row[start..end] {
+                *pixel = self.color;
+            }
+        }
+    }
+
+    /// Finish the image at the end of its sequence
+    pub fn finish(mut self) -> Option<SixelImage> {
+        if self.command != Command::Data {
+            self.finish_command(0);
+        }
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let (width, height) = (self.width, self.height);
+        let mut pixels = vec![TRANSPARENT; width * height];
+        for (y, row) in self.rows.iter().enumerate().take(height) {
+            let len = min(row.len(), width);
+            pixels[y * width..y * width + len].copy_from_slice(&row[..len]);
+        }
+
+        Some(SixelImage { width, height, pixels, transparent: self.transparent })
+    }
+}
+
+/// Color with channels from 0 to 100 percent
+fn rgb_percent(r: u32, g: u32, b: u32) -> Rgb {
+    let channel = |value: u32| (min(value, 100) * 255 / 100) as u8;
+    Rgb::new(channel(r), channel(g), channel(b))
+}
+
+/// Color from hue, lightness and saturation, where a hue of 0 degrees is blue
+fn hls(hue: u32, lightness: u32, saturation: u32) -> Rgb {
+    let lightness = f64::from(min(lightness, 100)) / 100.;
+    let saturation = f64::from(min(saturation, 100)) / 100.;
+
+    // Sixel hues start at blue instead of red
+    let hue = f64::from((hue % 360 + 240) % 360) / 60.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let m = lightness - chroma / 2.;
+    let channel = |value: f64| ((value + m) * 255.).round() as u8;
+    Rgb::new(channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SixelImage, SixelParser, TRANSPARENT};
+    use crate::term::color::Rgb;
+
+    fn decode(params: &[i64], data: &str) -> Option<SixelImage> {
+        let mut parser = SixelParser::new(params);
+        for byte in data.bytes() {
+            parser.put(byte);
+        }
+        parser.finish()
+    }
+
+    #[test]
+    fn draws_bands_of_six_pixels() {
+        // A full column followed by two partial columns, then a full column in the next band
+        let image = decode(&[0, 1], "#1;2;100;0;0~ab-~").unwrap();
+        assert_eq!((image.width, image.height), (3, 12));
+        assert!(image.transparent);
+
+        let red = Rgb::new(0xff, 0, 0);
+        let pixel = |x: usize, y: usize| image.pixels[y * image.width + x];
+        assert!((0..6).all(|y| pixel(0, y) == red));
+        assert_eq!(pixel(1, 0), TRANSPARENT);
+        assert_eq!(pixel(1, 1), red);
+        assert_eq!(pixel(2, 0), red);
+        assert_eq!(pixel(2, 2), TRANSPARENT);
+        assert!((6..12).all(|y| pixel(0, y) == red));
+        assert_eq!(pixel(1, 6).a, 0);
+    }
+
+    #[test]
+    fn repeats_and_carriage_returns() {
+        let image = decode(&[], "#2!4~$#1@").unwrap();
+        assert_eq!((image.width, image.height), (4, 6));
+        assert!(!image.transparent);
+
+        let blue = image.pixels[0];
+        let red = image.pixels[image.width];
+        assert_eq!(blue, Rgb::new(0x33, 0x33, 0xcc));
+        assert_eq!(red, Rgb::new(0xcc, 0x21, 0x21));
+        assert!(image.pixels[1..4].iter().all(|&pixel| pixel == red));
+    }
+
+    #[test]
+    fn raster_attributes_and_hls() {
+        let image = decode(&[], "\"1;1;8;10#3;1;120;50;100~").unwrap();
+        assert_eq!((image.width, image.height), (8, 10));
+        assert_eq!(image.pixels[0], Rgb::new(0xff, 0, 0));
+        assert_eq!(image.pixels[1].a, 0);
+    }
+
+    #[test]
+    fn saturated_hue() {
+        let image = decode(&[], "#0;1;4294967295;50;100~").unwrap();
+        assert_eq!(image.pixels[0], Rgb::new(0, 0xff, 0x40));
+    }
+
+    #[test]
+    fn empty_images_are_dropped() {
+        assert_eq!(decode(&[], ""), None);
+        assert_eq!(decode(&[], "#1;2;0;0;0"), None);
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Watch rules triggering actions when completed output lines match a regex
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::config::WatchRule;
+use crate::util::{start_daemon, RateLimit};
+
+/// Maximum length of a line in bytes, further wrapped rows are not matched
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
+struct Rule {
+    regex: Regex,
+    config: WatchRule,
+    limit: RateLimit,
+}
+
+/// Actions of a triggered rule which have to be performed by the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    /// Index of the rule in the configuration
+    pub rule: usize,
+    pub line: String,
+    pub notify: bool,
+    pub bell: bool,
+    pub mark: bool,
+}
+
+#[derive(Default)]
+pub struct Watcher {
+    rules: Vec<Rule>,
+    config: Vec<WatchRule>,
+
+    /// Text of the current line, which can span multiple wrapped rows
+    line: String,
+}
+
+impl Watcher {
+    pub fn new(config: &[WatchRule]) -> Watcher {
+        let rules = config
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.regex) {
+                Ok(regex) => Some(Rule {
+                    regex,
+                    config: rule.clone(),
+                    limit: RateLimit::new(rule.max_per_minute(), Duration::from_secs(60)),
+                }),
+                Err(err) => {
+                    error!("Invalid watch regex {:?}: {}", rule.regex, err);
+                    None
+                },
+            })
+            .collect();
+
+        Watcher { rules, config: config.to_vec(), line: String::new() }
+    }
+
+    pub fn update_config(&mut self, config: &[WatchRule]) {
+        if self.config[..] != *config {
+            *self = Watcher::new(config);
+        }
+    }
+
+    #[inline]
+    pub fn has_rules(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Add the text of a row to the current line
+    ///
+    /// Once a row which was not wrapped is added, the line is matched against all rules.
+    /// Commands are started right away, all other actions are left to the caller.
+    pub fn push(&mut self, text: &str, wrapped: bool) -> Vec<Trigger> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+
+        if self.line.len() + text.len() <= MAX_LINE_LENGTH {
+            self.line.push_str(text);
+        }
+        if wrapped {
+            return Vec::new();
+        }
+
+        let line = self.line.trim_end().to_owned();
+        self.line.clear();
+
+        let mut triggers = Vec::new();
+        for (index, rule) in self.rules.iter_mut().enumerate() {
+            if !rule.regex.is_match(&line) || !rule.limit.allow() {
+                continue;
+            }
+
+            if let Some(ref command) = rule.config.command {
+                let args = command.args.iter().map(String::as_str).chain(Some(line.as_str()));
+                if let Err(err) = start_daemon(&command.program, args) {
+                    warn!("Unable to run watch command {}: {}", command.program, err);
+                }
+            }
+
+            triggers.push(Trigger {
+                rule: index,
+                line: line.clone(),
+                notify: rule.config.notify,
+                bell: rule.config.bell,
+                mark: rule.config.mark,
+            });
+        }
+
+        triggers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Trigger, Watcher, MAX_LINE_LENGTH};
+    use crate::config::WatchRule;
+
+    #[test]
+    fn trigger_on_wrapped_line() {
+        let mut rule = WatchRule::default();
+        rule.regex = String::from("BUILD FAILED");
+        rule.bell = true;
+        let mut watcher = Watcher::new(&[rule]);
+
+        assert!(watcher.push("BUILD FA", true).is_empty());
+        assert_eq!(watcher.push("ILED  ", false), vec![Trigger {
+            rule: 0,
+            line: String::from("BUILD FAILED"),
+            notify: false,
+            bell: true,
+            mark: false,
+        }]);
+        assert!(watcher.push("BUILD PASSED", false).is_empty());
+    }
+
+    #[test]
+    fn long_lines_are_capped() {
+        let mut rule = WatchRule::default();
+        rule.regex = String::from("^x+$");
+        let mut watcher = Watcher::new(&[rule]);
+
+        let row = "x".repeat(1000);
+        for _ in 0..100 {
+            assert!(watcher.push(&row, true).is_empty());
+        }
+        assert!(watcher.line.len() <= MAX_LINE_LENGTH);
+
+        let triggers = watcher.push(&row, false);
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[0].line.len(), MAX_LINE_LENGTH / 1000 * 1000);
+        assert!(watcher.line.is_empty());
+    }
+
+    #[test]
+    fn rate_limit_triggers() {
+        let mut rule = WatchRule::default();
+        rule.regex = String::from("x");
+        let mut watcher = Watcher::new(&[rule]);
+
+        let triggered = (0..20).filter(|_| !watcher.push("x", false).is_empty()).count();
+        assert_eq!(triggered, 10);
+    }
+}
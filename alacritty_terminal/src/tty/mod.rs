@@ -25,11 +25,18 @@ mod unix;
 #[cfg(not(windows))]
 pub use self::unix::*;
 
+#[cfg(all(not(windows), feature = "utempter"))]
+mod utmp;
+
 #[cfg(windows)]
 mod windows;
 #[cfg(windows)]
 pub use self::windows::*;
 
+/// Variables only meant for Alacritty itself, which are not passed on to the shell
+const INTERNAL_ENV_VARS: &[&str] =
+    &["ALACRITTY_LOG", "RUST_LOG", "WINIT_HIDPI_FACTOR", "WINIT_UNIX_BACKEND"];
+
 /// This trait defines the behaviour needed to read and/or write to a stream.
 /// It defines an abstraction over mio's interface in order to allow either one
 /// read/write object or a separate read and write object.
@@ -97,3 +104,54 @@ pub fn setup_env(config: &Config) {
         env::set_var(key, value);
     }
 }
+
+/// Names of the variables removed from the environment of the shell
+///
+/// Variables only meant for Alacritty are scrubbed unless `hold_env` is set or they are set
+/// explicitly in the `env` config. Variables in `env_remove` are always removed.
+pub fn removed_env_vars(config: &Config) -> Vec<&str> {
+    let internal = INTERNAL_ENV_VARS
+        .iter()
+        .cloned()
+        .filter(|name| !config.hold_env && !config.env.contains_key(*name));
+    let mut names: Vec<&str> =
+        internal.chain(config.env_remove.iter().map(String::as_str)).collect();
+
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+
+    use super::removed_env_vars;
+
+    #[test]
+    fn scrubs_internal_vars_by_default() {
+        let config = Config::default();
+
+        assert_eq!(
+            removed_env_vars(&config),
+            vec!["ALACRITTY_LOG", "RUST_LOG", "WINIT_HIDPI_FACTOR", "WINIT_UNIX_BACKEND"]
+        );
+    }
+
+    #[test]
+    fn keeps_internal_vars_set_in_config() {
+        let mut config = Config::default();
+        config.env.insert("RUST_LOG".into(), "debug".into());
+
+        assert!(!removed_env_vars(&config).contains(&"RUST_LOG"));
+    }
+
+    #[test]
+    fn hold_env_only_removes_configured_vars() {
+        let mut config = Config::default();
+        config.hold_env = true;
+        config.env_remove = vec!["SSH_AUTH_SOCK".into(), "ALACRITTY_LOG".into()];
+
+        assert_eq!(removed_env_vars(&config), vec!["ALACRITTY_LOG", "SSH_AUTH_SOCK"]);
+    }
+}
@@ -18,7 +18,10 @@
 use crate::config::{Config, Shell};
 use crate::display::OnResize;
 use crate::term::SizeInfo;
-use crate::tty::{ChildEvent, EventedPty, EventedReadWrite};
+#[cfg(feature = "utempter")]
+use crate::tty::utmp;
+use crate::tty::{removed_env_vars, ChildEvent, EventedPty, EventedReadWrite};
+use crate::util;
 use mio;
 
 use libc::{self, c_int, pid_t, winsize, TIOCSCTTY};
@@ -27,15 +30,21 @@ use signal_hook::{self as sighook, iterator::Signals};
 
 use mio::unix::EventedFd;
 use std::ffi::CStr;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
 use std::os::unix::{
     io::{AsRawFd, FromRawFd, RawFd},
     process::CommandExt,
 };
+use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::ptr;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval of checking whether the shell exited after SIGHUP
+const HANG_UP_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// Process ID of child process
 ///
@@ -46,6 +55,16 @@ pub fn child_pid() -> pid_t {
     PID.load(Ordering::Relaxed) as pid_t
 }
 
+/// Current working directory of the shell
+pub fn child_working_directory() -> Option<PathBuf> {
+    #[cfg(not(target_os = "freebsd"))]
+    let proc_prefix = "";
+    #[cfg(target_os = "freebsd")]
+    let proc_prefix = "/compat/linux";
+
+    fs::read_link(format!("{}/proc/{}/cwd", proc_prefix, child_pid())).ok()
+}
+
 /// Get the current value of errno
 fn errno() -> c_int {
     ::errno::errno().0
@@ -135,6 +154,7 @@ pub struct Pty {
     token: mio::Token,
     signals: Signals,
     signals_token: mio::Token,
+    kill_timeout: Option<Duration>,
 }
 
 impl Pty {
@@ -177,11 +197,18 @@ pub fn new<T: ToWinsize>(config: &Config, size: &T, window_id: Option<usize>) ->
     }
 
     // Setup child stdin/stdout/stderr as slave fd of pty
-    // Ownership of fd is transferred to the Stdio structs and will be closed by them at the end of
-    // this scope. (It is not an issue that the fd is closed three times since File::drop ignores
-    // error on libc::close.)
-    builder.stdin(unsafe { Stdio::from_raw_fd(slave) });
-    builder.stderr(unsafe { Stdio::from_raw_fd(slave) });
+    // Ownership of the fd is transferred to the Stdio structs and it will be closed by them at the
+    // end of this scope. Every Stdio owns a separate fd, since closing one twice is a bug. The
+    // copies are closed in the child on exec, the slave itself is closed before that.
+    let slave_copy = || {
+        let fd = unsafe { libc::fcntl(slave, libc::F_DUPFD_CLOEXEC, 0) };
+        if fd == -1 {
+            die!("Failed to duplicate PTY slave: {}", errno());
+        }
+        unsafe { Stdio::from_raw_fd(fd) }
+    };
+    builder.stdin(slave_copy());
+    builder.stderr(slave_copy());
     builder.stdout(unsafe { Stdio::from_raw_fd(slave) });
 
     // Setup shell environment
@@ -194,6 +221,10 @@ pub fn new<T: ToWinsize>(config: &Config, size: &T, window_id: Option<usize>) ->
         builder.env("WINDOWID", format!("{}", window_id));
     }
 
+    for name in removed_env_vars(config) {
+        builder.env_remove(name);
+    }
+
     // TODO: Rust 1.34.0
     #[allow(deprecated)]
     builder.before_exec(move || {
@@ -243,12 +274,16 @@ pub fn new<T: ToWinsize>(config: &Config, size: &T, window_id: Option<usize>) ->
                 set_nonblocking(master);
             }
 
+            #[cfg(feature = "utempter")]
+            utmp::add_record(master);
+
             let pty = Pty {
                 child,
                 fd: unsafe { File::from_raw_fd(master) },
                 token: mio::Token::from(0),
                 signals,
                 signals_token: mio::Token::from(0),
+                kill_timeout: config.shutdown.kill_timeout(),
             };
             pty.resize(size);
             pty
@@ -259,6 +294,78 @@ pub fn new<T: ToWinsize>(config: &Config, size: &T, window_id: Option<usize>) ->
     }
 }
 
+impl Drop for Pty {
+    fn drop(&mut self) {
+        #[cfg(feature = "utempter")]
+        utmp::remove_record(self.fd.as_raw_fd());
+
+        hang_up(self.fd.as_raw_fd(), &mut self.child, self.kill_timeout);
+    }
+}
+
+/// Hang up the process groups of the shell and the foreground job, then reap the shell
+///
+/// With a timeout, the groups are killed if the shell or any other process in them is still
+/// running once it has passed. Without one, the shell is reaped in the background as soon as it
+/// exits, so closing the window doesn't wait for shells which ignore SIGHUP.
+fn hang_up(fd: RawFd, child: &mut Child, timeout: Option<Duration>) {
+    // The shell is the leader of its own session and process group, but a shell with job
+    // control runs the foreground job in a separate group, which the kernel only hangs up once
+    // the shell exits
+    let mut groups = vec![child.id() as pid_t];
+    let foreground = unsafe { libc::tcgetpgrp(fd) };
+    if foreground > 0 && foreground != groups[0] {
+        groups.push(foreground);
+    }
+
+    for group in &groups {
+        unsafe {
+            libc::killpg(*group, libc::SIGHUP);
+        }
+    }
+
+    let deadline = match timeout {
+        Some(timeout) => Instant::now() + timeout,
+        None => {
+            if let Ok(None) = child.try_wait() {
+                let pid = child.id() as pid_t;
+                util::thread::spawn_named("shell reaper", move || unsafe {
+                    libc::waitpid(pid, ptr::null_mut(), 0);
+                });
+            }
+            return;
+        },
+    };
+
+    loop {
+        // The shell is reaped as soon as it exits, background jobs may still outlive it
+        let exited = match child.try_wait() {
+            Ok(status) => status.is_some(),
+            Err(err) => {
+                error!("Error checking shell termination: {}", err);
+                true
+            },
+        };
+        let group_alive = groups.iter().any(|group| unsafe { libc::killpg(*group, 0) } == 0);
+        if exited && !group_alive {
+            return;
+        }
+
+        if Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(HANG_UP_POLL_INTERVAL);
+    }
+
+    info!("Process group of the shell did not exit after SIGHUP, killing it");
+    for group in &groups {
+        unsafe {
+            libc::killpg(*group, libc::SIGKILL);
+        }
+    }
+    let _ = child.wait();
+}
+
 impl EventedReadWrite for Pty {
     type Reader = File;
     type Writer = File;
@@ -362,7 +469,7 @@ pub trait ToWinsize {
     fn to_winsize(&self) -> winsize;
 }
 
-impl<'a> ToWinsize for &'a SizeInfo {
+impl ToWinsize for SizeInfo {
     fn to_winsize(&self) -> winsize {
         winsize {
             ws_row: self.lines().0 as libc::c_ushort,
@@ -397,3 +504,40 @@ fn test_get_pw_entry() {
     let mut buf: [i8; 1024] = [0; 1024];
     let _pw = get_pw_entry(&mut buf);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::config::{Config, Shell};
+    use crate::term::SizeInfo;
+
+    use super::HANG_UP_POLL_INTERVAL;
+
+    #[test]
+    fn hang_up_reaps_shell() {
+        let mut config = Config::default();
+        config.shell = Some(Shell::new_with_args("sleep", vec![String::from("10")]));
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let pty = super::new(&config, &size, None);
+        let shell = pty.child.id() as libc::pid_t;
+
+        drop(pty);
+
+        // The process only disappears once it's reaped, an unreaped one stays a zombie
+        let start = Instant::now();
+        while unsafe { libc::kill(shell, 0) } == 0 {
+            assert!(start.elapsed() < Duration::from_secs(5), "shell wasn't reaped");
+            thread::sleep(HANG_UP_POLL_INTERVAL);
+        }
+    }
+}
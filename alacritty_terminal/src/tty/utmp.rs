@@ -0,0 +1,47 @@
+// Copyright 2019 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Session registration in utmp and wtmp
+//!
+//! The records are written by the setgid `utempter` helper of libutempter, so Alacritty itself
+//! needs no access to the login databases.
+use std::env;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::os::unix::io::RawFd;
+use std::ptr;
+
+#[link(name = "utempter")]
+extern "C" {
+    fn utempter_add_record(master_fd: c_int, hostname: *const c_char) -> c_int;
+    fn utempter_remove_record(master_fd: c_int) -> c_int;
+}
+
+/// Register the session of the PTY, so `who` and `w` list it
+pub fn add_record(master: RawFd) {
+    // Like xterm, the X display is recorded as the host of the session
+    let host = env::var("DISPLAY").ok().and_then(|display| CString::new(display).ok());
+    let host = host.as_ref().map_or(ptr::null(), |host| host.as_ptr());
+
+    if unsafe { utempter_add_record(master, host) } == 0 {
+        warn!("Unable to register session in utmp");
+    }
+}
+
+/// Mark the session of the PTY as ended
+pub fn remove_record(master: RawFd) {
+    if unsafe { utempter_remove_record(master) } == 0 {
+        debug!("Unable to remove session from utmp");
+    }
+}
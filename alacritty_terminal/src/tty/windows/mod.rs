@@ -12,8 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::env;
 use std::io::{self, Read, Write};
 use std::os::raw::c_void;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use mio::{self, Evented, Poll, PollOpt, Ready, Token};
@@ -27,7 +29,7 @@ use winapi::um::winbase::WAIT_OBJECT_0;
 use crate::config::Config;
 use crate::display::OnResize;
 use crate::term::SizeInfo;
-use crate::tty::{EventedPty, EventedReadWrite};
+use crate::tty::{removed_env_vars, EventedPty, EventedReadWrite};
 
 mod conpty;
 mod winpty;
@@ -59,6 +61,11 @@ pub fn is_conpty() -> bool {
     IS_CONPTY.load(Ordering::Relaxed)
 }
 
+/// Current working directory of the shell, which is not available on Windows
+pub fn child_working_directory() -> Option<PathBuf> {
+    None
+}
+
 #[derive(Clone)]
 pub enum PtyHandle<'a> {
     Winpty(winpty::WinptyHandle<'a>),
@@ -84,6 +91,11 @@ impl<'a> Pty<'a> {
 }
 
 pub fn new<'a>(config: &Config, size: &SizeInfo, window_id: Option<usize>) -> Pty<'a> {
+    // The shell inherits the environment of Alacritty
+    for name in removed_env_vars(config) {
+        env::remove_var(name);
+    }
+
     if let Some(pty) = conpty::new(config, size, window_id) {
         info!("Using Conpty agent");
         IS_CONPTY.store(true, Ordering::Relaxed);
@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::env;
 use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{cmp, io};
 
 #[cfg(not(windows))]
@@ -44,6 +48,76 @@ pub fn limit<T: Ord>(value: T, min: T, max: T) -> T {
     cmp::min(cmp::max(value, min), max)
 }
 
+/// Limit for how often an event may happen
+///
+/// At most `max` events are allowed within every `window`.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    max: usize,
+    window: Duration,
+    window_start: Option<Instant>,
+    count: usize,
+}
+
+impl RateLimit {
+    pub fn new(max: usize, window: Duration) -> RateLimit {
+        RateLimit { max, window, window_start: None, count: 0 }
+    }
+
+    /// Check if another event is allowed now, counting it if it is
+    #[inline]
+    pub fn allow(&mut self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    /// Check if another event is allowed at `now`, counting it if it is
+    pub fn allow_at(&mut self, now: Instant) -> bool {
+        match self.window_start {
+            Some(start) if now.duration_since(start) < self.window => (),
+            _ => {
+                self.window_start = Some(now);
+                self.count = 0;
+            },
+        }
+
+        if self.count >= self.max {
+            return false;
+        }
+
+        self.count += 1;
+        true
+    }
+}
+
+/// Format the time of day in UTC as `HH:MM:SS`
+pub fn format_utc_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    let secs_of_day = secs % 86_400;
+    format!("{:02}:{:02}:{:02}", secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60)
+}
+
+/// Home directory of the current user
+fn home_dir() -> Option<PathBuf> {
+    #[cfg(not(windows))]
+    let home = env::var_os("HOME");
+    #[cfg(windows)]
+    let home = env::var_os("USERPROFILE");
+
+    home.filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+/// Expand a leading `~` to the home directory of the current user
+///
+/// Paths starting with `~user` are left untouched.
+pub fn expand_tilde<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) if rest.as_os_str().is_empty() => home,
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
 /// Utilities for writing to the
 pub mod fmt {
     use std::fmt;
@@ -132,9 +206,40 @@ where
         .map(|_| ())
 }
 
+/// Run a program with `input` written to its standard input
+///
+/// The input is written and the program is waited for on a separate thread, so a program
+/// which doesn't read its input can't block the caller.
+pub fn start_with_input<I, S>(program: &str, args: I, input: Vec<u8>) -> io::Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new(program);
+    command.args(args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null());
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
+
+    let mut child = command.spawn()?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let program = program.to_owned();
+    thread::spawn_named("input writer", move || {
+        if let Err(err) = stdin.write_all(&input) {
+            warn!("Unable to write to {}: {}", program, err);
+        }
+        drop(stdin);
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::limit;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant, UNIX_EPOCH};
+
+    use super::{expand_tilde, format_utc_time, home_dir, limit, RateLimit};
 
     #[test]
     fn limit_works() {
@@ -142,4 +247,32 @@ mod tests {
         assert_eq!(10, limit(5, 10, 100));
         assert_eq!(100, limit(1000, 10, 100));
     }
+
+    #[test]
+    fn rate_limit_window() {
+        let mut rate_limit = RateLimit::new(2, Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert!(rate_limit.allow_at(now));
+        assert!(rate_limit.allow_at(now + Duration::from_millis(500)));
+        assert!(!rate_limit.allow_at(now + Duration::from_millis(999)));
+        assert!(rate_limit.allow_at(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn utc_time_of_day() {
+        assert_eq!(format_utc_time(UNIX_EPOCH), "00:00:00");
+        assert_eq!(format_utc_time(UNIX_EPOCH + Duration::from_secs(86_400 + 3723)), "01:02:03");
+    }
+
+    #[test]
+    fn tilde_expansion() {
+        assert_eq!(expand_tilde("/tmp/~"), PathBuf::from("/tmp/~"));
+        assert_eq!(expand_tilde("~user/dir"), PathBuf::from("~user/dir"));
+
+        if let Some(home) = home_dir() {
+            assert_eq!(expand_tilde("~"), home);
+            assert_eq!(expand_tilde("~/dir"), home.join("dir"));
+        }
+    }
 }
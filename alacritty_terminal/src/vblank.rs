@@ -0,0 +1,116 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Vblank timing of X11 displays through GLX_OML_sync_control
+//!
+//! The X server reports the time of the last vblank of the window's monitor and its refresh
+//! rate. Contexts created through EGL, or drivers without the extension, have no vblank timing.
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_int, c_ulong};
+use std::time::{Duration, Instant};
+
+use x11_dl::glx::Glx;
+use x11_dl::xlib::{Bool, Display};
+
+use crate::pacing::Vblank;
+
+type GetSyncValues =
+    unsafe extern "C" fn(*mut Display, c_ulong, *mut i64, *mut i64, *mut i64) -> Bool;
+type GetMscRate = unsafe extern "C" fn(*mut Display, c_ulong, *mut i32, *mut i32) -> Bool;
+
+/// Vblanks further in the past are from a stopped display or a clock other than the monotonic
+const MAX_VBLANK_AGE: Duration = Duration::from_secs(1);
+
+pub struct SyncControl {
+    display: *mut Display,
+    drawable: c_ulong,
+    get_sync_values: GetSyncValues,
+    get_msc_rate: GetMscRate,
+
+    /// Keeps the library loaded while its functions are used
+    _glx: Glx,
+}
+
+impl SyncControl {
+    /// Load the extension for the current GLX context
+    pub fn new(screen: c_int) -> Option<SyncControl> {
+        let glx = Glx::open().ok()?;
+
+        unsafe {
+            let display = (glx.glXGetCurrentDisplay)();
+            let drawable = (glx.glXGetCurrentDrawable)();
+            if display.is_null() || drawable == 0 {
+                return None;
+            }
+
+            let extensions = (glx.glXQueryExtensionsString)(display, screen);
+            if extensions.is_null() {
+                return None;
+            }
+            let extensions = CStr::from_ptr(extensions).to_string_lossy();
+            if !extensions.split_whitespace().any(|ext| ext == "GLX_OML_sync_control") {
+                return None;
+            }
+
+            let get_sync_values = (glx.glXGetProcAddressARB)(b"glXGetSyncValuesOML\0".as_ptr())?;
+            let get_msc_rate = (glx.glXGetProcAddressARB)(b"glXGetMscRateOML\0".as_ptr())?;
+
+            Some(SyncControl {
+                display,
+                drawable,
+                get_sync_values: mem::transmute::<_, GetSyncValues>(get_sync_values),
+                get_msc_rate: mem::transmute::<_, GetMscRate>(get_msc_rate),
+                _glx: glx,
+            })
+        }
+    }
+
+    /// Time of the last vblank and the refresh period
+    pub fn vblank(&self) -> Option<Vblank> {
+        let (mut ust, mut msc, mut sbc) = (0, 0, 0);
+        let (mut numerator, mut denominator) = (0, 0);
+        unsafe {
+            let display = self.display;
+            if (self.get_sync_values)(display, self.drawable, &mut ust, &mut msc, &mut sbc) == 0
+                || (self.get_msc_rate)(display, self.drawable, &mut numerator, &mut denominator)
+                    == 0
+            {
+                return None;
+            }
+        }
+
+        if numerator <= 0 || denominator <= 0 {
+            return None;
+        }
+        let period = Duration::from_secs(1) * denominator as u32 / numerator as u32;
+
+        // The vblank time is taken from the monotonic clock, in microseconds
+        let now = Instant::now();
+        let age = monotonic_micros() - ust;
+        if age < 0 || age as u64 > MAX_VBLANK_AGE.as_secs() * 1_000_000 {
+            return None;
+        }
+
+        Some(Vblank { time: now - Duration::from_micros(age as u64), period })
+    }
+}
+
+fn monotonic_micros() -> i64 {
+    let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time);
+    }
+    time.tv_sec as i64 * 1_000_000 + time.tv_nsec as i64 / 1000
+}
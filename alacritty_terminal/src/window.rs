@@ -22,6 +22,8 @@ use glutin::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
 use glutin::os::macos::WindowExt;
 #[cfg(not(any(target_os = "macos", windows)))]
 use glutin::os::unix::{EventsLoopExt, WindowExt};
+#[cfg(windows)]
+use glutin::os::windows::WindowExt;
 #[cfg(not(target_os = "macos"))]
 use glutin::Icon;
 use glutin::{
@@ -31,7 +33,16 @@ use glutin::{
 #[cfg(not(target_os = "macos"))]
 use image::ImageFormat;
 
+use crate::ansi::Progress;
 use crate::config::{Config, Decorations, StartupMode, WindowConfig};
+use crate::pacing::Vblank;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd"
+))]
+use crate::vblank::SyncControl;
 
 // It's required to be in this directory due to the `windows.rc` file
 #[cfg(not(target_os = "macos"))]
@@ -61,6 +72,19 @@ pub struct Window {
     windowed_context: glutin::WindowedContext<PossiblyCurrent>,
     mouse_visible: bool,
 
+    /// Vblank timing of the display, unless the context doesn't support it
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "openbsd"
+    ))]
+    sync_control: Option<SyncControl>,
+
+    /// Interface of the taskbar button, created by the first progress report
+    #[cfg(windows)]
+    taskbar: Option<Taskbar>,
+
     /// Whether or not the window is the focused window.
     pub is_focused: bool,
 }
@@ -197,8 +221,29 @@ impl Window {
         // Set OpenGL symbol loader. This call MUST be after window.make_current on windows.
         gl::load_with(|symbol| windowed_context.get_proc_address(symbol) as *const _);
 
-        let window =
-            Window { event_loop, windowed_context, mouse_visible: true, is_focused: false };
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "openbsd"
+        ))]
+        let sync_control = window.get_xlib_screen_id().and_then(SyncControl::new);
+
+        let window = Window {
+            event_loop,
+            windowed_context,
+            mouse_visible: true,
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "freebsd",
+                target_os = "dragonfly",
+                target_os = "openbsd"
+            ))]
+            sync_control,
+            #[cfg(windows)]
+            taskbar: None,
+            is_focused: false,
+        };
 
         window.run_os_extensions();
 
@@ -226,11 +271,38 @@ impl Window {
         self.window().get_hidpi_factor()
     }
 
+    /// Check if the window's framebuffer has an alpha channel for transparency
+    #[inline]
+    pub fn has_alpha(&self) -> bool {
+        self.windowed_context.get_pixel_format().alpha_bits > 0
+    }
+
     #[inline]
     pub fn create_window_proxy(&self) -> Proxy {
         Proxy { inner: self.event_loop.create_proxy() }
     }
 
+    /// Time of the display's last vblank and its refresh period, when they are known
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "openbsd"
+    ))]
+    pub fn vblank(&self) -> Option<Vblank> {
+        self.sync_control.as_ref().and_then(SyncControl::vblank)
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "openbsd"
+    )))]
+    pub fn vblank(&self) -> Option<Vblank> {
+        None
+    }
+
     #[inline]
     pub fn swap_buffers(&self) -> Result<()> {
         self.windowed_context.swap_buffers().map_err(From::from)
@@ -265,6 +337,35 @@ impl Window {
         self.window().set_title(title);
     }
 
+    /// Constrain the window size to whole cells
+    ///
+    /// This needs to be updated whenever the cell size changes.
+    pub fn set_size_hints(
+        &self,
+        config: &WindowConfig,
+        cell_width: f32,
+        cell_height: f32,
+        dpr: f64,
+    ) {
+        let base = PhysicalSize::new(
+            f64::from(config.padding.x) * dpr * 2.,
+            f64::from(config.padding.y) * dpr * 2.,
+        );
+        let increments = PhysicalSize::new(f64::from(cell_width), f64::from(cell_height));
+
+        let columns = f64::from(config.min_dimensions.columns_u32().max(1));
+        let lines = f64::from(config.min_dimensions.lines_u32().max(1));
+        let min_size = PhysicalSize::new(
+            base.width + increments.width * columns,
+            base.height + increments.height * lines,
+        );
+        self.window().set_min_dimensions(Some(min_size.to_logical(dpr)));
+
+        if config.resize_increments() {
+            self.set_resize_increments(base, increments);
+        }
+    }
+
     #[inline]
     pub fn set_mouse_cursor(&self, cursor: MouseCursor) {
         self.window().set_cursor(cursor);
@@ -278,83 +379,63 @@ impl Window {
         }
     }
 
-    #[cfg(not(any(target_os = "macos", windows)))]
+    /// Window builder with all options supported on the current platform
+    #[cfg_attr(any(target_os = "macos", windows), allow(unused_variables))]
     pub fn get_platform_window(
         title: &str,
         class: &str,
         window_config: &WindowConfig,
     ) -> WindowBuilder {
-        use glutin::os::unix::WindowBuilderExt;
-
-        let decorations = match window_config.decorations {
-            Decorations::None => false,
-            _ => true,
-        };
-
-        let icon = Icon::from_bytes_with_format(WINDOW_ICON, ImageFormat::ICO);
-
-        WindowBuilder::new()
+        // The window is shown once it is set up, except on Windows
+        let mut window = WindowBuilder::new()
             .with_title(title)
-            .with_visibility(false)
+            .with_visibility(cfg!(windows))
             .with_transparency(true)
-            .with_decorations(decorations)
-            .with_maximized(window_config.startup_mode() == StartupMode::Maximized)
-            .with_window_icon(icon.ok())
-            // X11
-            .with_class(class.into(), DEFAULT_NAME.into())
-            // Wayland
-            .with_app_id(class.into())
-    }
+            .with_maximized(window_config.startup_mode() == StartupMode::Maximized);
 
-    #[cfg(windows)]
-    pub fn get_platform_window(
-        title: &str,
-        _class: &str,
-        window_config: &WindowConfig,
-    ) -> WindowBuilder {
-        let decorations = match window_config.decorations {
-            Decorations::None => false,
-            _ => true,
-        };
+        #[cfg(not(target_os = "macos"))]
+        {
+            let decorations = match window_config.decorations {
+                Decorations::None => false,
+                _ => true,
+            };
 
-        let icon = Icon::from_bytes_with_format(WINDOW_ICON, ImageFormat::ICO);
+            let icon = Icon::from_bytes_with_format(WINDOW_ICON, ImageFormat::ICO);
 
-        WindowBuilder::new()
-            .with_title(title)
-            .with_visibility(cfg!(windows))
-            .with_decorations(decorations)
-            .with_transparency(true)
-            .with_maximized(window_config.startup_mode() == StartupMode::Maximized)
-            .with_window_icon(icon.ok())
-    }
+            window = window.with_decorations(decorations).with_window_icon(icon.ok());
+        }
 
-    #[cfg(target_os = "macos")]
-    pub fn get_platform_window(
-        title: &str,
-        _class: &str,
-        window_config: &WindowConfig,
-    ) -> WindowBuilder {
-        use glutin::os::macos::WindowBuilderExt;
+        #[cfg(not(any(target_os = "macos", windows)))]
+        {
+            use glutin::os::unix::WindowBuilderExt;
 
-        let window = WindowBuilder::new()
-            .with_title(title)
-            .with_visibility(false)
-            .with_transparency(true)
-            .with_maximized(window_config.startup_mode() == StartupMode::Maximized);
+            window = window
+                // X11
+                .with_class(class.into(), DEFAULT_NAME.into())
+                // Wayland
+                .with_app_id(class.into());
+        }
 
-        match window_config.decorations {
-            Decorations::Full => window,
-            Decorations::Transparent => window
-                .with_title_hidden(true)
-                .with_titlebar_transparent(true)
-                .with_fullsize_content_view(true),
-            Decorations::Buttonless => window
-                .with_title_hidden(true)
-                .with_titlebar_buttons_hidden(true)
-                .with_titlebar_transparent(true)
-                .with_fullsize_content_view(true),
-            Decorations::None => window.with_titlebar_hidden(true),
+        #[cfg(target_os = "macos")]
+        {
+            use glutin::os::macos::WindowBuilderExt;
+
+            window = match window_config.decorations {
+                Decorations::Full => window,
+                Decorations::Transparent => window
+                    .with_title_hidden(true)
+                    .with_titlebar_transparent(true)
+                    .with_fullsize_content_view(true),
+                Decorations::Buttonless => window
+                    .with_title_hidden(true)
+                    .with_titlebar_buttons_hidden(true)
+                    .with_titlebar_transparent(true)
+                    .with_fullsize_content_view(true),
+                Decorations::None => window.with_titlebar_hidden(true),
+            };
         }
+
+        window
     }
 
     #[cfg(any(
@@ -375,6 +456,85 @@ impl Window {
     #[cfg(windows)]
     pub fn set_urgent(&self, _is_urgent: bool) {}
 
+    /// Show the progress of the running command on the launcher entry
+    ///
+    /// This uses the Unity launcher API, which is also implemented by KDE Plasma and docks like
+    /// Dash to Dock and Plank.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "openbsd"
+    ))]
+    pub fn set_progress(&self, progress: Option<Progress>) {
+        let (value, visible, urgent) = match progress {
+            Some(Progress::Normal(value)) | Some(Progress::Paused(value)) => (value, true, false),
+            Some(Progress::Error(value)) => (value, true, true),
+            Some(Progress::Indeterminate) => (0, true, false),
+            None => (0, false, false),
+        };
+
+        let path = format!("/com/canonical/unity/launcherentry/{}", ::std::process::id());
+        let properties = format!(
+            "{{'progress': <{:.2}>, 'progress-visible': <{}>, 'urgent': <{}>}}",
+            f64::from(value) / 100.,
+            visible,
+            urgent
+        );
+
+        let args = [
+            "emit",
+            "--session",
+            "--object-path",
+            &path,
+            "--signal",
+            "com.canonical.Unity.LauncherEntry.Update",
+            "application://alacritty.desktop",
+            &properties,
+        ];
+
+        if let Err(err) = crate::util::start_daemon("gdbus", &args) {
+            debug!("Unable to report progress to the launcher: {}", err);
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn set_progress(&self, _progress: Option<Progress>) {}
+
+    /// Show the progress of the running command on the taskbar button
+    #[cfg(windows)]
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        use winapi::shared::windef::HWND;
+        use winapi::um::shobjidl_core::{
+            TBPF_ERROR, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED,
+        };
+
+        let (value, state) = match progress {
+            Some(Progress::Normal(value)) => (value, TBPF_NORMAL),
+            Some(Progress::Error(value)) => (value, TBPF_ERROR),
+            Some(Progress::Paused(value)) => (value, TBPF_PAUSED),
+            Some(Progress::Indeterminate) => (0, TBPF_INDETERMINATE),
+            None => (0, TBPF_NOPROGRESS),
+        };
+
+        let hwnd = self.window().get_hwnd() as HWND;
+
+        if self.taskbar.is_none() {
+            self.taskbar = Taskbar::new();
+        }
+        let taskbar = match self.taskbar {
+            Some(ref taskbar) => unsafe { &*taskbar.0 },
+            None => return,
+        };
+
+        unsafe {
+            taskbar.SetProgressState(hwnd, state);
+            if state != TBPF_NOPROGRESS && state != TBPF_INDETERMINATE {
+                taskbar.SetProgressValue(hwnd, u64::from(value), 100);
+            }
+        }
+    }
+
     pub fn set_ime_spot(&self, pos: LogicalPosition) {
         self.window().set_ime_spot(pos);
     }
@@ -426,6 +586,11 @@ impl Window {
 
 pub trait OsExtensions {
     fn run_os_extensions(&self) {}
+
+    /// Ask the window manager to resize the window in steps of `increments` above `base`
+    ///
+    /// The increments can not be removed once set, since X11 has no way to unset them.
+    fn set_resize_increments(&self, _base: PhysicalSize, _increments: PhysicalSize) {}
 }
 
 #[cfg(not(any(
@@ -490,6 +655,89 @@ impl OsExtensions for Window {
             }
         }
     }
+
+    fn set_resize_increments(&self, base: PhysicalSize, increments: PhysicalSize) {
+        use std::os::raw::c_long;
+        use x11_dl::xlib::{self, PBaseSize, PResizeInc};
+
+        let xlib_display = self.window().get_xlib_display();
+        let xlib_window = self.window().get_xlib_window();
+
+        if let (Some(xlib_window), Some(xlib_display)) = (xlib_window, xlib_display) {
+            let xlib = xlib::Xlib::open().expect("get xlib");
+
+            // Update the existing hints, so the minimum size set by glutin is preserved
+            unsafe {
+                let hints = (xlib.XAllocSizeHints)();
+                if hints.is_null() {
+                    return;
+                }
+
+                let mut supplied: c_long = 0;
+                (xlib.XGetWMNormalHints)(xlib_display as _, xlib_window as _, hints, &mut supplied);
+
+                (*hints).flags |= PBaseSize | PResizeInc;
+                (*hints).base_width = base.width.round() as i32;
+                (*hints).base_height = base.height.round() as i32;
+                (*hints).width_inc = increments.width.round() as i32;
+                (*hints).height_inc = increments.height.round() as i32;
+
+                (xlib.XSetWMNormalHints)(xlib_display as _, xlib_window as _, hints);
+                (xlib.XFree)(hints as *mut _);
+                (xlib.XFlush)(xlib_display as _);
+            }
+        }
+    }
+}
+
+/// Initialized `ITaskbarList3`, released when dropped
+#[cfg(windows)]
+struct Taskbar(*mut winapi::um::shobjidl_core::ITaskbarList3);
+
+#[cfg(windows)]
+impl Taskbar {
+    fn new() -> Option<Taskbar> {
+        use std::ptr;
+
+        use winapi::shared::winerror::SUCCEEDED;
+        use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+        use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx};
+        use winapi::um::objbase::COINIT_APARTMENTTHREADED;
+        use winapi::um::shobjidl_core::{CLSID_TaskbarList, ITaskbarList3};
+        use winapi::Interface;
+
+        unsafe {
+            // Initializing COM again on the same thread is harmless
+            CoInitializeEx(ptr::null_mut(), COINIT_APARTMENTTHREADED);
+
+            let mut taskbar: *mut ITaskbarList3 = ptr::null_mut();
+            let result = CoCreateInstance(
+                &CLSID_TaskbarList,
+                ptr::null_mut(),
+                CLSCTX_INPROC_SERVER,
+                &ITaskbarList3::uuidof(),
+                &mut taskbar as *mut *mut ITaskbarList3 as *mut _,
+            );
+            if !SUCCEEDED(result) || taskbar.is_null() {
+                debug!("Unable to access the taskbar: {:#x}", result);
+                return None;
+            }
+
+            let taskbar = Taskbar(taskbar);
+            if !SUCCEEDED((*taskbar.0).HrInit()) {
+                return None;
+            }
+
+            Some(taskbar)
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Taskbar {
+    fn drop(&mut self) {
+        unsafe { (*self.0).Release() };
+    }
 }
 
 impl Proxy {
@@ -14,11 +14,24 @@
 
 use std::error::Error;
 
-// TODO: come up with some platform-agnostic API for richer types
+/// Text with alternative representations for rich text targets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RichText {
+    pub text: String,
+    pub html: String,
+    pub rtf: String,
+}
+
 /// Trait for clipboard access
 pub trait ClipboardProvider: Send {
     /// Method to get the clipboard contents as a String
     fn get_contents(&mut self) -> Result<String, Box<dyn Error>>;
     /// Method to set the clipboard contents as a String
     fn set_contents(&mut self, String) -> Result<(), Box<dyn Error>>;
+    /// Method to set the clipboard contents with all formats the platform supports
+    ///
+    /// Platforms without support for rich text only receive the plain text.
+    fn set_rich_contents(&mut self, data: RichText) -> Result<(), Box<dyn Error>> {
+        self.set_contents(data.text)
+    }
 }
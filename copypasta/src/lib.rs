@@ -23,6 +23,9 @@ extern crate smithay_clipboard;
 extern crate wayland_client;
 #[cfg(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
 extern crate x11_clipboard as x11_clipboard_crate;
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
+#[macro_use]
+extern crate log;
 
 #[cfg(windows)]
 extern crate clipboard_win;
@@ -36,7 +39,7 @@ extern crate objc_foundation;
 extern crate objc_id;
 
 mod common;
-pub use common::ClipboardProvider;
+pub use common::{ClipboardProvider, RichText};
 
 #[cfg(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
 pub mod wayland_clipboard;
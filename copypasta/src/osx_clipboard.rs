@@ -19,6 +19,7 @@ use objc_foundation::{NSArray, NSDictionary, NSObject, NSString};
 use objc_id::{Id, Owned};
 use std::error::Error;
 use std::mem::transmute;
+use std::ptr;
 
 pub struct OSXClipboardContext {
     pasteboard: Id<Object>,
@@ -73,6 +74,31 @@ impl ClipboardProvider for OSXClipboardContext {
             Err("NSPasteboard#writeObjects: returned false".into())
         };
     }
+
+    fn set_rich_contents(&mut self, data: RichText) -> Result<(), Box<dyn Error>> {
+        let contents = [
+            ("public.html", data.html),
+            ("public.rtf", data.rtf),
+            ("public.utf8-plain-text", data.text),
+        ];
+
+        let types: Vec<_> = contents.iter().map(|(ty, _)| NSString::from_str(ty)).collect();
+        let types = NSArray::from_vec(types);
+        let owner: *mut Object = ptr::null_mut();
+        let _: usize = unsafe { msg_send![self.pasteboard, declareTypes:&*types owner:owner] };
+
+        for (ty, content) in contents.iter() {
+            let ty = NSString::from_str(ty);
+            let content = NSString::from_str(content);
+            let success: bool =
+                unsafe { msg_send![self.pasteboard, setString:&*content forType:&*ty] };
+            if !success {
+                return Err("NSPasteboard#setString:forType: returned false".into());
+            }
+        }
+
+        Ok(())
+    }
 }
 
 // this is a convenience function that both cocoa-rs and
@@ -12,11 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clipboard_win::{get_clipboard_string, set_clipboard_string};
+use clipboard_win::raw::register_format;
+use clipboard_win::{get_clipboard_string, set_clipboard_string, Clipboard};
 
-use common::ClipboardProvider;
+use common::{ClipboardProvider, RichText};
 use std::error::Error;
 
+/// Header of the `HTML Format`, with placeholders for the byte offsets
+const HTML_HEADER: &str = "Version:0.9\r\nStartHTML:{start_html}\r\nEndHTML:{end_html}\r\n\
+                           StartFragment:{start_fragment}\r\nEndFragment:{end_fragment}\r\n";
+
 pub struct WindowsClipboardContext;
 
 impl WindowsClipboardContext {
@@ -33,4 +38,45 @@ impl ClipboardProvider for WindowsClipboardContext {
     fn set_contents(&mut self, data: String) -> Result<(), Box<dyn Error>> {
         Ok(set_clipboard_string(&data)?)
     }
+
+    fn set_rich_contents(&mut self, data: RichText) -> Result<(), Box<dyn Error>> {
+        let html_format = register_format("HTML Format").ok_or("unable to register HTML format")?;
+        let rtf_format = register_format("Rich Text Format").ok_or("unable to register RTF")?;
+
+        let clipboard = Clipboard::new()?;
+        clipboard.empty()?;
+        clipboard.set_string(&data.text)?;
+        clipboard.set(html_format, cf_html(&data.html).as_bytes())?;
+        clipboard.set(rtf_format, data.rtf.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Wrap an HTML fragment with the header required by the `HTML Format`
+///
+/// All offsets are formatted with ten digits, so the length of the header is known before
+/// the offsets are inserted.
+fn cf_html(fragment: &str) -> String {
+    let placeholder = format!("{:010}", 0);
+    let header_len = HTML_HEADER
+        .replace("{start_html}", &placeholder)
+        .replace("{end_html}", &placeholder)
+        .replace("{start_fragment}", &placeholder)
+        .replace("{end_fragment}", &placeholder)
+        .len();
+
+    let prefix = "<html><body><!--StartFragment-->";
+    let suffix = "<!--EndFragment--></body></html>";
+    let start_fragment = header_len + prefix.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + suffix.len();
+
+    let header = HTML_HEADER
+        .replace("{start_html}", &format!("{:010}", header_len))
+        .replace("{end_html}", &format!("{:010}", end_html))
+        .replace("{start_fragment}", &format!("{:010}", start_fragment))
+        .replace("{end_fragment}", &format!("{:010}", end_fragment));
+
+    format!("{}{}{}{}", header, prefix, fragment, suffix)
 }
@@ -13,12 +13,20 @@
 // limitations under the License.
 
 use common::*;
+use std::collections::HashMap;
 use std::error::Error;
 use std::marker::PhantomData;
+use std::str;
+use std::sync::{Arc, RwLock};
+use std::thread;
 use std::time::Duration;
-use x11_clipboard_crate::xcb::xproto::Atom;
+use x11_clipboard_crate::xcb::{self, xproto, Atom};
 use x11_clipboard_crate::Atoms;
 use x11_clipboard_crate::Clipboard as X11Clipboard;
+use x11_clipboard_crate::Context;
+
+/// Time to wait for the selection owner to reply to a request
+const TIMEOUT: Duration = Duration::from_secs(3);
 
 pub trait Selection: Send {
     fn atom(atoms: &Atoms) -> Atom;
@@ -32,6 +40,14 @@ impl Selection for Primary {
     }
 }
 
+pub struct Secondary;
+
+impl Selection for Secondary {
+    fn atom(_: &Atoms) -> Atom {
+        xproto::ATOM_SECONDARY
+    }
+}
+
 pub struct Clipboard;
 
 impl Selection for Clipboard {
@@ -40,7 +56,8 @@ impl Selection for Clipboard {
     }
 }
 
-pub struct X11ClipboardContext<S = Clipboard>(X11Clipboard, PhantomData<S>)
+/// Clipboard context, the owner of rich text is only started once it is needed
+pub struct X11ClipboardContext<S = Clipboard>(X11Clipboard, Option<RichOwner>, PhantomData<S>)
 where
     S: Selection;
 
@@ -49,7 +66,17 @@ where
     S: Selection,
 {
     pub fn new() -> Result<X11ClipboardContext<S>, Box<dyn Error>> {
-        Ok(X11ClipboardContext(X11Clipboard::new()?, PhantomData))
+        Ok(X11ClipboardContext(X11Clipboard::new()?, None, PhantomData))
+    }
+
+    /// Mirror the primary selection to the cut buffer for clients without selection support
+    fn mirror_cut_buffer(&self, text: &str) {
+        let setter = &self.0.setter;
+        if S::atom(&setter.atoms) == setter.atoms.primary {
+            if let Err(err) = write_cut_buffer(setter, text) {
+                warn!("Unable to write cut buffer: {}", err);
+            }
+        }
     }
 }
 
@@ -58,15 +85,330 @@ where
     S: Selection,
 {
     fn get_contents(&mut self) -> Result<String, Box<dyn Error>> {
-        Ok(String::from_utf8(self.0.load(
-            S::atom(&self.0.getter.atoms),
-            self.0.getter.atoms.utf8_string,
-            self.0.getter.atoms.property,
-            Duration::from_secs(3),
-        )?)?)
+        let getter = &self.0.getter;
+        let selection = S::atom(&getter.atoms);
+
+        // Request the text targets in order of preference, since legacy clients often
+        // only offer `COMPOUND_TEXT` or `STRING`
+        let compound_text = xproto::intern_atom(&getter.connection, false, "COMPOUND_TEXT")
+            .get_reply()?
+            .atom();
+        let targets = [getter.atoms.utf8_string, compound_text, getter.atoms.string];
+
+        for &target in targets.iter() {
+            let data = self.0.load(selection, target, getter.atoms.property, TIMEOUT)?;
+            if data.is_empty() {
+                continue;
+            }
+
+            let text = if target == getter.atoms.utf8_string {
+                Some(String::from_utf8(data)?)
+            } else if target == compound_text {
+                decode_compound_text(&data)
+            } else {
+                Some(decode_latin1(&data))
+            };
+
+            if let Some(text) = text {
+                return Ok(text);
+            }
+        }
+
+        // Without a selection owner, the primary selection falls back to the cut buffer
+        if selection == getter.atoms.primary {
+            return read_cut_buffer(getter);
+        }
+
+        Ok(String::new())
     }
 
     fn set_contents(&mut self, data: String) -> Result<(), Box<dyn Error>> {
-        Ok(self.0.store(S::atom(&self.0.setter.atoms), self.0.setter.atoms.utf8_string, data)?)
+        self.mirror_cut_buffer(&data);
+
+        let setter = &self.0.setter;
+        Ok(self.0.store(S::atom(&setter.atoms), setter.atoms.utf8_string, data)?)
+    }
+
+    /// Offer the plain text targets together with the rich text targets
+    ///
+    /// Text which is too large for a single request falls back to plain text, since rich text
+    /// is not sent incrementally.
+    fn set_rich_contents(&mut self, data: RichText) -> Result<(), Box<dyn Error>> {
+        if self.1.is_none() {
+            match RichOwner::new() {
+                Ok(owner) => self.1 = Some(owner),
+                Err(err) => {
+                    warn!("Unable to offer rich text: {}", err);
+                    return self.set_contents(data.text);
+                },
+            }
+        }
+
+        let offered = match self.1 {
+            Some(ref owner) => owner.offer(S::atom(&owner.context.atoms), &data)?,
+            None => false,
+        };
+
+        if offered {
+            self.mirror_cut_buffer(&data.text);
+            Ok(())
+        } else {
+            self.set_contents(data.text)
+        }
+    }
+}
+
+/// Target offered by the owner of rich text
+struct Offer {
+    target: Atom,
+
+    /// Type of the data, which differs from the target for `TEXT`
+    ty: Atom,
+    data: Vec<u8>,
+}
+
+/// Offered targets of every selection
+type Offers = Arc<RwLock<HashMap<Atom, Vec<Offer>>>>;
+
+/// Selection owner offering the same text as multiple targets
+///
+/// The owner of `x11_clipboard` can only offer a single target per selection, which isn't
+/// enough for rich text since most clients only request plain text.
+struct RichOwner {
+    context: Arc<Context>,
+    offers: Offers,
+    max_length: usize,
+}
+
+impl RichOwner {
+    fn new() -> Result<RichOwner, Box<dyn Error>> {
+        let context = Arc::new(Context::new(None)?);
+        let offers = Offers::default();
+        let max_length = context.connection.get_maximum_request_length() as usize * 4;
+
+        let (thread_context, thread_offers) = (context.clone(), offers.clone());
+        thread::Builder::new()
+            .name(String::from("x11 rich clipboard"))
+            .spawn(move || serve(&thread_context, &thread_offers))?;
+
+        Ok(RichOwner { context, offers, max_length })
+    }
+
+    /// Take ownership of the selection, offering all representations of the text
+    ///
+    /// Returns `false` without taking ownership if a target doesn't fit into a single request.
+    fn offer(&self, selection: Atom, data: &RichText) -> Result<bool, Box<dyn Error>> {
+        let atoms = &self.context.atoms;
+        let text = self.context.get_atom("TEXT")?;
+        let utf8 = data.text.clone().into_bytes();
+        let mut offers = vec![
+            Offer { target: atoms.utf8_string, ty: atoms.utf8_string, data: utf8.clone() },
+            Offer { target: text, ty: atoms.utf8_string, data: utf8 },
+            Offer { target: atoms.string, ty: atoms.string, data: encode_latin1(&data.text) },
+        ];
+        for &(name, rich) in &[("text/html", &data.html), ("text/rtf", &data.rtf)] {
+            if !rich.is_empty() {
+                let target = self.context.get_atom(name)?;
+                offers.push(Offer { target, ty: target, data: rich.as_bytes().to_vec() });
+            }
+        }
+
+        // Leave room for the header of the request
+        if offers.iter().any(|offer| offer.data.len() + 24 >= self.max_length) {
+            return Ok(false);
+        }
+
+        self.offers.write().map_err(|_| "rich text offers poisoned")?.insert(selection, offers);
+
+        let connection = &self.context.connection;
+        let window = self.context.window;
+        xproto::set_selection_owner(connection, window, selection, xproto::TIME_CURRENT_TIME);
+        if xproto::get_selection_owner(connection, selection).get_reply()?.owner() != window {
+            if let Ok(mut offers) = self.offers.write() {
+                offers.remove(&selection);
+            }
+            return Err("unable to take selection ownership".into());
+        }
+
+        Ok(true)
+    }
+}
+
+/// Answer selection requests until the connection is closed
+fn serve(context: &Context, offers: &Offers) {
+    while let Some(event) = context.connection.wait_for_event() {
+        match event.response_type() & !0x80 {
+            xproto::SELECTION_REQUEST => {
+                let event = unsafe { xcb::cast_event::<xproto::SelectionRequestEvent>(&event) };
+
+                // Obsolete clients don't specify a property
+                let property = match event.property() {
+                    xproto::ATOM_NONE => event.target(),
+                    property => property,
+                };
+
+                let answered = match offers.read() {
+                    Ok(offers) => offers
+                        .get(&event.selection())
+                        .map_or(false, |offers| answer(context, event, property, offers)),
+                    Err(_) => false,
+                };
+
+                // Requests for targets which aren't offered are refused
+                let property = if answered { property } else { xproto::ATOM_NONE };
+                let notify = xproto::SelectionNotifyEvent::new(
+                    event.time(),
+                    event.requestor(),
+                    event.selection(),
+                    event.target(),
+                    property,
+                );
+                xproto::send_event(&context.connection, false, event.requestor(), 0, &notify);
+                context.connection.flush();
+            },
+            xproto::SELECTION_CLEAR => {
+                let event = unsafe { xcb::cast_event::<xproto::SelectionClearEvent>(&event) };
+                if let Ok(mut offers) = offers.write() {
+                    offers.remove(&event.selection());
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Store the requested target in the property of the requestor
+fn answer(
+    context: &Context,
+    event: &xproto::SelectionRequestEvent,
+    property: Atom,
+    offers: &[Offer],
+) -> bool {
+    let connection = &context.connection;
+    let mode = xproto::PROP_MODE_REPLACE as u8;
+
+    if event.target() == context.atoms.targets {
+        let mut targets: Vec<Atom> = offers.iter().map(|offer| offer.target).collect();
+        targets.push(context.atoms.targets);
+        xproto::change_property(
+            connection,
+            mode,
+            event.requestor(),
+            property,
+            xproto::ATOM_ATOM,
+            32,
+            &targets[..],
+        );
+        return true;
+    }
+
+    match offers.iter().find(|offer| offer.target == event.target()) {
+        Some(offer) => {
+            xproto::change_property(
+                connection,
+                mode,
+                event.requestor(),
+                property,
+                offer.ty,
+                8,
+                &offer.data[..],
+            );
+            true
+        },
+        None => false,
+    }
+}
+
+/// Root window of the default screen
+fn root_window(context: &Context) -> Result<xproto::Window, Box<dyn Error>> {
+    let setup = context.connection.get_setup();
+    let screen = setup.roots().nth(context.screen as usize).ok_or("no X11 screen found")?;
+    Ok(screen.root())
+}
+
+/// Read text from `CUT_BUFFER0`
+fn read_cut_buffer(context: &Context) -> Result<String, Box<dyn Error>> {
+    let root = root_window(context)?;
+    let reply = xproto::get_property(
+        &context.connection,
+        false,
+        root,
+        xproto::ATOM_CUT_BUFFER0,
+        xproto::GET_PROPERTY_TYPE_ANY,
+        0,
+        ::std::u32::MAX / 4,
+    )
+    .get_reply()?;
+
+    let data = reply.value::<u8>();
+    if reply.type_() == context.atoms.utf8_string {
+        Ok(String::from_utf8(data.to_vec())?)
+    } else {
+        Ok(decode_latin1(data))
     }
 }
+
+/// Replace the content of `CUT_BUFFER0`
+///
+/// Text is stored as `STRING` when it can be represented in Latin-1.
+fn write_cut_buffer(context: &Context, text: &str) -> Result<(), Box<dyn Error>> {
+    let root = root_window(context)?;
+
+    let (ty, data) = if text.chars().all(|c| (c as u32) < 0x100) {
+        (context.atoms.string, text.chars().map(|c| c as u8).collect())
+    } else {
+        (context.atoms.utf8_string, text.as_bytes().to_vec())
+    };
+
+    xproto::change_property(
+        &context.connection,
+        xproto::PROP_MODE_REPLACE as u8,
+        root,
+        xproto::ATOM_CUT_BUFFER0,
+        ty,
+        8,
+        &data[..],
+    )
+    .request_check()?;
+
+    Ok(())
+}
+
+/// Encode text for the `STRING` target, replacing characters outside of Latin-1
+fn encode_latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| if (c as u32) < 0x100 { c as u8 } else { b'?' }).collect()
+}
+
+/// Decode text of the `STRING` target
+fn decode_latin1(data: &[u8]) -> String {
+    data.iter().map(|&byte| byte as char).collect()
+}
+
+/// Decode text of the `COMPOUND_TEXT` target
+///
+/// Only the default Latin-1 encoding and embedded UTF-8 segments are supported, other
+/// character sets cause the target to be skipped.
+fn decode_compound_text(data: &[u8]) -> Option<String> {
+    const UTF8_START: &[u8] = b"\x1b%G";
+    const UTF8_END: &[u8] = b"\x1b%@";
+
+    let mut text = String::new();
+    let mut rest = data;
+
+    while let Some(&byte) = rest.first() {
+        if rest.starts_with(UTF8_START) {
+            rest = &rest[UTF8_START.len()..];
+            let end =
+                rest.windows(UTF8_END.len()).position(|w| w == UTF8_END).unwrap_or(rest.len());
+            text.push_str(str::from_utf8(&rest[..end]).ok()?);
+            rest = &rest[(end + UTF8_END.len()).min(rest.len())..];
+        } else if byte == 0x1b {
+            return None;
+        } else {
+            text.push(byte as char);
+            rest = &rest[1..];
+        }
+    }
+
+    Some(text)
+}
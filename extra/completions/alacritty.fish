@@ -3,6 +3,9 @@ complete -c alacritty \
   -s "v" \
   -l "version" \
   -d "Prints version information"
+complete -c alacritty \
+  -l "verbose" \
+  -d "Prints build and environment details along with the version"
 complete -c alacritty \
   -s "h" \
   -l "help" \
@@ -18,6 +21,9 @@ complete -c alacritty \
 complete -c alacritty \
   -l "persistent-logging" \
   -d "Keep the log file after quitting Alacritty"
+complete -c alacritty \
+  -l "hold-env" \
+  -d "Pass environment variables only meant for Alacritty on to the shell"
 complete -c alacritty \
   -f \
   -l "config-file" \
@@ -61,6 +67,26 @@ complete \
   -c alacritty \
   -l "ref-test" \
   -d "Generates ref test"
+complete \
+  -c alacritty \
+  -n "__fish_use_subcommand" \
+  -a "config" \
+  -d "Prints the effective configuration"
+complete \
+  -c alacritty \
+  -n "__fish_seen_subcommand_from config" \
+  -l "default" \
+  -d "Prints the default configuration"
+complete \
+  -c alacritty \
+  -n "__fish_use_subcommand" \
+  -a "bindings" \
+  -d "Prints the effective key and mouse bindings"
+complete \
+  -c alacritty \
+  -f \
+  -l "trace-file" \
+  -d "Write a Chrome trace of the frame pipeline"
 
 complete \
   -c alacritty \
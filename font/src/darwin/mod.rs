@@ -43,7 +43,7 @@ use core_text::font_descriptor::{CTFontDescriptor, CTFontOrientation};
 
 use euclid::{Point2D, Rect, Size2D};
 
-use super::{FontDesc, FontKey, GlyphKey, Metrics, RasterizedGlyph};
+use super::{BitmapBuffer, FontDesc, FontKey, GlyphKey, Metrics, RasterizedGlyph};
 
 pub mod byte_order;
 use self::byte_order::extract_rgb;
@@ -177,6 +177,10 @@ impl ::Rasterize for Rasterizer {
         })
     }
 
+    fn has_glyph(&self, key: FontKey, c: char) -> bool {
+        self.fonts.get(&key).map_or(false, |font| font.glyph_index(c).is_some())
+    }
+
     fn update_dpr(&mut self, device_pixel_ratio: f32) {
         self.device_pixel_ratio = device_pixel_ratio;
     }
@@ -468,7 +472,7 @@ impl Font {
                 height: 0,
                 top: 0,
                 left: 0,
-                buf: Vec::new(),
+                buf: BitmapBuffer::RGB(Vec::new()),
             });
         }
 
@@ -517,7 +521,7 @@ impl Font {
 
         let rasterized_pixels = cg_context.data().to_vec();
 
-        let buf = extract_rgb(&rasterized_pixels);
+        let buf = BitmapBuffer::RGB(extract_rgb(&rasterized_pixels));
 
         Ok(RasterizedGlyph {
             c: character,
@@ -586,7 +590,7 @@ mod tests {
                 for row in 0..glyph.height {
                     for col in 0..glyph.width {
                         let index = ((glyph.width * 3 * row) + (col * 3)) as usize;
-                        let value = glyph.buf[index];
+                        let value = glyph.buf.bytes()[index];
                         let c = match value {
                             0...50 => ' ',
                             51...100 => '.',
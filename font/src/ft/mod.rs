@@ -23,8 +23,12 @@ use freetype::{self, Library};
 use libc::c_uint;
 
 pub mod fc;
+mod variations;
 
-use super::{FontDesc, FontKey, GlyphKey, Metrics, RasterizedGlyph, Size, Slant, Style, Weight};
+use super::{
+    BitmapBuffer, FontDesc, FontKey, GlyphKey, Metrics, RasterizedGlyph, Size, Slant, Style,
+    Variation, Weight,
+};
 
 struct FixedSize {
     pixelsize: f64,
@@ -62,15 +66,70 @@ impl fmt::Debug for Face {
 pub struct FreeTypeRasterizer {
     faces: HashMap<FontKey, Face>,
     library: Library,
-    keys: HashMap<PathBuf, FontKey>,
+    keys: HashMap<(PathBuf, Vec<Variation>), FontKey>,
     device_pixel_ratio: f32,
 }
 
+/// Load the fontconfig configuration and font cache
+///
+/// This otherwise happens during the first font lookup, calling it from another thread ahead
+/// of time takes it off the path to the first frame.
+pub fn preload() {
+    fc::Config::get_current();
+}
+
 #[inline]
 fn to_freetype_26_6(f: f32) -> isize {
     ((1i32 << 6) as f32 * f) as isize
 }
 
+/// Scale down an RGBA glyph by `factor`, averaging the pixels covered by every new pixel
+fn downsample_bitmap(mut glyph: RasterizedGlyph, factor: f32) -> RasterizedGlyph {
+    let src = match glyph.buf {
+        BitmapBuffer::RGBA(ref buf) => buf,
+        BitmapBuffer::RGB(_) => return glyph,
+    };
+
+    let src_width = glyph.width as usize;
+    let src_height = glyph.height as usize;
+    let width = ((glyph.width as f32 * factor).ceil() as usize).max(1);
+    let height = ((glyph.height as f32 * factor).ceil() as usize).max(1);
+
+    let mut dst = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let src_top = (y as f32 / factor) as usize;
+        let src_bot = ((((y + 1) as f32) / factor) as usize).max(src_top + 1).min(src_height);
+
+        for x in 0..width {
+            let src_left = (x as f32 / factor) as usize;
+            let src_right = ((((x + 1) as f32) / factor) as usize).max(src_left + 1).min(src_width);
+
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+            for src_y in src_top..src_bot {
+                for src_x in src_left..src_right {
+                    let offset = (src_y * src_width + src_x) * 4;
+                    for (channel, value) in sum.iter_mut().zip(&src[offset..offset + 4]) {
+                        *channel += u32::from(*value);
+                    }
+                    count += 1;
+                }
+            }
+
+            for channel in &sum {
+                dst.push((channel / count.max(1)) as u8);
+            }
+        }
+    }
+
+    glyph.buf = BitmapBuffer::RGBA(dst);
+    glyph.width = width as i32;
+    glyph.height = height as i32;
+    glyph.top = (glyph.top as f32 * factor).round() as i32;
+    glyph.left = (glyph.left as f32 * factor).round() as i32;
+    glyph
+}
+
 impl ::Rasterize for FreeTypeRasterizer {
     type Err = Error;
 
@@ -138,6 +197,10 @@ impl ::Rasterize for FreeTypeRasterizer {
         self.get_rendered_glyph(glyph_key)
     }
 
+    fn has_glyph(&self, key: FontKey, c: char) -> bool {
+        self.faces.get(&key).map_or(false, |face| face.ft_face.get_char_index(c as usize) != 0)
+    }
+
     fn update_dpr(&mut self, device_pixel_ratio: f32) {
         self.device_pixel_ratio = device_pixel_ratio;
     }
@@ -223,7 +286,7 @@ impl FreeTypeRasterizer {
         let font = fc::font_match(fc::Config::get_current(), &mut pattern)
             .ok_or_else(|| Error::MissingFont(desc.to_owned()))?;
 
-        self.face_from_pattern(&font).and_then(|pattern| {
+        self.face_from_pattern(&font, &desc.variations).and_then(|pattern| {
             pattern.map(Ok).unwrap_or_else(|| Err(Error::MissingFont(desc.to_owned())))
         })
     }
@@ -241,19 +304,25 @@ impl FreeTypeRasterizer {
 
         let font = fc::font_match(fc::Config::get_current(), &mut pattern)
             .ok_or_else(|| Error::MissingFont(desc.to_owned()))?;
-        self.face_from_pattern(&font).and_then(|pattern| {
+        self.face_from_pattern(&font, &desc.variations).and_then(|pattern| {
             pattern.map(Ok).unwrap_or_else(|| Err(Error::MissingFont(desc.to_owned())))
         })
     }
 
-    fn face_from_pattern(&mut self, pattern: &fc::Pattern) -> Result<Option<FontKey>, Error> {
+    fn face_from_pattern(
+        &mut self,
+        pattern: &fc::Pattern,
+        variations: &[Variation],
+    ) -> Result<Option<FontKey>, Error> {
         if let (Some(path), Some(index)) = (pattern.file(0), pattern.index().nth(0)) {
-            if let Some(key) = self.keys.get(&path) {
+            let id = (path, variations.to_vec());
+            if let Some(key) = self.keys.get(&id) {
                 return Ok(Some(*key));
             }
 
-            trace!("Got font path={:?}", path);
-            let ft_face = self.library.new_face(&path, index)?;
+            trace!("Got font path={:?}", id.0);
+            let mut ft_face = self.library.new_face(&id.0, index)?;
+            variations::set_variations(&self.library, &mut ft_face, variations);
 
             // Get available pixel sizes if font isn't scalable.
             let non_scalable = if pattern.scalable().next().unwrap_or(true) {
@@ -278,7 +347,7 @@ impl FreeTypeRasterizer {
 
             let key = face.key;
             self.faces.insert(key, face);
-            self.keys.insert(path, key);
+            self.keys.insert(id, key);
 
             Ok(Some(key))
         } else {
@@ -315,10 +384,8 @@ impl FreeTypeRasterizer {
         let face = &self.faces[&font_key];
         let index = face.ft_face.get_char_index(glyph_key.c as usize);
 
-        let size =
-            face.non_scalable.as_ref().map(|v| v.pixelsize as f32).unwrap_or_else(|| {
-                glyph_key.size.as_f32_pts() * self.device_pixel_ratio * 96. / 72.
-            });
+        let requested_size = glyph_key.size.as_f32_pts() * self.device_pixel_ratio * 96. / 72.;
+        let size = face.non_scalable.as_ref().map(|v| v.pixelsize as f32).unwrap_or(requested_size);
 
         face.ft_face.set_char_size(to_freetype_26_6(size), 0, 0, 0)?;
 
@@ -327,20 +394,29 @@ impl FreeTypeRasterizer {
             freetype::ffi::FT_Library_SetLcdFilter(ft_lib, face.lcd_filter);
         }
 
-        face.ft_face.load_glyph(index as u32, face.load_flags)?;
+        // Color glyphs are loaded as BGRA bitmaps, every other glyph is unaffected
+        let load_flags = face.load_flags | freetype::face::LoadFlag::COLOR;
+        face.ft_face.load_glyph(index as u32, load_flags)?;
         let glyph = face.ft_face.glyph();
         glyph.render_glyph(face.render_mode)?;
 
         let (pixel_height, pixel_width, buf) = Self::normalize_buffer(&glyph.bitmap())?;
 
-        Ok(RasterizedGlyph {
+        let rasterized = RasterizedGlyph {
             c: glyph_key.c,
             top: glyph.bitmap_top(),
             left: glyph.bitmap_left(),
             width: pixel_width,
             height: pixel_height,
             buf,
-        })
+        };
+
+        // Color bitmap fonts usually only have strikes which are far too large for a cell
+        if rasterized.buf.is_colored() && size > requested_size {
+            Ok(downsample_bitmap(rasterized, requested_size / size))
+        } else {
+            Ok(rasterized)
+        }
     }
 
     fn ft_load_flags(pat: &fc::Pattern) -> freetype::face::LoadFlag {
@@ -404,10 +480,12 @@ impl FreeTypeRasterizer {
 
     /// Given a FreeType `Bitmap`, returns packed buffer with 1 byte per LCD channel.
     ///
+    /// Color bitmaps are returned with 4 bytes per pixel instead, in RGBA order.
+    ///
     /// The i32 value in the return type is the number of pixels per row.
     fn normalize_buffer(
         bitmap: &freetype::bitmap::Bitmap,
-    ) -> freetype::FtResult<(i32, i32, Vec<u8>)> {
+    ) -> freetype::FtResult<(i32, i32, BitmapBuffer)> {
         use freetype::bitmap::PixelMode;
 
         let buf = bitmap.buffer();
@@ -420,7 +498,7 @@ impl FreeTypeRasterizer {
                     let stop = start + bitmap.width() as usize;
                     packed.extend_from_slice(&buf[start..stop]);
                 }
-                Ok((bitmap.rows(), bitmap.width() / 3, packed))
+                Ok((bitmap.rows(), bitmap.width() / 3, BitmapBuffer::RGB(packed)))
             },
             PixelMode::LcdV => {
                 for i in 0..bitmap.rows() / 3 {
@@ -431,7 +509,7 @@ impl FreeTypeRasterizer {
                         }
                     }
                 }
-                Ok((bitmap.rows() / 3, bitmap.width(), packed))
+                Ok((bitmap.rows() / 3, bitmap.width(), BitmapBuffer::RGB(packed)))
             },
             // Mono data is stored in a packed format using 1 bit per pixel.
             PixelMode::Mono => {
@@ -462,7 +540,7 @@ impl FreeTypeRasterizer {
                         byte += 1;
                     }
                 }
-                Ok((bitmap.rows(), bitmap.width(), packed))
+                Ok((bitmap.rows(), bitmap.width(), BitmapBuffer::RGB(packed)))
             },
             // Gray data is stored as a value between 0 and 255 using 1 byte per pixel.
             PixelMode::Gray => {
@@ -475,7 +553,18 @@ impl FreeTypeRasterizer {
                         packed.push(*byte);
                     }
                 }
-                Ok((bitmap.rows(), bitmap.width(), packed))
+                Ok((bitmap.rows(), bitmap.width(), BitmapBuffer::RGB(packed)))
+            },
+            // Color data is stored as premultiplied BGRA using 4 bytes per pixel.
+            PixelMode::Bgra => {
+                for i in 0..bitmap.rows() {
+                    let start = (i as usize) * pitch;
+                    let stop = start + bitmap.width() as usize * 4;
+                    for pixel in buf[start..stop].chunks(4) {
+                        packed.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                    }
+                }
+                Ok((bitmap.rows(), bitmap.width(), BitmapBuffer::RGBA(packed)))
             },
             mode => panic!("unhandled pixel mode: {:?}", mode),
         }
@@ -491,7 +580,7 @@ impl FreeTypeRasterizer {
         match fc::font_match(config, &mut pattern) {
             Some(pattern) => {
                 if let (Some(path), Some(_)) = (pattern.file(0), pattern.index().nth(0)) {
-                    match self.keys.get(&path) {
+                    match self.keys.get(&(path.clone(), Vec::new())) {
                         // We've previously loaded this font, so don't
                         // load it again.
                         Some(&key) => {
@@ -503,7 +592,7 @@ impl FreeTypeRasterizer {
                             debug!("Miss for font {:?}; loading now", path);
                             // Safe to unwrap the option since we've already checked for the path
                             // and index above.
-                            let key = self.face_from_pattern(&pattern)?.unwrap();
+                            let key = self.face_from_pattern(&pattern, &[])?.unwrap();
                             Ok(key)
                         },
                     }
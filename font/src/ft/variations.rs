@@ -0,0 +1,108 @@
+// Copyright 2019 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Design coordinates of variable fonts
+//!
+//! The FreeType bindings do not cover the multiple masters API, so the few functions and
+//! structs which are required are declared here.
+use std::ptr;
+use std::slice;
+
+use libc::c_void;
+
+use freetype::ffi::{FT_Error, FT_Face, FT_Fixed, FT_Library, FT_String, FT_UInt, FT_ULong};
+use freetype::{Face, Library};
+
+use crate::Variation;
+
+#[allow(non_camel_case_types, dead_code)]
+#[repr(C)]
+struct FT_Var_Axis {
+    name: *mut FT_String,
+    minimum: FT_Fixed,
+    def: FT_Fixed,
+    maximum: FT_Fixed,
+    tag: FT_ULong,
+    strid: FT_UInt,
+}
+
+#[allow(non_camel_case_types, dead_code)]
+#[repr(C)]
+struct FT_MM_Var {
+    num_axis: FT_UInt,
+    num_designs: FT_UInt,
+    num_namedstyles: FT_UInt,
+    axis: *mut FT_Var_Axis,
+    namedstyle: *mut c_void,
+}
+
+extern "C" {
+    fn FT_Get_MM_Var(face: FT_Face, amaster: *mut *mut FT_MM_Var) -> FT_Error;
+    fn FT_Done_MM_Var(library: FT_Library, amaster: *mut FT_MM_Var) -> FT_Error;
+    fn FT_Get_Var_Design_Coordinates(
+        face: FT_Face,
+        num_coords: FT_UInt,
+        coords: *mut FT_Fixed,
+    ) -> FT_Error;
+    fn FT_Set_Var_Design_Coordinates(
+        face: FT_Face,
+        num_coords: FT_UInt,
+        coords: *mut FT_Fixed,
+    ) -> FT_Error;
+}
+
+/// Move the axes of a variable font to the requested values
+///
+/// Axes without a requested value keep the position of the loaded instance. Values are clamped
+/// to the range supported by the font.
+pub fn set_variations(library: &Library, face: &mut Face, variations: &[Variation]) {
+    if variations.is_empty() {
+        return;
+    }
+
+    let raw_face = face.raw_mut() as FT_Face;
+
+    unsafe {
+        let mut mm_var = ptr::null_mut();
+        if FT_Get_MM_Var(raw_face, &mut mm_var) != 0 || mm_var.is_null() {
+            warn!("Ignoring font variations, the font has no variation axes");
+            return;
+        }
+
+        let axes = slice::from_raw_parts((*mm_var).axis, (*mm_var).num_axis as usize);
+
+        let mut coords: Vec<FT_Fixed> = axes.iter().map(|axis| axis.def).collect();
+        FT_Get_Var_Design_Coordinates(raw_face, coords.len() as FT_UInt, coords.as_mut_ptr());
+
+        for variation in variations {
+            let tag = FT_ULong::from(u32::from_be_bytes(variation.tag));
+            match axes.iter().position(|axis| axis.tag == tag) {
+                Some(index) => {
+                    let axis = &axes[index];
+                    let value = FT_Fixed::from(variation.fixed_value());
+                    coords[index] = value.max(axis.minimum).min(axis.maximum);
+                },
+                None => warn!("Ignoring font variation {}, the font has no such axis", variation),
+            }
+        }
+
+        if FT_Set_Var_Design_Coordinates(raw_face, coords.len() as FT_UInt, coords.as_mut_ptr())
+            != 0
+        {
+            warn!("Unable to apply font variations");
+        }
+
+        FT_Done_MM_Var(library.raw(), mm_var);
+    }
+}
@@ -53,7 +53,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 #[cfg(not(any(target_os = "macos", windows)))]
 pub mod ft;
 #[cfg(not(any(target_os = "macos", windows)))]
-pub use ft::{Error, FreeTypeRasterizer as Rasterizer};
+pub use ft::{preload, Error, FreeTypeRasterizer as Rasterizer};
 
 #[cfg(windows)]
 pub mod directwrite;
@@ -66,10 +66,44 @@ mod darwin;
 #[cfg(target_os = "macos")]
 pub use darwin::*;
 
+/// Prepare the system font lookup ahead of its first use
+///
+/// Core Text and DirectWrite have no upfront setup worth moving off the main thread.
+#[cfg(any(target_os = "macos", windows))]
+pub fn preload() {}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FontDesc {
     name: String,
     style: Style,
+    variations: Vec<Variation>,
+}
+
+/// Position on one of the design axes of a variable font
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Variation {
+    /// OpenType axis tag, like `wght` for the weight or `slnt` for the slant
+    pub tag: [u8; 4],
+
+    /// Axis value in 16.16 fixed point
+    value: i32,
+}
+
+impl Variation {
+    pub fn new(tag: [u8; 4], value: f32) -> Variation {
+        Variation { tag, value: (value * 65536.) as i32 }
+    }
+
+    /// Axis value in 16.16 fixed point
+    pub fn fixed_value(self) -> i32 {
+        self.value
+    }
+}
+
+impl fmt::Display for Variation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", String::from_utf8_lossy(&self.tag), f64::from(self.value) / 65536.)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -108,13 +142,26 @@ impl FontDesc {
     where
         S: Into<String>,
     {
-        FontDesc { name: name.into(), style }
+        FontDesc { name: name.into(), style, variations: Vec::new() }
+    }
+
+    /// Use the font with the given variable font axis values
+    pub fn with_variations(self, variations: Vec<Variation>) -> FontDesc {
+        FontDesc { variations, ..self }
     }
 }
 
 impl fmt::Display for FontDesc {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "name {} and style {}", self.name, self.style)
+        write!(f, "name {} and style {}", self.name, self.style)?;
+
+        if !self.variations.is_empty() {
+            let variations: Vec<String> =
+                self.variations.iter().map(Variation::to_string).collect();
+            write!(f, " with variations {}", variations.join(", "))?;
+        }
+
+        Ok(())
     }
 }
 
@@ -205,20 +252,59 @@ pub struct RasterizedGlyph {
     pub height: i32,
     pub top: i32,
     pub left: i32,
-    pub buf: Vec<u8>,
+    pub buf: BitmapBuffer,
+}
+
+/// Pixel data of a rasterized glyph
+#[derive(Clone, PartialEq, Eq)]
+pub enum BitmapBuffer {
+    /// Coverage mask with one value per subpixel, to be drawn in the text color
+    RGB(Vec<u8>),
+
+    /// Colored glyph with premultiplied alpha, like emoji
+    RGBA(Vec<u8>),
+}
+
+impl BitmapBuffer {
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            BitmapBuffer::RGB(buf) | BitmapBuffer::RGBA(buf) => buf,
+        }
+    }
+
+    #[inline]
+    pub fn is_colored(&self) -> bool {
+        match self {
+            BitmapBuffer::RGB(_) => false,
+            BitmapBuffer::RGBA(_) => true,
+        }
+    }
 }
 
 impl Default for RasterizedGlyph {
     fn default() -> RasterizedGlyph {
-        RasterizedGlyph { c: ' ', width: 0, height: 0, top: 0, left: 0, buf: Vec::new() }
+        RasterizedGlyph {
+            c: ' ',
+            width: 0,
+            height: 0,
+            top: 0,
+            left: 0,
+            buf: BitmapBuffer::RGB(Vec::new()),
+        }
     }
 }
 
-struct BufDebugger<'a>(&'a [u8]);
+struct BufDebugger<'a>(&'a BitmapBuffer);
 
 impl<'a> fmt::Debug for BufDebugger<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("GlyphBuffer").field("len", &self.0.len()).field("bytes", &self.0).finish()
+        let bytes = self.0.bytes();
+        f.debug_struct("GlyphBuffer")
+            .field("colored", &self.0.is_colored())
+            .field("len", &bytes.len())
+            .field("bytes", &bytes)
+            .finish()
     }
 }
 
@@ -230,7 +316,7 @@ impl fmt::Debug for RasterizedGlyph {
             .field("height", &self.height)
             .field("top", &self.top)
             .field("left", &self.left)
-            .field("buf", &BufDebugger(&self.buf[..]))
+            .field("buf", &BufDebugger(&self.buf))
             .finish()
     }
 }
@@ -264,6 +350,9 @@ pub trait Rasterize {
     /// Rasterize the glyph described by `GlyphKey`.
     fn get_glyph(&mut self, _: GlyphKey) -> Result<RasterizedGlyph, Self::Err>;
 
+    /// Check if the font itself has a glyph for the character, ignoring any fallback
+    fn has_glyph(&self, _: FontKey, _: char) -> bool;
+
     /// Update the Rasterizer's DPI factor
     fn update_dpr(&mut self, device_pixel_ratio: f32);
 }
@@ -13,7 +13,7 @@
 // limitations under the License.
 #version 330 core
 in vec2 TexCoords;
-flat in vec3 fg;
+flat in vec4 fg;
 flat in vec4 bg;
 uniform int backgroundPass;
 
@@ -30,9 +30,18 @@ void main()
 
         alphaMask = vec4(1.0);
         color = vec4(bg.rgb, 1.0);
+    } else if (fg.a != 0.0) {
+        // Colored glyphs contain premultiplied RGBA instead of a coverage mask
+        vec4 glyphColor = texture(mask, TexCoords);
+        if (glyphColor.a != 0.0) {
+            glyphColor.rgb /= glyphColor.a;
+        }
+
+        alphaMask = vec4(glyphColor.a);
+        color = vec4(glyphColor.rgb, 1.0);
     } else {
         vec3 textColor = texture(mask, TexCoords).rgb;
         alphaMask = vec4(textColor, textColor.r);
-        color = vec4(fg, 1.0);
+        color = vec4(fg.rgb, 1.0);
     }
 }
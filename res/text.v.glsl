@@ -21,14 +21,14 @@ layout (location = 1) in vec4 glyph;
 // uv mapping
 layout (location = 2) in vec4 uv;
 
-// text fg color
-layout (location = 3) in vec3 textColor;
+// text fg color, alpha is set for colored glyphs
+layout (location = 3) in vec4 textColor;
 
 // Background color
 layout (location = 4) in vec4 backgroundColor;
 
 out vec2 TexCoords;
-flat out vec3 fg;
+flat out vec4 fg;
 flat out vec4 bg;
 
 // Terminal properties
@@ -70,5 +70,5 @@ void main()
     }
 
     bg = vec4(backgroundColor.rgb / 255.0, backgroundColor.a);
-    fg = textColor / vec3(255.0, 255.0, 255.0);
+    fg = vec4(textColor.rgb / 255.0, textColor.a);
 }